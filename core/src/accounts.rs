@@ -0,0 +1,460 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::EpochSeconds;
+use crate::money::Money;
+use crate::storage;
+use crate::Error;
+
+/// Balance below which an account is flagged, unless configured otherwise.
+pub const DEFAULT_LOW_BALANCE: Money = Money::from_minor(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AccountId(pub u32);
+
+impl fmt::Display for AccountId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A prepaid customer. The balance only changes through ledger entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Account {
+    pub id: AccountId,
+    pub name: String,
+    /// Printed on the customer's card; unique across accounts when set.
+    #[serde(default)]
+    pub card_number: String,
+    /// Free-form grouping such as "Students" or "Staff".
+    #[serde(default)]
+    pub group: String,
+    pub balance: Money,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LedgerKind {
+    TopUp,
+    Charge,
+    /// Credit given back when a session's charge goes down, e.g. after a
+    /// category is excluded.
+    Refund,
+}
+
+impl fmt::Display for LedgerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerKind::TopUp => f.write_str("Top-up"),
+            LedgerKind::Charge => f.write_str("Charge"),
+            LedgerKind::Refund => f.write_str("Refund"),
+        }
+    }
+}
+
+/// One change to an account's balance. `amount` is always positive; the
+/// kind says which way it went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub at: EpochSeconds,
+    pub account: AccountId,
+    pub kind: LedgerKind,
+    pub amount: Money,
+    pub balance_after: Money,
+    #[serde(default)]
+    pub note: String,
+    /// Recording session the entry settles, so replaying a settlement after
+    /// a crash never books it twice.
+    #[serde(default)]
+    pub session: Option<String>,
+}
+
+/// Where a balance stands against the low-balance threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceState {
+    Ok,
+    Low,
+    /// Zero or below; sessions on the account are stopped.
+    Exhausted,
+}
+
+/// All accounts and the ledger of every top-up and charge, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountBook {
+    accounts: Vec<Account>,
+    ledger: Vec<LedgerEntry>,
+    #[serde(default = "default_low_balance")]
+    low_balance: Money,
+    next_id: u32,
+}
+
+impl Default for AccountBook {
+    fn default() -> Self {
+        Self {
+            accounts: Vec::new(),
+            ledger: Vec::new(),
+            low_balance: DEFAULT_LOW_BALANCE,
+            next_id: 1,
+        }
+    }
+}
+
+impl AccountBook {
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    pub fn ledger(&self) -> &[LedgerEntry] {
+        &self.ledger
+    }
+
+    pub fn account(&self, id: AccountId) -> Option<&Account> {
+        self.accounts.iter().find(|account| account.id == id)
+    }
+
+    /// Looks an account up by the number on its card, ignoring surrounding
+    /// whitespace.
+    pub fn account_by_card(&self, card_number: &str) -> Option<&Account> {
+        let card_number = card_number.trim();
+        if card_number.is_empty() {
+            return None;
+        }
+        self.accounts
+            .iter()
+            .find(|account| account.card_number == card_number)
+    }
+
+    pub fn entries_for(&self, id: AccountId) -> impl Iterator<Item = &LedgerEntry> {
+        self.ledger.iter().filter(move |entry| entry.account == id)
+    }
+
+    pub fn low_balance(&self) -> Money {
+        self.low_balance
+    }
+
+    pub fn set_low_balance(&mut self, threshold: Money) {
+        self.low_balance = threshold;
+    }
+
+    pub fn balance_state(&self, id: AccountId) -> Option<BalanceState> {
+        let balance = self.account(id)?.balance;
+        Some(if balance <= Money::ZERO {
+            BalanceState::Exhausted
+        } else if balance < self.low_balance {
+            BalanceState::Low
+        } else {
+            BalanceState::Ok
+        })
+    }
+
+    /// Adds an account with a zero balance and returns its id.
+    pub fn open_account(
+        &mut self,
+        name: &str,
+        card_number: &str,
+        group: &str,
+    ) -> Result<AccountId, Error> {
+        let name = name.trim();
+        let card_number = card_number.trim();
+        if name.is_empty() {
+            return Err(Error::Account {
+                account: "new account".to_string(),
+                details: "name is empty".to_string(),
+            });
+        }
+        if let Some(existing) = self.account_by_card(card_number) {
+            return Err(Error::Account {
+                account: name.to_string(),
+                details: format!("card {card_number} already belongs to {}", existing.name),
+            });
+        }
+        let id = AccountId(self.next_id);
+        self.next_id += 1;
+        self.accounts.push(Account {
+            id,
+            name: name.to_string(),
+            card_number: card_number.to_string(),
+            group: group.trim().to_string(),
+            balance: Money::ZERO,
+        });
+        Ok(id)
+    }
+
+    pub fn top_up(
+        &mut self,
+        id: AccountId,
+        amount: Money,
+        at: EpochSeconds,
+        note: &str,
+    ) -> Result<Money, Error> {
+        if amount <= Money::ZERO {
+            return Err(self.error(id, "top-ups must be positive"));
+        }
+        self.book(id, LedgerKind::TopUp, amount, at, note, None)
+    }
+
+    /// Debits `amount`. The balance may go below zero: the pages have
+    /// already been printed by the time they are charged.
+    pub fn charge(
+        &mut self,
+        id: AccountId,
+        amount: Money,
+        at: EpochSeconds,
+        note: &str,
+    ) -> Result<Money, Error> {
+        if amount <= Money::ZERO {
+            return Err(self.error(id, "charges must be positive"));
+        }
+        self.book(id, LedgerKind::Charge, amount, at, note, None)
+    }
+
+    /// Charges less refunds booked for `session`, or `None` when no entry
+    /// is tagged with it.
+    pub fn booked_for(&self, session: &str) -> Option<Money> {
+        let mut entries = self
+            .ledger
+            .iter()
+            .filter(|entry| entry.session.as_deref() == Some(session))
+            .peekable();
+        entries.peek()?;
+        Some(
+            entries
+                .map(|entry| match entry.kind {
+                    LedgerKind::Charge => entry.amount,
+                    LedgerKind::TopUp | LedgerKind::Refund => -entry.amount,
+                })
+                .sum(),
+        )
+    }
+
+    /// Brings what `session` has been charged so far up (or down) to `total`
+    /// and records the difference, tagged with `session`. What was charged
+    /// comes from the ledger, so settling again after a crash that lost the
+    /// session's own `charged` books nothing twice; `charged` is only used
+    /// for sessions settled before entries were tagged, and is set to
+    /// `total` afterwards.
+    pub fn settle(
+        &mut self,
+        id: AccountId,
+        session: &str,
+        charged: &mut Money,
+        total: Money,
+        at: EpochSeconds,
+        note: &str,
+    ) -> Result<Option<Money>, Error> {
+        let booked = self.booked_for(session).unwrap_or(*charged);
+        let difference = total - booked;
+        let (kind, amount) = match difference.cmp(&Money::ZERO) {
+            Ordering::Equal => {
+                *charged = total;
+                return Ok(None);
+            }
+            Ordering::Greater => (LedgerKind::Charge, difference),
+            Ordering::Less => (LedgerKind::Refund, -difference),
+        };
+        let balance = self.book(id, kind, amount, at, note, Some(session))?;
+        *charged = total;
+        Ok(Some(balance))
+    }
+
+    fn book(
+        &mut self,
+        id: AccountId,
+        kind: LedgerKind,
+        amount: Money,
+        at: EpochSeconds,
+        note: &str,
+        session: Option<&str>,
+    ) -> Result<Money, Error> {
+        let Some(account) = self.accounts.iter_mut().find(|account| account.id == id) else {
+            return Err(self.error(id, "account does not exist"));
+        };
+        account.balance = match kind {
+            LedgerKind::TopUp | LedgerKind::Refund => account.balance + amount,
+            LedgerKind::Charge => account.balance - amount,
+        };
+        let balance_after = account.balance;
+        self.ledger.push(LedgerEntry {
+            at,
+            account: id,
+            kind,
+            amount,
+            balance_after,
+            note: note.to_string(),
+            session: session.map(str::to_string),
+        });
+        Ok(balance_after)
+    }
+
+    fn error(&self, id: AccountId, details: &str) -> Error {
+        let account = self
+            .account(id)
+            .map(|account| account.name.clone())
+            .unwrap_or_else(|| id.to_string());
+        Error::Account {
+            account,
+            details: details.to_string(),
+        }
+    }
+
+    /// Reads a book and checks every balance against its ledger, so a file
+    /// edited by hand cannot hand out credit nobody paid for.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let book: Self = storage::load_ron(path.as_ref())?;
+        book.validate()?;
+        Ok(book)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        for account in &self.accounts {
+            let replayed = self
+                .entries_for(account.id)
+                .map(|entry| match entry.kind {
+                    LedgerKind::TopUp | LedgerKind::Refund => entry.amount,
+                    LedgerKind::Charge => -entry.amount,
+                })
+                .sum::<Money>();
+            if replayed != account.balance {
+                return Err(Error::Account {
+                    account: account.name.clone(),
+                    details: format!(
+                        "balance {} does not match the ledger total {}",
+                        account.balance.minor(),
+                        replayed.minor()
+                    ),
+                });
+            }
+            if account.id.0 >= self.next_id {
+                return Err(self.error(account.id, "id is not below the next free id"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes with `storage::write_atomic`, so a crash never loses booked
+    /// charges.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        storage::save_ron(path.as_ref(), self)
+    }
+}
+
+fn default_low_balance() -> Money {
+    DEFAULT_LOW_BALANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::storage::tests::TempDir;
+
+    fn cents(minor: i64) -> Money {
+        Money::from_minor(minor)
+    }
+
+    #[test]
+    fn settles_a_session_as_it_grows() {
+        let mut book = AccountBook::default();
+        let id = book
+            .open_account("Ada", " 1001 ", "Students")
+            .expect("open");
+        assert!(book.open_account("Bob", "1001", "").is_err());
+        assert!(book.open_account("  ", "", "").is_err());
+        assert_eq!(
+            book.account_by_card("1001").map(|account| account.id),
+            Some(id)
+        );
+
+        book.top_up(id, cents(500), 10, "cash").expect("top up");
+        assert_eq!(book.balance_state(id), Some(BalanceState::Ok));
+
+        let mut charged = Money::ZERO;
+        assert_eq!(
+            book.settle(id, "s", &mut charged, Money::ZERO, 20, "s")
+                .expect("settle"),
+            None
+        );
+        book.settle(id, "s", &mut charged, cents(150), 30, "s")
+            .expect("settle");
+        book.settle(id, "s", &mut charged, cents(400), 40, "s")
+            .expect("settle");
+        assert_eq!(charged, cents(400));
+        assert_eq!(book.balance_state(id), Some(BalanceState::Low));
+
+        // A category dropped from the session gives credit back.
+        book.settle(id, "s", &mut charged, cents(350), 50, "s")
+            .expect("settle");
+        book.settle(id, "s", &mut charged, cents(600), 60, "s")
+            .expect("settle");
+        assert_eq!(book.account(id).expect("account").balance, cents(-100));
+        assert_eq!(book.balance_state(id), Some(BalanceState::Exhausted));
+
+        let kinds: Vec<_> = book
+            .entries_for(id)
+            .map(|entry| {
+                (
+                    entry.kind,
+                    entry.amount.minor(),
+                    entry.balance_after.minor(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (LedgerKind::TopUp, 500, 500),
+                (LedgerKind::Charge, 150, 350),
+                (LedgerKind::Charge, 250, 100),
+                (LedgerKind::Refund, 50, 150),
+                (LedgerKind::Charge, 250, -100),
+            ]
+        );
+        assert_eq!(book.booked_for("s"), Some(cents(600)));
+
+        // Replaying the last settlement with a `charged` lost in a crash
+        // books nothing.
+        let mut lost = cents(350);
+        assert_eq!(
+            book.settle(id, "s", &mut lost, cents(600), 61, "s")
+                .expect("settle"),
+            None
+        );
+        assert_eq!(lost, cents(600));
+        assert_eq!(book.entries_for(id).count(), 5);
+
+        // Sessions charged before entries were tagged go by `charged`.
+        let mut legacy = cents(100);
+        book.settle(id, "legacy", &mut legacy, cents(150), 62, "")
+            .expect("settle");
+        assert_eq!(book.booked_for("legacy"), Some(cents(50)));
+        assert!(book.top_up(id, cents(-1), 70, "").is_err());
+        assert!(book.charge(AccountId(9), cents(1), 70, "").is_err());
+        book.validate().expect("valid book");
+    }
+
+    #[test]
+    fn rejects_balances_that_disagree_with_the_ledger() {
+        let dir = TempDir::new("accounts");
+        let path = dir.join("accounts.ron");
+
+        let mut book = AccountBook::default();
+        book.set_low_balance(cents(100));
+        let id = book.open_account("Ada", "1001", "").expect("open");
+        book.top_up(id, cents(300), 10, "").expect("top up");
+        book.charge(id, cents(120), 20, "").expect("charge");
+        book.save(&path).expect("save");
+        assert_eq!(AccountBook::load(&path).expect("load"), book);
+
+        let edited =
+            fs::read_to_string(&path)
+                .expect("read")
+                .replacen("balance: 180", "balance: 9180", 1);
+        fs::write(&path, edited).expect("write");
+        let error = AccountBook::load(&path).expect_err("tampered");
+        assert!(error.technical_detail().contains("ledger total 180"));
+    }
+}
@@ -0,0 +1,123 @@
+// Minimal BER encoder for the SNMP messages we build by hand: the USM
+// diagnosis probe and the agent simulator's responses. Decoding goes through
+// `snmp2::AsnReader`.
+
+use crate::snmp::{Oid, SnmpValue};
+
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OBJECT_IDENTIFIER: u8 = 0x06;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_IP_ADDRESS: u8 = 0x40;
+pub const TAG_COUNTER32: u8 = 0x41;
+pub const TAG_UNSIGNED32: u8 = 0x42;
+pub const TAG_TIMETICKS: u8 = 0x43;
+pub const TAG_OPAQUE: u8 = 0x44;
+pub const TAG_COUNTER64: u8 = 0x46;
+pub const TAG_NO_SUCH_OBJECT: u8 = 0x80;
+pub const TAG_NO_SUCH_INSTANCE: u8 = 0x81;
+pub const TAG_END_OF_MIB_VIEW: u8 = 0x82;
+
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+pub fn sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &items.concat())
+}
+
+pub fn integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1 {
+        let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    tlv(TAG_INTEGER, &bytes[start..])
+}
+
+/// Unsigned application types (Counter32, Gauge32, TimeTicks, Counter64).
+pub fn unsigned(tag: u8, value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+    let mut content = Vec::with_capacity(9);
+    if skip == bytes.len() || bytes[skip] & 0x80 != 0 {
+        content.push(0);
+    }
+    content.extend_from_slice(&bytes[skip..]);
+    tlv(tag, &content)
+}
+
+pub fn octet_string(bytes: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, bytes)
+}
+
+pub fn null() -> Vec<u8> {
+    tlv(TAG_NULL, &[])
+}
+
+pub fn oid(arcs: &[u32]) -> Vec<u8> {
+    let mut content = Vec::new();
+    if arcs.len() >= 2 {
+        push_base128(&mut content, arcs[0] * 40 + arcs[1]);
+    }
+    for arc in arcs.iter().skip(2) {
+        push_base128(&mut content, *arc);
+    }
+    tlv(TAG_OBJECT_IDENTIFIER, &content)
+}
+
+pub fn value(value: &SnmpValue) -> Vec<u8> {
+    match value {
+        SnmpValue::Null | SnmpValue::Other(_) => null(),
+        SnmpValue::EndOfMibView => tlv(TAG_END_OF_MIB_VIEW, &[]),
+        SnmpValue::NoSuchObject => tlv(TAG_NO_SUCH_OBJECT, &[]),
+        SnmpValue::NoSuchInstance => tlv(TAG_NO_SUCH_INSTANCE, &[]),
+        SnmpValue::Integer(value) => integer(*value),
+        SnmpValue::Unsigned32(value) => unsigned(TAG_UNSIGNED32, u64::from(*value)),
+        SnmpValue::Counter32(value) => unsigned(TAG_COUNTER32, u64::from(*value)),
+        SnmpValue::Counter64(value) => unsigned(TAG_COUNTER64, *value),
+        SnmpValue::Timeticks(value) => unsigned(TAG_TIMETICKS, u64::from(*value)),
+        SnmpValue::OctetString(bytes) => octet_string(bytes),
+        SnmpValue::Opaque(bytes) => tlv(TAG_OPAQUE, bytes),
+        SnmpValue::ObjectIdentifier(value) => oid(value.as_slice()),
+        SnmpValue::IpAddress(bytes) => tlv(TAG_IP_ADDRESS, bytes),
+    }
+}
+
+pub fn varbind(name: &Oid, data: &SnmpValue) -> Vec<u8> {
+    sequence(&[oid(name.as_slice()), value(data)])
+}
+
+fn push_base128(out: &mut Vec<u8>, mut value: u32) {
+    let mut stack = [0u8; 5];
+    let mut len = 0;
+    loop {
+        stack[len] = (value & 0x7f) as u8;
+        len += 1;
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+    }
+    for index in (0..len).rev() {
+        let continuation = if index == 0 { 0 } else { 0x80 };
+        out.push(stack[index] | continuation);
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::counter_table::{CounterRef, CounterTable};
+use crate::model::RecordingCategory;
+use crate::snmp::{Oid, SnmpVarBind};
+
+/// A numeric OID that went up between the two walks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidIncrease {
+    pub oid: Oid,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl OidIncrease {
+    pub fn delta(&self) -> u64 {
+        self.after - self.before
+    }
+}
+
+/// Every numeric OID present in both walks whose value increased, in OID
+/// order.
+pub fn increases_between(before: &[SnmpVarBind], after: &[SnmpVarBind]) -> Vec<OidIncrease> {
+    let before: HashMap<&Oid, u64> = before
+        .iter()
+        .filter_map(|varbind| Some((&varbind.oid, varbind.value.as_u64()?)))
+        .collect();
+    let mut increases: Vec<OidIncrease> = after
+        .iter()
+        .filter_map(|varbind| {
+            let after = varbind.value.as_u64()?;
+            let before = *before.get(&varbind.oid)?;
+            (after > before).then(|| OidIncrease {
+                oid: varbind.oid.clone(),
+                before,
+                after,
+            })
+        })
+        .collect();
+    increases.sort_by(|left, right| left.oid.cmp(&right.oid));
+    increases.dedup_by(|left, right| left.oid == right.oid);
+    increases
+}
+
+/// An OID proposed for one category.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalibrationCandidate {
+    pub counter: CounterRef,
+    pub oid: Oid,
+    pub delta: u64,
+    /// Other categories the same page count was asked for; the candidate
+    /// may count any of them.
+    pub shared_with: Vec<RecordingCategory>,
+}
+
+/// Candidates per category, best first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CalibrationProposal {
+    pub candidates: BTreeMap<RecordingCategory, Vec<CalibrationCandidate>>,
+    /// OIDs that went up by an amount nobody asked for.
+    pub unmatched: usize,
+}
+
+impl CalibrationProposal {
+    /// Ranks the OIDs that went up by exactly the pages made in each
+    /// category. Categories with no pages get no candidates. Counters of the
+    /// engine table come first and are proposed by name when the printer
+    /// reported one and no earlier row shares it, so the mapping carries over
+    /// to other models; other OIDs follow in OID order.
+    pub fn rank(
+        increases: &[OidIncrease],
+        expected: &BTreeMap<RecordingCategory, u64>,
+        table: &CounterTable,
+    ) -> Self {
+        let mut proposal = Self::default();
+        for increase in increases {
+            let delta = increase.delta();
+            let matching: Vec<RecordingCategory> = expected
+                .iter()
+                .filter(|(_, pages)| **pages == delta)
+                .map(|(category, _)| *category)
+                .collect();
+            if matching.is_empty() {
+                proposal.unmatched += 1;
+                continue;
+            }
+            let named = table
+                .counters()
+                .iter()
+                .find(|counter| counter.value_oid() == increase.oid)
+                .filter(|counter| {
+                    !counter.unnamed
+                        && table
+                            .find(&counter.name)
+                            .is_some_and(|first| first.index == counter.index)
+                });
+            let counter = match named {
+                Some(counter) => CounterRef::Named(counter.name.clone()),
+                None => CounterRef::Oid(increase.oid.clone()),
+            };
+            for category in &matching {
+                proposal
+                    .candidates
+                    .entry(*category)
+                    .or_default()
+                    .push(CalibrationCandidate {
+                        counter: counter.clone(),
+                        oid: increase.oid.clone(),
+                        delta,
+                        shared_with: matching
+                            .iter()
+                            .copied()
+                            .filter(|other| other != category)
+                            .collect(),
+                    });
+            }
+        }
+        for candidates in proposal.candidates.values_mut() {
+            candidates.sort_by_key(|candidate| {
+                (
+                    !matches!(candidate.counter, CounterRef::Named(_)),
+                    candidate.shared_with.len(),
+                )
+            });
+        }
+        proposal
+    }
+
+    /// The best candidate of each category.
+    pub fn mapping(&self) -> BTreeMap<RecordingCategory, Vec<CounterRef>> {
+        self.candidates
+            .iter()
+            .filter_map(|(category, candidates)| {
+                let best = candidates.first()?;
+                Some((*category, vec![best.counter.clone()]))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter_table::RICOH_ENG_COUNTER_ENTRY_OID;
+    use crate::snmp::SnmpValue;
+
+    fn engine(column: u32, index: u32, value: SnmpValue) -> SnmpVarBind {
+        let mut oid = RICOH_ENG_COUNTER_ENTRY_OID.to_vec();
+        oid.extend([column, index]);
+        SnmpVarBind {
+            oid: Oid(oid),
+            value,
+        }
+    }
+
+    fn counter(oid: &str, value: i64) -> SnmpVarBind {
+        SnmpVarBind {
+            oid: oid.parse().unwrap(),
+            value: SnmpValue::Integer(value),
+        }
+    }
+
+    fn walk(copies_bw: i64, prints_color: i64) -> Vec<SnmpVarBind> {
+        vec![
+            engine(
+                5,
+                3,
+                SnmpValue::OctetString(b"Copier: Black & White".to_vec()),
+            ),
+            engine(
+                5,
+                10,
+                SnmpValue::OctetString(b"Printer: Full Color".to_vec()),
+            ),
+            engine(9, 3, SnmpValue::Integer(copies_bw)),
+            engine(9, 10, SnmpValue::Integer(prints_color)),
+            // Printer-MIB life count: every page.
+            counter(
+                "1.3.6.1.2.1.43.10.2.1.4.1.1",
+                1_000 + copies_bw + prints_color,
+            ),
+            // A per-paper-size counter that saw the B/W copies too.
+            counter("1.3.6.1.4.1.367.3.2.1.2.19.4.0", copies_bw),
+            // The uptime and anything else going backwards are ignored.
+            counter("1.3.6.1.4.1.367.3.2.1.2.19.1.0", 9 - copies_bw),
+        ]
+    }
+
+    fn increase(oid: Oid, delta: u64) -> OidIncrease {
+        OidIncrease {
+            oid,
+            before: 1_000,
+            after: 1_000 + delta,
+        }
+    }
+
+    fn value_oid(index: u32) -> Oid {
+        let mut oid = RICOH_ENG_COUNTER_ENTRY_OID.to_vec();
+        oid.extend([9, index]);
+        Oid(oid)
+    }
+
+    #[test]
+    fn proposes_the_oids_that_moved_by_the_pages_made() {
+        let before = walk(100, 50);
+        let after = walk(103, 52);
+        let increases = increases_between(&before, &after);
+        assert_eq!(increases.len(), 4);
+
+        let expected = BTreeMap::from([
+            (RecordingCategory::CopiesBw, 3),
+            (RecordingCategory::CopiesColor, 0),
+            (RecordingCategory::PrintsColor, 2),
+        ]);
+        let table = CounterTable::from_walk(&before);
+        let proposal = CalibrationProposal::rank(&increases, &expected, &table);
+
+        let copies_bw = &proposal.candidates[&RecordingCategory::CopiesBw];
+        assert_eq!(copies_bw.len(), 2);
+        assert_eq!(
+            copies_bw[0].counter,
+            CounterRef::Named("Copier: Black & White".to_string())
+        );
+        assert_eq!(
+            copies_bw[1].counter,
+            CounterRef::Oid("1.3.6.1.4.1.367.3.2.1.2.19.4.0".parse().unwrap())
+        );
+        assert!(!proposal
+            .candidates
+            .contains_key(&RecordingCategory::CopiesColor));
+        // The life count went up by 5, which nobody asked for.
+        assert_eq!(proposal.unmatched, 1);
+        assert_eq!(
+            proposal.mapping()[&RecordingCategory::PrintsColor],
+            vec![CounterRef::Named("Printer: Full Color".to_string())]
+        );
+    }
+
+    #[test]
+    fn equal_deltas_are_proposed_for_every_category_that_asked() {
+        let table = CounterTable::from_walk(&[
+            engine(
+                5,
+                3,
+                SnmpValue::OctetString(b"Copier: Black & White".to_vec()),
+            ),
+            engine(9, 3, SnmpValue::Integer(100)),
+        ]);
+        let paper_size: Oid = "1.3.6.1.4.1.367.3.2.1.2.19.4.0".parse().unwrap();
+        let increases = [increase(value_oid(3), 2), increase(paper_size.clone(), 2)];
+        let expected = BTreeMap::from([
+            (RecordingCategory::CopiesBw, 2),
+            (RecordingCategory::PrintsBw, 2),
+        ]);
+
+        let proposal = CalibrationProposal::rank(&increases, &expected, &table);
+        assert_eq!(proposal.unmatched, 0);
+        for (category, other) in [
+            (RecordingCategory::CopiesBw, RecordingCategory::PrintsBw),
+            (RecordingCategory::PrintsBw, RecordingCategory::CopiesBw),
+        ] {
+            let candidates = &proposal.candidates[&category];
+            let counters: Vec<&CounterRef> = candidates
+                .iter()
+                .map(|candidate| &candidate.counter)
+                .collect();
+            assert_eq!(
+                counters,
+                [
+                    &CounterRef::Named("Copier: Black & White".to_string()),
+                    &CounterRef::Oid(paper_size.clone()),
+                ]
+            );
+            assert!(candidates
+                .iter()
+                .all(|candidate| candidate.shared_with == [other]));
+        }
+        // The operator has to tell them apart; the best guess is the same.
+        let mapping = proposal.mapping();
+        assert_eq!(
+            mapping[&RecordingCategory::CopiesBw],
+            mapping[&RecordingCategory::PrintsBw]
+        );
+    }
+
+    #[test]
+    fn counters_that_do_not_move_propose_nothing() {
+        let before = walk(100, 50);
+        let mut after = before.clone();
+        // A text value and an OID only the second walk has are not counters
+        // that moved either.
+        after.push(counter("1.3.6.1.4.1.367.3.2.1.2.19.9.0", 5));
+        after[0].value = SnmpValue::OctetString(b"Copier: Renamed".to_vec());
+        assert!(increases_between(&before, &after).is_empty());
+
+        let expected = BTreeMap::from([(RecordingCategory::CopiesBw, 3)]);
+        let table = CounterTable::from_walk(&before);
+        let proposal = CalibrationProposal::rank(&[], &expected, &table);
+        assert_eq!(proposal, CalibrationProposal::default());
+        assert!(proposal.mapping().is_empty());
+    }
+
+    #[test]
+    fn repeated_or_missing_names_are_proposed_by_oid() {
+        let table = CounterTable::from_walk(&[
+            engine(5, 1, SnmpValue::OctetString(b"Total".to_vec())),
+            engine(5, 2, SnmpValue::OctetString(b"Total".to_vec())),
+            engine(2, 4, SnmpValue::Integer(201)),
+            engine(9, 1, SnmpValue::Integer(500)),
+            engine(9, 2, SnmpValue::Integer(400)),
+            engine(9, 4, SnmpValue::Integer(30)),
+        ]);
+        let increases = [
+            increase(value_oid(1), 1),
+            increase(value_oid(2), 3),
+            increase(value_oid(4), 5),
+        ];
+        let expected = BTreeMap::from([
+            (RecordingCategory::CopiesColor, 1),
+            (RecordingCategory::CopiesBw, 3),
+            (RecordingCategory::PrintsBw, 5),
+        ]);
+
+        let mapping = CalibrationProposal::rank(&increases, &expected, &table).mapping();
+        // "Total" names row 1, so row 2 can only be mapped by its OID, and
+        // "Type 201" was made up for a row without a name.
+        assert_eq!(
+            mapping[&RecordingCategory::CopiesColor],
+            vec![CounterRef::Named("Total".to_string())]
+        );
+        assert_eq!(
+            mapping[&RecordingCategory::CopiesBw],
+            vec![CounterRef::Oid(value_oid(2))]
+        );
+        assert_eq!(
+            mapping[&RecordingCategory::PrintsBw],
+            vec![CounterRef::Oid(value_oid(4))]
+        );
+    }
+}
@@ -0,0 +1,599 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::snmp::{
+    PduSizeCache, SnmpClient, SnmpConfig, SnmpFuture, SnmpRequest, SnmpResponse, SnmpVarBind,
+    SnmpWalkRequest, WalkProgress,
+};
+use crate::storage;
+use crate::{targets, Error, Oid, SnmpAddress};
+
+/// Which `SnmpClient` call produced an exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SnmpOperation {
+    Get,
+    Walk,
+    BulkWalk,
+}
+
+/// The `SnmpConfig` a capture was recorded with, minus runtime-only state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapturedConfig {
+    pub community: String,
+    pub timeout_ms: u64,
+    pub retries: u32,
+}
+
+impl CapturedConfig {
+    pub fn from_config(config: &SnmpConfig) -> Self {
+        Self {
+            community: config.community.clone(),
+            timeout_ms: u64::try_from(config.timeout.as_millis()).unwrap_or(u64::MAX),
+            retries: config.retries,
+        }
+    }
+
+    pub fn to_config(&self) -> SnmpConfig {
+        SnmpConfig {
+            community: self.community.clone(),
+            timeout: Duration::from_millis(self.timeout_ms),
+            retries: self.retries,
+            pdu_sizes: PduSizeCache::default(),
+        }
+    }
+}
+
+/// SNMP errors in a form that survives a round trip through RON. Errors that
+/// are not SNMP-specific are kept as their technical detail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CapturedError {
+    Auth { details: Option<String> },
+    Timeout { timeout_ms: u64 },
+    Failure { details: String },
+    Unreachable { details: String },
+}
+
+impl CapturedError {
+    pub fn from_error(error: &Error) -> Self {
+        match error {
+            Error::SnmpAuth { details, .. } => CapturedError::Auth {
+                details: details.clone(),
+            },
+            Error::SnmpTimeout { timeout_ms, .. } => CapturedError::Timeout {
+                timeout_ms: *timeout_ms,
+            },
+            Error::SnmpFailure { details, .. } => CapturedError::Failure {
+                details: details.clone(),
+            },
+            Error::SnmpUnreachable { details, .. } => CapturedError::Unreachable {
+                details: details.clone(),
+            },
+            other => CapturedError::Failure {
+                details: other.technical_detail(),
+            },
+        }
+    }
+
+    pub fn to_error(&self, address: &SnmpAddress) -> Error {
+        let address = address.to_string();
+        match self {
+            CapturedError::Auth { details } => Error::SnmpAuth {
+                address,
+                details: details.clone(),
+            },
+            CapturedError::Timeout { timeout_ms } => Error::SnmpTimeout {
+                address,
+                timeout_ms: *timeout_ms,
+            },
+            CapturedError::Failure { details } => Error::SnmpFailure {
+                address,
+                details: details.clone(),
+            },
+            CapturedError::Unreachable { details } => Error::SnmpUnreachable {
+                address,
+                details: details.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CapturedOutcome {
+    Response(Vec<SnmpVarBind>),
+    Error(CapturedError),
+}
+
+/// One request and what came back. Walks record their root OID as the only
+/// entry in `oids`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedExchange {
+    pub operation: SnmpOperation,
+    pub address: SnmpAddress,
+    pub oids: Vec<Oid>,
+    pub latency_ms: u64,
+    pub outcome: CapturedOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnmpCapture {
+    pub config: CapturedConfig,
+    pub exchanges: Vec<CapturedExchange>,
+}
+
+impl SnmpCapture {
+    pub fn new(config: &SnmpConfig) -> Self {
+        Self {
+            config: CapturedConfig::from_config(config),
+            exchanges: Vec::new(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        storage::load_ron(path.as_ref())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        storage::save_ron(path.as_ref(), self)
+    }
+}
+
+/// A recorder with a path writes the capture once this many exchanges are
+/// unsaved, or once `FLUSH_INTERVAL` has passed since the last write.
+const FLUSH_EVERY: usize = 32;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+struct RecorderState {
+    capture: SnmpCapture,
+    unsaved: usize,
+    flushed_at: Instant,
+}
+
+/// Shared sink for `RecordingSnmpClient`s. Clones append to the same capture,
+/// so every client the app builds during a session lands in one file.
+#[derive(Debug, Clone)]
+pub struct SnmpRecorder {
+    state: Arc<Mutex<RecorderState>>,
+    path: Option<PathBuf>,
+}
+
+impl SnmpRecorder {
+    pub fn new(config: &SnmpConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RecorderState {
+                capture: SnmpCapture::new(config),
+                unsaved: 0,
+                flushed_at: Instant::now(),
+            })),
+            path: None,
+        }
+    }
+
+    /// Writes the capture to `path` in batches while recording, so a crash
+    /// loses at most the last batch; `flush` writes the rest.
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn state(&self) -> MutexGuard<'_, RecorderState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    pub fn capture(&self) -> SnmpCapture {
+        self.state().capture.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.state().capture.exchanges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn wrap(&self, inner: Arc<dyn SnmpClient>) -> Arc<dyn SnmpClient> {
+        Arc::new(RecordingSnmpClient::new(inner, self.clone()))
+    }
+
+    /// Writes everything recorded so far to the recorder's path, if any.
+    pub fn flush(&self) -> Result<(), Error> {
+        let mut state = self.state();
+        self.write(&mut state)
+    }
+
+    fn write(&self, state: &mut RecorderState) -> Result<(), Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        state.capture.save(path)?;
+        state.unsaved = 0;
+        state.flushed_at = Instant::now();
+        Ok(())
+    }
+
+    fn record(&self, exchange: CapturedExchange) {
+        let mut state = self.state();
+        state.capture.exchanges.push(exchange);
+        state.unsaved += 1;
+        if state.unsaved < FLUSH_EVERY && state.flushed_at.elapsed() < FLUSH_INTERVAL {
+            return;
+        }
+        if let Err(error) = self.write(&mut state) {
+            warn!(
+                target: targets::SNMP,
+                error = %error.technical_detail(),
+                "SNMP capture write failed"
+            );
+        }
+    }
+}
+
+/// Forwards to another client and records every exchange into an `SnmpRecorder`.
+pub struct RecordingSnmpClient {
+    inner: Arc<dyn SnmpClient>,
+    recorder: SnmpRecorder,
+}
+
+impl RecordingSnmpClient {
+    pub fn new(inner: Arc<dyn SnmpClient>, recorder: SnmpRecorder) -> Self {
+        Self { inner, recorder }
+    }
+
+    pub fn recorder(&self) -> &SnmpRecorder {
+        &self.recorder
+    }
+
+    fn recorded<'a>(
+        &'a self,
+        operation: SnmpOperation,
+        address: SnmpAddress,
+        oids: Vec<Oid>,
+        call: SnmpFuture<'a>,
+    ) -> SnmpFuture<'a> {
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = call.await;
+            let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+            let outcome = match &result {
+                Ok(response) => CapturedOutcome::Response(response.varbinds.clone()),
+                Err(error) => CapturedOutcome::Error(CapturedError::from_error(error)),
+            };
+            self.recorder.record(CapturedExchange {
+                operation,
+                address,
+                oids,
+                latency_ms,
+                outcome,
+            });
+            result
+        })
+    }
+}
+
+impl SnmpClient for RecordingSnmpClient {
+    fn get<'a>(&'a self, request: SnmpRequest) -> SnmpFuture<'a> {
+        let address = request.address.clone();
+        let oids = request.oids.clone();
+        self.recorded(SnmpOperation::Get, address, oids, self.inner.get(request))
+    }
+
+    fn walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        let address = request.address.clone();
+        let oids = vec![request.root_oid.clone()];
+        self.recorded(SnmpOperation::Walk, address, oids, self.inner.walk(request))
+    }
+
+    fn bulk_walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        let address = request.address.clone();
+        let oids = vec![request.root_oid.clone()];
+        self.recorded(
+            SnmpOperation::BulkWalk,
+            address,
+            oids,
+            self.inner.bulk_walk(request),
+        )
+    }
+}
+
+type ReplayKey = (SnmpOperation, SnmpAddress, Vec<Oid>);
+
+#[derive(Debug)]
+struct ReplayScript {
+    outcomes: Vec<(u64, CapturedOutcome)>,
+    next: usize,
+}
+
+/// Serves a capture back by operation, address and OIDs. Repeated requests get
+/// the recorded answers in order; once those run out the last one repeats, so
+/// a replayed UI keeps polling the final state.
+#[derive(Debug)]
+pub struct ReplaySnmpClient {
+    config: CapturedConfig,
+    scripts: Mutex<HashMap<ReplayKey, ReplayScript>>,
+    exchanges: usize,
+    recorded_latency: bool,
+}
+
+impl ReplaySnmpClient {
+    pub fn new(capture: SnmpCapture) -> Self {
+        let exchanges = capture.exchanges.len();
+        let mut scripts: HashMap<ReplayKey, ReplayScript> = HashMap::new();
+        for exchange in capture.exchanges {
+            let key = (exchange.operation, exchange.address, exchange.oids);
+            scripts
+                .entry(key)
+                .or_insert_with(|| ReplayScript {
+                    outcomes: Vec::new(),
+                    next: 0,
+                })
+                .outcomes
+                .push((exchange.latency_ms, exchange.outcome));
+        }
+        Self {
+            config: capture.config,
+            scripts: Mutex::new(scripts),
+            exchanges,
+            recorded_latency: false,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        SnmpCapture::load(path).map(Self::new)
+    }
+
+    /// Sleeps for each exchange's recorded latency before answering.
+    pub fn with_recorded_latency(mut self) -> Self {
+        self.recorded_latency = true;
+        self
+    }
+
+    pub fn config(&self) -> &CapturedConfig {
+        &self.config
+    }
+
+    pub fn exchanges(&self) -> usize {
+        self.exchanges
+    }
+
+    fn next_outcome(&self, key: &ReplayKey) -> Option<(u64, CapturedOutcome)> {
+        let mut scripts = self.scripts.lock().ok()?;
+        let script = scripts.get_mut(key)?;
+        let index = script.next.min(script.outcomes.len().checked_sub(1)?);
+        script.next = script.next.saturating_add(1);
+        script.outcomes.get(index).cloned()
+    }
+
+    fn replay<'a>(
+        &'a self,
+        operation: SnmpOperation,
+        address: SnmpAddress,
+        oids: Vec<Oid>,
+        walk: Option<(usize, Option<WalkProgress>)>,
+    ) -> SnmpFuture<'a> {
+        Box::pin(async move {
+            let key = (operation, address, oids);
+            let Some((latency_ms, outcome)) = self.next_outcome(&key) else {
+                let (operation, address, oids) = key;
+                let oids: Vec<String> = oids.iter().map(|oid| oid.to_string()).collect();
+                return Err(Error::SnmpFailure {
+                    address: address.to_string(),
+                    details: format!(
+                        "No captured {operation:?} exchange for {}",
+                        oids.join(", ")
+                    ),
+                });
+            };
+            let (_, address, _) = key;
+            debug!(
+                target: targets::SNMP,
+                address = %address,
+                operation = ?operation,
+                latency_ms,
+                "SNMP replay"
+            );
+            if self.recorded_latency {
+                tokio::time::sleep(Duration::from_millis(latency_ms)).await;
+            }
+            match outcome {
+                CapturedOutcome::Response(mut varbinds) => {
+                    if let Some((max_results, progress)) = walk {
+                        if max_results > 0 {
+                            varbinds.truncate(max_results);
+                        }
+                        if let Some(progress) = progress {
+                            progress.add(varbinds.len());
+                        }
+                    }
+                    Ok(SnmpResponse { address, varbinds })
+                }
+                CapturedOutcome::Error(error) => Err(error.to_error(&address)),
+            }
+        })
+    }
+
+    fn replay_walk<'a>(
+        &'a self,
+        operation: SnmpOperation,
+        request: SnmpWalkRequest,
+    ) -> SnmpFuture<'a> {
+        let SnmpWalkRequest {
+            address,
+            root_oid,
+            max_results,
+            progress,
+            ..
+        } = request;
+        self.replay(
+            operation,
+            address,
+            vec![root_oid],
+            Some((max_results, progress)),
+        )
+    }
+}
+
+impl SnmpClient for ReplaySnmpClient {
+    fn get<'a>(&'a self, request: SnmpRequest) -> SnmpFuture<'a> {
+        self.replay(SnmpOperation::Get, request.address, request.oids, None)
+    }
+
+    fn walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        self.replay_walk(SnmpOperation::Walk, request)
+    }
+
+    fn bulk_walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        self.replay_walk(SnmpOperation::BulkWalk, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::TempDir;
+    use crate::{MockSnmpClient, SnmpValue};
+
+    fn run_future<T>(future: impl std::future::Future<Output = T>) -> T {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("tokio runtime");
+        runtime.block_on(future)
+    }
+
+    fn varbind(oid: &str, value: SnmpValue) -> SnmpVarBind {
+        SnmpVarBind {
+            oid: oid.parse().expect("oid"),
+            value,
+        }
+    }
+
+    #[test]
+    fn recorded_exchanges_replay_in_order() {
+        let address = SnmpAddress::with_default_port("192.168.1.10");
+        let oid: Oid = "1.3.6.1.2.1.43.10.2.1.4.1.1".parse().expect("oid");
+        let mock = MockSnmpClient::new();
+        for total in [100, 105] {
+            mock.push_response(SnmpResponse {
+                address: address.clone(),
+                varbinds: vec![varbind(
+                    "1.3.6.1.2.1.43.10.2.1.4.1.1",
+                    SnmpValue::Counter32(total),
+                )],
+            });
+        }
+        mock.push_error(Error::SnmpTimeout {
+            address: address.to_string(),
+            timeout_ms: 2000,
+        });
+
+        let recorder = SnmpRecorder::new(&SnmpConfig::default());
+        let client = recorder.wrap(Arc::new(mock));
+        let request = SnmpRequest::new(address.clone(), vec![oid.clone()]);
+        run_future(async {
+            for _ in 0..3 {
+                let _ = client.get(request.clone()).await;
+            }
+        });
+        assert_eq!(recorder.len(), 3);
+
+        let capture = recorder.capture();
+        let ron = ron::ser::to_string(&capture).expect("serialize capture");
+        let decoded: SnmpCapture = ron::from_str(&ron).expect("deserialize capture");
+        assert_eq!(decoded, capture);
+        assert_eq!(decoded.config.community, "public");
+
+        let replay = ReplaySnmpClient::new(decoded);
+        let totals: Vec<Result<Option<u64>, String>> = run_future(async {
+            let mut totals = Vec::new();
+            for _ in 0..4 {
+                totals.push(
+                    replay
+                        .get(request.clone())
+                        .await
+                        .map(|response| response.varbinds[0].value.as_u64())
+                        .map_err(|error| error.technical_detail()),
+                );
+            }
+            totals
+        });
+        assert_eq!(totals[0], Ok(Some(100)));
+        assert_eq!(totals[1], Ok(Some(105)));
+        assert!(totals[2].as_ref().is_err_and(|detail| detail.contains("2000ms")));
+        assert_eq!(totals[3], totals[2]);
+    }
+
+    #[test]
+    fn writes_the_capture_in_batches_and_on_flush() {
+        let dir = TempDir::new("capture");
+        let path = dir.join("capture.ron");
+        let address = SnmpAddress::with_default_port("192.168.1.10");
+        let request = SnmpRequest::new(address, vec!["1.3.6.1.2.1.1.1.0".parse().expect("oid")]);
+        let recorder = SnmpRecorder::new(&SnmpConfig::default()).with_path(&path);
+        // The empty mock answers every request with an error, which is
+        // recorded like any other outcome.
+        let client = recorder.wrap(Arc::new(MockSnmpClient::new()));
+        let saved = || SnmpCapture::load(&path).map(|capture| capture.exchanges.len());
+
+        run_future(async {
+            for _ in 0..FLUSH_EVERY - 1 {
+                let _ = client.get(request.clone()).await;
+            }
+        });
+        assert!(saved().is_err());
+        run_future(client.get(request.clone())).expect_err("empty mock");
+        assert_eq!(saved().expect("first batch"), FLUSH_EVERY);
+
+        run_future(client.get(request)).expect_err("empty mock");
+        assert_eq!(saved().expect("still the first batch"), FLUSH_EVERY);
+        recorder.flush().expect("flush");
+        assert_eq!(saved().expect("flushed"), FLUSH_EVERY + 1);
+    }
+
+    #[test]
+    fn replay_matches_walks_by_root_and_rejects_unknown_requests() {
+        let address = SnmpAddress::with_default_port("192.168.1.10");
+        let root: Oid = "1.3.6.1.2.1.43.11".parse().expect("oid");
+        let capture = SnmpCapture {
+            config: CapturedConfig::from_config(&SnmpConfig::default()),
+            exchanges: vec![CapturedExchange {
+                operation: SnmpOperation::BulkWalk,
+                address: address.clone(),
+                oids: vec![root.clone()],
+                latency_ms: 12,
+                outcome: CapturedOutcome::Response(vec![
+                    varbind("1.3.6.1.2.1.43.11.1.1.9.1.1", SnmpValue::Integer(80)),
+                    varbind("1.3.6.1.2.1.43.11.1.1.9.1.2", SnmpValue::Integer(40)),
+                ]),
+            }],
+        };
+        let replay = ReplaySnmpClient::new(capture);
+        let progress = WalkProgress::new();
+
+        let walked = run_future(replay.bulk_walk(
+            SnmpWalkRequest::new(address.clone(), root.clone())
+                .with_max_results(1)
+                .with_progress(progress.clone()),
+        ))
+        .expect("replayed walk");
+        assert_eq!(walked.varbinds.len(), 1);
+        assert_eq!(progress.collected(), 1);
+
+        let other_address = SnmpAddress::with_default_port("192.168.1.11");
+        let unknown = SnmpWalkRequest::new(other_address, root.clone());
+        assert!(run_future(replay.bulk_walk(unknown)).is_err());
+        assert!(run_future(replay.walk(SnmpWalkRequest::new(address, root))).is_err());
+    }
+}
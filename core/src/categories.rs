@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::RecordingCategory;
+use crate::storage;
+use crate::Error;
+
+/// The categories a shop records, shows and prices. Categories left out are
+/// neither read from the printers nor listed, but sessions that recorded
+/// them keep their pages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryList {
+    enabled: Vec<RecordingCategory>,
+}
+
+impl Default for CategoryList {
+    fn default() -> Self {
+        Self {
+            enabled: RecordingCategory::DEFAULT.to_vec(),
+        }
+    }
+}
+
+impl CategoryList {
+    /// Enabled categories in `RecordingCategory::ALL` order.
+    pub fn enabled(&self) -> &[RecordingCategory] {
+        &self.enabled
+    }
+
+    pub fn is_enabled(&self, category: RecordingCategory) -> bool {
+        self.enabled.contains(&category)
+    }
+
+    pub fn set_enabled(&mut self, category: RecordingCategory, enabled: bool) {
+        self.enabled.retain(|existing| *existing != category);
+        if enabled {
+            self.enabled.push(category);
+        }
+        self.normalize();
+    }
+
+    fn normalize(&mut self) {
+        self.enabled.sort();
+        self.enabled.dedup();
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut list: Self = storage::load_ron(path.as_ref())?;
+        list.normalize();
+        Ok(list)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        storage::save_ron(path.as_ref(), self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::storage::tests::TempDir;
+
+    #[test]
+    fn keeps_categories_in_display_order_and_round_trips() {
+        let mut list = CategoryList::default();
+        assert_eq!(list.enabled(), RecordingCategory::DEFAULT);
+
+        list.set_enabled(RecordingCategory::Large, true);
+        list.set_enabled(RecordingCategory::Scans, true);
+        list.set_enabled(RecordingCategory::Scans, true);
+        list.set_enabled(RecordingCategory::PrintsBw, false);
+        assert_eq!(
+            list.enabled(),
+            [
+                RecordingCategory::CopiesBw,
+                RecordingCategory::CopiesColor,
+                RecordingCategory::PrintsColor,
+                RecordingCategory::Scans,
+                RecordingCategory::Large,
+            ]
+        );
+
+        let dir = TempDir::new("categories");
+        let path = dir.join("categories.ron");
+        list.save(&path).expect("save");
+        assert_eq!(CategoryList::load(&path).expect("load"), list);
+
+        // A hand-edited file may list categories out of order or twice.
+        fs::write(&path, "(enabled: [Duplex, CopiesBw, Duplex])").expect("write");
+        assert_eq!(
+            CategoryList::load(&path).expect("load").enabled(),
+            [RecordingCategory::CopiesBw, RecordingCategory::Duplex]
+        );
+    }
+
+    #[test]
+    fn only_the_defaults_are_base_and_only_overlapping_ones_surcharges() {
+        let base: Vec<_> = RecordingCategory::ALL
+            .into_iter()
+            .filter(|category| category.is_base())
+            .collect();
+        assert_eq!(base, RecordingCategory::DEFAULT);
+        let surcharges: Vec<_> = RecordingCategory::ALL
+            .into_iter()
+            .filter(|category| category.is_surcharge())
+            .collect();
+        assert_eq!(
+            surcharges,
+            [
+                RecordingCategory::Large,
+                RecordingCategory::TwoColor,
+                RecordingCategory::SingleColor,
+                RecordingCategory::Duplex,
+            ]
+        );
+        // Scans and faxes count no printed pages, so they are priced alone.
+        for category in [RecordingCategory::Scans, RecordingCategory::Faxes] {
+            assert!(!category.is_base() && !category.is_surcharge());
+        }
+    }
+}
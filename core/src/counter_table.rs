@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::snmp::{Oid, SnmpVarBind};
+
+/// ricohEngCounterEntry: one row per engine counter, indexed by row number.
+/// Row numbers and counter types differ between models, the names do not.
+pub const RICOH_ENG_COUNTER_ENTRY_OID: [u32; 14] = [1, 3, 6, 1, 4, 1, 367, 3, 2, 1, 2, 19, 5, 1];
+
+const ENG_COUNTER_TYPE: u32 = 2;
+const ENG_COUNTER_VALUE: u32 = 9;
+/// Firmware puts the counter name in one of these text columns, or leaves
+/// both out; the first non-empty one wins.
+const ENG_COUNTER_NAME_COLUMNS: [u32; 2] = [5, 6];
+
+/// One row of a printer's counter table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedCounter {
+    pub index: u32,
+    /// As reported by the printer, e.g. "Copier: Black & White"; "Type 201"
+    /// when the printer reports no name.
+    pub name: String,
+    pub type_code: Option<u64>,
+    /// Value when the table was read.
+    #[serde(default)]
+    pub value: Option<u64>,
+    /// The printer reported no name, so `name` is made up from the type code
+    /// or row and means nothing on other models.
+    #[serde(default)]
+    pub unnamed: bool,
+}
+
+impl NamedCounter {
+    /// OID polled for this counter's value.
+    pub fn value_oid(&self) -> Oid {
+        let mut oid = RICOH_ENG_COUNTER_ENTRY_OID.to_vec();
+        oid.extend([ENG_COUNTER_VALUE, self.index]);
+        Oid(oid)
+    }
+}
+
+/// The named counters of one printer, in row order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterTable {
+    counters: Vec<NamedCounter>,
+}
+
+impl CounterTable {
+    /// Builds the table from a walk of `RICOH_ENG_COUNTER_ENTRY_OID`. Rows
+    /// without a value are left out.
+    pub fn from_walk(varbinds: &[SnmpVarBind]) -> Self {
+        struct Row {
+            type_code: Option<u64>,
+            names: [Option<String>; 2],
+            value: Option<u64>,
+        }
+
+        let mut rows: BTreeMap<u32, Row> = BTreeMap::new();
+        for varbind in varbinds {
+            let Some(&[column, index]) = varbind
+                .oid
+                .as_slice()
+                .strip_prefix(&RICOH_ENG_COUNTER_ENTRY_OID)
+            else {
+                continue;
+            };
+            if varbind.value.is_missing() {
+                continue;
+            }
+            let row = rows.entry(index).or_insert(Row {
+                type_code: None,
+                names: [None, None],
+                value: None,
+            });
+            match column {
+                ENG_COUNTER_TYPE => row.type_code = varbind.value.as_u64(),
+                ENG_COUNTER_VALUE => row.value = varbind.value.as_u64(),
+                _ => {
+                    if let Some(slot) = ENG_COUNTER_NAME_COLUMNS
+                        .iter()
+                        .position(|name_column| *name_column == column)
+                    {
+                        row.names[slot] = varbind
+                            .value
+                            .as_text_lossy()
+                            .map(|name| name.trim_matches(char::from(0)).trim().to_string())
+                            .filter(|name| !name.is_empty());
+                    }
+                }
+            }
+        }
+
+        let counters = rows
+            .into_iter()
+            .filter(|(_, row)| row.value.is_some())
+            .map(|(index, row)| {
+                let [first, second] = row.names;
+                let reported = first.or(second);
+                let unnamed = reported.is_none();
+                let name = reported.unwrap_or_else(|| match row.type_code {
+                    Some(code) => format!("Type {code}"),
+                    None => format!("Row {index}"),
+                });
+                NamedCounter {
+                    index,
+                    name,
+                    type_code: row.type_code,
+                    value: row.value,
+                    unnamed,
+                }
+            })
+            .collect();
+        Self { counters }
+    }
+
+    pub fn counters(&self) -> &[NamedCounter] {
+        &self.counters
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    /// The counter with this name, ignoring case and surrounding spaces.
+    /// When a name repeats (Ricoh lists "Total" twice) the first row wins.
+    pub fn find(&self, name: &str) -> Option<&NamedCounter> {
+        let name = name.trim();
+        self.counters
+            .iter()
+            .find(|counter| counter.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A counter in a mapping: a fixed OID, or a name looked up in each
+/// printer's counter table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CounterRef {
+    Oid(Oid),
+    Named(String),
+}
+
+impl CounterRef {
+    /// The OID to poll on a printer with this table; `None` for a name the
+    /// table does not have.
+    pub fn resolve(&self, table: &CounterTable) -> Option<Oid> {
+        match self {
+            CounterRef::Oid(oid) => Some(oid.clone()),
+            CounterRef::Named(name) => table.find(name).map(NamedCounter::value_oid),
+        }
+    }
+}
+
+/// OIDs print as dotted strings and names in double quotes, the form the
+/// mapping inputs accept.
+impl fmt::Display for CounterRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CounterRef::Oid(oid) => write!(f, "{oid}"),
+            CounterRef::Named(name) => write!(f, "\"{name}\""),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snmp::SnmpValue;
+
+    fn varbind(column: u32, index: u32, value: SnmpValue) -> SnmpVarBind {
+        let mut oid = RICOH_ENG_COUNTER_ENTRY_OID.to_vec();
+        oid.extend([column, index]);
+        SnmpVarBind {
+            oid: Oid(oid),
+            value,
+        }
+    }
+
+    #[test]
+    fn names_rows_and_resolves_them_by_name() {
+        let varbinds = vec![
+            varbind(2, 3, SnmpValue::Integer(201)),
+            varbind(2, 9, SnmpValue::Integer(401)),
+            varbind(2, 12, SnmpValue::Integer(10)),
+            varbind(
+                5,
+                3,
+                SnmpValue::OctetString(b"Copier: Black & White".to_vec()),
+            ),
+            varbind(5, 9, SnmpValue::OctetString(Vec::new())),
+            varbind(
+                6,
+                9,
+                SnmpValue::OctetString(b"Printer: Black & White\0".to_vec()),
+            ),
+            varbind(9, 3, SnmpValue::Integer(28_378)),
+            varbind(9, 9, SnmpValue::Integer(455_687)),
+            varbind(9, 12, SnmpValue::Integer(901_113)),
+            // A row without a value is not a counter.
+            varbind(5, 20, SnmpValue::OctetString(b"Scanner".to_vec())),
+        ];
+
+        let table = CounterTable::from_walk(&varbinds);
+        let names: Vec<&str> = table
+            .counters()
+            .iter()
+            .map(|counter| counter.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["Copier: Black & White", "Printer: Black & White", "Type 10"]
+        );
+        assert_eq!(table.counters()[1].value, Some(455_687));
+
+        let named = CounterRef::Named(" printer: black & white ".to_string());
+        assert_eq!(
+            named.resolve(&table),
+            Some("1.3.6.1.4.1.367.3.2.1.2.19.5.1.9.9".parse().unwrap())
+        );
+        assert_eq!(CounterRef::Named("Fax".to_string()).resolve(&table), None);
+        assert_eq!(CounterRef::Named("Fax".to_string()).to_string(), "\"Fax\"");
+    }
+
+    #[test]
+    fn made_up_names_and_repeated_names() {
+        let varbinds = vec![
+            varbind(5, 1, SnmpValue::OctetString(b"Total".to_vec())),
+            varbind(5, 2, SnmpValue::OctetString(b" total ".to_vec())),
+            varbind(2, 4, SnmpValue::Integer(201)),
+            varbind(9, 1, SnmpValue::Integer(500)),
+            varbind(9, 2, SnmpValue::Integer(400)),
+            varbind(9, 4, SnmpValue::Integer(30)),
+            varbind(9, 7, SnmpValue::Integer(7)),
+        ];
+
+        let table = CounterTable::from_walk(&varbinds);
+        let rows: Vec<(&str, bool)> = table
+            .counters()
+            .iter()
+            .map(|counter| (counter.name.as_str(), counter.unnamed))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("Total", false),
+                ("total", false),
+                ("Type 201", true),
+                ("Row 7", true),
+            ]
+        );
+        // The first of two rows with the same name wins.
+        assert_eq!(table.find("TOTAL").map(|counter| counter.index), Some(1));
+        assert_eq!(table.find("Type 201").map(|counter| counter.index), Some(4));
+        assert!(CounterTable::from_walk(&[]).is_empty());
+    }
+}
@@ -3,9 +3,9 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use crate::model::{CounterOids, CounterSnapshot, EpochSeconds};
-use crate::snmp::{Oid, SnmpVarBind};
+use crate::snmp::{Oid, SnmpValue, SnmpVarBind, SYS_UPTIME_OID};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CounterKind {
     Bw,
     Color,
@@ -22,7 +22,7 @@ impl fmt::Display for CounterKind {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CounterMode {
     BwColor,
     TotalOnly,
@@ -30,7 +30,7 @@ pub enum CounterMode {
     Missing,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CounterWarning {
     Missing { kind: CounterKind },
     UsedTotalFallback,
@@ -57,7 +57,7 @@ impl fmt::Display for CounterWarning {
     }
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CounterOidSet {
     pub bw: Vec<Oid>,
     pub color: Vec<Oid>,
@@ -85,6 +85,14 @@ pub fn resolve_counters(
     let total = find_counter_value(CounterKind::Total, &oids.total, varbinds, &mut warnings);
 
     let mut snapshot = CounterSnapshot::new(timestamp);
+    let uptime_oid = Oid::from_slice(&SYS_UPTIME_OID);
+    snapshot.uptime = varbinds
+        .iter()
+        .find(|varbind| varbind.oid == uptime_oid)
+        .and_then(|varbind| match varbind.value {
+            SnmpValue::Timeticks(ticks) => Some(u64::from(ticks)),
+            _ => None,
+        });
 
     snapshot.source_oids = CounterOids {
         bw: bw.oid.as_ref().map(|oid| oid.to_string()),
@@ -92,13 +100,13 @@ pub fn resolve_counters(
         total: total.oid.as_ref().map(|oid| oid.to_string()),
     };
 
-    let mode = if bw.value.is_some() && color.value.is_some() {
+    let mode = if let (Some(bw_value), Some(color_value)) = (bw.value, color.value) {
         snapshot.bw = bw.value;
         snapshot.color = color.value;
         if let Some(total_value) = total.value {
             snapshot.total = Some(total_value);
         } else {
-            snapshot.total = Some(bw.value.unwrap() + color.value.unwrap());
+            snapshot.total = Some(bw_value + color_value);
             warnings.push(CounterWarning::DerivedTotal);
             snapshot.source_oids.total = None;
         }
@@ -182,7 +190,6 @@ fn find_counter_value(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::snmp::SnmpValue;
 
     fn oid(value: &str) -> Oid {
         value.parse().expect("oid")
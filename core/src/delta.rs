@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+
+use crate::counters::CounterKind;
+use crate::model::{CounterSnapshot, PrinterId};
+use crate::Error;
+
+/// Counter32 values restart from zero at this point.
+pub const COUNTER32_MODULUS: u64 = 1 << 32;
+
+/// A wrap is only believed when it accounts for at most half the Counter32
+/// range; a bigger jump back is far more likely a reset.
+const MAX_WRAP_DELTA: u64 = COUNTER32_MODULUS / 2;
+
+/// One counter value together with the sysUpTime polled alongside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterReading {
+    pub value: u64,
+    /// Hundredths of a second since the agent restarted.
+    pub uptime: Option<u64>,
+}
+
+impl CounterReading {
+    pub fn new(value: u64, uptime: Option<u64>) -> Self {
+        Self { value, uptime }
+    }
+
+    pub fn from_snapshot(snapshot: &CounterSnapshot, kind: CounterKind) -> Option<Self> {
+        let value = match kind {
+            CounterKind::Bw => snapshot.bw,
+            CounterKind::Color => snapshot.color,
+            CounterKind::Total => snapshot.total,
+        }?;
+        Some(Self::new(value, snapshot.uptime))
+    }
+}
+
+/// A counter that went backwards without a plausible Counter32 wrap, e.g.
+/// after a controller board swap or a service reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterReset {
+    pub previous: u64,
+    pub current: u64,
+    /// sysUpTime went down between the two readings.
+    pub rebooted: bool,
+}
+
+impl CounterReset {
+    /// Pages billed across the gap under `resolution`.
+    pub fn bridge(&self, resolution: ResetResolution) -> u64 {
+        match resolution {
+            ResetResolution::CountFromZero => self.current,
+            ResetResolution::Rebaseline => 0,
+        }
+    }
+
+    pub fn error(&self, printer_id: &PrinterId, counter: impl Into<String>) -> Error {
+        Error::CounterReset {
+            printer_id: printer_id.0.clone(),
+            counter: counter.into(),
+            previous: self.previous,
+            current: self.current,
+            rebooted: self.rebooted,
+        }
+    }
+}
+
+/// How an operator chose to bridge a reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResetResolution {
+    /// The counter restarted at zero, so everything it shows now was printed
+    /// after the reset.
+    CountFromZero,
+    /// Nothing is billed across the gap; the new value is the new baseline.
+    Rebaseline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CounterDelta {
+    Increase(u64),
+    /// The counter passed 2^32 and restarted; the value is the pages in
+    /// between.
+    Wrapped(u64),
+    Reset(CounterReset),
+}
+
+impl CounterDelta {
+    /// Classifies the step from `previous` to `current`. A drop is a wrap
+    /// only when the earlier value fits in 32 bits, sysUpTime did not go
+    /// down, and the wrapped distance is under half the range.
+    pub fn between(previous: CounterReading, current: CounterReading) -> Self {
+        if current.value >= previous.value {
+            return CounterDelta::Increase(current.value - previous.value);
+        }
+
+        let rebooted = matches!(
+            (previous.uptime, current.uptime),
+            (Some(before), Some(after)) if after < before
+        );
+        if !rebooted && previous.value < COUNTER32_MODULUS {
+            let wrapped = COUNTER32_MODULUS - previous.value + current.value;
+            if wrapped <= MAX_WRAP_DELTA {
+                return CounterDelta::Wrapped(wrapped);
+            }
+        }
+
+        CounterDelta::Reset(CounterReset {
+            previous: previous.value,
+            current: current.value,
+            rebooted,
+        })
+    }
+
+    /// Pages counted, or `None` for a reset that still needs a resolution.
+    pub fn pages(&self, resolution: Option<ResetResolution>) -> Option<u64> {
+        match self {
+            CounterDelta::Increase(pages) | CounterDelta::Wrapped(pages) => Some(*pages),
+            CounterDelta::Reset(reset) => resolution.map(|resolution| reset.bridge(resolution)),
+        }
+    }
+}
+
+/// Running page count for one counter across successive polls, so a reset
+/// between two polls is caught even when the counter later climbs past its
+/// starting value again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterTracker {
+    start: CounterReading,
+    last: CounterReading,
+    counted: u64,
+    resets: Vec<CounterReset>,
+}
+
+impl CounterTracker {
+    pub fn new(start: CounterReading) -> Self {
+        Self {
+            start,
+            last: start,
+            counted: 0,
+            resets: Vec::new(),
+        }
+    }
+
+    pub fn start(&self) -> CounterReading {
+        self.start
+    }
+
+    pub fn last(&self) -> CounterReading {
+        self.last
+    }
+
+    pub fn resets(&self) -> &[CounterReset] {
+        &self.resets
+    }
+
+    pub fn observe(&mut self, reading: CounterReading) -> CounterDelta {
+        let delta = CounterDelta::between(self.last, reading);
+        match delta {
+            CounterDelta::Increase(pages) | CounterDelta::Wrapped(pages) => {
+                self.counted = self.counted.saturating_add(pages);
+            }
+            CounterDelta::Reset(reset) => self.resets.push(reset),
+        }
+        self.last = reading;
+        delta
+    }
+
+    /// Pages since `start`, with every reset bridged by `resolution`.
+    /// `None` while a reset is waiting for one.
+    pub fn pages(&self, resolution: Option<ResetResolution>) -> Option<u64> {
+        if self.resets.is_empty() {
+            return Some(self.counted);
+        }
+        let resolution = resolution?;
+        Some(self.resets.iter().fold(self.counted, |total, reset| {
+            total.saturating_add(reset.bridge(resolution))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(value: u64, uptime: u64) -> CounterReading {
+        CounterReading::new(value, Some(uptime))
+    }
+
+    #[test]
+    fn tells_wraps_from_resets() {
+        let near_max = COUNTER32_MODULUS - 100;
+        assert_eq!(
+            CounterDelta::between(reading(100, 1_000), reading(150, 2_000)),
+            CounterDelta::Increase(50)
+        );
+        assert_eq!(
+            CounterDelta::between(reading(near_max, 1_000), reading(20, 2_000)),
+            CounterDelta::Wrapped(120)
+        );
+        // Same values, but the agent restarted in between.
+        assert_eq!(
+            CounterDelta::between(reading(near_max, 1_000), reading(20, 500)),
+            CounterDelta::Reset(CounterReset {
+                previous: near_max,
+                current: 20,
+                rebooted: true,
+            })
+        );
+        // Too far back to be a wrap, and a 64-bit counter never wraps at 2^32.
+        let drop = CounterDelta::between(reading(120_000, 1_000), reading(30, 2_000));
+        assert!(matches!(
+            drop,
+            CounterDelta::Reset(CounterReset {
+                rebooted: false,
+                ..
+            })
+        ));
+        let wide = CounterDelta::between(
+            CounterReading::new(COUNTER32_MODULUS + 5, None),
+            CounterReading::new(10, None),
+        );
+        assert!(matches!(wide, CounterDelta::Reset(_)));
+    }
+
+    #[test]
+    fn tracker_holds_resets_until_resolved() {
+        let mut tracker = CounterTracker::new(reading(1_000, 10_000));
+        tracker.observe(reading(1_040, 20_000));
+        let delta = tracker.observe(reading(5, 300));
+        tracker.observe(reading(1_200, 9_000));
+
+        let CounterDelta::Reset(reset) = delta else {
+            panic!("expected reset");
+        };
+        assert!(reset.rebooted);
+        assert_eq!(tracker.resets().len(), 1);
+        assert_eq!(tracker.pages(None), None);
+        // 40 before the reset, 1195 after it, plus whatever the gap held.
+        assert_eq!(
+            tracker.pages(Some(ResetResolution::Rebaseline)),
+            Some(1_235)
+        );
+        assert_eq!(
+            tracker.pages(Some(ResetResolution::CountFromZero)),
+            Some(1_240)
+        );
+
+        let error = reset.error(&PrinterId::new("printer-1"), "bw");
+        assert!(error.technical_detail().contains("1040 -> 5"));
+    }
+}
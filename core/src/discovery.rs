@@ -1,12 +1,16 @@
 use std::fmt;
 use std::net::Ipv4Addr;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use get_if_addrs::{get_if_addrs, IfAddr};
 use tracing::{debug, info, warn};
 
-use crate::model::{EpochSeconds, PrinterId, PrinterRecord, PrinterStatus, SnmpAddress};
-use crate::snmp::{Oid, SnmpConfig, SnmpRequest, SnmpV2cClient, SnmpValue, SnmpVarBind};
+use crate::counter_table::CounterTable;
+use crate::model::{
+    EpochSeconds, PollSettings, PrinterId, PrinterRecord, PrinterStatus, SnmpAddress,
+};
+use crate::snmp::{Oid, SnmpClient, SnmpRequest, SnmpValue, SnmpVarBind};
 use crate::{targets, Error};
 
 const SYS_DESCR_OID: [u32; 9] = [1, 3, 6, 1, 2, 1, 1, 1, 0];
@@ -159,10 +163,14 @@ pub fn default_discovery_cidr() -> Option<String> {
     None
 }
 
+/// Probes one address and returns a record when it looks like a printer.
+///
+/// The record carries the community that was used; SNMPv3 credentials live
+/// in the client, so callers attach them to the record themselves.
 pub async fn probe_printer(
+    client: Arc<dyn SnmpClient>,
     address: SnmpAddress,
     community: Option<String>,
-    config: SnmpConfig,
 ) -> Result<Option<PrinterRecord>, Error> {
     let mut request = SnmpRequest::new(
         address.clone(),
@@ -183,14 +191,13 @@ pub async fn probe_printer(
         "Discovery probe"
     );
 
-    let client = SnmpV2cClient::new(config);
     let response = client.get(request).await?;
     let sys_descr = extract_text(&response.varbinds, &Oid::from_slice(&SYS_DESCR_OID));
     let sys_object_id = extract_object_id(&response.varbinds, &Oid::from_slice(&SYS_OBJECT_ID_OID));
 
-    let printer_name = probe_printer_name(&client, &address, community.as_deref()).await;
+    let printer_name = probe_printer_name(client.as_ref(), &address, community.as_deref()).await;
     let marker_present = if printer_name.is_none() {
-        probe_marker_life_count(&client, &address, community.as_deref()).await
+        probe_marker_life_count(client.as_ref(), &address, community.as_deref()).await
     } else {
         false
     };
@@ -224,13 +231,17 @@ pub async fn probe_printer(
         sys_object_id: sys_object_id_text,
         snmp_address: Some(address),
         community,
+        snmp_v3: None,
         status: PrinterStatus::Online,
         last_seen,
+        polling: PollSettings::default(),
+        counter_table: CounterTable::default(),
+        counter_mapping: None,
     }))
 }
 
 async fn probe_printer_name(
-    client: &SnmpV2cClient,
+    client: &dyn SnmpClient,
     address: &SnmpAddress,
     community: Option<&str>,
 ) -> Option<String> {
@@ -259,7 +270,7 @@ async fn probe_printer_name(
 }
 
 async fn probe_marker_life_count(
-    client: &SnmpV2cClient,
+    client: &dyn SnmpClient,
     address: &SnmpAddress,
     community: Option<&str>,
 ) -> bool {
@@ -361,3 +372,85 @@ fn ipv4_to_u32(ip: Ipv4Addr) -> u32 {
 fn u32_to_ipv4(value: u32) -> Ipv4Addr {
     Ipv4Addr::from(value)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snmp::{MockSnmpClient, SnmpResponse};
+
+    fn run_future<T>(future: impl std::future::Future<Output = T>) -> T {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("tokio runtime");
+        runtime.block_on(future)
+    }
+
+    fn response(address: &SnmpAddress, varbinds: Vec<(&[u32], SnmpValue)>) -> SnmpResponse {
+        SnmpResponse {
+            address: address.clone(),
+            varbinds: varbinds
+                .into_iter()
+                .map(|(oid, value)| SnmpVarBind {
+                    oid: Oid::from_slice(oid),
+                    value,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn probe_finds_printer_by_name() {
+        let mock = MockSnmpClient::new();
+        let address = SnmpAddress::with_default_port("192.168.1.20");
+        mock.push_response(response(
+            &address,
+            vec![
+                (&SYS_DESCR_OID, SnmpValue::OctetString(b"RICOH IM C3000".to_vec())),
+                (
+                    &SYS_OBJECT_ID_OID,
+                    SnmpValue::ObjectIdentifier(Oid(vec![1, 3, 6, 1, 4, 1, 367, 1, 1])),
+                ),
+            ],
+        ));
+        mock.push_response(response(
+            &address,
+            vec![(
+                &PRT_GENERAL_PRINTER_NAME_OID,
+                SnmpValue::OctetString(b"Front Office".to_vec()),
+            )],
+        ));
+
+        let record = run_future(probe_printer(
+            Arc::new(mock),
+            address.clone(),
+            Some("public".to_string()),
+        ))
+        .expect("probe")
+        .expect("printer");
+        assert_eq!(record.model.as_deref(), Some("Front Office"));
+        assert_eq!(record.sys_object_id.as_deref(), Some("1.3.6.1.4.1.367.1.1"));
+        assert_eq!(record.snmp_address, Some(address));
+        assert_eq!(record.community.as_deref(), Some("public"));
+    }
+
+    #[test]
+    fn probe_skips_non_printer() {
+        let mock = MockSnmpClient::new();
+        let address = SnmpAddress::with_default_port("192.168.1.21");
+        mock.push_response(response(
+            &address,
+            vec![(&SYS_DESCR_OID, SnmpValue::OctetString(b"Linux router".to_vec()))],
+        ));
+        mock.push_response(response(
+            &address,
+            vec![(&PRT_GENERAL_PRINTER_NAME_OID, SnmpValue::NoSuchObject)],
+        ));
+        mock.push_response(response(
+            &address,
+            vec![(&PRT_MARKER_LIFECOUNT_1_OID, SnmpValue::NoSuchObject)],
+        ));
+
+        let record = run_future(probe_printer(Arc::new(mock), address, None)).expect("probe");
+        assert!(record.is_none());
+    }
+}
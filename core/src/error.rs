@@ -17,6 +17,11 @@ pub enum Error {
         address: String,
         details: String,
     },
+    #[error("SNMP transport failure for {address}")]
+    SnmpUnreachable {
+        address: String,
+        details: String,
+    },
     #[error("Unsupported Ricoh model: {model}")]
     UnsupportedModel {
         model: String,
@@ -30,8 +35,35 @@ pub enum Error {
     #[error("Counter reset detected for {printer_id}")]
     CounterReset {
         printer_id: String,
+        counter: String,
         previous: u64,
         current: u64,
+        rebooted: bool,
+    },
+    #[error("Invalid price list {name}")]
+    InvalidPriceList {
+        name: String,
+        details: String,
+    },
+    #[error("Invalid amount {input}")]
+    InvalidAmount {
+        input: String,
+        details: String,
+    },
+    #[error("Receipt printer {target} failed")]
+    ReceiptPrinter {
+        target: String,
+        details: String,
+    },
+    #[error("Account {account} rejected the change")]
+    Account {
+        account: String,
+        details: String,
+    },
+    #[error("Printer profile {profile} is invalid")]
+    Profile {
+        profile: String,
+        details: String,
     },
     #[error("Discovery failure")]
     DiscoveryFailure {
@@ -81,14 +113,28 @@ impl Error {
             Error::SnmpFailure { address, .. } => {
                 format!("SNMP error for {address}.")
             }
+            Error::SnmpUnreachable { address, .. } => {
+                format!("Printer {address} could not be reached.")
+            }
             Error::UnsupportedModel { model, .. } => {
                 format!("Unsupported Ricoh model: {model}.")
             }
             Error::MissingCounters { printer_id, .. } => {
                 format!("Missing counters for printer {printer_id}.")
             }
-            Error::CounterReset { printer_id, .. } => {
-                format!("Counter reset detected for printer {printer_id}.")
+            Error::CounterReset {
+                printer_id,
+                counter,
+                ..
+            } => format!("Counter reset detected for printer {printer_id} ({counter})."),
+            Error::InvalidPriceList { name, .. } => format!("Price list {name} is not valid."),
+            Error::InvalidAmount { input, .. } => format!("Amount {input:?} is not valid."),
+            Error::ReceiptPrinter { target, .. } => {
+                format!("Could not print to receipt printer {target}.")
+            }
+            Error::Account { account, .. } => format!("Account {account} could not be updated."),
+            Error::Profile { profile, .. } => {
+                format!("Printer profile {profile} could not be loaded.")
             }
             Error::DiscoveryFailure { .. } => "Discovery failed.".to_string(),
             Error::Ron { action, .. } => format!("Failed to {action} configuration data."),
@@ -112,6 +158,9 @@ impl Error {
             Error::SnmpFailure { address, details } => {
                 format!("SNMP failure for {address}: {details}")
             }
+            Error::SnmpUnreachable { address, details } => {
+                format!("SNMP transport failure for {address}: {details}")
+            }
             Error::UnsupportedModel {
                 model,
                 sys_object_id,
@@ -131,11 +180,32 @@ impl Error {
             ),
             Error::CounterReset {
                 printer_id,
+                counter,
                 previous,
                 current,
-            } => format!(
-                "Counter reset for {printer_id}: {previous} -> {current}."
-            ),
+                rebooted,
+            } => {
+                let evidence = if *rebooted {
+                    "sysUpTime dropped"
+                } else {
+                    "no restart seen"
+                };
+                format!(
+                    "Counter reset for {printer_id} {counter}: {previous} -> {current} \
+                     ({evidence})."
+                )
+            }
+            Error::InvalidPriceList { name, details } => {
+                format!("Invalid price list {name}: {details}.")
+            }
+            Error::InvalidAmount { input, details } => {
+                format!("Invalid amount {input:?}: {details}.")
+            }
+            Error::ReceiptPrinter { target, details } => {
+                format!("Receipt printer {target}: {details}.")
+            }
+            Error::Account { account, details } => format!("Account {account}: {details}."),
+            Error::Profile { profile, details } => format!("Profile {profile}: {details}."),
             Error::DiscoveryFailure { range, details } => {
                 let range = range
                     .as_ref()
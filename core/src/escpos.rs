@@ -0,0 +1,368 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::time::timeout;
+
+use crate::receipt::{push_columns, Line, Receipt};
+use crate::Error;
+
+pub const DEFAULT_ESCPOS_PORT: u16 = 9100;
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+const LF: u8 = b'\n';
+/// ESC t 19 selects PC858, which is PC850 with the euro sign.
+const CODE_PAGE_PC858: u8 = 19;
+
+/// PC858 characters 0x80 to 0xFF in order.
+const PC858_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', 'ø', '£', 'Ø', '×', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '®', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', 'Á', 'Â', 'À', '©', '╣', '║', '╗', '╝', '¢', '¥', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', 'ã', 'Ã', '╚', '╔', '╩', '╦', '╠', '═', '╬', '¤', //
+    'ð', 'Ð', 'Ê', 'Ë', 'È', '€', 'Í', 'Î', 'Ï', '┘', '┌', '█', '▄', '¦', 'Ì', '▀', //
+    'Ó', 'ß', 'Ô', 'Ò', 'õ', 'Õ', 'µ', 'þ', 'Þ', 'Ú', 'Û', 'Ù', 'ý', 'Ý', '¯', '´', //
+    '\u{ad}', '±', '‗', '¾', '¶', '§', '÷', '¸', '°', '¨', '·', '¹', '³', '²', '■', '\u{a0}',
+];
+
+/// Paper and cutter of a thermal receipt printer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EscPosOptions {
+    /// Characters per line in font A: 48 on 80 mm paper, 32 on 58 mm.
+    pub columns: usize,
+    /// Lines fed after the receipt so it clears the cutter.
+    pub feed_lines: u8,
+    pub cut: bool,
+}
+
+impl Default for EscPosOptions {
+    fn default() -> Self {
+        Self {
+            columns: 48,
+            feed_lines: 4,
+            cut: true,
+        }
+    }
+}
+
+/// Where ESC/POS bytes go.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EscPosTarget {
+    /// A raw TCP print port as "host" or "host:port"; the port defaults to
+    /// 9100.
+    Tcp(String),
+    /// A character device such as /dev/usb/lp0, written directly.
+    Device(PathBuf),
+}
+
+impl EscPosTarget {
+    /// Paths (anything starting with "/", "." or a Windows device name)
+    /// are devices; everything else is a TCP host. Empty input is `None`.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        let windows_device = ["COM", "LPT"].iter().any(|prefix| {
+            input.len() > prefix.len()
+                && input[..prefix.len()].eq_ignore_ascii_case(prefix)
+                && input[prefix.len()..].chars().all(|ch| ch.is_ascii_digit())
+        });
+        if input.starts_with('/') || input.starts_with('.') || windows_device {
+            Some(EscPosTarget::Device(PathBuf::from(input)))
+        } else {
+            Some(EscPosTarget::Tcp(input.to_string()))
+        }
+    }
+
+    /// Sends `bytes` in one go and waits until they are handed to the
+    /// printer or `limit` passes.
+    pub async fn send(&self, bytes: &[u8], limit: Duration) -> Result<(), Error> {
+        let error = |details: String| Error::ReceiptPrinter {
+            target: self.to_string(),
+            details,
+        };
+        let result = match self {
+            EscPosTarget::Tcp(address) => {
+                let address = if address.contains(':') {
+                    address.clone()
+                } else {
+                    format!("{address}:{DEFAULT_ESCPOS_PORT}")
+                };
+                timeout(limit, async {
+                    let mut stream = tokio::net::TcpStream::connect(&address).await?;
+                    stream.write_all(bytes).await?;
+                    stream.shutdown().await
+                })
+                .await
+            }
+            EscPosTarget::Device(path) => {
+                timeout(limit, async {
+                    let mut device = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+                    device.write_all(bytes).await?;
+                    device.flush().await
+                })
+                .await
+            }
+        };
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(source)) => Err(error(source.to_string())),
+            Err(_) => Err(error(format!("no answer within {}ms", limit.as_millis()))),
+        }
+    }
+}
+
+impl fmt::Display for EscPosTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EscPosTarget::Tcp(address) => f.write_str(address),
+            EscPosTarget::Device(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl Receipt {
+    /// The receipt as an ESC/POS job: shop header centered, title bold,
+    /// total bold at double height, then feed and cut. Text is sent in
+    /// code page PC858; characters it lacks print as "?".
+    pub fn to_escpos(&self, options: &EscPosOptions) -> Vec<u8> {
+        let width = options.columns;
+        let mut out = vec![ESC, b'@', ESC, b't', CODE_PAGE_PC858];
+        let mut centered = false;
+        for line in self.lines() {
+            // ESC a only takes effect at the start of a line.
+            let heading = matches!(line, Line::Heading(_));
+            if heading != centered {
+                out.extend_from_slice(&[ESC, b'a', u8::from(heading)]);
+                centered = heading;
+            }
+            match line {
+                Line::Heading(heading) => {
+                    out.extend(encode_pc858(&heading));
+                    out.push(LF);
+                }
+                Line::Title(title) => {
+                    out.extend_from_slice(&[ESC, b'E', 1]);
+                    out.extend(encode_pc858(&title));
+                    out.extend_from_slice(&[ESC, b'E', 0, LF]);
+                }
+                Line::Row {
+                    label,
+                    value,
+                    indent,
+                } => {
+                    let label = if indent { format!("  {label}") } else { label };
+                    let mut text = String::new();
+                    push_columns(&mut text, &label, &value, width);
+                    out.extend(encode_pc858(&text));
+                    out.push(LF);
+                }
+                Line::Total { label, value } => {
+                    let mut text = String::new();
+                    push_columns(&mut text, &label.to_uppercase(), &value, width);
+                    out.extend_from_slice(&[ESC, b'E', 1, GS, b'!', 0x01]);
+                    out.extend(encode_pc858(&text));
+                    out.extend_from_slice(&[LF, GS, b'!', 0x00, ESC, b'E', 0]);
+                }
+                Line::Rule => {
+                    out.extend(std::iter::repeat_n(b'-', width));
+                    out.push(LF);
+                }
+            }
+        }
+        if options.cut {
+            // GS V 66 n feeds n lines and then makes a partial cut.
+            out.extend_from_slice(&[GS, b'V', 66, options.feed_lines]);
+        } else {
+            out.extend_from_slice(&[ESC, b'd', options.feed_lines]);
+        }
+        out
+    }
+}
+
+fn encode_pc858(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|ch| match ch {
+            '\n' => LF,
+            ' '..='~' => ch as u8,
+            _ => PC858_HIGH
+                .iter()
+                .position(|high| *high == ch)
+                .map_or(b'?', |index| 0x80 + index as u8),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::path::Path;
+
+    use super::*;
+    use crate::model::RecordingCategory;
+    use crate::pricing::PriceList;
+    use crate::receipt::{CounterSpan, ReceiptConfig, ShopDetails};
+    use crate::storage::tests::TempDir;
+
+    fn run_future<T>(future: impl std::future::Future<Output = T>) -> T {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .enable_time()
+            .build()
+            .expect("tokio runtime");
+        runtime.block_on(future)
+    }
+
+    fn receipt() -> Receipt {
+        let config = ReceiptConfig {
+            shop: ShopDetails {
+                name: "Copy & Print".to_string(),
+                address_lines: vec!["Hauptstraße 1".to_string()],
+                vat_id: None,
+                footer_lines: vec!["Danke schön!".to_string()],
+            },
+            ..ReceiptConfig::default()
+        };
+        let pages: HashMap<_, _> = [
+            (RecordingCategory::CopiesBw, 7),
+            (RecordingCategory::PrintsColor, 3),
+        ]
+        .into_iter()
+        .collect();
+        let span = |start, end| CounterSpan {
+            start: Some(start),
+            end: Some(end),
+        };
+        Receipt::new("R-000042", 1_760_623_320, PriceList::default(), &pages)
+            .with_config(&config)
+            .with_printer("ricoh-1")
+            .with_session(Some(1_760_620_000), Some(1_760_623_200))
+            .with_counters([
+                (RecordingCategory::CopiesBw, span(12_000, 12_007)),
+                (RecordingCategory::CopiesColor, span(500, 500)),
+                (RecordingCategory::PrintsBw, span(80_100, 80_100)),
+                (RecordingCategory::PrintsColor, span(4_321, 4_324)),
+            ])
+    }
+
+    /// Control bytes as <1B 40>, printable text as is and a line break after
+    /// each LF, so golden files can be read and diffed.
+    fn readable(bytes: &[u8]) -> String {
+        let mut out = String::new();
+        let mut control = Vec::new();
+        let flush = |out: &mut String, control: &mut Vec<u8>| {
+            if !control.is_empty() {
+                let hex: Vec<String> = control.iter().map(|byte| format!("{byte:02X}")).collect();
+                out.push_str(&format!("<{}>", hex.join(" ")));
+                control.clear();
+            }
+        };
+        for byte in bytes {
+            match byte {
+                b' '..=b'~' if *byte != b'<' => {
+                    flush(&mut out, &mut control);
+                    out.push(*byte as char);
+                }
+                _ => {
+                    control.push(*byte);
+                    if *byte == LF {
+                        flush(&mut out, &mut control);
+                        out.push('\n');
+                    }
+                }
+            }
+        }
+        flush(&mut out, &mut control);
+        out
+    }
+
+    /// Compares against `testdata/<name>`; set PRINTCOUNTPAY_UPDATE_GOLDEN=1
+    /// to rewrite the file after an intended layout change.
+    fn assert_golden(name: &str, bytes: &[u8]) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join(name);
+        let actual = readable(bytes);
+        if std::env::var_os("PRINTCOUNTPAY_UPDATE_GOLDEN").is_some() {
+            fs::write(&path, &actual).expect("write golden file");
+        }
+        let expected = fs::read_to_string(&path).expect("read golden file");
+        assert_eq!(actual, expected, "{name} differs from the golden file");
+    }
+
+    #[test]
+    fn matches_golden_layouts() {
+        assert_golden(
+            "escpos_receipt_48.txt",
+            &receipt().to_escpos(&EscPosOptions::default()),
+        );
+        let narrow = EscPosOptions {
+            columns: 32,
+            feed_lines: 3,
+            cut: false,
+        };
+        assert_golden("escpos_receipt_32.txt", &receipt().to_escpos(&narrow));
+    }
+
+    #[test]
+    fn encodes_text_in_pc858() {
+        assert_eq!(
+            encode_pc858("Straße 1,50 €"),
+            b"Stra\xe1e 1,50 \xd5".to_vec()
+        );
+        assert_eq!(encode_pc858("Ünïcode ✓"), b"\x9an\x8bcode ?".to_vec());
+    }
+
+    #[test]
+    fn parses_targets() {
+        assert_eq!(
+            EscPosTarget::parse(" 192.168.1.50 "),
+            Some(EscPosTarget::Tcp("192.168.1.50".to_string()))
+        );
+        assert_eq!(
+            EscPosTarget::parse("/dev/usb/lp0"),
+            Some(EscPosTarget::Device(PathBuf::from("/dev/usb/lp0")))
+        );
+        assert_eq!(
+            EscPosTarget::parse("com3"),
+            Some(EscPosTarget::Device(PathBuf::from("com3")))
+        );
+        assert_eq!(EscPosTarget::parse("  "), None);
+    }
+
+    #[test]
+    fn sends_over_tcp_and_to_devices() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let address = listener.local_addr().expect("address").to_string();
+        let reader = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).expect("read");
+            received
+        });
+        let job = receipt().to_escpos(&EscPosOptions::default());
+        run_future(EscPosTarget::Tcp(address).send(&job, Duration::from_secs(5)))
+            .expect("send over tcp");
+        assert_eq!(reader.join().expect("reader"), job);
+
+        let dir = TempDir::new("escpos");
+        let device = dir.join("lp0");
+        fs::write(&device, b"").expect("create device file");
+        run_future(EscPosTarget::Device(device.clone()).send(&job, Duration::from_secs(5)))
+            .expect("send to device");
+        assert_eq!(fs::read(&device).expect("read device"), job);
+
+        let missing = EscPosTarget::Device(dir.join("missing").join("lp0"));
+        let error = run_future(missing.send(&job, Duration::from_secs(5))).expect_err("missing");
+        assert!(error.technical_detail().contains("lp0"));
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{EpochSeconds, PrinterStatus};
+use crate::Error;
+
+pub const DEFAULT_OFFLINE_AFTER_TIMEOUTS: u32 = 3;
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Transitions kept per printer; older ones are dropped first.
+const MAX_TRANSITIONS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthPolicy {
+    /// Timeouts or transport failures in a row before a printer counts as
+    /// offline.
+    pub offline_after: u32,
+    /// Ceiling for the doubling poll delay of offline printers.
+    pub max_backoff: Duration,
+}
+
+impl Default for HealthPolicy {
+    fn default() -> Self {
+        Self {
+            offline_after: DEFAULT_OFFLINE_AFTER_TIMEOUTS,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusTransition {
+    pub from: PrinterStatus,
+    pub to: PrinterStatus,
+    pub at: EpochSeconds,
+    pub reason: String,
+}
+
+/// Health of one printer as seen by the poller.
+///
+/// Successes bring a printer online. Timeouts and transport failures, such as
+/// an unreachable network or a refused connection, are misses: they only take
+/// it offline once `HealthPolicy::offline_after` of them happen in a row, and
+/// back off from there. Auth and PDU failures put it in `Error` straight away.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrinterHealth {
+    status: PrinterStatus,
+    since: Option<EpochSeconds>,
+    last_seen: Option<EpochSeconds>,
+    consecutive_misses: u32,
+    transitions: VecDeque<StatusTransition>,
+}
+
+impl PrinterHealth {
+    /// Starts from what a stored `PrinterRecord` last knew.
+    pub fn new(status: PrinterStatus, last_seen: Option<EpochSeconds>) -> Self {
+        Self {
+            status,
+            last_seen,
+            ..Self::default()
+        }
+    }
+
+    pub fn status(&self) -> PrinterStatus {
+        self.status
+    }
+
+    /// When the current status was entered, if it changed while tracked.
+    pub fn since(&self) -> Option<EpochSeconds> {
+        self.since
+    }
+
+    pub fn last_seen(&self) -> Option<EpochSeconds> {
+        self.last_seen
+    }
+
+    /// Timeouts and transport failures since the last success.
+    pub fn consecutive_misses(&self) -> u32 {
+        self.consecutive_misses
+    }
+
+    /// Oldest first.
+    pub fn transitions(&self) -> impl Iterator<Item = &StatusTransition> {
+        self.transitions.iter()
+    }
+
+    pub fn record_success(&mut self, at: EpochSeconds) -> Option<StatusTransition> {
+        self.last_seen = Some(at);
+        self.consecutive_misses = 0;
+        self.transition(PrinterStatus::Online, at, "poll succeeded".to_string())
+    }
+
+    pub fn record_failure(
+        &mut self,
+        error: &Error,
+        at: EpochSeconds,
+        policy: &HealthPolicy,
+    ) -> Option<StatusTransition> {
+        match error {
+            Error::SnmpTimeout { .. } | Error::SnmpUnreachable { .. } => {
+                self.consecutive_misses = self.consecutive_misses.saturating_add(1);
+                if self.consecutive_misses < policy.offline_after.max(1) {
+                    return None;
+                }
+                let reason = match error {
+                    Error::SnmpTimeout { .. } => {
+                        format!("{} consecutive timeouts", self.consecutive_misses)
+                    }
+                    other => format!(
+                        "{} consecutive misses, last: {}",
+                        self.consecutive_misses,
+                        other.technical_detail()
+                    ),
+                };
+                self.transition(PrinterStatus::Offline, at, reason)
+            }
+            other => {
+                self.consecutive_misses = 0;
+                self.transition(PrinterStatus::Error, at, other.technical_detail())
+            }
+        }
+    }
+
+    /// Delay before the next poll: `interval` normally, doubling per miss past
+    /// the offline threshold up to `max_backoff` while offline.
+    pub fn next_delay(&self, interval: Duration, policy: &HealthPolicy) -> Duration {
+        if self.status != PrinterStatus::Offline {
+            return interval;
+        }
+        let extra = self
+            .consecutive_misses
+            .saturating_sub(policy.offline_after.max(1))
+            .saturating_add(1)
+            .min(16);
+        interval
+            .saturating_mul(1 << extra)
+            .min(policy.max_backoff.max(interval))
+    }
+
+    fn transition(
+        &mut self,
+        to: PrinterStatus,
+        at: EpochSeconds,
+        reason: String,
+    ) -> Option<StatusTransition> {
+        if self.status == to {
+            return None;
+        }
+        let transition = StatusTransition {
+            from: self.status,
+            to,
+            at,
+            reason,
+        };
+        self.status = to;
+        self.since = Some(at);
+        if self.transitions.len() == MAX_TRANSITIONS {
+            self.transitions.pop_front();
+        }
+        self.transitions.push_back(transition.clone());
+        Some(transition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeout() -> Error {
+        Error::SnmpTimeout {
+            address: "192.168.1.10:161".to_string(),
+            timeout_ms: 2000,
+        }
+    }
+
+    #[test]
+    fn goes_offline_after_consecutive_timeouts_and_recovers() {
+        let policy = HealthPolicy::default();
+        let mut health = PrinterHealth::new(PrinterStatus::Online, Some(100));
+
+        assert!(health.record_failure(&timeout(), 200, &policy).is_none());
+        assert!(health.record_failure(&timeout(), 205, &policy).is_none());
+        let offline = health
+            .record_failure(&timeout(), 210, &policy)
+            .expect("offline transition");
+        assert_eq!(offline.from, PrinterStatus::Online);
+        assert_eq!(offline.to, PrinterStatus::Offline);
+        assert_eq!(health.since(), Some(210));
+        assert_eq!(health.last_seen(), Some(100));
+
+        let online = health.record_success(400).expect("online transition");
+        assert_eq!(online.to, PrinterStatus::Online);
+        assert_eq!(health.last_seen(), Some(400));
+        assert_eq!(health.consecutive_misses(), 0);
+        assert_eq!(health.transitions().count(), 2);
+    }
+
+    #[test]
+    fn auth_failures_are_errors_immediately() {
+        let policy = HealthPolicy::default();
+        let mut health = PrinterHealth::default();
+        let error = Error::SnmpAuth {
+            address: "192.168.1.10:161".to_string(),
+            details: Some("wrong digest".to_string()),
+        };
+
+        let transition = health
+            .record_failure(&error, 50, &policy)
+            .expect("error transition");
+        assert_eq!(transition.to, PrinterStatus::Error);
+        assert!(transition.reason.contains("wrong digest"));
+        assert!(health.record_failure(&error, 55, &policy).is_none());
+    }
+
+    #[test]
+    fn backoff_doubles_while_offline_up_to_the_cap() {
+        let policy = HealthPolicy {
+            offline_after: 2,
+            max_backoff: Duration::from_secs(60),
+        };
+        let interval = Duration::from_secs(5);
+        let mut health = PrinterHealth::default();
+
+        health.record_failure(&timeout(), 1, &policy);
+        assert_eq!(health.next_delay(interval, &policy), interval);
+        let delays: Vec<u64> = (2..=6)
+            .map(|at| {
+                health.record_failure(&timeout(), at, &policy);
+                health.next_delay(interval, &policy).as_secs()
+            })
+            .collect();
+        assert_eq!(delays, vec![10, 20, 40, 60, 60]);
+
+        health.record_success(10);
+        assert_eq!(health.next_delay(interval, &policy), interval);
+    }
+
+    #[test]
+    fn unreachable_printers_go_offline_and_back_off() {
+        let policy = HealthPolicy {
+            offline_after: 2,
+            max_backoff: Duration::from_secs(60),
+        };
+        let interval = Duration::from_secs(5);
+        let refused = Error::SnmpUnreachable {
+            address: "192.168.1.10:161".to_string(),
+            details: "Connection refused (os error 111)".to_string(),
+        };
+        let mut health = PrinterHealth::new(PrinterStatus::Online, Some(100));
+
+        assert!(health.record_failure(&refused, 200, &policy).is_none());
+        let offline = health
+            .record_failure(&timeout(), 205, &policy)
+            .expect("offline transition");
+        assert_eq!(offline.to, PrinterStatus::Offline);
+        assert_eq!(health.next_delay(interval, &policy).as_secs(), 10);
+        let transition = health.record_failure(&refused, 210, &policy);
+        assert!(transition.is_none());
+        assert_eq!(health.consecutive_misses(), 3);
+        assert_eq!(health.next_delay(interval, &policy).as_secs(), 20);
+    }
+}
@@ -0,0 +1,636 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::counters::{CounterMode, CounterResolution, CounterWarning};
+use crate::model::{CounterSnapshot, EpochSeconds, PrinterId};
+use crate::storage::{self, storage_error};
+use crate::{targets, Error, StorageAction};
+
+const HISTORY_EXTENSION: &str = "history";
+
+/// One polled snapshot as it is stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub snapshot: CounterSnapshot,
+    pub mode: CounterMode,
+    #[serde(default)]
+    pub warnings: Vec<CounterWarning>,
+}
+
+impl HistoryEntry {
+    pub fn from_resolution(resolution: &CounterResolution) -> Self {
+        Self {
+            snapshot: resolution.snapshot.clone(),
+            mode: resolution.mode,
+            warnings: resolution.warnings.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RollupPeriod {
+    Hourly,
+    Daily,
+}
+
+impl RollupPeriod {
+    pub fn seconds(self) -> u64 {
+        match self {
+            RollupPeriod::Hourly => 3_600,
+            RollupPeriod::Daily => 86_400,
+        }
+    }
+
+    /// Start of the UTC hour or day containing `timestamp`.
+    pub fn bucket(self, timestamp: EpochSeconds) -> EpochSeconds {
+        timestamp - timestamp % self.seconds()
+    }
+}
+
+/// Several snapshots folded into one period. Counters only grow, so the
+/// first and last snapshot are enough to bill the period.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRollup {
+    pub period: RollupPeriod,
+    pub start: EpochSeconds,
+    pub samples: u32,
+    pub first: CounterSnapshot,
+    pub last: CounterSnapshot,
+    pub mode: CounterMode,
+    #[serde(default)]
+    pub warnings: Vec<CounterWarning>,
+}
+
+impl HistoryRollup {
+    fn from_entry(period: RollupPeriod, entry: HistoryEntry) -> Self {
+        Self {
+            period,
+            start: period.bucket(entry.snapshot.timestamp),
+            samples: 1,
+            first: entry.snapshot.clone(),
+            last: entry.snapshot,
+            mode: entry.mode,
+            warnings: entry.warnings,
+        }
+    }
+
+    /// Last second covered by the period.
+    pub fn end(&self) -> EpochSeconds {
+        self.start + self.period.seconds() - 1
+    }
+
+    fn widen(mut self, period: RollupPeriod) -> Self {
+        self.period = period;
+        self.start = period.bucket(self.start);
+        self
+    }
+
+    fn merge(&mut self, other: HistoryRollup) {
+        if other.first.timestamp < self.first.timestamp {
+            self.first = other.first;
+        }
+        if other.last.timestamp >= self.last.timestamp {
+            self.last = other.last;
+            self.mode = other.mode;
+        }
+        self.samples = self.samples.saturating_add(other.samples);
+        for warning in other.warnings {
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HistoryRecord {
+    Snapshot(HistoryEntry),
+    Rollup(HistoryRollup),
+}
+
+impl HistoryRecord {
+    pub fn start(&self) -> EpochSeconds {
+        match self {
+            HistoryRecord::Snapshot(entry) => entry.snapshot.timestamp,
+            HistoryRecord::Rollup(rollup) => rollup.start,
+        }
+    }
+
+    pub fn end(&self) -> EpochSeconds {
+        match self {
+            HistoryRecord::Snapshot(entry) => entry.snapshot.timestamp,
+            HistoryRecord::Rollup(rollup) => rollup.end(),
+        }
+    }
+
+    /// Latest snapshot the record knows about.
+    pub fn last_snapshot(&self) -> &CounterSnapshot {
+        match self {
+            HistoryRecord::Snapshot(entry) => &entry.snapshot,
+            HistoryRecord::Rollup(rollup) => &rollup.last,
+        }
+    }
+
+    fn overlaps(&self, range: &impl RangeBounds<EpochSeconds>) -> bool {
+        let after_start = match range.start_bound() {
+            Bound::Included(start) => self.end() >= *start,
+            Bound::Excluded(start) => self.end() > *start,
+            Bound::Unbounded => true,
+        };
+        let before_end = match range.end_bound() {
+            Bound::Included(end) => self.start() <= *end,
+            Bound::Excluded(end) => self.start() < *end,
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+}
+
+/// How long detail is kept before `HistoryStore::compact` folds it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionPolicy {
+    /// Raw snapshots older than this become hourly rollups.
+    pub raw_for: Duration,
+    /// Hourly rollups older than this become daily rollups.
+    pub hourly_for: Duration,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self {
+            raw_for: Duration::from_secs(7 * 86_400),
+            hourly_for: Duration::from_secs(90 * 86_400),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionReport {
+    pub records_before: usize,
+    pub records_after: usize,
+}
+
+/// Append-only counter history, one file per printer under `dir`.
+///
+/// Each record is one RON line written with a single append and synced
+/// before `append` returns. A line torn by a crash is skipped on read and
+/// dropped by the next compaction, which rewrites through a temp file and a
+/// rename so the stream is never half-replaced.
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    dir: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl HistoryStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn append(&self, printer_id: &PrinterId, entry: &HistoryEntry) -> Result<(), Error> {
+        let _guard = self.lock();
+        let path = self.path_for(printer_id);
+        let record = HistoryRecord::Snapshot(entry.clone());
+        let line = ron::ser::to_string(&record).map_err(|source| Error::Ron {
+            action: StorageAction::Save,
+            path: Some(path.display().to_string()),
+            source,
+        })?;
+
+        let result = (|| {
+            fs::create_dir_all(&self.dir)?;
+            let mut file = OpenOptions::new()
+                .create(true)
+                .read(true)
+                .append(true)
+                .open(&path)?;
+            let mut bytes = Vec::with_capacity(line.len() + 2);
+            if ends_mid_line(&mut file)? {
+                bytes.push(b'\n');
+            }
+            bytes.extend_from_slice(line.as_bytes());
+            bytes.push(b'\n');
+            file.write_all(&bytes)?;
+            file.sync_data()
+        })();
+        result.map_err(|source| storage_error(StorageAction::Save, &path, source))
+    }
+
+    /// Records overlapping `range` (epoch seconds), oldest first.
+    pub fn query(
+        &self,
+        printer_id: &PrinterId,
+        range: impl RangeBounds<EpochSeconds>,
+    ) -> Result<Vec<HistoryRecord>, Error> {
+        let _guard = self.lock();
+        let mut records = read_records(&self.path_for(printer_id))?;
+        records.retain(|record| record.overlaps(&range));
+        records.sort_by_key(HistoryRecord::start);
+        Ok(records)
+    }
+
+    /// Printers with a stored stream.
+    pub fn printers(&self) -> Result<Vec<PrinterId>, Error> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(storage_error(StorageAction::Load, &self.dir, error)),
+        };
+
+        let mut printers = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|value| value.to_str()) != Some(HISTORY_EXTENSION) {
+                continue;
+            }
+            if let Some(id) = path
+                .file_stem()
+                .and_then(|value| value.to_str())
+                .and_then(decode_file_stem)
+            {
+                printers.push(PrinterId::new(id));
+            }
+        }
+        printers.sort_by(|left, right| left.0.cmp(&right.0));
+        Ok(printers)
+    }
+
+    /// Rolls up old snapshots and rewrites the stream whenever that changes
+    /// anything, including rollups widened in place and unreadable lines.
+    pub fn compact(
+        &self,
+        printer_id: &PrinterId,
+        now: EpochSeconds,
+        policy: &CompactionPolicy,
+    ) -> Result<CompactionReport, Error> {
+        let _guard = self.lock();
+        let path = self.path_for(printer_id);
+        let (records, unreadable) = read_stream(&path)?;
+        let records_before = records.len();
+        let compacted = compact_records(records.clone(), now, policy);
+        let records_after = compacted.len();
+        if unreadable > 0 || compacted != records {
+            write_records(&path, &compacted)?;
+        }
+        Ok(CompactionReport {
+            records_before,
+            records_after,
+        })
+    }
+
+    pub fn compact_all(
+        &self,
+        now: EpochSeconds,
+        policy: &CompactionPolicy,
+    ) -> Result<CompactionReport, Error> {
+        let mut total = CompactionReport::default();
+        for printer_id in self.printers()? {
+            let report = self.compact(&printer_id, now, policy)?;
+            total.records_before += report.records_before;
+            total.records_after += report.records_after;
+        }
+        Ok(total)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn path_for(&self, printer_id: &PrinterId) -> PathBuf {
+        let stem = encode_file_stem(&printer_id.0);
+        self.dir.join(format!("{stem}.{HISTORY_EXTENSION}"))
+    }
+}
+
+fn compact_records(
+    records: Vec<HistoryRecord>,
+    now: EpochSeconds,
+    policy: &CompactionPolicy,
+) -> Vec<HistoryRecord> {
+    let raw_cutoff = now.saturating_sub(policy.raw_for.as_secs());
+    let hourly_cutoff = now.saturating_sub(policy.hourly_for.as_secs());
+
+    let mut kept = Vec::new();
+    let mut hourly: BTreeMap<EpochSeconds, HistoryRollup> = BTreeMap::new();
+    let mut daily: BTreeMap<EpochSeconds, HistoryRollup> = BTreeMap::new();
+
+    for record in records {
+        match record {
+            HistoryRecord::Snapshot(entry) if entry.snapshot.timestamp < raw_cutoff => {
+                fold_rollup(
+                    &mut hourly,
+                    HistoryRollup::from_entry(RollupPeriod::Hourly, entry),
+                );
+            }
+            HistoryRecord::Snapshot(entry) => kept.push(HistoryRecord::Snapshot(entry)),
+            HistoryRecord::Rollup(rollup) => match rollup.period {
+                RollupPeriod::Hourly => fold_rollup(&mut hourly, rollup),
+                RollupPeriod::Daily => fold_rollup(&mut daily, rollup),
+            },
+        }
+    }
+
+    for rollup in hourly.into_values() {
+        if rollup.end() < hourly_cutoff {
+            fold_rollup(&mut daily, rollup.widen(RollupPeriod::Daily));
+        } else {
+            kept.push(HistoryRecord::Rollup(rollup));
+        }
+    }
+    kept.extend(daily.into_values().map(HistoryRecord::Rollup));
+    kept.sort_by_key(HistoryRecord::start);
+    kept
+}
+
+fn fold_rollup(rollups: &mut BTreeMap<EpochSeconds, HistoryRollup>, rollup: HistoryRollup) {
+    match rollups.get_mut(&rollup.start) {
+        Some(existing) => existing.merge(rollup),
+        None => {
+            rollups.insert(rollup.start, rollup);
+        }
+    }
+}
+
+fn read_records(path: &Path) -> Result<Vec<HistoryRecord>, Error> {
+    read_stream(path).map(|(records, _)| records)
+}
+
+/// The readable records and the number of lines skipped as unreadable.
+fn read_stream(path: &Path) -> Result<(Vec<HistoryRecord>, usize), Error> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(error) => return Err(storage_error(StorageAction::Load, path, error)),
+    };
+
+    let mut records = Vec::new();
+    let mut unreadable = 0;
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match ron::from_str::<HistoryRecord>(line) {
+            Ok(record) => records.push(record),
+            Err(error) => {
+                unreadable += 1;
+                warn!(
+                    target: targets::STORAGE,
+                    path = %path.display(),
+                    line = index + 1,
+                    error = %error,
+                    "Skipping unreadable history record"
+                );
+            }
+        }
+    }
+    Ok((records, unreadable))
+}
+
+fn write_records(path: &Path, records: &[HistoryRecord]) -> Result<(), Error> {
+    let mut contents = String::new();
+    for record in records {
+        let line = ron::ser::to_string(record).map_err(|source| Error::Ron {
+            action: StorageAction::Save,
+            path: Some(path.display().to_string()),
+            source,
+        })?;
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+
+    storage::write_atomic(path, contents.as_bytes())
+}
+
+fn ends_mid_line(file: &mut File) -> io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0u8; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
+
+/// Printer IDs contain dots, colons and the like; anything outside
+/// `[A-Za-z0-9._-]` is percent-encoded so every ID maps to one file name.
+fn encode_file_stem(id: &str) -> String {
+    let mut stem = String::with_capacity(id.len());
+    for byte in id.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.') {
+            stem.push(char::from(byte));
+        } else {
+            stem.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    stem
+}
+
+fn decode_file_stem(stem: &str) -> Option<String> {
+    let bytes = stem.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = stem.get(index + 1..index + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::CounterKind;
+    use crate::storage::tests::TempDir;
+
+    fn temp_store() -> (TempDir, HistoryStore) {
+        let dir = TempDir::new("history");
+        let store = HistoryStore::new(dir.path());
+        (dir, store)
+    }
+
+    fn entry(timestamp: EpochSeconds, total: u64) -> HistoryEntry {
+        let mut snapshot = CounterSnapshot::new(timestamp);
+        snapshot.total = Some(total);
+        snapshot.source_oids.total = Some("1.3.6.1.2.1.43.10.2.1.4.1.1".to_string());
+        HistoryEntry {
+            snapshot,
+            mode: CounterMode::TotalOnly,
+            warnings: vec![CounterWarning::UsedTotalFallback],
+        }
+    }
+
+    #[test]
+    fn appends_and_queries_by_time_range() {
+        let (_dir, store) = temp_store();
+        let printer = PrinterId::new("snmp-192.168.1.5");
+        for (timestamp, total) in [(1_000, 10), (2_000, 20), (3_000, 30)] {
+            store
+                .append(&printer, &entry(timestamp, total))
+                .expect("append");
+        }
+
+        let records = store.query(&printer, 1_500..=3_000).expect("query");
+        let totals: Vec<Option<u64>> = records
+            .iter()
+            .map(|record| record.last_snapshot().total)
+            .collect();
+        assert_eq!(totals, vec![Some(20), Some(30)]);
+        let HistoryRecord::Snapshot(stored) = &records[0] else {
+            panic!("expected raw snapshot");
+        };
+        assert_eq!(stored, &entry(2_000, 20));
+
+        assert_eq!(store.printers().expect("printers"), vec![printer.clone()]);
+        assert!(
+            store
+                .query(&PrinterId::new("other"), ..)
+                .expect("query")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn torn_tail_is_skipped_and_appends_continue() {
+        let (_dir, store) = temp_store();
+        let printer = PrinterId::new("manual-printer 1");
+        store.append(&printer, &entry(1_000, 10)).expect("append");
+        let path = store.path_for(&printer);
+        let mut file = OpenOptions::new().append(true).open(&path).expect("open");
+        file.write_all(b"Snapshot((snapshot:(bw:No")
+            .expect("torn write");
+        drop(file);
+
+        store
+            .append(&printer, &entry(2_000, 20))
+            .expect("append after crash");
+        let records = store.query(&printer, ..).expect("query");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].last_snapshot().total, Some(20));
+
+        // Nothing is old enough to roll up, but the torn line still goes.
+        let report = store
+            .compact(&printer, 3_000, &CompactionPolicy::default())
+            .expect("compact");
+        assert_eq!(report.records_before, report.records_after);
+        assert_eq!(read_stream(&path).expect("read").1, 0);
+        assert_eq!(store.query(&printer, ..).expect("query"), records);
+    }
+
+    #[test]
+    fn compaction_rolls_up_hours_then_days() {
+        let (_dir, store) = temp_store();
+        let printer = PrinterId::new("printer-001");
+        let day = 86_400;
+        let now = 100 * day;
+        // Two old days with two polls per hour, plus one recent poll.
+        let mut timestamps = Vec::new();
+        for old_day in [5, 60] {
+            for minute in [0, 30, 60, 90] {
+                timestamps.push(old_day * day + minute * 60);
+            }
+        }
+        timestamps.push(now - 60);
+        for (index, timestamp) in timestamps.iter().enumerate() {
+            let mut stored = entry(*timestamp, 100 + index as u64);
+            if index == 1 {
+                stored.warnings.push(CounterWarning::Missing {
+                    kind: CounterKind::Bw,
+                });
+            }
+            store.append(&printer, &stored).expect("append");
+        }
+
+        let report = store
+            .compact(&printer, now, &CompactionPolicy::default())
+            .expect("compact");
+        assert_eq!(report.records_before, 9);
+
+        let records = store.query(&printer, ..).expect("query");
+        let shapes: Vec<(Option<RollupPeriod>, EpochSeconds)> = records
+            .iter()
+            .map(|record| match record {
+                HistoryRecord::Snapshot(_) => (None, record.start()),
+                HistoryRecord::Rollup(rollup) => (Some(rollup.period), rollup.start),
+            })
+            .collect();
+        assert_eq!(
+            shapes,
+            vec![
+                (Some(RollupPeriod::Daily), 5 * day),
+                (Some(RollupPeriod::Hourly), 60 * day),
+                (Some(RollupPeriod::Hourly), 60 * day + 3_600),
+                (None, now - 60),
+            ]
+        );
+        let HistoryRecord::Rollup(daily) = &records[0] else {
+            panic!("expected daily rollup");
+        };
+        assert_eq!(daily.samples, 4);
+        assert_eq!(daily.first.total, Some(100));
+        assert_eq!(daily.last.total, Some(103));
+        assert_eq!(daily.warnings.len(), 2);
+        assert_eq!(report.records_after, records.len());
+
+        let again = store
+            .compact(&printer, now, &CompactionPolicy::default())
+            .expect("compact again");
+        assert_eq!(again.records_before, again.records_after);
+    }
+
+    #[test]
+    fn compaction_saves_rollups_widened_in_place() {
+        let (_dir, store) = temp_store();
+        let printer = PrinterId::new("printer-002");
+        let day = 86_400;
+        for minute in [0, 30] {
+            store
+                .append(&printer, &entry(day + minute * 60, 100 + minute))
+                .expect("append");
+        }
+        let policy = CompactionPolicy::default();
+        store.compact(&printer, 10 * day, &policy).expect("compact");
+
+        // The one hourly rollup becomes one daily rollup: same count, new file.
+        let report = store
+            .compact(&printer, 100 * day, &policy)
+            .expect("compact");
+        assert_eq!((report.records_before, report.records_after), (1, 1));
+        let records = store.query(&printer, ..).expect("query");
+        let HistoryRecord::Rollup(rollup) = &records[0] else {
+            panic!("expected rollup");
+        };
+        assert_eq!(rollup.period, RollupPeriod::Daily);
+    }
+
+    #[test]
+    fn file_stems_round_trip() {
+        for id in ["snmp-192.168.1.5", "manual-host:161", "a/b\\c%d"] {
+            let stem = encode_file_stem(id);
+            assert!(!stem.contains(['/', '\\', ':']));
+            assert_eq!(decode_file_stem(&stem).as_deref(), Some(id));
+        }
+    }
+}
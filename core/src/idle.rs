@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use crate::model::{EpochSeconds, RecordingCategory};
+
+/// What one poll means for a recording that stops once its counters go idle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdleCheck {
+    /// A counter moved. The poll is the recording's new last change; the
+    /// next poll is compared with `counters`, which keep the last known
+    /// value of any counter this poll was missing.
+    Changed {
+        counters: BTreeMap<RecordingCategory, u64>,
+    },
+    /// Nothing moved, and the limit has not been reached.
+    Quiet,
+    /// Nothing moved for the limit or longer. The recording ends at its last
+    /// change, so the quiet polls after it are not billed as session time.
+    Stop { idle_secs: u64 },
+}
+
+/// Weighs a poll at `polled_at` against the counters of the recording's last
+/// change, made at `changed_at`. A counter missing from the poll is not a
+/// change, and neither is one that comes back with the value it had.
+pub fn check_idle(
+    changed_at: EpochSeconds,
+    previous: &BTreeMap<RecordingCategory, u64>,
+    polled_at: EpochSeconds,
+    counters: &BTreeMap<RecordingCategory, u64>,
+    limit: Option<u64>,
+) -> IdleCheck {
+    let changed = counters
+        .iter()
+        .any(|(category, value)| previous.get(category) != Some(value));
+    if changed {
+        let mut counters = counters.clone();
+        for (category, value) in previous {
+            counters.entry(*category).or_insert(*value);
+        }
+        return IdleCheck::Changed { counters };
+    }
+    let idle_secs = polled_at.saturating_sub(changed_at);
+    match limit {
+        Some(limit) if idle_secs >= limit => IdleCheck::Stop { idle_secs },
+        _ => IdleCheck::Quiet,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: Option<u64> = Some(600);
+
+    fn counters(entries: &[(RecordingCategory, u64)]) -> BTreeMap<RecordingCategory, u64> {
+        entries.iter().copied().collect()
+    }
+
+    #[test]
+    fn stops_once_quiet_for_the_limit() {
+        let start = counters(&[
+            (RecordingCategory::CopiesBw, 100),
+            (RecordingCategory::PrintsBw, 40),
+        ]);
+        let copied = counters(&[
+            (RecordingCategory::CopiesBw, 103),
+            (RecordingCategory::PrintsBw, 40),
+        ]);
+        assert_eq!(
+            check_idle(1_000, &start, 1_300, &copied, LIMIT),
+            IdleCheck::Changed {
+                counters: copied.clone()
+            }
+        );
+
+        // Measured from the last change at 1 300, not from the start.
+        assert_eq!(
+            check_idle(1_300, &copied, 1_899, &copied, LIMIT),
+            IdleCheck::Quiet
+        );
+        assert_eq!(
+            check_idle(1_300, &copied, 1_900, &copied, LIMIT),
+            IdleCheck::Stop { idle_secs: 600 }
+        );
+        assert_eq!(
+            check_idle(1_300, &copied, 2_500, &copied, LIMIT),
+            IdleCheck::Stop { idle_secs: 1_200 }
+        );
+        // Without a limit a recording stays open.
+        assert_eq!(
+            check_idle(1_300, &copied, 9_000, &copied, None),
+            IdleCheck::Quiet
+        );
+    }
+
+    #[test]
+    fn missing_counters_are_not_changes() {
+        let last = counters(&[
+            (RecordingCategory::CopiesBw, 100),
+            (RecordingCategory::PrintsBw, 40),
+        ]);
+        // The print counter drops out of a poll: still idle.
+        let partial = counters(&[(RecordingCategory::CopiesBw, 100)]);
+        assert_eq!(
+            check_idle(1_000, &last, 1_300, &partial, LIMIT),
+            IdleCheck::Quiet
+        );
+        assert_eq!(
+            check_idle(1_000, &last, 1_600, &partial, LIMIT),
+            IdleCheck::Stop { idle_secs: 600 }
+        );
+        // It comes back unchanged: still idle.
+        assert_eq!(
+            check_idle(1_000, &last, 1_300, &last, LIMIT),
+            IdleCheck::Quiet
+        );
+
+        // A copy lands while the print counter is missing; the change keeps
+        // its last value, so its return with that value is no change.
+        let copied = counters(&[(RecordingCategory::CopiesBw, 101)]);
+        let IdleCheck::Changed { counters: kept } = check_idle(1_000, &last, 1_300, &copied, LIMIT)
+        else {
+            panic!("a copy is a change");
+        };
+        assert_eq!(kept.get(&RecordingCategory::PrintsBw), Some(&40));
+        let back = counters(&[
+            (RecordingCategory::CopiesBw, 101),
+            (RecordingCategory::PrintsBw, 40),
+        ]);
+        assert_eq!(
+            check_idle(1_300, &kept, 1_600, &back, LIMIT),
+            IdleCheck::Quiet
+        );
+        // A counter seen for the first time is a change.
+        let scans = counters(&[(RecordingCategory::Scans, 7)]);
+        assert!(matches!(
+            check_idle(1_300, &kept, 1_600, &scans, LIMIT),
+            IdleCheck::Changed { .. }
+        ));
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{EpochSeconds, RecordingCategory};
+use crate::snmp::SnmpVarBind;
+
+/// jmJobEntry of the Job Monitoring MIB (RFC 2707). Rows are indexed by
+/// job set and job index; printers without the MIB return nothing.
+pub const JM_JOB_ENTRY_OID: [u32; 13] = [1, 3, 6, 1, 4, 1, 2699, 1, 1, 1, 3, 1, 1];
+
+const JM_JOB_STATE: u32 = 2;
+const JM_JOB_IMPRESSIONS_COMPLETED: u32 = 8;
+const JM_JOB_OWNER: u32 = 9;
+
+/// jmJobState values; the agent keeps finished jobs for a while.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobState {
+    Pending,
+    Held,
+    Processing,
+    Stopped,
+    Canceled,
+    Aborted,
+    Completed,
+    Unknown,
+}
+
+impl JobState {
+    fn from_code(code: u64) -> Self {
+        match code {
+            3 => JobState::Pending,
+            4 => JobState::Held,
+            5 => JobState::Processing,
+            6 => JobState::Stopped,
+            7 => JobState::Canceled,
+            8 => JobState::Aborted,
+            9 => JobState::Completed,
+            _ => JobState::Unknown,
+        }
+    }
+}
+
+/// One row of the printer's job log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrintJob {
+    pub index: u32,
+    pub state: JobState,
+    /// Who submitted the job, usually the user name on the sending PC.
+    pub owner: Option<String>,
+    pub impressions: Option<u64>,
+}
+
+/// Collects the jobs of the first job set from a walk of
+/// `JM_JOB_ENTRY_OID`, ordered by job index.
+pub fn jobs_from_walk(varbinds: &[SnmpVarBind]) -> Vec<PrintJob> {
+    let mut jobs: BTreeMap<u32, PrintJob> = BTreeMap::new();
+    for varbind in varbinds {
+        let Some(&[column, job_set, index]) =
+            varbind.oid.as_slice().strip_prefix(&JM_JOB_ENTRY_OID)
+        else {
+            continue;
+        };
+        if job_set != 1 || varbind.value.is_missing() {
+            continue;
+        }
+        let job = jobs.entry(index).or_insert_with(|| PrintJob {
+            index,
+            state: JobState::Unknown,
+            owner: None,
+            impressions: None,
+        });
+        match column {
+            JM_JOB_STATE => {
+                if let Some(code) = varbind.value.as_u64() {
+                    job.state = JobState::from_code(code);
+                }
+            }
+            JM_JOB_IMPRESSIONS_COMPLETED => job.impressions = varbind.value.as_u64(),
+            JM_JOB_OWNER => {
+                job.owner = varbind
+                    .value
+                    .as_text_lossy()
+                    .map(|owner| owner.trim_matches(char::from(0)).trim().to_string())
+                    .filter(|owner| !owner.is_empty());
+            }
+            _ => {}
+        }
+    }
+    jobs.into_values().collect()
+}
+
+/// Print clicks that arrived between two polls of a session. Walk-up
+/// customers copy, so these may be a PC job sent to the same machine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConcurrentActivity {
+    pub at: EpochSeconds,
+    pub pages: BTreeMap<RecordingCategory, u64>,
+    /// Left out of the session's pages and price.
+    pub excluded: bool,
+}
+
+/// Print pages between two polls: how far each print counter polled both
+/// times went up. A counter that went backwards is left to the reset
+/// handling.
+pub fn print_pages_between(
+    previous: &BTreeMap<RecordingCategory, u64>,
+    current: &BTreeMap<RecordingCategory, u64>,
+) -> BTreeMap<RecordingCategory, u64> {
+    current
+        .iter()
+        .filter(|(category, _)| category.is_print())
+        .filter_map(|(category, value)| {
+            let last = previous.get(category)?;
+            (value > last).then(|| (*category, value - last))
+        })
+        .collect()
+}
+
+/// Print clicks of `category` the operator left out of a session.
+pub fn excluded_pages(activity: &[ConcurrentActivity], category: RecordingCategory) -> u64 {
+    activity
+        .iter()
+        .filter(|activity| activity.excluded)
+        .filter_map(|activity| activity.pages.get(&category))
+        .sum()
+}
+
+/// The `pages` a session counted for `category`, less its excluded print
+/// clicks.
+pub fn billed_pages(
+    pages: u64,
+    activity: &[ConcurrentActivity],
+    category: RecordingCategory,
+) -> u64 {
+    pages.saturating_sub(excluded_pages(activity, category))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snmp::{Oid, SnmpValue};
+
+    fn varbind(column: u32, job_set: u32, index: u32, value: SnmpValue) -> SnmpVarBind {
+        let mut oid = JM_JOB_ENTRY_OID.to_vec();
+        oid.extend([column, job_set, index]);
+        SnmpVarBind {
+            oid: Oid::from_slice(&oid),
+            value,
+        }
+    }
+
+    #[test]
+    fn reads_owner_state_and_impressions_per_job() {
+        let varbinds = vec![
+            varbind(2, 1, 7, SnmpValue::Integer(9)),
+            varbind(2, 1, 12, SnmpValue::Integer(5)),
+            varbind(8, 1, 7, SnmpValue::Integer(4)),
+            varbind(8, 1, 12, SnmpValue::NoSuchInstance),
+            varbind(9, 1, 7, SnmpValue::OctetString(b"alice\0".to_vec())),
+            varbind(9, 1, 12, SnmpValue::OctetString(Vec::new())),
+            // Other job sets and columns are ignored.
+            varbind(9, 2, 3, SnmpValue::OctetString(b"bob".to_vec())),
+            varbind(4, 1, 7, SnmpValue::Integer(0)),
+        ];
+
+        assert_eq!(
+            jobs_from_walk(&varbinds),
+            vec![
+                PrintJob {
+                    index: 7,
+                    state: JobState::Completed,
+                    owner: Some("alice".to_string()),
+                    impressions: Some(4),
+                },
+                PrintJob {
+                    index: 12,
+                    state: JobState::Processing,
+                    owner: None,
+                    impressions: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_print_clicks_during_a_copy_session() {
+        let poll = |copies, prints, color| {
+            BTreeMap::from([
+                (RecordingCategory::CopiesBw, copies),
+                (RecordingCategory::PrintsBw, prints),
+                (RecordingCategory::PrintsColor, color),
+            ])
+        };
+        // Copies alone raise no flag.
+        assert!(print_pages_between(&poll(100, 40, 9), &poll(104, 40, 9)).is_empty());
+        // A PC job lands between two polls of the copy session.
+        assert_eq!(
+            print_pages_between(&poll(104, 40, 9), &poll(106, 43, 10)),
+            BTreeMap::from([
+                (RecordingCategory::PrintsBw, 3),
+                (RecordingCategory::PrintsColor, 1),
+            ])
+        );
+        // A print counter missing from the earlier poll, or one that went
+        // backwards, is not flagged.
+        let mut partial = poll(106, 43, 10);
+        partial.remove(&RecordingCategory::PrintsBw);
+        assert!(print_pages_between(&partial, &poll(106, 45, 10)).is_empty());
+        assert!(print_pages_between(&poll(106, 43, 10), &poll(106, 2, 10)).is_empty());
+    }
+
+    #[test]
+    fn excluding_print_clicks_lowers_the_billed_pages() {
+        let clicks = |at, pages: u64, excluded| ConcurrentActivity {
+            at,
+            pages: BTreeMap::from([(RecordingCategory::PrintsBw, pages)]),
+            excluded,
+        };
+        let mut activity = vec![clicks(1_300, 3, false), clicks(1_600, 2, false)];
+        // Flagged clicks stay billed until the operator excludes them.
+        assert_eq!(billed_pages(8, &activity, RecordingCategory::PrintsBw), 8);
+
+        activity[0].excluded = true;
+        assert_eq!(excluded_pages(&activity, RecordingCategory::PrintsBw), 3);
+        assert_eq!(billed_pages(8, &activity, RecordingCategory::PrintsBw), 5);
+        assert_eq!(billed_pages(4, &activity, RecordingCategory::CopiesBw), 4);
+
+        // Pages typed over the counters can end up below the exclusions.
+        activity[1].excluded = true;
+        assert_eq!(billed_pages(4, &activity, RecordingCategory::PrintsBw), 0);
+    }
+}
@@ -1,22 +1,97 @@
+pub mod accounts;
+pub mod ber;
+pub mod calibration;
+pub mod capture;
+pub mod categories;
 pub mod error;
+pub mod counter_table;
 pub mod counters;
+pub mod delta;
 pub mod discovery;
+pub mod escpos;
+pub mod health;
+pub mod history;
+pub mod idle;
+pub mod jobs;
+pub mod mapping;
 pub mod model;
-pub mod ricoh;
+pub mod money;
+pub mod poller;
+pub mod pricing;
+pub mod profiles;
+pub mod receipt;
+pub mod reconcile;
 pub mod snmp;
+pub mod storage;
 pub mod targets;
+pub mod usm;
+pub mod zreport;
 
 pub use error::{Error, StorageAction};
+pub use accounts::{
+    Account, AccountBook, AccountId, BalanceState, LedgerEntry, LedgerKind, DEFAULT_LOW_BALANCE,
+};
+pub use calibration::{
+    increases_between, CalibrationCandidate, CalibrationProposal, OidIncrease,
+};
+pub use capture::{
+    CapturedConfig, CapturedError, CapturedExchange, CapturedOutcome, RecordingSnmpClient,
+    ReplaySnmpClient, SnmpCapture, SnmpOperation, SnmpRecorder,
+};
+pub use categories::CategoryList;
+pub use counter_table::{CounterRef, CounterTable, NamedCounter, RICOH_ENG_COUNTER_ENTRY_OID};
 pub use counters::{
     resolve_counters, CounterKind, CounterMode, CounterOidSet, CounterResolution, CounterWarning,
 };
+pub use delta::{
+    CounterDelta, CounterReading, CounterReset, CounterTracker, ResetResolution, COUNTER32_MODULUS,
+};
 pub use discovery::{default_discovery_cidr, CidrParseError, CidrRange, probe_printer};
+pub use escpos::{EscPosOptions, EscPosTarget, DEFAULT_ESCPOS_PORT};
+pub use health::{
+    HealthPolicy, PrinterHealth, StatusTransition, DEFAULT_MAX_BACKOFF,
+    DEFAULT_OFFLINE_AFTER_TIMEOUTS,
+};
+pub use history::{
+    CompactionPolicy, CompactionReport, HistoryEntry, HistoryRecord, HistoryRollup, HistoryStore,
+    RollupPeriod,
+};
+pub use idle::{check_idle, IdleCheck};
+pub use jobs::{
+    billed_pages, excluded_pages, jobs_from_walk, print_pages_between, ConcurrentActivity,
+    JobState, PrintJob, JM_JOB_ENTRY_OID,
+};
+pub use mapping::{CounterMapping, MappingTemplate, MappingTemplates};
 pub use model::{
-    CounterOids, CounterSnapshot, EpochSeconds, PrinterId, PrinterRecord, PrinterStatus, SnmpAddress,
-    DEFAULT_SNMP_PORT,
+    format_utc_timestamp, utc_timestamp, CounterOids, CounterSnapshot, EpochSeconds, PollSettings,
+    PrinterId, PrinterRecord, PrinterStatus, RecordingCategory, SnmpAddress, SnmpAuthProtocol,
+    SnmpPrivProtocol, SnmpSecurityLevel, SnmpV3Credentials, DEFAULT_SNMP_PORT,
+};
+pub use poller::{
+    PollEvent, PollTarget, Poller, DEFAULT_MAX_CONCURRENT_POLLS, DEFAULT_POLL_INTERVAL,
+};
+pub use money::{
+    Currency, DecimalSeparator, Money, MoneyFormat, Rounding, RoundingMode, ThousandsSeparator,
+    VatRate,
+};
+pub use pricing::{
+    PriceBasis, PriceBook, PriceList, PriceListVersion, PriceQuote, PriceTier, Tariff,
+    TariffCharge, TierCharge, VatLine,
+};
+pub use profiles::{
+    Capabilities, CounterAvailability, CounterStrategy, ModelRule, PrinterProfile, ProfileCounters,
+    ProfileMatch, ProfileRegistry, TonerSupply, VendorProfile,
+};
+pub use receipt::{CounterSpan, Receipt, ReceiptConfig, ShopDetails};
+pub use reconcile::{
+    GapAction, GapDecision, Reconciliation, ReconciliationLog, SessionSpan, UnbilledGap,
+    SECONDS_PER_DAY,
 };
-pub use ricoh::{CounterAvailability, CounterStrategy, RicohMatch, RicohProfile};
 pub use snmp::{
-    MockSnmpClient, Oid, OidParseError, SnmpClient, SnmpConfig, SnmpFuture, SnmpRequest,
-    SnmpResponse, SnmpV2cClient, SnmpValue, SnmpVarBind, SnmpWalkRequest,
+    snmp_client_for, MockSnmpClient, Oid, OidParseError, PduSize, PduSizeCache, SnmpClient,
+    SnmpConfig, SnmpFuture, SnmpRequest, SnmpResponse, SnmpV2cClient, SnmpV3Client, SnmpValue,
+    SnmpVarBind, SnmpWalkRequest, WalkProgress, SYS_UPTIME_OID,
 };
+pub use storage::write_atomic;
+pub use usm::UsmFailure;
+pub use zreport::{PaymentMethod, ZReport, ZReportSession, ZReportStore, ZReportTotals};
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::counter_table::{CounterRef, CounterTable};
+use crate::counters::CounterOidSet;
+use crate::model::{PrinterRecord, RecordingCategory};
+use crate::snmp::Oid;
+use crate::storage;
+use crate::Error;
+
+/// Which counters to read on one printer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterMapping {
+    /// Counters a recording session bills, tried in order per category.
+    #[serde(default)]
+    pub recording: BTreeMap<RecordingCategory, Vec<CounterRef>>,
+    /// Click totals kept in the counter history.
+    #[serde(default)]
+    pub clicks: CounterOidSet,
+}
+
+impl CounterMapping {
+    /// OIDs of `category` on a printer with `table`, in the order tried.
+    pub fn recording_oids(&self, category: RecordingCategory, table: &CounterTable) -> Vec<Oid> {
+        self.recording
+            .get(&category)
+            .into_iter()
+            .flatten()
+            .filter_map(|counter| counter.resolve(table))
+            .collect()
+    }
+
+    /// Every OID the mapping reads on a printer with `table`, without
+    /// duplicates.
+    pub fn poll_oids(&self, table: &CounterTable) -> Vec<Oid> {
+        let mut oids: Vec<Oid> = Vec::new();
+        let recording = RecordingCategory::ALL
+            .into_iter()
+            .flat_map(|category| self.recording_oids(category, table));
+        let clicks = self
+            .clicks
+            .bw
+            .iter()
+            .chain(&self.clicks.color)
+            .chain(&self.clicks.total)
+            .cloned();
+        for oid in recording.chain(clicks) {
+            if !oids.contains(&oid) {
+                oids.push(oid);
+            }
+        }
+        oids
+    }
+}
+
+/// A mapping shared by every printer of one model. Printers match on the
+/// sysObjectID and model string the template was saved from; a key left
+/// unset matches any value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MappingTemplate {
+    pub sys_object_id: Option<String>,
+    pub model: Option<String>,
+    pub mapping: CounterMapping,
+}
+
+impl MappingTemplate {
+    /// A template keyed on `record`'s model; `None` when the printer reported
+    /// neither a sysObjectID nor a model.
+    pub fn for_printer(record: &PrinterRecord, mapping: CounterMapping) -> Option<Self> {
+        let sys_object_id = normalized(record.sys_object_id.as_deref());
+        let model = normalized(record.model.as_deref());
+        if sys_object_id.is_none() && model.is_none() {
+            return None;
+        }
+        Some(Self {
+            sys_object_id,
+            model,
+            mapping,
+        })
+    }
+
+    pub fn matches(&self, record: &PrinterRecord) -> bool {
+        if self.sys_object_id.is_none() && self.model.is_none() {
+            return false;
+        }
+        let key_matches = |key: &Option<String>, value: Option<&str>| {
+            key.as_ref().is_none_or(|key| {
+                normalized(value).is_some_and(|value| value.eq_ignore_ascii_case(key))
+            })
+        };
+        key_matches(&self.sys_object_id, record.sys_object_id.as_deref())
+            && key_matches(&self.model, record.model.as_deref())
+    }
+
+    /// How many keys the template names; more is more specific.
+    fn specificity(&self) -> usize {
+        usize::from(self.sys_object_id.is_some()) + usize::from(self.model.is_some())
+    }
+
+    fn same_key(&self, other: &MappingTemplate) -> bool {
+        self.sys_object_id == other.sys_object_id
+            && self.model.as_deref().map(str::to_lowercase)
+                == other.model.as_deref().map(str::to_lowercase)
+    }
+}
+
+fn normalized(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// Saved model templates, most specific first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MappingTemplates {
+    templates: Vec<MappingTemplate>,
+}
+
+impl MappingTemplates {
+    pub fn templates(&self) -> &[MappingTemplate] {
+        &self.templates
+    }
+
+    /// The most specific template for `record`, preferring one that names
+    /// both keys whatever order the file lists them in.
+    pub fn find(&self, record: &PrinterRecord) -> Option<&MappingTemplate> {
+        self.templates
+            .iter()
+            .filter(|template| template.matches(record))
+            .min_by_key(|template| std::cmp::Reverse(template.specificity()))
+    }
+
+    /// Stores `template`, replacing one saved for the same model.
+    pub fn set(&mut self, template: MappingTemplate) {
+        self.templates
+            .retain(|existing| !existing.same_key(&template));
+        self.templates.push(template);
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.templates
+            .sort_by_key(|template| std::cmp::Reverse(template.specificity()));
+    }
+
+    /// Reads saved templates; a file edited by hand is put back in order.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut templates: Self = storage::load_ron(path.as_ref())?;
+        templates.sort();
+        Ok(templates)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        storage::save_ron(path.as_ref(), self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter_table::RICOH_ENG_COUNTER_ENTRY_OID;
+    use crate::model::PrinterId;
+    use crate::snmp::{SnmpValue, SnmpVarBind};
+    use crate::storage::tests::TempDir;
+
+    fn printer(id: &str, sys_object_id: &str, model: &str) -> PrinterRecord {
+        let mut record = PrinterRecord::new(PrinterId::new(id));
+        record.sys_object_id = Some(sys_object_id.to_string());
+        record.model = Some(model.to_string());
+        record
+    }
+
+    fn mapping(oid: &str) -> CounterMapping {
+        let oid: Oid = oid.parse().unwrap();
+        CounterMapping {
+            recording: BTreeMap::from([(
+                RecordingCategory::CopiesBw,
+                vec![
+                    CounterRef::Named("Copier: Black & White".to_string()),
+                    CounterRef::Oid(oid.clone()),
+                ],
+            )]),
+            clicks: CounterOidSet {
+                bw: vec![oid],
+                ..CounterOidSet::default()
+            },
+        }
+    }
+
+    #[test]
+    fn templates_match_printers_of_the_same_model() {
+        let c4502 = printer("a", "1.3.6.1.4.1.367.1.1", "RICOH Aficio MP C4502");
+        let other_c4502 = printer("b", "1.3.6.1.4.1.367.1.1", "ricoh aficio mp c4502 ");
+        let mp7502 = printer("c", "1.3.6.1.4.1.367.1.1", "RICOH Aficio MP 7502");
+
+        let mut templates = MappingTemplates::default();
+        templates.set(
+            MappingTemplate::for_printer(&c4502, mapping("1.3.6.1.2.1.43.10.2.1.4.1.1")).unwrap(),
+        );
+        // A vendor-wide fallback sorts after the model template.
+        templates.set(MappingTemplate {
+            sys_object_id: Some("1.3.6.1.4.1.367.1.1".to_string()),
+            model: None,
+            mapping: mapping("1.3.6.1.2.1.43.10.2.1.4.1.3"),
+        });
+        templates.set(
+            MappingTemplate::for_printer(&other_c4502, mapping("1.3.6.1.2.1.43.10.2.1.4.1.2"))
+                .unwrap(),
+        );
+
+        assert_eq!(templates.templates().len(), 2);
+        let found = templates.find(&other_c4502).unwrap();
+        assert_eq!(found.mapping, mapping("1.3.6.1.2.1.43.10.2.1.4.1.2"));
+        let fallback = templates.find(&mp7502).unwrap();
+        assert_eq!(fallback.model, None);
+        assert!(templates
+            .find(&PrinterRecord::new(PrinterId::new("d")))
+            .is_none());
+        assert!(MappingTemplate::for_printer(
+            &PrinterRecord::new(PrinterId::new("d")),
+            CounterMapping::default()
+        )
+        .is_none());
+
+        // A file listing the vendor-wide template first still finds the
+        // model one, both before and after loading.
+        let mut edited = templates.clone();
+        edited.templates.reverse();
+        assert_eq!(edited.find(&other_c4502).unwrap().model, found.model);
+        let dir = TempDir::new("mapping");
+        let path = dir.join("templates.ron");
+        edited.save(&path).expect("save");
+        assert_eq!(MappingTemplates::load(&path).expect("load"), templates);
+    }
+
+    #[test]
+    fn poll_oids_resolve_names_per_printer() {
+        let mapping = mapping("1.3.6.1.2.1.43.10.2.1.4.1.1");
+        let fallback: Oid = "1.3.6.1.2.1.43.10.2.1.4.1.1".parse().unwrap();
+        assert_eq!(
+            mapping.poll_oids(&CounterTable::default()),
+            vec![fallback.clone()]
+        );
+
+        let mut name_oid = RICOH_ENG_COUNTER_ENTRY_OID.to_vec();
+        name_oid.extend([5, 18]);
+        let mut value_oid = RICOH_ENG_COUNTER_ENTRY_OID.to_vec();
+        value_oid.extend([9, 18]);
+        let table = CounterTable::from_walk(&[
+            SnmpVarBind {
+                oid: Oid(name_oid),
+                value: SnmpValue::OctetString(b"Copier: Black & White".to_vec()),
+            },
+            SnmpVarBind {
+                oid: Oid(value_oid.clone()),
+                value: SnmpValue::Integer(28_378),
+            },
+        ]);
+        assert_eq!(
+            mapping.recording_oids(RecordingCategory::CopiesBw, &table),
+            vec![Oid(value_oid), fallback]
+        );
+        assert!(mapping
+            .recording_oids(RecordingCategory::PrintsBw, &table)
+            .is_empty());
+    }
+}
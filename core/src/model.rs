@@ -2,6 +2,9 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
+use crate::counter_table::CounterTable;
+use crate::mapping::CounterMapping;
+
 pub type EpochSeconds = u64;
 
 pub const DEFAULT_SNMP_PORT: u16 = 161;
@@ -21,7 +24,7 @@ impl fmt::Display for PrinterId {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SnmpAddress {
     pub host: String,
     #[serde(default = "default_snmp_port")]
@@ -54,17 +57,130 @@ fn default_snmp_port() -> u16 {
     DEFAULT_SNMP_PORT
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PrinterStatus {
+    #[default]
     Unknown,
     Online,
     Offline,
     Error,
 }
 
-impl Default for PrinterStatus {
-    fn default() -> Self {
-        PrinterStatus::Unknown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnmpSecurityLevel {
+    NoAuthNoPriv,
+    AuthNoPriv,
+    #[default]
+    AuthPriv,
+}
+
+impl SnmpSecurityLevel {
+    pub const ALL: [SnmpSecurityLevel; 3] = [
+        SnmpSecurityLevel::NoAuthNoPriv,
+        SnmpSecurityLevel::AuthNoPriv,
+        SnmpSecurityLevel::AuthPriv,
+    ];
+}
+
+impl fmt::Display for SnmpSecurityLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnmpSecurityLevel::NoAuthNoPriv => f.write_str("noAuthNoPriv"),
+            SnmpSecurityLevel::AuthNoPriv => f.write_str("authNoPriv"),
+            SnmpSecurityLevel::AuthPriv => f.write_str("authPriv"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnmpAuthProtocol {
+    Md5,
+    #[default]
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl SnmpAuthProtocol {
+    pub const ALL: [SnmpAuthProtocol; 6] = [
+        SnmpAuthProtocol::Md5,
+        SnmpAuthProtocol::Sha1,
+        SnmpAuthProtocol::Sha224,
+        SnmpAuthProtocol::Sha256,
+        SnmpAuthProtocol::Sha384,
+        SnmpAuthProtocol::Sha512,
+    ];
+}
+
+impl fmt::Display for SnmpAuthProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnmpAuthProtocol::Md5 => f.write_str("MD5"),
+            SnmpAuthProtocol::Sha1 => f.write_str("SHA"),
+            SnmpAuthProtocol::Sha224 => f.write_str("SHA-224"),
+            SnmpAuthProtocol::Sha256 => f.write_str("SHA-256"),
+            SnmpAuthProtocol::Sha384 => f.write_str("SHA-384"),
+            SnmpAuthProtocol::Sha512 => f.write_str("SHA-512"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnmpPrivProtocol {
+    Des,
+    #[default]
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+impl SnmpPrivProtocol {
+    pub const ALL: [SnmpPrivProtocol; 4] = [
+        SnmpPrivProtocol::Des,
+        SnmpPrivProtocol::Aes128,
+        SnmpPrivProtocol::Aes192,
+        SnmpPrivProtocol::Aes256,
+    ];
+}
+
+impl fmt::Display for SnmpPrivProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnmpPrivProtocol::Des => f.write_str("DES"),
+            SnmpPrivProtocol::Aes128 => f.write_str("AES-128"),
+            SnmpPrivProtocol::Aes192 => f.write_str("AES-192"),
+            SnmpPrivProtocol::Aes256 => f.write_str("AES-256"),
+        }
+    }
+}
+
+/// USM credentials for printers that only answer SNMPv3.
+///
+/// Passwords are unused below the matching security level; the engine ID is
+/// discovered from the agent on every new session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnmpV3Credentials {
+    pub username: String,
+    #[serde(default)]
+    pub security_level: SnmpSecurityLevel,
+    #[serde(default)]
+    pub auth_protocol: SnmpAuthProtocol,
+    #[serde(default)]
+    pub auth_password: String,
+    #[serde(default)]
+    pub priv_protocol: SnmpPrivProtocol,
+    #[serde(default)]
+    pub priv_password: String,
+}
+
+impl SnmpV3Credentials {
+    pub fn new(username: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            ..Self::default()
+        }
     }
 }
 
@@ -77,8 +193,32 @@ pub struct PrinterRecord {
     pub snmp_address: Option<SnmpAddress>,
     pub community: Option<String>,
     #[serde(default)]
+    pub snmp_v3: Option<SnmpV3Credentials>,
+    #[serde(default)]
     pub status: PrinterStatus,
     pub last_seen: Option<EpochSeconds>,
+    #[serde(default)]
+    pub polling: PollSettings,
+    /// Named counters last read from the printer.
+    #[serde(default)]
+    pub counter_table: CounterTable,
+    /// This printer's own counters; unset falls back to its model template.
+    #[serde(default)]
+    pub counter_mapping: Option<CounterMapping>,
+}
+
+/// Per-printer overrides for the fleet poller.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Falls back to the poller's global interval when unset.
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    /// Stops an active recording once none of its counters has moved for
+    /// this long; unset leaves recordings open until stopped by hand.
+    #[serde(default)]
+    pub auto_stop_idle_secs: Option<u64>,
 }
 
 impl PrinterRecord {
@@ -90,8 +230,12 @@ impl PrinterRecord {
             sys_object_id: None,
             snmp_address: None,
             community: None,
+            snmp_v3: None,
             status: PrinterStatus::Unknown,
             last_seen: None,
+            polling: PollSettings::default(),
+            counter_table: CounterTable::default(),
+            counter_mapping: None,
         }
     }
 }
@@ -111,6 +255,9 @@ pub struct CounterSnapshot {
     pub timestamp: EpochSeconds,
     #[serde(default)]
     pub source_oids: CounterOids,
+    /// sysUpTime polled with the counters, used to tell resets from wraps.
+    #[serde(default)]
+    pub uptime: Option<u64>,
 }
 
 impl CounterSnapshot {
@@ -121,8 +268,170 @@ impl CounterSnapshot {
             total: None,
             timestamp,
             source_oids: CounterOids::default(),
+            uptime: None,
+        }
+    }
+}
+
+/// Page categories a recording session counts and a price list charges.
+/// Every page falls into exactly one of the copies and prints categories;
+/// the others count some of those pages again, or scans and faxes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum RecordingCategory {
+    CopiesBw,
+    CopiesColor,
+    PrintsBw,
+    PrintsColor,
+    Scans,
+    Faxes,
+    /// A3 and other large sheets.
+    Large,
+    TwoColor,
+    SingleColor,
+    /// Sheets printed on both sides.
+    Duplex,
+}
+
+impl RecordingCategory {
+    pub const ALL: [RecordingCategory; 10] = [
+        RecordingCategory::CopiesBw,
+        RecordingCategory::CopiesColor,
+        RecordingCategory::PrintsBw,
+        RecordingCategory::PrintsColor,
+        RecordingCategory::Scans,
+        RecordingCategory::Faxes,
+        RecordingCategory::Large,
+        RecordingCategory::TwoColor,
+        RecordingCategory::SingleColor,
+        RecordingCategory::Duplex,
+    ];
+
+    /// The categories recorded until others are turned on.
+    pub const DEFAULT: [RecordingCategory; 4] = [
+        RecordingCategory::CopiesBw,
+        RecordingCategory::CopiesColor,
+        RecordingCategory::PrintsBw,
+        RecordingCategory::PrintsColor,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RecordingCategory::CopiesBw => "Copies B/W",
+            RecordingCategory::CopiesColor => "Copies color",
+            RecordingCategory::PrintsBw => "Prints B/W",
+            RecordingCategory::PrintsColor => "Prints color",
+            RecordingCategory::Scans => "Scans",
+            RecordingCategory::Faxes => "Faxes",
+            RecordingCategory::Large => "Large (A3)",
+            RecordingCategory::TwoColor => "Two-color",
+            RecordingCategory::SingleColor => "Single-color",
+            RecordingCategory::Duplex => "Duplex",
         }
     }
+
+    /// One of the copies and prints categories, whose counters add up to
+    /// the printer's clicks.
+    pub fn is_base(self) -> bool {
+        Self::DEFAULT.contains(&self)
+    }
+
+    /// Counts pages a copies or prints category counts too, so a price for
+    /// it is charged on top of theirs rather than instead of it.
+    pub fn is_surcharge(self) -> bool {
+        matches!(
+            self,
+            RecordingCategory::Large
+                | RecordingCategory::TwoColor
+                | RecordingCategory::SingleColor
+                | RecordingCategory::Duplex
+        )
+    }
+
+    pub fn is_color(self) -> bool {
+        matches!(
+            self,
+            RecordingCategory::CopiesColor
+                | RecordingCategory::PrintsColor
+                | RecordingCategory::TwoColor
+                | RecordingCategory::SingleColor
+        )
+    }
+
+    /// Pages sent from a PC rather than copied at the machine.
+    pub fn is_print(self) -> bool {
+        matches!(self, RecordingCategory::PrintsBw | RecordingCategory::PrintsColor)
+    }
+}
+
+/// "YYYY-MM-DD HH:MM UTC", as printed on receipts and in the price history.
+pub fn format_utc_timestamp(timestamp: EpochSeconds) -> String {
+    let (year, month, day) = civil_from_days(timestamp / 86_400);
+    let seconds_of_day = timestamp % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds_of_day / 3_600,
+        (seconds_of_day % 3_600) / 60
+    )
+}
+
+/// Unix seconds for a UTC date and time, or `None` when the date does not
+/// exist or falls outside 1970 to 9999.
+pub fn utc_timestamp(
+    year: u64,
+    month: u64,
+    day: u64,
+    hours: u64,
+    minutes: u64,
+) -> Option<EpochSeconds> {
+    if !(1970..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hours > 23
+        || minutes > 59
+    {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86_400 + hours * 3_600 + minutes * 60)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date from 1970 on.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
 }
 
 #[cfg(test)]
@@ -138,8 +447,16 @@ mod tests {
             sys_object_id: Some("1.3.6.1.4.1.367.3.2".to_string()),
             snmp_address: Some(SnmpAddress::with_default_port("192.168.1.5")),
             community: Some("public".to_string()),
+            snmp_v3: None,
             status: PrinterStatus::Online,
             last_seen: Some(1_725_000_000),
+            polling: PollSettings {
+                enabled: true,
+                interval_secs: Some(30),
+                auto_stop_idle_secs: Some(1_800),
+            },
+            counter_table: CounterTable::default(),
+            counter_mapping: None,
         };
 
         let snapshot = CounterSnapshot {
@@ -152,6 +469,7 @@ mod tests {
                 color: Some("1.3.6.1.2.1.43.10.2.1.4.1.2".to_string()),
                 total: Some("1.3.6.1.2.1.43.10.2.1.4.1.3".to_string()),
             },
+            uptime: Some(8_640_000),
         };
 
         let ron = ron::ser::to_string_pretty(
@@ -166,4 +484,37 @@ mod tests {
         assert_eq!(decoded.0.snmp_address.unwrap().port, DEFAULT_SNMP_PORT);
         assert_eq!(decoded.1.total, Some(165));
     }
+
+    #[test]
+    fn printer_record_without_v3_field_loads() {
+        let ron = r#"(
+            id: ("printer-002"),
+            ip_or_hostname: Some("192.168.1.6"),
+            model: None,
+            sys_object_id: None,
+            snmp_address: Some((host: "192.168.1.6")),
+            community: None,
+            last_seen: None,
+        )"#;
+        let record: PrinterRecord = ron::from_str(ron).expect("deserialize RON");
+        assert!(record.snmp_v3.is_none());
+        assert_eq!(record.status, PrinterStatus::Unknown);
+    }
+
+    #[test]
+    fn snmp_v3_credentials_roundtrip() {
+        let mut record = PrinterRecord::new(PrinterId::new("printer-003"));
+        record.snmp_v3 = Some(SnmpV3Credentials {
+            username: "billing".to_string(),
+            security_level: SnmpSecurityLevel::AuthPriv,
+            auth_protocol: SnmpAuthProtocol::Sha256,
+            auth_password: "authpass123".to_string(),
+            priv_protocol: SnmpPrivProtocol::Aes256,
+            priv_password: "privpass123".to_string(),
+        });
+
+        let ron = ron::ser::to_string(&record).expect("serialize RON");
+        let decoded: PrinterRecord = ron::from_str(&ron).expect("deserialize RON");
+        assert_eq!(decoded.snmp_v3, record.snmp_v3);
+    }
 }
@@ -0,0 +1,565 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// An exact amount in the minor unit of its currency, e.g. cents for EUR.
+/// Arithmetic saturates instead of wrapping.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_minor(minor: i64) -> Self {
+        Self(minor)
+    }
+
+    pub const fn minor(self) -> i64 {
+        self.0
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The price of `quantity` items at `self` each.
+    pub fn times(self, quantity: u64) -> Self {
+        let quantity = i64::try_from(quantity).unwrap_or(i64::MAX);
+        Self(self.0.saturating_mul(quantity))
+    }
+
+    /// Parses an amount such as "1.234,50" under `format`. More decimal places
+    /// than `currency` has are rejected rather than rounded. A trailing or
+    /// leading currency code is ignored.
+    pub fn parse(input: &str, currency: &Currency, format: &MoneyFormat) -> Result<Self, Error> {
+        let invalid = |details: &str| Error::InvalidAmount {
+            input: input.to_string(),
+            details: details.to_string(),
+        };
+
+        let code = currency.code.as_str();
+        let mut text = input.trim();
+        if !code.is_empty() {
+            if let Some(head) = text.get(..code.len())
+                && head.eq_ignore_ascii_case(code)
+            {
+                text = text[code.len()..].trim_start();
+            }
+            if let Some(start) = text.len().checked_sub(code.len())
+                && let Some(tail) = text.get(start..)
+                && tail.eq_ignore_ascii_case(code)
+            {
+                text = text[..start].trim_end();
+            }
+        }
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, text),
+        };
+
+        let thousands = format.thousands.as_char();
+        let digits: String = text
+            .chars()
+            .filter(|ch| Some(*ch) != thousands && !(thousands == Some(' ') && *ch == '\u{a0}'))
+            .collect();
+        // The other of '.' and ',' is taken as the decimal point too, as long
+        // as it is not the thousands separator.
+        let decimal = format.decimal.as_char();
+        let alternative = if decimal == '.' { ',' } else { '.' };
+        let split_at = digits
+            .find(decimal)
+            .or_else(|| (thousands != Some(alternative)).then(|| digits.find(alternative))?);
+        let (whole, fraction) = match split_at {
+            Some(index) => (&digits[..index], &digits[index + 1..]),
+            None => (digits.as_str(), ""),
+        };
+
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid("no digits"));
+        }
+        if !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|ch| ch.is_ascii_digit())
+        {
+            return Err(invalid("unexpected characters"));
+        }
+        let minor_digits = currency.digits();
+        if fraction.len() > minor_digits {
+            return Err(invalid(&format!(
+                "{} allows {minor_digits} decimal places",
+                currency.code
+            )));
+        }
+
+        let too_large = || invalid("amount too large");
+        let whole: i64 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().map_err(|_| too_large())?
+        };
+        let fraction: i64 = if fraction.is_empty() {
+            0
+        } else {
+            format!("{fraction:0<minor_digits$}")
+                .parse()
+                .map_err(|_| too_large())?
+        };
+        let minor = whole
+            .checked_mul(currency.minor_factor())
+            .and_then(|minor| minor.checked_add(fraction))
+            .ok_or_else(too_large)?;
+        Ok(Self(if negative { -minor } else { minor }))
+    }
+
+    /// The amount without currency code, e.g. "1.234,50".
+    pub fn format_amount(self, currency: &Currency, format: &MoneyFormat) -> String {
+        let magnitude = self.0.unsigned_abs();
+        let factor = currency.minor_factor().unsigned_abs();
+        let whole = (magnitude / factor).to_string();
+        let mut grouped = String::with_capacity(whole.len() + whole.len() / 3);
+        for (index, digit) in whole.chars().enumerate() {
+            if let Some(separator) = format.thousands.as_char()
+                && index > 0
+                && (whole.len() - index).is_multiple_of(3)
+            {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let minor_digits = currency.digits();
+        if minor_digits == 0 {
+            return format!("{sign}{grouped}");
+        }
+        format!(
+            "{sign}{grouped}{}{:0minor_digits$}",
+            format.decimal.as_char(),
+            magnitude % factor
+        )
+    }
+
+    /// The amount followed by the currency code, e.g. "1.234,50 EUR".
+    pub fn format(self, currency: &Currency, format: &MoneyFormat) -> String {
+        let amount = self.format_amount(currency, format);
+        if currency.code.is_empty() {
+            amount
+        } else {
+            format!("{amount} {}", currency.code)
+        }
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(self.0.saturating_neg())
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Currency {
+    /// ISO 4217 code shown next to amounts.
+    pub code: String,
+    /// Decimal places of the minor unit: 2 for EUR, 0 for JPY.
+    pub minor_digits: u32,
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self::new("EUR", 2)
+    }
+}
+
+impl Currency {
+    /// More decimal places than this do not fit a minor unit in `Money`.
+    pub const MAX_MINOR_DIGITS: u32 = 4;
+
+    pub fn new(code: impl Into<String>, minor_digits: u32) -> Self {
+        Self {
+            code: code.into(),
+            minor_digits,
+        }
+    }
+
+    /// Minor units in one major unit.
+    pub fn minor_factor(&self) -> i64 {
+        10i64.pow(self.digits() as u32)
+    }
+
+    fn digits(&self) -> usize {
+        self.minor_digits.min(Self::MAX_MINOR_DIGITS) as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecimalSeparator {
+    #[default]
+    Point,
+    Comma,
+}
+
+impl DecimalSeparator {
+    pub const ALL: [DecimalSeparator; 2] = [DecimalSeparator::Point, DecimalSeparator::Comma];
+
+    pub fn as_char(self) -> char {
+        match self {
+            DecimalSeparator::Point => '.',
+            DecimalSeparator::Comma => ',',
+        }
+    }
+}
+
+impl fmt::Display for DecimalSeparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecimalSeparator::Point => f.write_str("Point (1.50)"),
+            DecimalSeparator::Comma => f.write_str("Comma (1,50)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThousandsSeparator {
+    #[default]
+    None,
+    Point,
+    Comma,
+    Space,
+    Apostrophe,
+}
+
+impl ThousandsSeparator {
+    pub const ALL: [ThousandsSeparator; 5] = [
+        ThousandsSeparator::None,
+        ThousandsSeparator::Point,
+        ThousandsSeparator::Comma,
+        ThousandsSeparator::Space,
+        ThousandsSeparator::Apostrophe,
+    ];
+
+    pub fn as_char(self) -> Option<char> {
+        match self {
+            ThousandsSeparator::None => None,
+            ThousandsSeparator::Point => Some('.'),
+            ThousandsSeparator::Comma => Some(','),
+            ThousandsSeparator::Space => Some(' '),
+            ThousandsSeparator::Apostrophe => Some('\''),
+        }
+    }
+}
+
+impl fmt::Display for ThousandsSeparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThousandsSeparator::None => f.write_str("None (1000)"),
+            ThousandsSeparator::Point => f.write_str("Point (1.000)"),
+            ThousandsSeparator::Comma => f.write_str("Comma (1,000)"),
+            ThousandsSeparator::Space => f.write_str("Space (1 000)"),
+            ThousandsSeparator::Apostrophe => f.write_str("Apostrophe (1'000)"),
+        }
+    }
+}
+
+/// How amounts are written and read back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoneyFormat {
+    pub decimal: DecimalSeparator,
+    pub thousands: ThousandsSeparator,
+}
+
+impl MoneyFormat {
+    /// Whether amounts written this way can be read back unambiguously.
+    pub fn is_unambiguous(&self) -> bool {
+        self.thousands.as_char() != Some(self.decimal.as_char())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoundingMode {
+    /// Halves go up, as in 0.25 -> 0.50 with a 0.50 step.
+    #[serde(alias = "Nearest")]
+    HalfUp,
+    /// Halves go to the even multiple, as in 0.25 -> 0.00 and 0.75 -> 1.00
+    /// with a 0.50 step.
+    HalfEven,
+    Up,
+    Down,
+}
+
+impl RoundingMode {
+    pub const ALL: [RoundingMode; 4] = [
+        RoundingMode::HalfUp,
+        RoundingMode::HalfEven,
+        RoundingMode::Up,
+        RoundingMode::Down,
+    ];
+}
+
+impl fmt::Display for RoundingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundingMode::HalfUp => f.write_str("Half up"),
+            RoundingMode::HalfEven => f.write_str("Half even"),
+            RoundingMode::Up => f.write_str("Up"),
+            RoundingMode::Down => f.write_str("Down"),
+        }
+    }
+}
+
+/// Rounds an amount to a multiple of `step`, e.g. 0.05 for cash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rounding {
+    #[serde(alias = "step_cents")]
+    pub step: Money,
+    pub mode: RoundingMode,
+}
+
+impl Rounding {
+    pub fn half_up(step: Money) -> Self {
+        Self {
+            step,
+            mode: RoundingMode::HalfUp,
+        }
+    }
+
+    /// Negative amounts round like their magnitude, so -0.25 with a 0.50
+    /// half-up step becomes -0.50.
+    pub fn apply(&self, amount: Money) -> Money {
+        let step = self.step.minor().unsigned_abs();
+        if step <= 1 {
+            return amount;
+        }
+        let magnitude = amount.minor().unsigned_abs();
+        let below = magnitude / step * step;
+        let remainder = magnitude - below;
+        let up = remainder > 0
+            && match self.mode {
+                RoundingMode::HalfUp => remainder * 2 >= step,
+                RoundingMode::HalfEven if remainder * 2 == step => (below / step) % 2 == 1,
+                RoundingMode::HalfEven => remainder * 2 > step,
+                RoundingMode::Up => true,
+                RoundingMode::Down => false,
+            };
+        let rounded = if up {
+            below.saturating_add(step)
+        } else {
+            below
+        };
+        let rounded = Money(i64::try_from(rounded).unwrap_or(i64::MAX));
+        if amount.minor() < 0 {
+            -rounded
+        } else {
+            rounded
+        }
+    }
+}
+
+/// A VAT rate in hundredths of a percent, so 1900 is 19% and 770 is 7.7%.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct VatRate(u32);
+
+impl VatRate {
+    pub const ZERO: VatRate = VatRate(0);
+
+    pub const fn from_basis_points(basis_points: u32) -> Self {
+        Self(basis_points)
+    }
+
+    pub const fn basis_points(self) -> u32 {
+        self.0
+    }
+
+    /// Parses a percentage such as "19", "7.7" or "7,7 %".
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let percent = input.trim().trim_end_matches('%');
+        let minor = Money::parse(percent, &Currency::new("", 2), &MoneyFormat::default())?.minor();
+        u32::try_from(minor)
+            .ok()
+            .filter(|basis_points| *basis_points <= 10_000)
+            .map(Self)
+            .ok_or_else(|| Error::InvalidAmount {
+                input: input.to_string(),
+                details: "VAT rate must be between 0 and 100%".to_string(),
+            })
+    }
+
+    /// The VAT contained in a price that includes it, to the nearest minor
+    /// unit with halves rounded up.
+    pub fn included_in(self, gross: Money) -> Money {
+        Self::divide_rounded(
+            i128::from(gross.minor()) * i128::from(self.0),
+            10_000 + self.0,
+        )
+    }
+
+    /// The VAT to add to a price that excludes it, to the nearest minor unit
+    /// with halves rounded up.
+    pub fn on_net(self, net: Money) -> Money {
+        Self::divide_rounded(i128::from(net.minor()) * i128::from(self.0), 10_000)
+    }
+
+    fn divide_rounded(numerator: i128, denominator: u32) -> Money {
+        let denominator = i128::from(denominator);
+        let half = denominator / 2;
+        let quotient = if numerator >= 0 {
+            (numerator + half) / denominator
+        } else {
+            (numerator - half) / denominator
+        };
+        Money(i64::try_from(quotient).unwrap_or(i64::MAX))
+    }
+}
+
+impl fmt::Display for VatRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let whole = self.0 / 100;
+        match self.0 % 100 {
+            0 => write!(f, "{whole}%"),
+            fraction if fraction % 10 == 0 => write!(f, "{whole}.{}%", fraction / 10),
+            fraction => write!(f, "{whole}.{fraction:02}%"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eur() -> Currency {
+        Currency::default()
+    }
+
+    #[test]
+    fn parses_and_formats_exactly() {
+        let german = MoneyFormat {
+            decimal: DecimalSeparator::Comma,
+            thousands: ThousandsSeparator::Point,
+        };
+        let amount = Money::parse("1.234,5 EUR", &eur(), &german).expect("parse");
+        assert_eq!(amount, Money::from_minor(123_450));
+        assert_eq!(amount.format(&eur(), &german), "1.234,50 EUR");
+        assert_eq!(
+            amount.format_amount(&eur(), &MoneyFormat::default()),
+            "1234.50"
+        );
+
+        // With no thousands separator either mark is read as the decimal point.
+        let plain = MoneyFormat::default();
+        assert_eq!(
+            Money::parse("0,07", &eur(), &plain).expect("parse"),
+            Money::from_minor(7)
+        );
+        // 0.1 + 0.2 stays exact.
+        let sum = Money::parse("0.1", &eur(), &plain).expect("parse")
+            + Money::parse("0.2", &eur(), &plain).expect("parse");
+        assert_eq!(sum, Money::from_minor(30));
+        assert_eq!((-sum).format(&eur(), &plain), "-0.30 EUR");
+
+        assert!(Money::parse("0.255", &eur(), &plain).is_err());
+        assert!(Money::parse("1.2.3", &eur(), &plain).is_err());
+        let yen = Currency::new("JPY", 0);
+        assert_eq!(
+            Money::parse("1500", &yen, &plain)
+                .expect("parse")
+                .format(&yen, &plain),
+            "1500 JPY"
+        );
+    }
+
+    #[test]
+    fn rounding_modes() {
+        let cents = Money::from_minor;
+        let step = |minor, mode| Rounding {
+            step: cents(minor),
+            mode,
+        };
+        assert_eq!(Rounding::half_up(cents(50)).apply(cents(124)), cents(100));
+        assert_eq!(Rounding::half_up(cents(50)).apply(cents(125)), cents(150));
+        assert_eq!(step(50, RoundingMode::HalfEven).apply(cents(25)), cents(0));
+        assert_eq!(
+            step(50, RoundingMode::HalfEven).apply(cents(75)),
+            cents(100)
+        );
+        assert_eq!(
+            step(5, RoundingMode::HalfEven).apply(cents(103)),
+            cents(105)
+        );
+        assert_eq!(
+            step(10, RoundingMode::HalfEven).apply(cents(115)),
+            cents(120)
+        );
+        assert_eq!(
+            step(10, RoundingMode::HalfEven).apply(cents(125)),
+            cents(120)
+        );
+        assert_eq!(step(5, RoundingMode::Up).apply(cents(101)), cents(105));
+        assert_eq!(step(5, RoundingMode::Up).apply(cents(100)), cents(100));
+        assert_eq!(step(100, RoundingMode::Down).apply(cents(199)), cents(100));
+        assert_eq!(Rounding::half_up(cents(50)).apply(cents(-25)), cents(-50));
+    }
+
+    #[test]
+    fn vat_rates() {
+        let rate = VatRate::parse("7,7 %").expect("parse");
+        assert_eq!(rate, VatRate::from_basis_points(770));
+        assert_eq!(rate.to_string(), "7.7%");
+        assert_eq!(VatRate::from_basis_points(1900).to_string(), "19%");
+        assert!(VatRate::parse("101").is_err());
+
+        let standard = VatRate::from_basis_points(1900);
+        // 2.00 gross holds 0.3193... VAT, 2.00 net adds 0.38.
+        assert_eq!(
+            standard.included_in(Money::from_minor(200)),
+            Money::from_minor(32)
+        );
+        assert_eq!(
+            standard.on_net(Money::from_minor(200)),
+            Money::from_minor(38)
+        );
+        assert_eq!(
+            VatRate::ZERO.included_in(Money::from_minor(200)),
+            Money::ZERO
+        );
+    }
+}
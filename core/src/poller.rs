@@ -0,0 +1,667 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::{mpsc, Notify};
+use tokio::time::timeout;
+use tracing::{debug, info};
+
+use crate::counters::{resolve_counters, CounterOidSet, CounterResolution};
+use crate::health::{HealthPolicy, PrinterHealth, StatusTransition};
+use crate::model::{EpochSeconds, PrinterId, PrinterStatus, SnmpAddress};
+use crate::snmp::{Oid, SnmpClient, SnmpRequest, SYS_UPTIME_OID};
+use crate::{targets, Error};
+
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+pub const DEFAULT_MAX_CONCURRENT_POLLS: usize = 4;
+
+/// Longest the scheduler sleeps when nothing is due; any change wakes it early.
+const IDLE_WAIT: Duration = Duration::from_secs(60);
+
+/// A printer the poller knows about. Disabled targets are only polled when
+/// `Poller::poll_now` asks for them.
+#[derive(Clone)]
+pub struct PollTarget {
+    pub printer_id: PrinterId,
+    pub address: SnmpAddress,
+    pub community: Option<String>,
+    pub client: Arc<dyn SnmpClient>,
+    /// Overrides the poller's global interval for this printer.
+    pub interval: Option<Duration>,
+    pub enabled: bool,
+    /// Seeds the printer's health the first time the poller sees it.
+    pub status: PrinterStatus,
+    pub last_seen: Option<EpochSeconds>,
+    /// This printer's own counters and extra OIDs, used instead of the ones
+    /// set with `Poller::set_oids`.
+    pub oids: Option<(CounterOidSet, Vec<Oid>)>,
+}
+
+impl PollTarget {
+    pub fn new(printer_id: PrinterId, address: SnmpAddress, client: Arc<dyn SnmpClient>) -> Self {
+        Self {
+            printer_id,
+            address,
+            community: None,
+            client,
+            interval: None,
+            enabled: true,
+            status: PrinterStatus::Unknown,
+            last_seen: None,
+            oids: None,
+        }
+    }
+
+    pub fn with_community(mut self, community: impl Into<String>) -> Self {
+        self.community = Some(community.into());
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn with_last_known(
+        mut self,
+        status: PrinterStatus,
+        last_seen: Option<EpochSeconds>,
+    ) -> Self {
+        self.status = status;
+        self.last_seen = last_seen;
+        self
+    }
+
+    pub fn with_oids(mut self, counter_oids: CounterOidSet, extra: Vec<Oid>) -> Self {
+        self.oids = Some((counter_oids, extra));
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct PollEvent {
+    pub printer_id: PrinterId,
+    pub address: SnmpAddress,
+    pub polled_at: EpochSeconds,
+    pub result: Result<CounterResolution, Error>,
+    /// Health after this poll; `transition` is set when the status changed.
+    pub status: PrinterStatus,
+    pub last_seen: Option<EpochSeconds>,
+    pub transition: Option<StatusTransition>,
+}
+
+/// Polls every enabled printer on its own schedule, at most `max_concurrent`
+/// at a time, backing off from printers that stopped answering. Clones share
+/// the same schedule, so the UI can keep one handle for configuration while
+/// another drives `run`.
+#[derive(Clone, Default)]
+pub struct Poller {
+    shared: Arc<PollerShared>,
+}
+
+#[derive(Default)]
+struct PollerShared {
+    state: Mutex<PollerState>,
+    wake: Notify,
+}
+
+struct PollerState {
+    interval: Duration,
+    max_concurrent: usize,
+    health_policy: HealthPolicy,
+    counter_oids: CounterOidSet,
+    request_oids: Vec<Oid>,
+    targets: HashMap<PrinterId, ScheduledTarget>,
+    in_flight: usize,
+}
+
+impl Default for PollerState {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_POLL_INTERVAL,
+            max_concurrent: DEFAULT_MAX_CONCURRENT_POLLS,
+            health_policy: HealthPolicy::default(),
+            counter_oids: CounterOidSet::default(),
+            request_oids: Vec::new(),
+            targets: HashMap::new(),
+            in_flight: 0,
+        }
+    }
+}
+
+struct ScheduledTarget {
+    target: PollTarget,
+    next_due: Instant,
+    in_flight: bool,
+    poll_now: bool,
+    health: PrinterHealth,
+}
+
+struct PollJob {
+    target: PollTarget,
+    request_oids: Vec<Oid>,
+    counter_oids: CounterOidSet,
+}
+
+struct PollOutcome {
+    printer_id: PrinterId,
+    address: SnmpAddress,
+    polled_at: EpochSeconds,
+    result: Result<CounterResolution, Error>,
+}
+
+impl Poller {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_interval(self, interval: Duration) -> Self {
+        self.set_interval(interval);
+        self
+    }
+
+    pub fn with_max_concurrent(self, max_concurrent: usize) -> Self {
+        self.set_max_concurrent(max_concurrent);
+        self
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.lock().interval
+    }
+
+    pub fn set_interval(&self, interval: Duration) {
+        self.lock().interval = interval;
+        self.shared.wake.notify_one();
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.lock().max_concurrent
+    }
+
+    pub fn set_max_concurrent(&self, max_concurrent: usize) {
+        self.lock().max_concurrent = max_concurrent.max(1);
+        self.shared.wake.notify_one();
+    }
+
+    pub fn with_health_policy(self, policy: HealthPolicy) -> Self {
+        self.set_health_policy(policy);
+        self
+    }
+
+    pub fn set_health_policy(&self, policy: HealthPolicy) {
+        self.lock().health_policy = policy;
+    }
+
+    pub fn health(&self, printer_id: &PrinterId) -> Option<PrinterHealth> {
+        self.lock()
+            .targets
+            .get(printer_id)
+            .map(|scheduled| scheduled.health.clone())
+    }
+
+    /// Sets the counters every poll resolves, plus any extra OIDs fetched in
+    /// the same GET (printer name, recording counters). sysUpTime is always
+    /// requested so deltas can tell a restart from a counter wrap.
+    pub fn set_oids(&self, counter_oids: CounterOidSet, extra: &[Oid]) {
+        let request_oids = request_oids_for(&counter_oids, extra);
+        let mut state = self.lock();
+        state.counter_oids = counter_oids;
+        state.request_oids = request_oids;
+    }
+
+    /// Replaces the fleet. Printers that stay keep their schedule; new ones
+    /// are due immediately.
+    pub fn set_targets(&self, targets: impl IntoIterator<Item = PollTarget>) {
+        let now = Instant::now();
+        let mut state = self.lock();
+        let mut previous = std::mem::take(&mut state.targets);
+        for target in targets {
+            let printer_id = target.printer_id.clone();
+            let scheduled = match previous.remove(&printer_id) {
+                Some(mut existing) => {
+                    existing.target = target;
+                    existing
+                }
+                None => ScheduledTarget {
+                    health: PrinterHealth::new(target.status, target.last_seen),
+                    target,
+                    next_due: now,
+                    in_flight: false,
+                    poll_now: false,
+                },
+            };
+            state.targets.insert(printer_id, scheduled);
+        }
+        drop(state);
+        self.shared.wake.notify_one();
+    }
+
+    /// Polls `printer_id` as soon as a slot is free, even when it is disabled.
+    /// Returns false for printers the poller does not know.
+    pub fn poll_now(&self, printer_id: &PrinterId) -> bool {
+        let mut state = self.lock();
+        let Some(scheduled) = state.targets.get_mut(printer_id) else {
+            return false;
+        };
+        scheduled.poll_now = true;
+        drop(state);
+        self.shared.wake.notify_one();
+        true
+    }
+
+    pub fn is_polling(&self, printer_id: &PrinterId) -> bool {
+        self.lock()
+            .targets
+            .get(printer_id)
+            .is_some_and(|scheduled| scheduled.in_flight)
+    }
+
+    /// Drives the schedule, sending one event per completed poll. Returns once
+    /// the receiving side of `events` is dropped.
+    pub async fn run(&self, events: mpsc::Sender<PollEvent>) {
+        loop {
+            if events.is_closed() {
+                return;
+            }
+
+            let (jobs, wait) = self.take_due(Instant::now());
+            for job in jobs {
+                let poller = self.clone();
+                let events = events.clone();
+                tokio::spawn(async move {
+                    let outcome = job.poll().await;
+                    let event = poller.finish(outcome);
+                    let _ = events.send(event).await;
+                });
+            }
+
+            let _ = timeout(wait, self.shared.wake.notified()).await;
+        }
+    }
+
+    fn take_due(&self, now: Instant) -> (Vec<PollJob>, Duration) {
+        let mut state = self.lock();
+        let mut candidates: Vec<(bool, Instant, PrinterId)> = state
+            .targets
+            .values()
+            .filter(|scheduled| {
+                !scheduled.in_flight && (scheduled.poll_now || scheduled.target.enabled)
+            })
+            .map(|scheduled| {
+                (
+                    !scheduled.poll_now,
+                    scheduled.next_due,
+                    scheduled.target.printer_id.clone(),
+                )
+            })
+            .collect();
+        candidates.sort_by_key(|(scheduled_only, next_due, _)| (*scheduled_only, *next_due));
+
+        let request_oids = state.request_oids.clone();
+        let counter_oids = state.counter_oids.clone();
+        let mut jobs = Vec::new();
+        let mut wait = IDLE_WAIT;
+        for (scheduled_only, next_due, printer_id) in candidates {
+            if scheduled_only && next_due > now {
+                wait = wait.min(next_due - now);
+                continue;
+            }
+            if state.in_flight >= state.max_concurrent {
+                break;
+            }
+            let Some(scheduled) = state.targets.get_mut(&printer_id) else {
+                continue;
+            };
+            scheduled.in_flight = true;
+            scheduled.poll_now = false;
+            let (request_oids, counter_oids) = match &scheduled.target.oids {
+                Some((own, extra)) => (request_oids_for(own, extra), own.clone()),
+                None => (request_oids.clone(), counter_oids.clone()),
+            };
+            jobs.push(PollJob {
+                target: scheduled.target.clone(),
+                request_oids,
+                counter_oids,
+            });
+            state.in_flight += 1;
+        }
+
+        (jobs, wait)
+    }
+
+    /// Frees the poll slot, updates the printer's health and schedules its
+    /// next poll, backing off while it is offline.
+    fn finish(&self, outcome: PollOutcome) -> PollEvent {
+        let PollOutcome {
+            printer_id,
+            address,
+            polled_at,
+            result,
+        } = outcome;
+
+        let mut state = self.lock();
+        state.in_flight = state.in_flight.saturating_sub(1);
+        let interval = state.interval;
+        let policy = state.health_policy;
+        let mut untracked = PrinterHealth::default();
+        let health = match state.targets.get_mut(&printer_id) {
+            Some(scheduled) => {
+                scheduled.in_flight = false;
+                &mut scheduled.health
+            }
+            None => &mut untracked,
+        };
+        let transition = match &result {
+            Ok(_) => health.record_success(polled_at),
+            Err(error) => health.record_failure(error, polled_at, &policy),
+        };
+        let status = health.status();
+        let last_seen = health.last_seen();
+        if let Some(scheduled) = state.targets.get_mut(&printer_id) {
+            let interval = scheduled.target.interval.unwrap_or(interval);
+            scheduled.next_due = Instant::now() + scheduled.health.next_delay(interval, &policy);
+        }
+        drop(state);
+        self.shared.wake.notify_one();
+
+        if let Some(transition) = &transition {
+            info!(
+                target: targets::POLLING,
+                printer = %printer_id,
+                from = ?transition.from,
+                to = ?transition.to,
+                reason = %transition.reason,
+                "Printer status changed"
+            );
+        }
+
+        PollEvent {
+            printer_id,
+            address,
+            polled_at,
+            result,
+            status,
+            last_seen,
+            transition,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PollerState> {
+        match self.shared.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl PollJob {
+    async fn poll(self) -> PollOutcome {
+        let PollTarget {
+            printer_id,
+            address,
+            community,
+            client,
+            ..
+        } = self.target;
+
+        let mut request = SnmpRequest::new(address.clone(), self.request_oids);
+        if let Some(community) = community {
+            request = request.with_community(community);
+        }
+
+        let response = client.get(request).await;
+        let polled_at = now_epoch_seconds();
+        debug!(
+            target: targets::POLLING,
+            printer = %printer_id,
+            address = %address,
+            ok = response.is_ok(),
+            "Scheduled poll finished"
+        );
+        let result = response.map(|response| {
+            resolve_counters(polled_at, &self.counter_oids, &response.varbinds)
+        });
+
+        PollOutcome {
+            printer_id,
+            address,
+            polled_at,
+            result,
+        }
+    }
+}
+
+fn now_epoch_seconds() -> EpochSeconds {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// sysUpTime first, then the counters and extras without duplicates.
+fn request_oids_for(counter_oids: &CounterOidSet, extra: &[Oid]) -> Vec<Oid> {
+    let mut request_oids: Vec<Oid> = vec![Oid::from_slice(&SYS_UPTIME_OID)];
+    for oid in counter_oids
+        .bw
+        .iter()
+        .chain(&counter_oids.color)
+        .chain(&counter_oids.total)
+        .chain(extra)
+    {
+        if !request_oids.contains(oid) {
+            request_oids.push(oid.clone());
+        }
+    }
+    request_oids
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::snmp::{MockSnmpClient, SnmpFuture, SnmpResponse, SnmpValue, SnmpVarBind, SnmpWalkRequest};
+
+    const TOTAL_OID: &str = "1.3.6.1.2.1.43.10.2.1.4.1.1";
+
+    /// Answers every GET with a fixed total after a short delay and records
+    /// how many requests overlapped.
+    #[derive(Default)]
+    struct CountingClient {
+        active: AtomicUsize,
+        peak: AtomicUsize,
+        calls: AtomicUsize,
+    }
+
+    impl SnmpClient for CountingClient {
+        fn get<'a>(&'a self, request: SnmpRequest) -> SnmpFuture<'a> {
+            Box::pin(async move {
+                let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(active, Ordering::SeqCst);
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                self.active.fetch_sub(1, Ordering::SeqCst);
+                Ok(SnmpResponse {
+                    address: request.address,
+                    varbinds: vec![SnmpVarBind {
+                        oid: TOTAL_OID.parse().expect("oid"),
+                        value: SnmpValue::Counter32(1234),
+                    }],
+                })
+            })
+        }
+
+        fn walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+            self.bulk_walk(request)
+        }
+
+        fn bulk_walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+            Box::pin(async move {
+                Err(Error::SnmpFailure {
+                    address: request.address.to_string(),
+                    details: "walks are not scripted".to_string(),
+                })
+            })
+        }
+    }
+
+    fn run_future<T>(future: impl std::future::Future<Output = T>) -> T {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("tokio runtime");
+        runtime.block_on(future)
+    }
+
+    fn fleet(client: &Arc<CountingClient>, count: usize) -> Vec<PollTarget> {
+        (0..count)
+            .map(|index| {
+                PollTarget::new(
+                    PrinterId::new(format!("printer-{index}")),
+                    SnmpAddress::with_default_port(format!("192.168.1.{}", 10 + index)),
+                    client.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn counter_oids() -> CounterOidSet {
+        CounterOidSet {
+            total: vec![TOTAL_OID.parse().expect("oid")],
+            ..CounterOidSet::default()
+        }
+    }
+
+    #[test]
+    fn polls_every_enabled_printer_within_the_concurrency_limit() {
+        let client = Arc::new(CountingClient::default());
+        let poller = Poller::new()
+            .with_interval(Duration::from_secs(60))
+            .with_max_concurrent(2);
+        poller.set_oids(counter_oids(), &[]);
+        let mut targets = fleet(&client, 5);
+        targets.push(
+            PollTarget::new(
+                PrinterId::new("disabled"),
+                SnmpAddress::with_default_port("192.168.1.99"),
+                client.clone(),
+            )
+            .with_enabled(false),
+        );
+        poller.set_targets(targets);
+
+        let mut polled: Vec<String> = run_future(async {
+            let (sender, mut receiver) = mpsc::channel(16);
+            let runner = poller.clone();
+            tokio::spawn(async move { runner.run(sender).await });
+            let mut polled = Vec::new();
+            for _ in 0..5 {
+                let event = receiver.recv().await.expect("poll event");
+                let resolution = event.result.expect("resolution");
+                assert_eq!(resolution.snapshot.total, Some(1234));
+                polled.push(event.printer_id.0);
+            }
+            polled
+        });
+        polled.sort();
+
+        assert_eq!(
+            polled,
+            vec!["printer-0", "printer-1", "printer-2", "printer-3", "printer-4"]
+        );
+        assert_eq!(client.calls.load(Ordering::SeqCst), 5);
+        assert!(client.peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn poll_now_reaches_disabled_printers_and_intervals_repeat() {
+        let client = Arc::new(CountingClient::default());
+        let poller = Poller::new().with_interval(Duration::from_secs(60));
+        poller.set_oids(counter_oids(), &[]);
+        let fast = PrinterId::new("fast");
+        let manual = PrinterId::new("manual");
+        poller.set_targets(vec![
+            PollTarget::new(
+                fast.clone(),
+                SnmpAddress::with_default_port("192.168.1.10"),
+                client.clone(),
+            )
+            .with_interval(Duration::from_millis(10)),
+            PollTarget::new(
+                manual.clone(),
+                SnmpAddress::with_default_port("192.168.1.11"),
+                client.clone(),
+            )
+            .with_enabled(false),
+        ]);
+        assert!(poller.poll_now(&manual));
+        assert!(!poller.poll_now(&PrinterId::new("unknown")));
+
+        let events: Vec<PrinterId> = run_future(async {
+            let (sender, mut receiver) = mpsc::channel(16);
+            let runner = poller.clone();
+            tokio::spawn(async move { runner.run(sender).await });
+            let mut events = Vec::new();
+            for _ in 0..4 {
+                events.push(receiver.recv().await.expect("poll event").printer_id);
+            }
+            events
+        });
+
+        assert_eq!(events.iter().filter(|id| **id == manual).count(), 1);
+        assert_eq!(events.iter().filter(|id| **id == fast).count(), 3);
+    }
+
+    #[test]
+    fn timeouts_take_printers_offline_and_back_off() {
+        let client = MockSnmpClient::new();
+        let address = SnmpAddress::with_default_port("192.168.1.10");
+        for _ in 0..2 {
+            client.push_error(Error::SnmpTimeout {
+                address: address.to_string(),
+                timeout_ms: 2000,
+            });
+        }
+        let policy = HealthPolicy {
+            offline_after: 2,
+            max_backoff: Duration::from_secs(60),
+        };
+        let interval = Duration::from_millis(5);
+        let poller = Poller::new()
+            .with_interval(interval)
+            .with_health_policy(policy);
+        poller.set_oids(counter_oids(), &[]);
+        let printer_id = PrinterId::new("flaky");
+        poller.set_targets(vec![
+            PollTarget::new(printer_id.clone(), address, Arc::new(client))
+                .with_last_known(PrinterStatus::Online, Some(100)),
+        ]);
+
+        let events: Vec<PollEvent> = run_future(async {
+            let (sender, mut receiver) = mpsc::channel(16);
+            let runner = poller.clone();
+            tokio::spawn(async move { runner.run(sender).await });
+            let mut events = Vec::new();
+            for _ in 0..2 {
+                events.push(receiver.recv().await.expect("poll event"));
+            }
+            events
+        });
+
+        assert_eq!(events[0].status, PrinterStatus::Online);
+        assert!(events[0].transition.is_none());
+        assert_eq!(events[1].status, PrinterStatus::Offline);
+        assert_eq!(events[1].last_seen, Some(100));
+        let transition = events[1].transition.as_ref().expect("offline transition");
+        assert_eq!(transition.from, PrinterStatus::Online);
+
+        let health = poller.health(&printer_id).expect("tracked health");
+        assert_eq!(health.status(), PrinterStatus::Offline);
+        assert_eq!(health.next_delay(interval, &policy), interval * 2);
+    }
+}
@@ -0,0 +1,862 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{EpochSeconds, RecordingCategory};
+use crate::money::{Currency, Money, MoneyFormat, Rounding, VatRate};
+use crate::storage;
+use crate::Error;
+
+/// One price band. Tiers are cumulative: `up_to: Some(10)` after
+/// `up_to: Some(5)` prices pages 6 to 10.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceTier {
+    /// Last page priced by this tier; `None` for the open-ended last tier.
+    pub up_to: Option<u64>,
+    #[serde(alias = "unit_cents")]
+    pub unit_price: Money,
+}
+
+impl PriceTier {
+    pub fn up_to(pages: u64, unit_price: Money) -> Self {
+        Self {
+            up_to: Some(pages),
+            unit_price,
+        }
+    }
+
+    pub fn rest(unit_price: Money) -> Self {
+        Self {
+            up_to: None,
+            unit_price,
+        }
+    }
+}
+
+/// Prices the pages of one or more categories together, so e.g. B/W copies
+/// and prints can share one set of tiers. A tariff for surcharge categories
+/// (large, two-color, single-color, duplex) is charged on top of the copies
+/// and prints tariffs, which price the same pages too.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tariff {
+    pub name: String,
+    pub categories: Vec<RecordingCategory>,
+    pub tiers: Vec<PriceTier>,
+    /// Charged instead of the tier price when that is lower, as long as any
+    /// page was made.
+    #[serde(default, alias = "minimum_cents")]
+    pub minimum: Money,
+    #[serde(default)]
+    pub rounding: Option<Rounding>,
+}
+
+/// Whether listed prices already contain VAT.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PriceBasis {
+    /// Prices are what the customer pays; VAT is worked out of them.
+    #[default]
+    Gross,
+    /// VAT is added on top of the listed prices.
+    Net,
+}
+
+impl PriceBasis {
+    pub const ALL: [PriceBasis; 2] = [PriceBasis::Gross, PriceBasis::Net];
+}
+
+impl fmt::Display for PriceBasis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceBasis::Gross => f.write_str("Prices include VAT"),
+            PriceBasis::Net => f.write_str("VAT added to prices"),
+        }
+    }
+}
+
+/// A shop's tariff: what each category costs per session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceList {
+    pub name: String,
+    #[serde(default)]
+    pub currency: Currency,
+    /// Added once to every session.
+    #[serde(default, alias = "base_fee_cents")]
+    pub base_fee: Money,
+    #[serde(default)]
+    pub base_fee_vat: VatRate,
+    pub tariffs: Vec<Tariff>,
+    /// Applied to the session total, VAT included.
+    #[serde(default)]
+    pub rounding: Option<Rounding>,
+    /// VAT per category; categories left out carry none. Categories that
+    /// share a tariff must share a rate.
+    #[serde(default)]
+    pub vat_rates: BTreeMap<RecordingCategory, VatRate>,
+    #[serde(default)]
+    pub price_basis: PriceBasis,
+}
+
+impl Default for PriceList {
+    /// B/W at 0.25 for the first 5 pages, 0.10 for the next 5 and 0.06 after
+    /// that, rounded to 0.50; color at a flat 0.50. No VAT.
+    fn default() -> Self {
+        let cents = Money::from_minor;
+        Self {
+            name: "Standard".to_string(),
+            currency: Currency::default(),
+            base_fee: Money::ZERO,
+            base_fee_vat: VatRate::ZERO,
+            tariffs: vec![
+                Tariff {
+                    name: "B/W".to_string(),
+                    categories: vec![RecordingCategory::CopiesBw, RecordingCategory::PrintsBw],
+                    tiers: vec![
+                        PriceTier::up_to(5, cents(25)),
+                        PriceTier::up_to(10, cents(10)),
+                        PriceTier::rest(cents(6)),
+                    ],
+                    minimum: Money::ZERO,
+                    rounding: Some(Rounding::half_up(cents(50))),
+                },
+                Tariff {
+                    name: "Color".to_string(),
+                    categories: vec![
+                        RecordingCategory::CopiesColor,
+                        RecordingCategory::PrintsColor,
+                    ],
+                    tiers: vec![PriceTier::rest(cents(50))],
+                    minimum: Money::ZERO,
+                    rounding: None,
+                },
+            ],
+            rounding: None,
+            vat_rates: BTreeMap::new(),
+            price_basis: PriceBasis::Gross,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TierCharge {
+    pub pages: u64,
+    pub unit_price: Money,
+    pub amount: Money,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TariffCharge {
+    pub tariff: String,
+    pub pages: u64,
+    pub tiers: Vec<TierCharge>,
+    pub minimum_applied: bool,
+    /// Charged on top of the copies and prints tariffs for the same pages.
+    pub surcharge: bool,
+    /// What rounding added (positive) or removed (negative).
+    pub rounding: Money,
+    /// At list price, so gross or net as the price list's basis says.
+    pub amount: Money,
+    pub vat_rate: VatRate,
+}
+
+/// Everything charged at one VAT rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VatLine {
+    pub rate: VatRate,
+    pub net: Money,
+    pub vat: Money,
+    pub gross: Money,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceQuote {
+    pub charges: Vec<TariffCharge>,
+    pub base_fee: Money,
+    /// One line per rate, lowest first. VAT is worked out per rate rather
+    /// than per charge, as on an invoice.
+    pub vat: Vec<VatLine>,
+    pub net: Money,
+    pub vat_total: Money,
+    /// What rounding the gross total added (positive) or removed (negative).
+    pub rounding: Money,
+    /// Gross, after rounding.
+    pub total: Money,
+}
+
+impl PriceList {
+    /// Checks that tiers climb and end open-ended, that amounts are not
+    /// negative, that no category is priced twice and that no tariff mixes
+    /// surcharge categories with others, whose pages it would count twice.
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |details: String| Error::InvalidPriceList {
+            name: self.name.clone(),
+            details,
+        };
+
+        if self.currency.minor_digits > Currency::MAX_MINOR_DIGITS {
+            return Err(invalid(format!(
+                "currency {} has more than {} decimal places",
+                self.currency.code,
+                Currency::MAX_MINOR_DIGITS
+            )));
+        }
+        let negative = |amount: Money| amount < Money::ZERO;
+        if negative(self.base_fee)
+            || self
+                .rounding
+                .is_some_and(|rounding| negative(rounding.step))
+        {
+            return Err(invalid("amounts must not be negative".to_string()));
+        }
+
+        let mut priced = HashMap::new();
+        for tariff in &self.tariffs {
+            if tariff.tiers.is_empty() {
+                return Err(invalid(format!("tariff {} has no tiers", tariff.name)));
+            }
+            if negative(tariff.minimum)
+                || tariff
+                    .rounding
+                    .is_some_and(|rounding| negative(rounding.step))
+                || tariff.tiers.iter().any(|tier| negative(tier.unit_price))
+            {
+                return Err(invalid(format!(
+                    "tariff {} has a negative amount",
+                    tariff.name
+                )));
+            }
+            let mut previous = 0;
+            for (index, tier) in tariff.tiers.iter().enumerate() {
+                let last = index + 1 == tariff.tiers.len();
+                match tier.up_to {
+                    Some(up_to) if up_to <= previous => {
+                        return Err(invalid(format!(
+                            "tariff {} tier {} must end above page {previous}",
+                            tariff.name,
+                            index + 1
+                        )));
+                    }
+                    Some(_) if last => {
+                        return Err(invalid(format!(
+                            "tariff {} must end with an open-ended tier",
+                            tariff.name
+                        )));
+                    }
+                    Some(up_to) => previous = up_to,
+                    None if !last => {
+                        return Err(invalid(format!(
+                            "tariff {} has an open-ended tier before its last",
+                            tariff.name
+                        )));
+                    }
+                    None => {}
+                }
+            }
+            if tariff
+                .categories
+                .iter()
+                .any(|category| category.is_surcharge())
+                && !tariff.is_surcharge()
+            {
+                return Err(invalid(format!(
+                    "tariff {} mixes surcharge categories with others",
+                    tariff.name
+                )));
+            }
+            let rate = self.tariff_vat_rate(tariff);
+            for category in &tariff.categories {
+                if let Some(other) = priced.insert(*category, &tariff.name) {
+                    return Err(invalid(format!(
+                        "{} is priced by both {other} and {}",
+                        category.label(),
+                        tariff.name
+                    )));
+                }
+                if self.vat_rate(*category) != rate {
+                    return Err(invalid(format!(
+                        "tariff {} mixes VAT rates {rate} and {}",
+                        tariff.name,
+                        self.vat_rate(*category)
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn vat_rate(&self, category: RecordingCategory) -> VatRate {
+        self.vat_rates.get(&category).copied().unwrap_or_default()
+    }
+
+    /// The rate of the tariff's categories, which `validate` keeps equal.
+    pub fn tariff_vat_rate(&self, tariff: &Tariff) -> VatRate {
+        tariff
+            .categories
+            .first()
+            .map_or(VatRate::ZERO, |category| self.vat_rate(*category))
+    }
+
+    /// Prices a session. Categories missing from `pages` count as zero;
+    /// categories no tariff covers are free.
+    pub fn quote(&self, pages: &HashMap<RecordingCategory, u64>) -> PriceQuote {
+        let charges: Vec<TariffCharge> = self
+            .tariffs
+            .iter()
+            .map(|tariff| {
+                let count = tariff
+                    .categories
+                    .iter()
+                    .filter_map(|category| pages.get(category))
+                    .fold(0u64, |total, pages| total.saturating_add(*pages));
+                let mut charge = tariff.charge(count);
+                charge.vat_rate = self.tariff_vat_rate(tariff);
+                charge
+            })
+            .collect();
+
+        let mut by_rate = BTreeMap::new();
+        let amounts = charges
+            .iter()
+            .map(|charge| (charge.vat_rate, charge.amount))
+            .chain([(self.base_fee_vat, self.base_fee)]);
+        for (rate, amount) in amounts {
+            if !amount.is_zero() {
+                *by_rate.entry(rate).or_insert(Money::ZERO) += amount;
+            }
+        }
+        let vat: Vec<VatLine> = by_rate
+            .into_iter()
+            .map(|(rate, amount)| match self.price_basis {
+                PriceBasis::Gross => {
+                    let vat = rate.included_in(amount);
+                    VatLine {
+                        rate,
+                        net: amount - vat,
+                        vat,
+                        gross: amount,
+                    }
+                }
+                PriceBasis::Net => {
+                    let vat = rate.on_net(amount);
+                    VatLine {
+                        rate,
+                        net: amount,
+                        vat,
+                        gross: amount + vat,
+                    }
+                }
+            })
+            .collect();
+
+        let subtotal: Money = vat.iter().map(|line| line.gross).sum();
+        let total = self
+            .rounding
+            .map_or(subtotal, |rounding| rounding.apply(subtotal));
+        PriceQuote {
+            charges,
+            base_fee: self.base_fee,
+            net: vat.iter().map(|line| line.net).sum(),
+            vat_total: vat.iter().map(|line| line.vat).sum(),
+            vat,
+            rounding: total - subtotal,
+            total,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let list: Self = storage::load_ron(path.as_ref())?;
+        list.validate()?;
+        Ok(list)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        storage::save_ron(path.as_ref(), self)
+    }
+}
+
+/// One published revision of a price list. Versions are never edited once
+/// published, so a session pinned to one is always priced the same way.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceListVersion {
+    pub version: u32,
+    /// Unix seconds; sessions starting at or after this use this version.
+    pub effective_from: EpochSeconds,
+    /// Unix seconds the version was added, for the audit trail.
+    #[serde(default)]
+    pub published_at: EpochSeconds,
+    pub price_list: PriceList,
+}
+
+/// Every price list version ever published, in publishing order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceBook {
+    versions: Vec<PriceListVersion>,
+    /// How amounts are shown and typed, whatever the version.
+    #[serde(default)]
+    format: MoneyFormat,
+}
+
+impl Default for PriceBook {
+    fn default() -> Self {
+        Self::new(PriceList::default())
+    }
+}
+
+impl PriceBook {
+    /// A book whose first version applies to every session, past or future.
+    pub fn new(price_list: PriceList) -> Self {
+        Self {
+            versions: vec![PriceListVersion {
+                version: 1,
+                effective_from: 0,
+                published_at: 0,
+                price_list,
+            }],
+            format: MoneyFormat::default(),
+        }
+    }
+
+    pub fn format(&self) -> MoneyFormat {
+        self.format
+    }
+
+    pub fn set_format(&mut self, format: MoneyFormat) {
+        self.format = format;
+    }
+
+    pub fn versions(&self) -> &[PriceListVersion] {
+        &self.versions
+    }
+
+    pub fn version(&self, version: u32) -> Option<&PriceListVersion> {
+        self.versions.iter().find(|entry| entry.version == version)
+    }
+
+    /// The most recently published version.
+    pub fn latest(&self) -> Option<&PriceListVersion> {
+        self.versions.last()
+    }
+
+    /// The version in effect at `timestamp`: the latest `effective_from` not
+    /// after it, and of those the most recently published. `None` when every
+    /// version starts later.
+    pub fn active_at(&self, timestamp: EpochSeconds) -> Option<&PriceListVersion> {
+        self.versions
+            .iter()
+            .filter(|entry| entry.effective_from <= timestamp)
+            .max_by_key(|entry| (entry.effective_from, entry.version))
+    }
+
+    /// Adds `price_list` as a new version and returns its number.
+    pub fn publish(
+        &mut self,
+        price_list: PriceList,
+        effective_from: EpochSeconds,
+        published_at: EpochSeconds,
+    ) -> Result<u32, Error> {
+        price_list.validate()?;
+        let version = self
+            .versions
+            .iter()
+            .map(|entry| entry.version)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.versions.push(PriceListVersion {
+            version,
+            effective_from,
+            published_at,
+            price_list,
+        });
+        Ok(version)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut previous = 0;
+        for entry in &self.versions {
+            if entry.version <= previous {
+                return Err(Error::InvalidPriceList {
+                    name: entry.price_list.name.clone(),
+                    details: format!(
+                        "version {} is listed after version {previous}",
+                        entry.version
+                    ),
+                });
+            }
+            previous = entry.version;
+            entry.price_list.validate()?;
+        }
+        if self.versions.is_empty() {
+            return Err(Error::InvalidPriceList {
+                name: "history".to_string(),
+                details: "no versions published".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Reads a book, or a single price list saved before lists were
+    /// versioned, which becomes version 1.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let book = match storage::load_ron::<Self>(path) {
+            Ok(book) => book,
+            Err(error @ Error::Ron { .. }) => storage::load_ron::<PriceList>(path)
+                .map(Self::new)
+                .map_err(|_| error)?,
+            Err(error) => return Err(error),
+        };
+        book.validate()?;
+        Ok(book)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        storage::save_ron(path.as_ref(), self)
+    }
+}
+
+impl Tariff {
+    /// Whether the tariff only prices surcharge categories.
+    pub fn is_surcharge(&self) -> bool {
+        !self.categories.is_empty()
+            && self
+                .categories
+                .iter()
+                .all(|category| category.is_surcharge())
+    }
+
+    /// The charge before VAT is worked out; `vat_rate` is left at zero for
+    /// `PriceList::quote` to fill in.
+    pub fn charge(&self, pages: u64) -> TariffCharge {
+        let mut tiers = Vec::new();
+        let mut priced = 0u64;
+        for tier in &self.tiers {
+            if priced >= pages {
+                break;
+            }
+            let end = tier.up_to.map_or(pages, |up_to| up_to.min(pages));
+            let in_tier = end.saturating_sub(priced);
+            if in_tier == 0 {
+                continue;
+            }
+            tiers.push(TierCharge {
+                pages: in_tier,
+                unit_price: tier.unit_price,
+                amount: tier.unit_price.times(in_tier),
+            });
+            priced = end;
+        }
+
+        let tiered: Money = tiers.iter().map(|tier| tier.amount).sum();
+        let minimum_applied = pages > 0 && tiered < self.minimum;
+        let before_rounding = if minimum_applied {
+            self.minimum
+        } else {
+            tiered
+        };
+        let amount = self
+            .rounding
+            .map_or(before_rounding, |rounding| rounding.apply(before_rounding));
+
+        TariffCharge {
+            tariff: self.name.clone(),
+            pages,
+            tiers,
+            minimum_applied,
+            surcharge: self.is_surcharge(),
+            rounding: amount - before_rounding,
+            amount,
+            vat_rate: VatRate::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::money::RoundingMode;
+    use crate::storage::tests::TempDir;
+
+    fn pages(entries: &[(RecordingCategory, u64)]) -> HashMap<RecordingCategory, u64> {
+        entries.iter().copied().collect()
+    }
+
+    fn cents(minor: i64) -> Money {
+        Money::from_minor(minor)
+    }
+
+    #[test]
+    fn default_list_matches_the_old_fixed_tiers() {
+        let list = PriceList::default();
+        list.validate().expect("valid default");
+
+        // 7 copies + 5 prints B/W: 5 x 0.25 + 5 x 0.10 + 2 x 0.06 = 1.87 -> 2.00.
+        let quote = list.quote(&pages(&[
+            (RecordingCategory::CopiesBw, 7),
+            (RecordingCategory::PrintsBw, 5),
+            (RecordingCategory::PrintsColor, 3),
+        ]));
+        let bw = &quote.charges[0];
+        assert_eq!(bw.pages, 12);
+        assert_eq!(
+            bw.tiers.iter().map(|tier| tier.pages).collect::<Vec<_>>(),
+            vec![5, 5, 2]
+        );
+        assert_eq!(bw.rounding, cents(13));
+        assert_eq!(bw.amount, cents(200));
+        assert_eq!(quote.charges[1].amount, cents(150));
+        assert_eq!(quote.total, cents(350));
+        assert_eq!(quote.vat_total, Money::ZERO);
+    }
+
+    #[test]
+    fn applies_minimums_base_fee_and_total_rounding() {
+        let list = PriceList {
+            name: "Campus".to_string(),
+            base_fee: cents(30),
+            tariffs: vec![Tariff {
+                name: "Color prints".to_string(),
+                categories: vec![RecordingCategory::PrintsColor],
+                tiers: vec![PriceTier::up_to(100, cents(40)), PriceTier::rest(cents(25))],
+                minimum: cents(100),
+                rounding: None,
+            }],
+            rounding: Some(Rounding {
+                step: cents(10),
+                mode: RoundingMode::Up,
+            }),
+            ..PriceList::default()
+        };
+        list.validate().expect("valid list");
+
+        let small = list.quote(&pages(&[(RecordingCategory::PrintsColor, 1)]));
+        assert!(small.charges[0].minimum_applied);
+        assert_eq!(small.total, cents(130));
+
+        let large = list.quote(&pages(&[
+            (RecordingCategory::PrintsColor, 103),
+            (RecordingCategory::CopiesBw, 50),
+        ]));
+        // 100 x 0.40 + 3 x 0.25 + 0.30 = 41.05 -> 41.10; B/W copies are free here.
+        assert_eq!(large.charges[0].amount, cents(4_075));
+        assert_eq!(large.rounding, cents(5));
+        assert_eq!(large.total, cents(4_110));
+
+        let idle = list.quote(&HashMap::new());
+        assert!(!idle.charges[0].minimum_applied);
+        assert_eq!(idle.total, cents(30));
+    }
+
+    #[test]
+    fn splits_vat_per_rate() {
+        let reduced = VatRate::from_basis_points(700);
+        let standard = VatRate::from_basis_points(1900);
+        let mut list = PriceList {
+            vat_rates: [
+                (RecordingCategory::CopiesBw, reduced),
+                (RecordingCategory::PrintsBw, reduced),
+                (RecordingCategory::CopiesColor, standard),
+                (RecordingCategory::PrintsColor, standard),
+            ]
+            .into_iter()
+            .collect(),
+            base_fee: cents(100),
+            base_fee_vat: standard,
+            ..PriceList::default()
+        };
+        list.validate().expect("valid list");
+
+        let session = pages(&[
+            (RecordingCategory::CopiesBw, 2),
+            (RecordingCategory::PrintsColor, 3),
+        ]);
+        // B/W 0.50 at 7%, color 1.50 + base fee 1.00 at 19%, all gross.
+        let gross = list.quote(&session);
+        assert_eq!(gross.charges[0].vat_rate, reduced);
+        assert_eq!(gross.vat.len(), 2);
+        assert_eq!(gross.vat[0].vat, cents(3));
+        assert_eq!(gross.vat[1].gross, cents(250));
+        assert_eq!(gross.vat[1].vat, cents(40));
+        assert_eq!(gross.net, cents(257));
+        assert_eq!(gross.total, cents(300));
+
+        list.price_basis = PriceBasis::Net;
+        let net = list.quote(&session);
+        assert_eq!(net.net, cents(300));
+        assert_eq!(net.vat_total, cents(4 + 48));
+        assert_eq!(net.total, cents(352));
+
+        list.vat_rates.insert(
+            RecordingCategory::PrintsBw,
+            VatRate::from_basis_points(1900),
+        );
+        let error = list.validate().expect_err("mixed rates");
+        assert!(error.technical_detail().contains("mixes VAT rates"));
+    }
+
+    #[test]
+    fn rejects_malformed_lists() {
+        let mut list = PriceList::default();
+        list.tariffs[0].tiers = vec![
+            PriceTier::up_to(5, cents(25)),
+            PriceTier::up_to(3, cents(10)),
+        ];
+        assert!(list.validate().is_err());
+
+        let mut list = PriceList::default();
+        list.tariffs[1].tiers = vec![PriceTier::up_to(5, cents(25))];
+        assert!(list.validate().is_err());
+
+        let mut list = PriceList::default();
+        list.tariffs[1].minimum = cents(-1);
+        assert!(list.validate().is_err());
+
+        let mut list = PriceList::default();
+        list.tariffs[1].categories.push(RecordingCategory::CopiesBw);
+        let error = list.validate().expect_err("double pricing");
+        assert!(error.technical_detail().contains("Copies B/W"));
+    }
+
+    #[test]
+    fn prices_overlapping_categories_as_surcharges() {
+        let single = |name: &str, category, unit_price| Tariff {
+            name: name.to_string(),
+            categories: vec![category],
+            tiers: vec![PriceTier::rest(unit_price)],
+            minimum: Money::ZERO,
+            rounding: None,
+        };
+        let mut list = PriceList::default();
+        list.tariffs.extend([
+            single("A3 surcharge", RecordingCategory::Large, cents(20)),
+            single("Duplex", RecordingCategory::Duplex, cents(-5)),
+        ]);
+        assert!(list.validate().is_err());
+        list.tariffs[3].tiers = vec![PriceTier::rest(Money::ZERO)];
+        list.tariffs
+            .push(single("Scans", RecordingCategory::Scans, cents(5)));
+        list.validate().expect("valid list");
+        assert!(list.tariffs[2].is_surcharge());
+        assert!(!list.tariffs[0].is_surcharge());
+        assert!(!list.tariffs[4].is_surcharge());
+
+        // 4 of the 7 B/W copies and 1 of the 3 color prints were A3, and 2
+        // sheets were duplex.
+        let quote = list.quote(&pages(&[
+            (RecordingCategory::CopiesBw, 7),
+            (RecordingCategory::PrintsColor, 3),
+            (RecordingCategory::Large, 5),
+            (RecordingCategory::Duplex, 2),
+            (RecordingCategory::Scans, 6),
+        ]));
+        let amounts: Vec<Money> = quote.charges.iter().map(|charge| charge.amount).collect();
+        // The base tariffs charge every page, A3 or not; the surcharges come
+        // on top.
+        assert_eq!(
+            amounts,
+            vec![cents(150), cents(150), cents(100), Money::ZERO, cents(30)]
+        );
+        assert_eq!(
+            quote
+                .charges
+                .iter()
+                .map(|charge| charge.surcharge)
+                .collect::<Vec<_>>(),
+            vec![false, false, true, true, false]
+        );
+        assert_eq!(quote.charges[0].pages, 7);
+        assert_eq!(quote.total, cents(430));
+
+        // Surcharges only add to what the copies and prints cost.
+        let without = PriceList::default().quote(&pages(&[
+            (RecordingCategory::CopiesBw, 7),
+            (RecordingCategory::PrintsColor, 3),
+            (RecordingCategory::Large, 5),
+        ]));
+        assert_eq!(without.total, cents(300));
+
+        // A tariff counting A3 pages with the copies would count them twice.
+        let mut list = PriceList::default();
+        list.tariffs[0].categories.push(RecordingCategory::Large);
+        let error = list.validate().expect_err("mixed surcharge");
+        assert!(error
+            .technical_detail()
+            .contains("mixes surcharge categories"));
+    }
+
+    #[test]
+    fn picks_the_version_in_effect() {
+        let mut book = PriceBook::default();
+        let mut raised = PriceList::default();
+        raised.tariffs[1].tiers = vec![PriceTier::rest(cents(60))];
+        assert_eq!(book.publish(raised, 1_000, 900).expect("publish"), 2);
+        let mut corrected = PriceList::default();
+        corrected.tariffs[1].tiers = vec![PriceTier::rest(cents(55))];
+        assert_eq!(book.publish(corrected, 1_000, 950).expect("publish"), 3);
+        let mut broken = PriceList::default();
+        broken.tariffs[0].tiers.clear();
+        assert!(book.publish(broken, 2_000, 2_000).is_err());
+
+        let version_at = |timestamp| book.active_at(timestamp).map(|entry| entry.version);
+        assert_eq!(version_at(999), Some(1));
+        // Same effective date: the later publication wins.
+        assert_eq!(version_at(1_000), Some(3));
+        assert_eq!(book.latest().map(|entry| entry.version), Some(3));
+        assert_eq!(book.versions().len(), 3);
+        book.validate().expect("valid book");
+    }
+
+    #[test]
+    fn round_trips_through_ron() {
+        let dir = TempDir::new("pricing");
+        let path = dir.join("pricing.ron");
+
+        let list = PriceList::default();
+        list.save(&path).expect("save");
+        assert_eq!(PriceList::load(&path).expect("load"), list);
+        // A plain list from before versioning loads as version 1.
+        let book = PriceBook::load(&path).expect("load book");
+        assert_eq!(book, PriceBook::new(list));
+
+        let mut book = book;
+        book.publish(PriceList::default(), 5_000, 5_000)
+            .expect("publish");
+        book.save(&path).expect("save book");
+        assert_eq!(PriceBook::load(&path).expect("load book"), book);
+
+        // Amounts used to be plain cent fields.
+        let legacy = r#"(
+            name: "Standard",
+            base_fee_cents: 0,
+            tariffs: [(
+                name: "B/W",
+                categories: [CopiesBw, PrintsBw],
+                tiers: [
+                    (up_to: Some(5), unit_cents: 25),
+                    (up_to: Some(10), unit_cents: 10),
+                    (up_to: None, unit_cents: 6),
+                ],
+                minimum_cents: 0,
+                rounding: Some((step_cents: 50, mode: Nearest)),
+            ), (
+                name: "Color",
+                categories: [CopiesColor, PrintsColor],
+                tiers: [(up_to: None, unit_cents: 50)],
+            )],
+        )"#;
+        fs::write(&path, legacy).expect("write legacy");
+        let book = PriceBook::load(&path).expect("load legacy");
+        assert_eq!(
+            book.latest().expect("version").price_list,
+            PriceList::default()
+        );
+
+        // A half-written file is neither a book nor a list.
+        fs::write(&path, &legacy[..40]).expect("write truncated");
+        let error = PriceBook::load(&path).expect_err("truncated");
+        assert!(matches!(error, Error::Ron { .. }));
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use regex_automata::meta::Regex;
+use regex_automata::util::syntax;
+use serde::{Deserialize, Serialize};
+
+use crate::counters::CounterOidSet;
+use crate::model::{PrinterRecord, RecordingCategory};
+use crate::snmp::Oid;
+use crate::Error;
+
+/// Profiles compiled into the binary; files loaded later with the same name
+/// replace them.
+const BUILTIN_PROFILES: [(&str, &str); 5] = [
+    ("ricoh.ron", include_str!("../profiles/ricoh.ron")),
+    ("xerox.ron", include_str!("../profiles/xerox.ron")),
+    ("lexmark.ron", include_str!("../profiles/lexmark.ron")),
+    ("hp.ron", include_str!("../profiles/hp.ron")),
+    ("canon.ron", include_str!("../profiles/canon.ron")),
+];
+
+/// How far a printer could be matched to a profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileMatch {
+    /// No profile recognises the printer.
+    Unknown,
+    /// The vendor is known but not the model, so its counters are not.
+    Unmapped,
+    Known,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterStrategy {
+    BwColorPreferred,
+    BwOnly,
+    TotalOnly,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterAvailability {
+    pub bw: bool,
+    pub color: bool,
+    pub total: bool,
+}
+
+impl CounterAvailability {
+    pub const NONE: CounterAvailability = CounterAvailability {
+        bw: false,
+        color: false,
+        total: false,
+    };
+}
+
+/// A printer family as written in a profile file. Patterns are regular
+/// expressions matched case-insensitively anywhere in the text unless
+/// anchored; OIDs are dotted strings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VendorProfile {
+    pub name: String,
+    /// A sysObjectID under any of these identifies the vendor.
+    #[serde(default)]
+    pub sys_object_id_prefixes: Vec<String>,
+    /// A sysDescr matching this identifies the vendor too.
+    #[serde(default)]
+    pub sys_descr_pattern: Option<String>,
+    /// Matched against sysDescr; the first group is the model.
+    #[serde(default)]
+    pub model_pattern: Option<String>,
+    /// Tried in order against the model; the first match tells whether it
+    /// prints color. Models matching none stay unmapped.
+    #[serde(default)]
+    pub models: Vec<ModelRule>,
+    #[serde(default)]
+    pub counters: ProfileCounters,
+    #[serde(default)]
+    pub toner: Vec<TonerSupply>,
+    #[serde(default)]
+    pub capabilities: Capabilities,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelRule {
+    pub pattern: String,
+    pub color: bool,
+}
+
+/// Counter OIDs, each list tried in order until one answers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileCounters {
+    /// Copy and print counters for recording sessions.
+    #[serde(default)]
+    pub recording: BTreeMap<RecordingCategory, Vec<String>>,
+    /// Fallbacks for the polled B/W and color totals, after the recording
+    /// counters of that color.
+    #[serde(default)]
+    pub bw: Vec<String>,
+    #[serde(default)]
+    pub color: Vec<String>,
+    #[serde(default)]
+    pub total: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TonerSupply {
+    pub name: String,
+    pub oid: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Copies are counted apart from prints.
+    #[serde(default)]
+    pub copier: bool,
+}
+
+/// What the registry made of one printer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrinterProfile {
+    /// Name of the matching profile.
+    pub vendor: Option<String>,
+    pub match_status: ProfileMatch,
+    pub model: Option<String>,
+    pub sys_object_id: Option<String>,
+    pub sys_descr: Option<String>,
+    pub color: Option<bool>,
+    pub counters: CounterAvailability,
+    pub strategy: CounterStrategy,
+    /// Polled counters, recording counters first.
+    pub counter_oids: CounterOidSet,
+    pub recording_oids: BTreeMap<RecordingCategory, Vec<Oid>>,
+    pub toner: Vec<(String, Oid)>,
+    pub capabilities: Capabilities,
+    pub notes: Vec<String>,
+}
+
+/// A profile with its patterns compiled and OIDs parsed.
+#[derive(Debug, Clone)]
+struct CompiledProfile {
+    profile: VendorProfile,
+    sys_object_id_prefixes: Vec<Oid>,
+    sys_descr: Option<Regex>,
+    model: Option<Regex>,
+    models: Vec<(Regex, bool)>,
+    recording_oids: BTreeMap<RecordingCategory, Vec<Oid>>,
+    counter_oids: CounterOidSet,
+    toner: Vec<(String, Oid)>,
+}
+
+impl CompiledProfile {
+    fn compile(profile: VendorProfile) -> Result<Self, Error> {
+        let error = |details: String| Error::Profile {
+            profile: profile.name.clone(),
+            details,
+        };
+        let pattern = |pattern: &str| {
+            Regex::builder()
+                .syntax(syntax::Config::new().case_insensitive(true))
+                .build(pattern)
+                .map_err(|source| error(format!("pattern {pattern:?}: {source}")))
+        };
+        let oid = |oid: &str| {
+            Oid::from_str(oid).map_err(|source| error(format!("OID {oid:?}: {source}")))
+        };
+        let oids = |oids: &[String]| {
+            oids.iter()
+                .map(|value| oid(value))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        if profile.name.trim().is_empty() {
+            return Err(error("name is empty".to_string()));
+        }
+        let counters = &profile.counters;
+        let recording_oids = counters
+            .recording
+            .iter()
+            .map(|(category, list)| Ok((*category, oids(list)?)))
+            .collect::<Result<BTreeMap<_, _>, Error>>()?;
+        let recording = |color: bool| {
+            recording_oids
+                .iter()
+                .filter(move |(category, _)| category.is_base() && category.is_color() == color)
+                .flat_map(|(_, oids)| oids.iter().cloned())
+        };
+        let mut bw: Vec<Oid> = recording(false).collect();
+        bw.extend(oids(&counters.bw)?);
+        let mut color: Vec<Oid> = recording(true).collect();
+        color.extend(oids(&counters.color)?);
+        let counter_oids = CounterOidSet {
+            bw,
+            color,
+            total: oids(&counters.total)?,
+        };
+
+        Ok(Self {
+            sys_object_id_prefixes: oids(&profile.sys_object_id_prefixes)?,
+            sys_descr: profile
+                .sys_descr_pattern
+                .as_deref()
+                .map(pattern)
+                .transpose()?,
+            model: profile.model_pattern.as_deref().map(pattern).transpose()?,
+            models: profile
+                .models
+                .iter()
+                .map(|rule| Ok((pattern(&rule.pattern)?, rule.color)))
+                .collect::<Result<_, Error>>()?,
+            recording_oids,
+            counter_oids,
+            toner: profile
+                .toner
+                .iter()
+                .map(|supply| Ok((supply.name.clone(), oid(&supply.oid)?)))
+                .collect::<Result<_, Error>>()?,
+            profile,
+        })
+    }
+
+    fn model_in(&self, sys_descr: &str) -> Option<String> {
+        let regex = self.model.as_ref()?;
+        let mut captures = regex.create_captures();
+        regex.captures(sys_descr, &mut captures);
+        let span = captures.get_group(1)?;
+        let model = sys_descr[span.start..span.end].trim();
+        (!model.is_empty()).then(|| model.to_string())
+    }
+
+    fn color_of(&self, model: &str) -> Option<bool> {
+        self.models
+            .iter()
+            .find(|(regex, _)| regex.is_match(model))
+            .map(|(_, color)| *color)
+    }
+}
+
+/// Every known printer family. Profiles added later are tried first, so a
+/// narrow profile for one model can sit in front of its vendor's.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileRegistry {
+    profiles: Vec<CompiledProfile>,
+}
+
+impl ProfileRegistry {
+    /// The profiles shipped with the application.
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        for (file, contents) in BUILTIN_PROFILES {
+            let profile = parse_profile(file, contents)
+                .and_then(|profile| registry.add(profile))
+                .map(|_| ());
+            if let Err(error) = profile {
+                panic!("shipped {file} is invalid: {}", error.technical_detail());
+            }
+        }
+        registry
+    }
+
+    pub fn profiles(&self) -> impl Iterator<Item = &VendorProfile> {
+        self.profiles.iter().map(|compiled| &compiled.profile)
+    }
+
+    /// Adds a profile in front of the others, replacing one with the same
+    /// name.
+    pub fn add(&mut self, profile: VendorProfile) -> Result<&mut Self, Error> {
+        let compiled = CompiledProfile::compile(profile)?;
+        self.profiles
+            .retain(|existing| existing.profile.name != compiled.profile.name);
+        self.profiles.insert(0, compiled);
+        Ok(self)
+    }
+
+    /// Adds every `*.ron` profile in `dir`, in file name order. A missing
+    /// directory adds nothing; a file that does not load is skipped and
+    /// reported.
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Vec<Error> {
+        let dir = dir.as_ref();
+        let storage_error = |source: io::Error, path: &Path| Error::StorageIo {
+            action: crate::StorageAction::Load,
+            path: Some(path.display().to_string()),
+            source,
+        };
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(error) => return vec![storage_error(error, dir)],
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+            .collect();
+        paths.sort();
+
+        let mut errors = Vec::new();
+        for path in paths {
+            let loaded = fs::read_to_string(&path)
+                .map_err(|source| storage_error(source, &path))
+                .and_then(|contents| parse_profile(&path.display().to_string(), &contents))
+                .and_then(|profile| self.add(profile).map(|_| ()));
+            if let Err(error) = loaded {
+                errors.push(error);
+            }
+        }
+        errors
+    }
+
+    pub fn identify(&self, sys_object_id: Option<&str>, sys_descr: Option<&str>) -> PrinterProfile {
+        let sys_object_id = sys_object_id
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        let sys_descr = sys_descr.map(str::trim).filter(|value| !value.is_empty());
+        let object_id = sys_object_id.and_then(|value| Oid::from_str(value).ok());
+
+        let mut identified = PrinterProfile {
+            vendor: None,
+            match_status: ProfileMatch::Unknown,
+            model: None,
+            sys_object_id: sys_object_id.map(str::to_string),
+            sys_descr: sys_descr.map(str::to_string),
+            color: None,
+            counters: CounterAvailability::NONE,
+            strategy: CounterStrategy::Unknown,
+            counter_oids: CounterOidSet::default(),
+            recording_oids: BTreeMap::new(),
+            toner: Vec::new(),
+            capabilities: Capabilities::default(),
+            notes: Vec::new(),
+        };
+
+        let matched = self.profiles.iter().find_map(|compiled| {
+            let by_oid = object_id.as_ref().is_some_and(|oid| {
+                compiled
+                    .sys_object_id_prefixes
+                    .iter()
+                    .any(|prefix| oid.as_slice().starts_with(prefix.as_slice()))
+            });
+            let by_descr = sys_descr.is_some_and(|descr| {
+                compiled
+                    .sys_descr
+                    .as_ref()
+                    .is_some_and(|regex| regex.is_match(descr))
+            });
+            (by_oid || by_descr).then_some((compiled, by_oid, by_descr))
+        });
+        let Some((compiled, by_oid, by_descr)) = matched else {
+            return identified;
+        };
+
+        let name = &compiled.profile.name;
+        if by_oid && !by_descr {
+            identified
+                .notes
+                .push(format!("{name} identified via sysObjectID."));
+        }
+        if by_descr && !by_oid {
+            identified
+                .notes
+                .push(format!("{name} identified via sysDescr."));
+        }
+        identified.vendor = Some(name.clone());
+        identified.counter_oids = compiled.counter_oids.clone();
+        identified.recording_oids = compiled.recording_oids.clone();
+        identified.toner = compiled.toner.clone();
+        identified.capabilities = compiled.profile.capabilities;
+        identified.model = sys_descr.and_then(|descr| compiled.model_in(descr));
+        identified.color = identified
+            .model
+            .as_deref()
+            .and_then(|model| compiled.color_of(model));
+
+        let oids = &compiled.counter_oids;
+        let (match_status, counters, strategy) = match (&identified.model, identified.color) {
+            (None, _) => {
+                identified
+                    .notes
+                    .push(format!("{name} model string not found."));
+                (
+                    ProfileMatch::Unmapped,
+                    CounterAvailability::NONE,
+                    CounterStrategy::Unknown,
+                )
+            }
+            (Some(_), None) => {
+                identified.notes.push(format!(
+                    "Unmapped {name} model; counter availability unknown."
+                ));
+                (
+                    ProfileMatch::Unmapped,
+                    CounterAvailability::NONE,
+                    CounterStrategy::Unknown,
+                )
+            }
+            (Some(_), Some(color)) => {
+                let counters = CounterAvailability {
+                    bw: !oids.bw.is_empty(),
+                    color: color && !oids.color.is_empty(),
+                    total: !oids.total.is_empty(),
+                };
+                let strategy = match (counters.bw, counters.color, counters.total) {
+                    (true, true, _) => CounterStrategy::BwColorPreferred,
+                    (true, false, _) => CounterStrategy::BwOnly,
+                    (false, _, true) => CounterStrategy::TotalOnly,
+                    _ => CounterStrategy::Unknown,
+                };
+                (ProfileMatch::Known, counters, strategy)
+            }
+        };
+        identified.match_status = match_status;
+        identified.counters = counters;
+        identified.strategy = strategy;
+        identified
+    }
+
+    pub fn identify_printer(&self, record: &PrinterRecord) -> PrinterProfile {
+        self.identify(record.sys_object_id.as_deref(), record.model.as_deref())
+    }
+}
+
+fn parse_profile(source: &str, contents: &str) -> Result<VendorProfile, Error> {
+    ron::from_str(contents).map_err(|error| Error::Profile {
+        profile: source.to_string(),
+        details: error.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::TempDir;
+
+    #[test]
+    fn identifies_ricoh_from_oid() {
+        let registry = ProfileRegistry::builtin();
+        let profile = registry.identify(Some("1.3.6.1.4.1.367.3.2.1"), Some("Generic Printer"));
+        assert_eq!(profile.vendor.as_deref(), Some("Ricoh"));
+        assert_eq!(profile.match_status, ProfileMatch::Unmapped);
+        assert!(profile
+            .notes
+            .iter()
+            .any(|note| note.contains("Ricoh identified")));
+    }
+
+    #[test]
+    fn identifies_color_model_from_descr() {
+        let registry = ProfileRegistry::builtin();
+        let profile = registry.identify(None, Some("Ricoh IM C3000"));
+        assert_eq!(profile.match_status, ProfileMatch::Known);
+        assert!(profile.counters.color);
+        assert_eq!(profile.strategy, CounterStrategy::BwColorPreferred);
+        assert_eq!(profile.model.as_deref(), Some("IM C3000"));
+        assert_eq!(
+            profile.recording_oids[&RecordingCategory::CopiesColor],
+            vec![Oid::from_str("1.3.6.1.4.1.367.3.2.1.2.19.5.1.9.17").unwrap()]
+        );
+        // B/W and color come from the engine counters only; the Printer-MIB
+        // life count is a total.
+        let printer_mib = Oid::from_str("1.3.6.1.2.1.43").unwrap();
+        let oids = &profile.counter_oids;
+        assert!(oids
+            .bw
+            .iter()
+            .chain(&oids.color)
+            .all(|oid| !oid.as_slice().starts_with(printer_mib.as_slice())));
+        assert_eq!(
+            oids.total.last(),
+            Some(&Oid::from_str("1.3.6.1.2.1.43.10.2.1.4.1.1").unwrap())
+        );
+        assert_eq!(profile.toner.len(), 4);
+    }
+
+    #[test]
+    fn identifies_mono_model_from_descr() {
+        let registry = ProfileRegistry::builtin();
+        let profile = registry.identify(None, Some("RICOH IM 4000"));
+        assert_eq!(profile.match_status, ProfileMatch::Known);
+        assert!(!profile.counters.color);
+        assert_eq!(profile.strategy, CounterStrategy::BwOnly);
+        assert_eq!(profile.model.as_deref(), Some("IM 4000"));
+    }
+
+    #[test]
+    fn ships_other_vendors_and_rejects_unknown_printers() {
+        let registry = ProfileRegistry::builtin();
+        let hp = registry.identify(
+            Some("1.3.6.1.4.1.11.2.3.9.1"),
+            Some("HP Color LaserJet MFP M480f"),
+        );
+        assert_eq!(hp.vendor.as_deref(), Some("HP"));
+        assert_eq!(hp.strategy, CounterStrategy::BwColorPreferred);
+        let canon = registry.identify(None, Some("Canon iR-ADV C5535 /P"));
+        assert_eq!(canon.model.as_deref(), Some("iR-ADV C5535"));
+        assert_eq!(canon.strategy, CounterStrategy::TotalOnly);
+
+        // Arcs are compared whole: enterprise 110 is not HP's 11.
+        let unknown = registry.identify(Some("1.3.6.1.4.1.110.1"), Some("Generic Printer"));
+        assert_eq!(unknown.match_status, ProfileMatch::Unknown);
+        assert_eq!(unknown.counters, CounterAvailability::NONE);
+    }
+
+    #[test]
+    fn loaded_profiles_go_first_and_replace_by_name() {
+        let dir = TempDir::new("profiles");
+        fs::write(
+            dir.join("aficio.ron"),
+            r#"(
+                name: "Ricoh Aficio MP 7502",
+                sys_descr_pattern: Some("aficio mp 7502"),
+                model_pattern: Some("ricoh (aficio mp 7502)"),
+                models: [(pattern: ".", color: false)],
+                counters: (total: ["1.3.6.1.4.1.367.3.2.1.2.19.1.0"]),
+            )"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("broken.ron"),
+            "(name: \"Broken\", models: [(pattern: \"(\", color: true)])",
+        )
+        .unwrap();
+
+        let mut registry = ProfileRegistry::builtin();
+        let errors = registry.load_dir(dir.path());
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].technical_detail().contains("Broken"));
+        let aficio = registry.identify(Some("1.3.6.1.4.1.367.1"), Some("RICOH Aficio MP 7502"));
+        assert_eq!(aficio.vendor.as_deref(), Some("Ricoh Aficio MP 7502"));
+        assert_eq!(aficio.strategy, CounterStrategy::TotalOnly);
+        let other = registry.identify(None, Some("Ricoh MP C3004"));
+        assert_eq!(other.vendor.as_deref(), Some("Ricoh"));
+        assert_eq!(registry.profiles().count(), 6);
+    }
+}
@@ -0,0 +1,766 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::escpos::{EscPosOptions, EscPosTarget};
+use crate::model::{format_utc_timestamp, EpochSeconds, RecordingCategory};
+use crate::money::{Money, MoneyFormat, VatRate};
+use crate::pricing::{PriceList, PriceQuote};
+use crate::storage::{self, storage_error};
+use crate::{Error, StorageAction};
+
+/// Columns of the plain text layout inside a PDF page.
+const PDF_COLUMNS: usize = 72;
+/// A4 in points.
+const PDF_PAGE_WIDTH: u32 = 595;
+const PDF_PAGE_HEIGHT: u32 = 842;
+const PDF_MARGIN: u32 = 56;
+const PDF_FONT_SIZE: u32 = 10;
+const PDF_LEADING: u32 = 12;
+
+/// What the shop prints above and below every document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShopDetails {
+    pub name: String,
+    pub address_lines: Vec<String>,
+    pub vat_id: Option<String>,
+    pub footer_lines: Vec<String>,
+}
+
+/// Shop details and the invoice number sequence. The next number is saved
+/// before a number is handed out, so numbers stay unique across restarts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReceiptConfig {
+    pub shop: ShopDetails,
+    /// Printed before the number, e.g. "Receipt" or "Invoice".
+    pub title: String,
+    pub number_prefix: String,
+    /// Numbers are zero-padded to this many digits.
+    pub number_digits: usize,
+    pub next_number: u64,
+    /// Thermal printer each issued receipt is also sent to, if any.
+    pub escpos_target: Option<EscPosTarget>,
+    pub escpos: EscPosOptions,
+}
+
+impl Default for ReceiptConfig {
+    fn default() -> Self {
+        Self {
+            shop: ShopDetails::default(),
+            title: "Receipt".to_string(),
+            number_prefix: "R-".to_string(),
+            number_digits: 6,
+            next_number: 1,
+            escpos_target: None,
+            escpos: EscPosOptions::default(),
+        }
+    }
+}
+
+impl ReceiptConfig {
+    pub fn format_number(&self, number: u64) -> String {
+        format!(
+            "{}{number:0width$}",
+            self.number_prefix,
+            width = self.number_digits
+        )
+    }
+
+    /// Takes the next number and saves the advanced sequence to `path`. The
+    /// sequence is left alone when saving fails.
+    pub fn issue_number(&mut self, path: impl AsRef<Path>) -> Result<String, Error> {
+        let number = self.format_number(self.next_number);
+        self.next_number += 1;
+        if let Err(error) = self.save(path) {
+            self.next_number -= 1;
+            return Err(error);
+        }
+        Ok(number)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        storage::load_ron(path.as_ref())
+    }
+
+    /// Writes with `storage::write_atomic`, so a crash never leaves a
+    /// half-written sequence behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        storage::save_ron(path.as_ref(), self)
+    }
+}
+
+/// A category's counter readings at the start and end of the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterSpan {
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+}
+
+/// A numbered receipt for one recording session, priced by the price list
+/// version the session was pinned to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub number: String,
+    pub title: String,
+    pub issued_at: EpochSeconds,
+    pub shop: ShopDetails,
+    pub printer: Option<String>,
+    pub started_at: Option<EpochSeconds>,
+    pub stopped_at: Option<EpochSeconds>,
+    pub price_list: PriceList,
+    pub price_version: Option<u32>,
+    pub format: MoneyFormat,
+    /// Pages per category as billed, including categories no tariff covers.
+    pub pages: BTreeMap<RecordingCategory, u64>,
+    /// Readings printed for reference; empty to leave the section out.
+    pub counters: BTreeMap<RecordingCategory, CounterSpan>,
+    pub quote: PriceQuote,
+}
+
+/// One line of the layout every renderer shares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Line {
+    Heading(String),
+    Title(String),
+    Row {
+        label: String,
+        value: String,
+        indent: bool,
+    },
+    Total {
+        label: String,
+        value: String,
+    },
+    Rule,
+}
+
+impl Line {
+    fn row(label: impl Into<String>, value: impl Into<String>) -> Self {
+        Line::Row {
+            label: label.into(),
+            value: value.into(),
+            indent: false,
+        }
+    }
+
+    fn detail(label: impl Into<String>, value: impl Into<String>) -> Self {
+        Line::Row {
+            label: label.into(),
+            value: value.into(),
+            indent: true,
+        }
+    }
+}
+
+impl Receipt {
+    pub fn new(
+        number: impl Into<String>,
+        issued_at: EpochSeconds,
+        price_list: PriceList,
+        pages: &HashMap<RecordingCategory, u64>,
+    ) -> Self {
+        let quote = price_list.quote(pages);
+        Self {
+            number: number.into(),
+            title: ReceiptConfig::default().title,
+            issued_at,
+            shop: ShopDetails::default(),
+            printer: None,
+            started_at: None,
+            stopped_at: None,
+            price_list,
+            price_version: None,
+            format: MoneyFormat::default(),
+            pages: pages
+                .iter()
+                .map(|(category, pages)| (*category, *pages))
+                .collect(),
+            counters: BTreeMap::new(),
+            quote,
+        }
+    }
+
+    /// Takes the shop details and title from `config`.
+    pub fn with_config(mut self, config: &ReceiptConfig) -> Self {
+        self.shop = config.shop.clone();
+        self.title = config.title.clone();
+        self
+    }
+
+    pub fn with_printer(mut self, printer: impl Into<String>) -> Self {
+        self.printer = Some(printer.into());
+        self
+    }
+
+    pub fn with_session(
+        mut self,
+        started_at: Option<EpochSeconds>,
+        stopped_at: Option<EpochSeconds>,
+    ) -> Self {
+        self.started_at = started_at;
+        self.stopped_at = stopped_at;
+        self
+    }
+
+    pub fn with_counters(
+        mut self,
+        counters: impl IntoIterator<Item = (RecordingCategory, CounterSpan)>,
+    ) -> Self {
+        self.counters = counters.into_iter().collect();
+        self
+    }
+
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.price_version = Some(version);
+        self
+    }
+
+    pub fn with_format(mut self, format: MoneyFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Fixed-width text, e.g. 48 columns for an 80 mm receipt printer.
+    /// Values that do not fit beside their label move to the next line.
+    pub fn to_text(&self, width: usize) -> String {
+        let mut out = String::new();
+        for line in self.lines() {
+            match line {
+                Line::Heading(heading) => {
+                    let pad = width.saturating_sub(heading.chars().count()) / 2;
+                    out.push_str(&" ".repeat(pad));
+                    out.push_str(&heading);
+                }
+                Line::Title(title) => out.push_str(&title),
+                Line::Row {
+                    label,
+                    value,
+                    indent,
+                } => {
+                    let label = if indent { format!("  {label}") } else { label };
+                    push_columns(&mut out, &label, &value, width);
+                }
+                Line::Total { label, value } => {
+                    push_columns(&mut out, &label.to_uppercase(), &value, width);
+                }
+                Line::Rule => out.push_str(&"-".repeat(width)),
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// A standalone page with its own styles, escaped throughout.
+    pub fn to_html(&self) -> String {
+        let mut body = String::new();
+        let mut in_table = false;
+        for line in self.lines() {
+            let table_line = matches!(line, Line::Row { .. } | Line::Total { .. });
+            if table_line && !in_table {
+                body.push_str("<table>\n");
+            } else if !table_line && in_table {
+                body.push_str("</table>\n");
+            }
+            in_table = table_line;
+            match line {
+                Line::Heading(heading) => {
+                    body.push_str(&format!(
+                        "<p class=\"shop\">{}</p>\n",
+                        escape_html(&heading)
+                    ));
+                }
+                Line::Title(title) => {
+                    body.push_str(&format!("<h1>{}</h1>\n", escape_html(&title)));
+                }
+                Line::Row {
+                    label,
+                    value,
+                    indent,
+                } => {
+                    let class = if indent { " class=\"detail\"" } else { "" };
+                    body.push_str(&format!(
+                        "<tr{class}><td>{}</td><td class=\"value\">{}</td></tr>\n",
+                        escape_html(&label),
+                        escape_html(&value)
+                    ));
+                }
+                Line::Total { label, value } => {
+                    body.push_str(&format!(
+                        "<tr class=\"total\"><td>{}</td><td class=\"value\">{}</td></tr>\n",
+                        escape_html(&label),
+                        escape_html(&value)
+                    ));
+                }
+                Line::Rule => body.push_str("<hr>\n"),
+            }
+        }
+        if in_table {
+            body.push_str("</table>\n");
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{} {}</title>\n<style>\n\
+             body {{ font-family: sans-serif; max-width: 32em; margin: 2em auto; }}\n\
+             .shop {{ text-align: center; margin: 0.2em 0; }}\n\
+             h1 {{ font-size: 1.2em; }}\n\
+             table {{ width: 100%; border-collapse: collapse; }}\n\
+             td.value {{ text-align: right; white-space: nowrap; }}\n\
+             tr.detail td:first-child {{ padding-left: 1.5em; color: #555; }}\n\
+             tr.total td {{ font-weight: bold; border-top: 1px solid #000; }}\n\
+             </style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+            escape_html(&self.title),
+            escape_html(&self.number)
+        )
+    }
+
+    /// A4 pages of the text layout in the built-in Courier font, so no font
+    /// has to be embedded. Characters outside WinAnsi print as "?".
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let text = self.to_text(PDF_COLUMNS);
+        let lines: Vec<&str> = text.lines().collect();
+        let per_page = ((PDF_PAGE_HEIGHT - 2 * PDF_MARGIN) / PDF_LEADING) as usize;
+        let pages: Vec<&[&str]> = if lines.is_empty() {
+            vec![&[]]
+        } else {
+            lines.chunks(per_page).collect()
+        };
+
+        // 1: catalog, 2: page tree, 3: font, then a page and its contents for
+        // each page.
+        let page_ids: Vec<usize> = (0..pages.len()).map(|index| 4 + index * 2).collect();
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids
+                    .iter()
+                    .map(|id| format!("{id} 0 R"))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                pages.len()
+            )
+            .into_bytes(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        ];
+        for (page, id) in pages.iter().zip(&page_ids) {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PDF_PAGE_WIDTH} \
+                     {PDF_PAGE_HEIGHT}] /Resources << /Font << /F1 3 0 R >> >> \
+                     /Contents {} 0 R >>",
+                    id + 1
+                )
+                .into_bytes(),
+            );
+            let mut content = format!(
+                "BT\n/F1 {PDF_FONT_SIZE} Tf\n{PDF_LEADING} TL\n{PDF_MARGIN} {} Td\n",
+                PDF_PAGE_HEIGHT - PDF_MARGIN
+            )
+            .into_bytes();
+            for line in page.iter() {
+                content.push(b'(');
+                content.extend(pdf_string(line));
+                content.extend_from_slice(b") Tj T*\n");
+            }
+            content.extend_from_slice(b"ET");
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend(content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n", index + 1).into_bytes());
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+        for offset in offsets {
+            pdf.extend(format!("{offset:010} 00000 n \n").into_bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+                objects.len() + 1
+            )
+            .into_bytes(),
+        );
+        pdf
+    }
+
+    /// Writes `<number>.txt`, `.html` and `.pdf` into `dir`, creating it
+    /// when needed, and returns the paths written. Each file is replaced
+    /// whole, so an interrupted write never leaves half a receipt.
+    pub fn write_documents(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)
+            .map_err(|source| storage_error(StorageAction::Save, dir, source))?;
+        let stem: String = self
+            .number
+            .chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                    ch
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let documents = [
+            ("txt", self.to_text(48).into_bytes()),
+            ("html", self.to_html().into_bytes()),
+            ("pdf", self.to_pdf()),
+        ];
+        let mut written = Vec::with_capacity(documents.len());
+        for (extension, contents) in documents {
+            let path = dir.join(format!("{stem}.{extension}"));
+            storage::write_atomic(&path, &contents)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    fn money(&self, amount: Money) -> String {
+        amount.format_amount(&self.price_list.currency, &self.format)
+    }
+
+    fn signed_money(&self, amount: Money) -> String {
+        let sign = if amount < Money::ZERO { "" } else { "+" };
+        format!("{sign}{}", self.money(amount))
+    }
+
+    pub(crate) fn lines(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let shop = &self.shop;
+        if !shop.name.trim().is_empty() {
+            lines.push(Line::Heading(shop.name.clone()));
+        }
+        lines.extend(shop.address_lines.iter().cloned().map(Line::Heading));
+        if let Some(vat_id) = &shop.vat_id {
+            lines.push(Line::Heading(format!("VAT ID {vat_id}")));
+        }
+        if !lines.is_empty() {
+            lines.push(Line::Rule);
+        }
+
+        lines.push(Line::Title(format!("{} {}", self.title, self.number)));
+        lines.push(Line::row("Issued", format_utc_timestamp(self.issued_at)));
+        if let Some(printer) = &self.printer {
+            lines.push(Line::row("Printer", printer.clone()));
+        }
+        if let Some(started_at) = self.started_at {
+            lines.push(Line::row("Started", format_utc_timestamp(started_at)));
+        }
+        if let Some(stopped_at) = self.stopped_at {
+            lines.push(Line::row("Stopped", format_utc_timestamp(stopped_at)));
+        }
+        let price_list = match self.price_version {
+            Some(version) => format!("{} v{version}", self.price_list.name),
+            None => self.price_list.name.clone(),
+        };
+        lines.push(Line::row("Price list", price_list));
+        lines.push(Line::Rule);
+
+        if !self.counters.is_empty() {
+            let reading =
+                |value: Option<u64>| value.map_or("n/a".to_string(), |value| value.to_string());
+            for (category, span) in &self.counters {
+                let billed = match self.pages.get(category) {
+                    Some(pages) => pages_label(*pages),
+                    None => "not billed".to_string(),
+                };
+                lines.push(Line::row(
+                    category.label(),
+                    format!("{} -> {}, {billed}", reading(span.start), reading(span.end)),
+                ));
+            }
+            lines.push(Line::Rule);
+        }
+
+        let pages_of = |category: &RecordingCategory| self.pages.get(category).copied();
+        for (tariff, charge) in self.price_list.tariffs.iter().zip(&self.quote.charges) {
+            if charge.pages == 0 && charge.amount.is_zero() {
+                continue;
+            }
+            for category in &tariff.categories {
+                if let Some(pages) = pages_of(category).filter(|pages| *pages > 0) {
+                    let pages = if charge.surcharge {
+                        format!("{}, surcharge", pages_label(pages))
+                    } else {
+                        pages_label(pages)
+                    };
+                    lines.push(Line::row(category.label(), pages));
+                }
+            }
+            for tier in &charge.tiers {
+                lines.push(Line::detail(
+                    format!("{} x {}", tier.pages, self.money(tier.unit_price)),
+                    self.money(tier.amount),
+                ));
+            }
+            if charge.minimum_applied {
+                lines.push(Line::detail("Minimum charge", self.money(tariff.minimum)));
+            }
+            if !charge.rounding.is_zero() {
+                lines.push(Line::detail("Rounding", self.signed_money(charge.rounding)));
+            }
+            lines.push(Line::row(tariff.name.clone(), self.money(charge.amount)));
+        }
+        for (category, pages) in &self.pages {
+            let priced = self
+                .price_list
+                .tariffs
+                .iter()
+                .any(|tariff| tariff.categories.contains(category));
+            if !priced && *pages > 0 {
+                lines.push(Line::row(
+                    category.label(),
+                    format!("{}, not charged", pages_label(*pages)),
+                ));
+            }
+        }
+        if !self.quote.base_fee.is_zero() {
+            lines.push(Line::row("Base fee", self.money(self.quote.base_fee)));
+        }
+        lines.push(Line::Rule);
+
+        let taxed: Vec<_> = self
+            .quote
+            .vat
+            .iter()
+            .filter(|line| line.rate != VatRate::ZERO)
+            .collect();
+        if !taxed.is_empty() {
+            lines.push(Line::row("Net", self.money(self.quote.net)));
+            for line in taxed {
+                lines.push(Line::row(
+                    format!("VAT {} on {}", line.rate, self.money(line.net)),
+                    self.money(line.vat),
+                ));
+            }
+        }
+        if !self.quote.rounding.is_zero() {
+            lines.push(Line::row(
+                "Rounding",
+                self.signed_money(self.quote.rounding),
+            ));
+        }
+        lines.push(Line::Total {
+            label: "Total".to_string(),
+            value: self
+                .quote
+                .total
+                .format(&self.price_list.currency, &self.format),
+        });
+
+        if !shop.footer_lines.is_empty() {
+            lines.push(Line::Rule);
+            lines.extend(shop.footer_lines.iter().cloned().map(Line::Heading));
+        }
+        lines
+    }
+}
+
+fn pages_label(pages: u64) -> String {
+    if pages == 1 {
+        "1 page".to_string()
+    } else {
+        format!("{pages} pages")
+    }
+}
+
+pub(crate) fn push_columns(out: &mut String, label: &str, value: &str, width: usize) {
+    let used = label.chars().count() + value.chars().count();
+    if used < width {
+        out.push_str(label);
+        out.push_str(&" ".repeat(width - used));
+        out.push_str(value);
+    } else {
+        out.push_str(label);
+        out.push('\n');
+        let pad = width.saturating_sub(value.chars().count());
+        out.push_str(&" ".repeat(pad));
+        out.push_str(value);
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// The body of a PDF literal string in WinAnsiEncoding, with everything
+/// outside printable ASCII written as an octal escape.
+fn pdf_string(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+    for ch in value.chars() {
+        let byte = match ch {
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            ' '..='~' | '\u{a0}'..='\u{ff}' => ch as u8,
+            _ => b'?',
+        };
+        match byte {
+            b'(' | b')' | b'\\' => bytes.extend_from_slice(&[b'\\', byte]),
+            0x20..=0x7e => bytes.push(byte),
+            _ => bytes.extend(format!("\\{byte:03o}").into_bytes()),
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::{PriceTier, Tariff};
+    use crate::storage::tests::TempDir;
+
+    fn receipt() -> Receipt {
+        let config = ReceiptConfig {
+            shop: ShopDetails {
+                name: "Copy & Print".to_string(),
+                address_lines: vec!["Hauptstraße 1".to_string()],
+                vat_id: Some("DE123456789".to_string()),
+                footer_lines: vec!["Thank you!".to_string()],
+            },
+            ..ReceiptConfig::default()
+        };
+        let pages = [
+            (RecordingCategory::CopiesBw, 7),
+            (RecordingCategory::PrintsBw, 5),
+            (RecordingCategory::PrintsColor, 3),
+        ]
+        .into_iter()
+        .collect();
+        Receipt::new("R-000042", 1_760_623_320, PriceList::default(), &pages)
+            .with_config(&config)
+            .with_printer("ricoh-1")
+            .with_session(Some(1_760_620_000), Some(1_760_623_200))
+            .with_version(2)
+    }
+
+    #[test]
+    fn numbers_continue_after_a_restart() {
+        let dir = TempDir::new("receipt-numbers");
+        let path = dir.join("receipts.ron");
+        let mut config = ReceiptConfig::default();
+        assert_eq!(config.issue_number(&path).expect("issue"), "R-000001");
+        assert_eq!(config.issue_number(&path).expect("issue"), "R-000002");
+
+        let mut restarted = ReceiptConfig::load(&path).expect("load");
+        assert_eq!(restarted.issue_number(&path).expect("issue"), "R-000003");
+
+        // A number that could not be saved is not handed out.
+        let missing = dir.join("missing").join("receipts.ron");
+        assert!(restarted.issue_number(&missing).is_err());
+        assert_eq!(restarted.next_number, 4);
+    }
+
+    #[test]
+    fn lists_each_category_with_its_tiers() {
+        let text = receipt().to_text(48);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines.iter().all(|line| line.chars().count() <= 48));
+        assert_eq!(lines[0].trim(), "Copy & Print");
+        for expected in [
+            "Receipt R-000042",
+            "Issued                      2025-10-16 14:02 UTC",
+            "Copies B/W                               7 pages",
+            "Prints B/W                               5 pages",
+            "  5 x 0.25                                  1.25",
+            "  Rounding                                 +0.13",
+            "B/W                                         2.00",
+            "Prints color                             3 pages",
+            "TOTAL                                   3.50 EUR",
+        ] {
+            assert!(lines.contains(&expected), "missing {expected:?} in\n{text}");
+        }
+        assert!(!text.contains("Copies color"));
+
+        let html = receipt().to_html();
+        assert!(html.contains("<p class=\"shop\">Copy &amp; Print</p>"));
+        assert!(html.contains("<tr class=\"total\"><td>Total</td>"));
+    }
+
+    #[test]
+    fn marks_surcharge_pages() {
+        let mut price_list = PriceList::default();
+        price_list.tariffs.push(Tariff {
+            name: "Large (A3) surcharge".to_string(),
+            categories: vec![RecordingCategory::Large],
+            tiers: vec![PriceTier::rest(Money::from_minor(20))],
+            minimum: Money::ZERO,
+            rounding: None,
+        });
+        let pages = [
+            (RecordingCategory::CopiesBw, 3),
+            (RecordingCategory::Large, 2),
+        ]
+        .into_iter()
+        .collect();
+        let text = Receipt::new("R-000043", 1_760_623_320, price_list, &pages).to_text(48);
+        let lines: Vec<&str> = text.lines().collect();
+        for expected in [
+            "Copies B/W                               3 pages",
+            "Large (A3)                    2 pages, surcharge",
+            "Large (A3) surcharge                        0.40",
+            "TOTAL                                   1.40 EUR",
+        ] {
+            assert!(lines.contains(&expected), "missing {expected:?} in\n{text}");
+        }
+    }
+
+    #[test]
+    fn writes_a_pdf_with_a_valid_xref() {
+        let pdf = receipt().to_pdf();
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let text = String::from_utf8_lossy(&pdf);
+        // "ß" is written as its WinAnsi code.
+        assert!(text.contains("(                             Hauptstra\\337e 1) Tj T*"));
+
+        let startxref = text.rfind("startxref\n").expect("startxref") + "startxref\n".len();
+        let offset: usize = text[startxref..]
+            .lines()
+            .next()
+            .and_then(|line| line.parse().ok())
+            .expect("offset");
+        assert!(pdf[offset..].starts_with(b"xref\n0 6\n"));
+        let xref = String::from_utf8_lossy(&pdf[offset..]);
+        let first_object = xref.lines().nth(3).expect("entry");
+        let first_offset: usize = first_object[..10].parse().expect("entry offset");
+        assert!(pdf[first_offset..].starts_with(b"1 0 obj\n"));
+
+        let dir = TempDir::new("receipt-documents");
+        let written = receipt().write_documents(dir.path()).expect("write");
+        assert_eq!(written.len(), 3);
+        assert_eq!(fs::read(&written[2]).expect("read pdf"), pdf);
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::delta::{CounterDelta, CounterReading};
+use crate::history::HistoryRecord;
+use crate::model::{CounterSnapshot, EpochSeconds, PrinterId};
+use crate::storage;
+use crate::Error;
+
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A recording session as far as reconciliation cares: when it ran and how
+/// many pages it recorded. `pages` sums the copies and prints categories
+/// only, the ones the total counter counts; the extended categories either
+/// count the same pages again (large, duplex, two- and single-color) or no
+/// printed pages at all (scans, faxes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionSpan {
+    pub start: EpochSeconds,
+    pub end: EpochSeconds,
+    pub pages: u64,
+}
+
+/// Pages the counters show outside every session, between two polls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnbilledGap {
+    /// Poll before the first uncovered pages.
+    pub from: EpochSeconds,
+    /// Poll after the last uncovered pages.
+    pub to: EpochSeconds,
+    pub pages: u64,
+}
+
+/// Counter totals of one printer over one UTC day against its sessions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reconciliation {
+    pub printer: PrinterId,
+    pub day_start: EpochSeconds,
+    /// Pages between consecutive polls that ended on this day.
+    pub counter_pages: u64,
+    /// Of those, pages polled while a session ran.
+    pub covered_pages: u64,
+    /// Pages the sessions that ran on this day recorded themselves.
+    pub session_pages: u64,
+    pub gaps: Vec<UnbilledGap>,
+    /// Steps where the counter went backwards; their pages are unknown and
+    /// left out of every sum.
+    pub resets: usize,
+}
+
+impl Reconciliation {
+    /// Walks the polled totals of the day. A step between two polls counts
+    /// for a session when its midpoint falls inside the session, so the
+    /// polls a session starts and stops on split the pages correctly even
+    /// when their timestamps differ by a second or two.
+    pub fn build(
+        printer: &PrinterId,
+        day_start: EpochSeconds,
+        records: &[HistoryRecord],
+        sessions: &[SessionSpan],
+    ) -> Self {
+        let day_start = day_start - day_start % SECONDS_PER_DAY;
+        let day_end = day_start + SECONDS_PER_DAY;
+        let mut points: Vec<(EpochSeconds, CounterReading)> = records
+            .iter()
+            .flat_map(|record| match record {
+                HistoryRecord::Snapshot(entry) => vec![&entry.snapshot],
+                HistoryRecord::Rollup(rollup) => vec![&rollup.first, &rollup.last],
+            })
+            .filter_map(|snapshot| Some((snapshot.timestamp, total_reading(snapshot)?)))
+            .collect();
+        points.sort_by_key(|(timestamp, _)| *timestamp);
+        points.dedup_by_key(|(timestamp, _)| *timestamp);
+
+        let mut reconciliation = Self {
+            printer: printer.clone(),
+            day_start,
+            counter_pages: 0,
+            covered_pages: 0,
+            session_pages: sessions
+                .iter()
+                .filter(|session| session.start < day_end && session.end >= day_start)
+                .map(|session| session.pages)
+                .sum(),
+            gaps: Vec::new(),
+            resets: 0,
+        };
+        let mut open_gap: Option<UnbilledGap> = None;
+        for pair in points.windows(2) {
+            let ((from, previous), (to, current)) = (pair[0], pair[1]);
+            if to < day_start || to >= day_end {
+                continue;
+            }
+            let pages = match CounterDelta::between(previous, current) {
+                CounterDelta::Increase(pages) | CounterDelta::Wrapped(pages) => pages,
+                CounterDelta::Reset(_) => {
+                    reconciliation.resets += 1;
+                    continue;
+                }
+            };
+            reconciliation.counter_pages += pages;
+            let midpoint = from + (to - from) / 2;
+            let covered = sessions
+                .iter()
+                .any(|session| session.start <= midpoint && midpoint <= session.end);
+            if covered {
+                reconciliation.covered_pages += pages;
+                reconciliation.gaps.extend(open_gap.take());
+            } else if pages > 0 {
+                match &mut open_gap {
+                    Some(gap) => {
+                        gap.to = to;
+                        gap.pages += pages;
+                    }
+                    None => open_gap = Some(UnbilledGap { from, to, pages }),
+                }
+            }
+        }
+        reconciliation.gaps.extend(open_gap);
+        reconciliation
+    }
+
+    pub fn unbilled_pages(&self) -> u64 {
+        self.gaps.iter().map(|gap| gap.pages).sum()
+    }
+}
+
+/// The total counter, or B/W plus color when the printer has no total. A
+/// sum that overflows is garbage from the device and counts as no reading.
+fn total_reading(snapshot: &CounterSnapshot) -> Option<CounterReading> {
+    let value = match (snapshot.total, snapshot.bw, snapshot.color) {
+        (Some(total), _, _) => total,
+        (None, Some(bw), Some(color)) => bw.checked_add(color)?,
+        _ => return None,
+    };
+    Some(CounterReading::new(value, snapshot.uptime))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GapAction {
+    /// Billed some other way, e.g. a walk-up sale rung up by hand.
+    Attributed,
+    /// Not billed, e.g. test prints or a jam.
+    WrittenOff,
+}
+
+impl fmt::Display for GapAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GapAction::Attributed => f.write_str("Attributed"),
+            GapAction::WrittenOff => f.write_str("Written off"),
+        }
+    }
+}
+
+/// What an operator decided about one gap.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GapDecision {
+    pub printer: PrinterId,
+    pub gap: UnbilledGap,
+    pub action: GapAction,
+    #[serde(default)]
+    pub note: String,
+    pub decided_at: EpochSeconds,
+}
+
+/// Every gap decision, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconciliationLog {
+    decisions: Vec<GapDecision>,
+}
+
+impl ReconciliationLog {
+    pub fn decisions(&self) -> &[GapDecision] {
+        &self.decisions
+    }
+
+    /// The decision for a gap with the same bounds on the same printer.
+    pub fn decision_for(&self, printer: &PrinterId, gap: &UnbilledGap) -> Option<&GapDecision> {
+        self.decisions.iter().find(|decision| {
+            &decision.printer == printer
+                && decision.gap.from == gap.from
+                && decision.gap.to == gap.to
+        })
+    }
+
+    /// Records a decision, replacing an earlier one for the same gap.
+    pub fn decide(&mut self, decision: GapDecision) {
+        self.decisions.retain(|existing| {
+            existing.printer != decision.printer
+                || existing.gap.from != decision.gap.from
+                || existing.gap.to != decision.gap.to
+        });
+        self.decisions.push(decision);
+    }
+
+    /// Pages of `reconciliation`'s gaps nobody has decided on yet.
+    pub fn open_pages(&self, reconciliation: &Reconciliation) -> u64 {
+        reconciliation
+            .gaps
+            .iter()
+            .filter(|gap| self.decision_for(&reconciliation.printer, gap).is_none())
+            .map(|gap| gap.pages)
+            .sum()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        storage::load_ron(path.as_ref())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        storage::save_ron(path.as_ref(), self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::CounterMode;
+    use crate::history::{HistoryEntry, HistoryRollup, RollupPeriod};
+
+    const DAY: EpochSeconds = 19_700 * SECONDS_PER_DAY;
+
+    fn poll(offset: u64, total: u64) -> HistoryRecord {
+        let mut snapshot = CounterSnapshot::new(DAY + offset);
+        snapshot.total = Some(total);
+        HistoryRecord::Snapshot(HistoryEntry {
+            snapshot,
+            mode: CounterMode::TotalOnly,
+            warnings: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn finds_pages_printed_outside_sessions() {
+        let printer = PrinterId("ricoh-1".to_string());
+        let records = vec![
+            poll(0, 100),
+            // 5 walk-up copies before the session.
+            poll(300, 105),
+            // Session from 301 to 899 (clocks a second off the polls).
+            poll(600, 120),
+            poll(900, 130),
+            // 4 more after it, then a quiet poll, then 2 more.
+            poll(1_200, 134),
+            poll(1_500, 134),
+            poll(1_800, 136),
+            // A counter reset is left out.
+            poll(2_100, 3),
+            poll(2_400, 3),
+        ];
+        let sessions = [SessionSpan {
+            start: DAY + 301,
+            end: DAY + 899,
+            pages: 25,
+        }];
+
+        let reconciliation = Reconciliation::build(&printer, DAY + 4_000, &records, &sessions);
+        assert_eq!(reconciliation.day_start, DAY);
+        assert_eq!(reconciliation.counter_pages, 36);
+        assert_eq!(reconciliation.covered_pages, 25);
+        assert_eq!(reconciliation.session_pages, 25);
+        assert_eq!(reconciliation.resets, 1);
+        let gap = |from, to, pages| UnbilledGap {
+            from: DAY + from,
+            to: DAY + to,
+            pages,
+        };
+        assert_eq!(
+            reconciliation.gaps,
+            vec![gap(0, 300, 5), gap(900, 1_800, 6)]
+        );
+        assert_eq!(reconciliation.unbilled_pages(), 11);
+
+        let mut log = ReconciliationLog::default();
+        assert_eq!(log.open_pages(&reconciliation), 11);
+        let decision = |action| GapDecision {
+            printer: printer.clone(),
+            gap: reconciliation.gaps[1],
+            action,
+            note: String::new(),
+            decided_at: DAY + 5_000,
+        };
+        log.decide(decision(GapAction::WrittenOff));
+        log.decide(decision(GapAction::Attributed));
+        assert_eq!(log.decisions().len(), 1);
+        assert_eq!(log.open_pages(&reconciliation), 5);
+    }
+
+    #[test]
+    fn counts_the_step_into_the_day_and_rollups() {
+        let printer = PrinterId("ricoh-1".to_string());
+        let mut first = CounterSnapshot::new(DAY - 7_200);
+        first.bw = Some(40);
+        first.color = Some(10);
+        let mut last = first.clone();
+        last.timestamp = DAY - 3_700;
+        last.bw = Some(45);
+        let mut morning = CounterSnapshot::new(DAY + 3_600);
+        morning.bw = Some(47);
+        morning.color = Some(12);
+        let records = vec![
+            HistoryRecord::Rollup(HistoryRollup {
+                period: RollupPeriod::Hourly,
+                start: DAY - 7_200,
+                samples: 12,
+                first,
+                last,
+                mode: CounterMode::BwColor,
+                warnings: Vec::new(),
+            }),
+            HistoryRecord::Snapshot(HistoryEntry {
+                snapshot: morning,
+                mode: CounterMode::BwColor,
+                warnings: Vec::new(),
+            }),
+        ];
+
+        let mut garbage = CounterSnapshot::new(DAY + 1_800);
+        garbage.bw = Some(u64::MAX);
+        garbage.color = Some(1);
+        assert!(total_reading(&garbage).is_none());
+
+        let reconciliation = Reconciliation::build(&printer, DAY, &records, &[]);
+        // Only the overnight step ends on this day.
+        assert_eq!(reconciliation.counter_pages, 4);
+        assert_eq!(
+            reconciliation.gaps,
+            vec![UnbilledGap {
+                from: DAY - 3_700,
+                to: DAY + 3_600,
+                pages: 4,
+            }]
+        );
+    }
+}
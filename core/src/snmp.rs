@@ -1,31 +1,41 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::future::Future;
 use std::io;
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 use snmp2::{
-    snmp as snmp2_constants, AsyncSession, Error as Snmp2Error, Oid as Snmp2Oid,
-    Value as Snmp2Value,
+    snmp as snmp2_constants, v3 as snmp2_v3, AsyncSession, Error as Snmp2Error,
+    Oid as Snmp2Oid, Value as Snmp2Value,
 };
 
 use tokio::time::timeout;
 use tracing::{debug, trace, warn};
 
+use crate::model::{SnmpAuthProtocol, SnmpPrivProtocol, SnmpSecurityLevel, SnmpV3Credentials};
 use crate::targets;
+use crate::usm::{self, UsmFailure};
 use crate::{Error, SnmpAddress};
 
+/// sysUpTime.0, in hundredths of a second since the agent restarted.
+pub const SYS_UPTIME_OID: [u32; 9] = [1, 3, 6, 1, 2, 1, 1, 3, 0];
+
 const MAX_OIDS_PER_GET: usize = 24;
+const DEFAULT_MAX_REPETITIONS: u32 = 10;
 
 #[derive(Debug, Clone)]
 pub struct SnmpConfig {
     pub community: String,
     pub timeout: Duration,
     pub retries: u32,
+    /// Shared between clones so every client built from one config learns
+    /// the same per-printer limits.
+    pub pdu_sizes: PduSizeCache,
 }
 
 impl Default for SnmpConfig {
@@ -34,10 +44,85 @@ impl Default for SnmpConfig {
             community: "public".to_string(),
             timeout: Duration::from_secs(2),
             retries: 1,
+            pdu_sizes: PduSizeCache::default(),
+        }
+    }
+}
+
+/// PDU shape an agent is known to answer without `tooBig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PduSize {
+    pub max_repetitions: u32,
+    pub oids_per_get: usize,
+}
+
+impl Default for PduSize {
+    fn default() -> Self {
+        Self {
+            max_repetitions: u32::MAX,
+            oids_per_get: MAX_OIDS_PER_GET,
+        }
+    }
+}
+
+/// Per-printer memory of the largest GET batch and GETBULK repetition count
+/// that worked, keyed by `host:port`. Limits only ever shrink.
+#[derive(Debug, Clone, Default)]
+pub struct PduSizeCache {
+    sizes: Arc<Mutex<HashMap<String, PduSize>>>,
+}
+
+impl PduSizeCache {
+    pub fn get(&self, address: &SnmpAddress) -> PduSize {
+        self.sizes
+            .lock()
+            .ok()
+            .and_then(|sizes| sizes.get(&address.to_string()).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut sizes) = self.sizes.lock() {
+            sizes.clear();
+        }
+    }
+
+    fn max_repetitions(&self, address: &SnmpAddress, requested: u32) -> u32 {
+        requested.min(self.get(address).max_repetitions).max(1)
+    }
+
+    fn oids_per_get(&self, address: &SnmpAddress) -> usize {
+        self.get(address).oids_per_get.max(1)
+    }
+
+    fn update(&self, address: &SnmpAddress, apply: impl FnOnce(&mut PduSize)) {
+        if let Ok(mut sizes) = self.sizes.lock() {
+            apply(sizes.entry(address.to_string()).or_default());
         }
     }
 }
 
+/// Running count of varbinds collected by a walk, readable while it runs.
+/// Clones share the counter, so one handle can follow several walks.
+#[derive(Debug, Clone, Default)]
+pub struct WalkProgress {
+    collected: Arc<AtomicUsize>,
+}
+
+impl WalkProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn collected(&self) -> usize {
+        self.collected.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add(&self, count: usize) {
+        self.collected.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SnmpRequest {
     pub address: SnmpAddress,
@@ -66,6 +151,9 @@ pub struct SnmpWalkRequest {
     pub community: Option<String>,
     pub root_oid: Oid,
     pub max_results: usize,
+    /// Rows requested per GETBULK; ignored by GETNEXT walks.
+    pub max_repetitions: u32,
+    pub progress: Option<WalkProgress>,
 }
 
 impl SnmpWalkRequest {
@@ -75,6 +163,8 @@ impl SnmpWalkRequest {
             community: None,
             root_oid,
             max_results: 64,
+            max_repetitions: DEFAULT_MAX_REPETITIONS,
+            progress: None,
         }
     }
 
@@ -87,6 +177,16 @@ impl SnmpWalkRequest {
         self.max_results = max_results;
         self
     }
+
+    pub fn with_max_repetitions(mut self, max_repetitions: u32) -> Self {
+        self.max_repetitions = max_repetitions.max(1);
+        self
+    }
+
+    pub fn with_progress(mut self, progress: WalkProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -95,13 +195,14 @@ pub struct SnmpResponse {
     pub varbinds: Vec<SnmpVarBind>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SnmpVarBind {
     pub oid: Oid,
     pub value: SnmpValue,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Ordering is lexicographic by arc, which matches SNMP's GETNEXT order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Oid(pub Vec<u32>);
 
 impl Oid {
@@ -178,7 +279,7 @@ impl FromStr for Oid {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SnmpValue {
     Null,
     EndOfMibView,
@@ -303,6 +404,23 @@ pub type SnmpFuture<'a> =
 
 pub trait SnmpClient: Send + Sync {
     fn get<'a>(&'a self, request: SnmpRequest) -> SnmpFuture<'a>;
+
+    /// Walks the subtree under `root_oid` with GETNEXT.
+    fn walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a>;
+
+    /// Walks the subtree under `root_oid` with GETBULK.
+    fn bulk_walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a>;
+}
+
+/// Picks the v3 client when credentials are set, otherwise v2c.
+pub fn snmp_client_for(
+    config: SnmpConfig,
+    snmp_v3: Option<SnmpV3Credentials>,
+) -> Arc<dyn SnmpClient> {
+    match snmp_v3 {
+        Some(credentials) => Arc::new(SnmpV3Client::new(config, credentials)),
+        None => Arc::new(SnmpV2cClient::new(config)),
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -328,117 +446,197 @@ impl SnmpV2cClient {
 
         let config = self.config.clone();
         let community = community.unwrap_or_else(|| config.community.clone());
-        let address_label = address.to_string();
-        let oids_label: Vec<String> = oids.iter().map(|oid| oid.to_string()).collect();
-
-        debug!(
-            target: targets::SNMP,
-            address = %address_label,
-            oids = ?oids_label,
-            timeout_ms = config.timeout.as_millis(),
-            retries = config.retries,
-            "SNMP GET"
-        );
+        instrumented_get(address, SessionSecurity::Community(community), oids, config).await
+    }
 
-        for oid in &oids {
-            trace!(target: targets::SNMP, address = %address_label, oid = %oid, "SNMP OID");
-        }
+    pub async fn walk(&self, request: SnmpWalkRequest) -> Result<SnmpResponse, Error> {
+        self.walk_with(request, WalkStrategy::GetNext).await
+    }
 
-        match async_get(address, community, oids, config).await {
-            Ok(response) => {
-                debug!(
-                    target: targets::SNMP,
-                    address = %address_label,
-                    count = response.varbinds.len(),
-                    "SNMP GET ok"
-                );
-                for varbind in &response.varbinds {
-                    trace!(
-                        target: targets::SNMP,
-                        address = %address_label,
-                        oid = %varbind.oid,
-                        value = %varbind.value,
-                        "SNMP value"
-                    );
-                }
-                Ok(response)
-            }
-            Err(error) => {
-                warn!(
-                    target: targets::SNMP,
-                    address = %address_label,
-                    error = %error,
-                    "SNMP GET failed"
-                );
-                Err(error)
-            }
-        }
+    pub async fn bulk_walk(&self, request: SnmpWalkRequest) -> Result<SnmpResponse, Error> {
+        let strategy = WalkStrategy::Bulk {
+            max_repetitions: request.max_repetitions,
+        };
+        self.walk_with(request, strategy).await
     }
 
-    pub async fn walk(&self, request: SnmpWalkRequest) -> Result<SnmpResponse, Error> {
+    async fn walk_with(
+        &self,
+        request: SnmpWalkRequest,
+        strategy: WalkStrategy,
+    ) -> Result<SnmpResponse, Error> {
         let SnmpWalkRequest {
             address,
             community,
             root_oid,
             max_results,
+            progress,
+            ..
         } = request;
 
         let config = self.config.clone();
         let community = community.unwrap_or_else(|| config.community.clone());
-        let address_label = address.to_string();
+        instrumented_walk(
+            address,
+            SessionSecurity::Community(community),
+            WalkScope {
+                root_oid,
+                max_results,
+                progress,
+            },
+            strategy,
+            config,
+        )
+        .await
+    }
+}
 
-        debug!(
-            target: targets::SNMP,
-            address = %address_label,
-            root = %root_oid,
+impl SnmpClient for SnmpV2cClient {
+    fn get<'a>(&'a self, request: SnmpRequest) -> SnmpFuture<'a> {
+        Box::pin(async move { SnmpV2cClient::get(self, request).await })
+    }
+
+    fn walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        Box::pin(async move { SnmpV2cClient::walk(self, request).await })
+    }
+
+    fn bulk_walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        Box::pin(async move { SnmpV2cClient::bulk_walk(self, request).await })
+    }
+}
+
+/// SNMPv3 client using the User-based Security Model.
+///
+/// The community on requests is ignored; every request is sent as the
+/// configured USM user. Rejections are diagnosed with an unauthenticated probe
+/// so `Error::SnmpAuth` carries the USM reason.
+#[derive(Debug, Clone)]
+pub struct SnmpV3Client {
+    config: SnmpConfig,
+    credentials: SnmpV3Credentials,
+}
+
+impl SnmpV3Client {
+    pub fn new(config: SnmpConfig, credentials: SnmpV3Credentials) -> Self {
+        Self {
+            config,
+            credentials,
+        }
+    }
+
+    pub fn config(&self) -> &SnmpConfig {
+        &self.config
+    }
+
+    pub fn credentials(&self) -> &SnmpV3Credentials {
+        &self.credentials
+    }
+
+    pub async fn get(&self, request: SnmpRequest) -> Result<SnmpResponse, Error> {
+        let SnmpRequest { address, oids, .. } = request;
+        let security = SessionSecurity::V3(self.credentials.clone());
+        let result = instrumented_get(address.clone(), security, oids, self.config.clone()).await;
+        self.explain_rejection(&address, result).await
+    }
+
+    pub async fn walk(&self, request: SnmpWalkRequest) -> Result<SnmpResponse, Error> {
+        self.walk_with(request, WalkStrategy::GetNext).await
+    }
+
+    pub async fn bulk_walk(&self, request: SnmpWalkRequest) -> Result<SnmpResponse, Error> {
+        let strategy = WalkStrategy::Bulk {
+            max_repetitions: request.max_repetitions,
+        };
+        self.walk_with(request, strategy).await
+    }
+
+    async fn walk_with(
+        &self,
+        request: SnmpWalkRequest,
+        strategy: WalkStrategy,
+    ) -> Result<SnmpResponse, Error> {
+        let SnmpWalkRequest {
+            address,
+            root_oid,
             max_results,
-            timeout_ms = config.timeout.as_millis(),
-            retries = config.retries,
-            "SNMP WALK"
-        );
+            progress,
+            ..
+        } = request;
+        let security = SessionSecurity::V3(self.credentials.clone());
+        let result = instrumented_walk(
+            address.clone(),
+            security,
+            WalkScope {
+                root_oid,
+                max_results,
+                progress,
+            },
+            strategy,
+            self.config.clone(),
+        )
+        .await;
+        self.explain_rejection(&address, result).await
+    }
 
-        match async_walk(address, community, root_oid, max_results, config).await {
-            Ok(response) => {
-                debug!(
-                    target: targets::SNMP,
-                    address = %address_label,
-                    count = response.varbinds.len(),
-                    "SNMP WALK ok"
-                );
-                for varbind in &response.varbinds {
-                    trace!(
-                        target: targets::SNMP,
-                        address = %address_label,
-                        oid = %varbind.oid,
-                        value = %varbind.value,
-                        "SNMP walk value"
-                    );
-                }
-                Ok(response)
-            }
-            Err(error) => {
+    async fn explain_rejection(
+        &self,
+        address: &SnmpAddress,
+        result: Result<SnmpResponse, Error>,
+    ) -> Result<SnmpResponse, Error> {
+        match result {
+            Err(Error::SnmpAuth {
+                address: label,
+                details: None,
+            }) => {
+                let failure =
+                    usm::diagnose_rejection(address, &self.credentials, self.config.timeout)
+                        .await;
                 warn!(
                     target: targets::SNMP,
-                    address = %address_label,
-                    error = %error,
-                    "SNMP WALK failed"
+                    address = %label,
+                    user = %self.credentials.username,
+                    reason = %failure,
+                    "SNMPv3 request rejected"
                 );
-                Err(error)
+                Err(Error::SnmpAuth {
+                    address: label,
+                    details: Some(failure.to_string()),
+                })
             }
+            other => other,
         }
     }
 }
 
-impl SnmpClient for SnmpV2cClient {
+impl SnmpClient for SnmpV3Client {
     fn get<'a>(&'a self, request: SnmpRequest) -> SnmpFuture<'a> {
-        Box::pin(async move { SnmpV2cClient::get(self, request).await })
+        Box::pin(async move { SnmpV3Client::get(self, request).await })
+    }
+
+    fn walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        Box::pin(async move { SnmpV3Client::walk(self, request).await })
+    }
+
+    fn bulk_walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        Box::pin(async move { SnmpV3Client::bulk_walk(self, request).await })
     }
 }
 
+type WalkScript = VecDeque<Result<Vec<SnmpVarBind>, Error>>;
+
+/// Scripted client for tests: GETs pop a shared queue, walks pop a queue keyed
+/// by the walk's root OID. Both walk kinds are served from the same script.
 #[derive(Debug, Clone)]
 pub struct MockSnmpClient {
     config: SnmpConfig,
     queue: Arc<Mutex<VecDeque<Result<SnmpResponse, Error>>>>,
+    walks: Arc<Mutex<HashMap<Oid, WalkScript>>>,
+}
+
+impl Default for MockSnmpClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MockSnmpClient {
@@ -450,6 +648,7 @@ impl MockSnmpClient {
         Self {
             config,
             queue: Arc::new(Mutex::new(VecDeque::new())),
+            walks: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -477,6 +676,61 @@ impl MockSnmpClient {
         }
         None
     }
+
+    /// Queues the varbinds returned by the next walk of `root_oid`. Varbinds
+    /// outside the subtree are dropped, as a real agent would never return them.
+    pub fn push_walk(&self, root_oid: Oid, varbinds: Vec<SnmpVarBind>) {
+        let varbinds = varbinds
+            .into_iter()
+            .filter(|varbind| oid_is_descendant(&root_oid, &varbind.oid))
+            .collect();
+        self.push_walk_result(root_oid, Ok(varbinds));
+    }
+
+    pub fn push_walk_error(&self, root_oid: Oid, error: Error) {
+        self.push_walk_result(root_oid, Err(error));
+    }
+
+    fn push_walk_result(&self, root_oid: Oid, result: Result<Vec<SnmpVarBind>, Error>) {
+        if let Ok(mut walks) = self.walks.lock() {
+            walks.entry(root_oid).or_default().push_back(result);
+        }
+    }
+
+    fn pop_walk(&self, root_oid: &Oid) -> Option<Result<Vec<SnmpVarBind>, Error>> {
+        if let Ok(mut walks) = self.walks.lock() {
+            return walks.get_mut(root_oid).and_then(VecDeque::pop_front);
+        }
+        None
+    }
+
+    fn scripted_walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        Box::pin(async move {
+            let SnmpWalkRequest {
+                address,
+                root_oid,
+                max_results,
+                progress,
+                ..
+            } = request;
+            match self.pop_walk(&root_oid) {
+                Some(Ok(mut varbinds)) => {
+                    if max_results > 0 {
+                        varbinds.truncate(max_results);
+                    }
+                    if let Some(progress) = progress {
+                        progress.add(varbinds.len());
+                    }
+                    Ok(SnmpResponse { address, varbinds })
+                }
+                Some(Err(error)) => Err(error),
+                None => Err(Error::SnmpFailure {
+                    address: address.to_string(),
+                    details: format!("MockSnmpClient has no scripted walk for {root_oid}"),
+                }),
+            }
+        })
+    }
 }
 
 impl SnmpClient for MockSnmpClient {
@@ -493,31 +747,201 @@ impl SnmpClient for MockSnmpClient {
             })
         })
     }
+
+    fn walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        self.scripted_walk(request)
+    }
+
+    fn bulk_walk<'a>(&'a self, request: SnmpWalkRequest) -> SnmpFuture<'a> {
+        self.scripted_walk(request)
+    }
+}
+
+#[derive(Debug)]
+struct WalkScope {
+    root_oid: Oid,
+    max_results: usize,
+    progress: Option<WalkProgress>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum WalkStrategy {
+    GetNext,
+    Bulk { max_repetitions: u32 },
+}
+
+impl WalkStrategy {
+    fn label(self) -> &'static str {
+        match self {
+            WalkStrategy::GetNext => "getnext",
+            WalkStrategy::Bulk { .. } => "getbulk",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum SessionSecurity {
+    Community(String),
+    V3(SnmpV3Credentials),
+}
+
+impl SessionSecurity {
+    fn version_label(&self) -> &'static str {
+        match self {
+            SessionSecurity::Community(_) => "v2c",
+            SessionSecurity::V3(_) => "v3",
+        }
+    }
+}
+
+async fn instrumented_get(
+    address: SnmpAddress,
+    security: SessionSecurity,
+    oids: Vec<Oid>,
+    config: SnmpConfig,
+) -> Result<SnmpResponse, Error> {
+    let address_label = address.to_string();
+    let oids_label: Vec<String> = oids.iter().map(|oid| oid.to_string()).collect();
+
+    debug!(
+        target: targets::SNMP,
+        address = %address_label,
+        version = security.version_label(),
+        oids = ?oids_label,
+        timeout_ms = config.timeout.as_millis(),
+        retries = config.retries,
+        "SNMP GET"
+    );
+
+    for oid in &oids {
+        trace!(target: targets::SNMP, address = %address_label, oid = %oid, "SNMP OID");
+    }
+
+    match async_get(address, security, oids, config).await {
+        Ok(response) => {
+            debug!(
+                target: targets::SNMP,
+                address = %address_label,
+                count = response.varbinds.len(),
+                "SNMP GET ok"
+            );
+            for varbind in &response.varbinds {
+                trace!(
+                    target: targets::SNMP,
+                    address = %address_label,
+                    oid = %varbind.oid,
+                    value = %varbind.value,
+                    "SNMP value"
+                );
+            }
+            Ok(response)
+        }
+        Err(error) => {
+            warn!(
+                target: targets::SNMP,
+                address = %address_label,
+                error = %error,
+                "SNMP GET failed"
+            );
+            Err(error)
+        }
+    }
+}
+
+async fn instrumented_walk(
+    address: SnmpAddress,
+    security: SessionSecurity,
+    scope: WalkScope,
+    strategy: WalkStrategy,
+    config: SnmpConfig,
+) -> Result<SnmpResponse, Error> {
+    let address_label = address.to_string();
+
+    debug!(
+        target: targets::SNMP,
+        address = %address_label,
+        version = security.version_label(),
+        strategy = strategy.label(),
+        root = %scope.root_oid,
+        max_results = scope.max_results,
+        timeout_ms = config.timeout.as_millis(),
+        retries = config.retries,
+        "SNMP WALK"
+    );
+
+    match async_walk(address, security, scope, strategy, config).await {
+        Ok(response) => {
+            debug!(
+                target: targets::SNMP,
+                address = %address_label,
+                count = response.varbinds.len(),
+                "SNMP WALK ok"
+            );
+            for varbind in &response.varbinds {
+                trace!(
+                    target: targets::SNMP,
+                    address = %address_label,
+                    oid = %varbind.oid,
+                    value = %varbind.value,
+                    "SNMP walk value"
+                );
+            }
+            Ok(response)
+        }
+        Err(error) => {
+            warn!(
+                target: targets::SNMP,
+                address = %address_label,
+                error = %error,
+                "SNMP WALK failed"
+            );
+            Err(error)
+        }
+    }
 }
 
 async fn async_get(
     address: SnmpAddress,
-    community: String,
+    security: SessionSecurity,
     oids: Vec<Oid>,
     config: SnmpConfig,
 ) -> Result<SnmpResponse, Error> {
     let address_label = address.to_string();
-    let mut session = open_session(&address, &community, &config).await?;
+    let mut session = open_session(&address, &security, &config).await?;
     let snmp_oids = to_snmp2_oids(&address, &oids)?;
     let mut varbinds = Vec::new();
-
-    for chunk in snmp_oids.chunks(MAX_OIDS_PER_GET) {
-        let oid_refs: Vec<&Snmp2Oid> = chunk.iter().collect();
-        varbinds.extend(
-            get_many_with_retries(
-                &mut session,
-                &address,
-                &address_label,
-                &config,
-                oid_refs.as_slice(),
-            )
-            .await?,
-        );
+    let mut batch_size = config.pdu_sizes.oids_per_get(&address);
+    let mut start = 0;
+
+    while start < snmp_oids.len() {
+        let end = (start + batch_size).min(snmp_oids.len());
+        let oid_refs: Vec<&Snmp2Oid> = snmp_oids[start..end].iter().collect();
+        let batch = get_many_with_retries(
+            &mut session,
+            &address,
+            &address_label,
+            &config,
+            oid_refs.as_slice(),
+        )
+        .await?;
+        match batch {
+            Some(batch) => {
+                varbinds.extend(batch);
+                start = end;
+            }
+            None => {
+                batch_size = (oid_refs.len() / 2).max(1);
+                config
+                    .pdu_sizes
+                    .update(&address, |size| size.oids_per_get = batch_size);
+                warn!(
+                    target: targets::SNMP,
+                    address = %address_label,
+                    batch_size,
+                    "SNMP GET tooBig; shrinking batch"
+                );
+            }
+        }
     }
 
     Ok(SnmpResponse { address, varbinds })
@@ -525,32 +949,53 @@ async fn async_get(
 
 async fn async_walk(
     address: SnmpAddress,
-    community: String,
-    root_oid: Oid,
-    max_results: usize,
+    security: SessionSecurity,
+    scope: WalkScope,
+    strategy: WalkStrategy,
     config: SnmpConfig,
 ) -> Result<SnmpResponse, Error> {
+    let WalkScope {
+        root_oid,
+        max_results,
+        progress,
+    } = scope;
     let address_label = address.to_string();
-    let mut session = open_session(&address, &community, &config).await?;
+    let mut repetitions = match strategy {
+        WalkStrategy::GetNext => 1,
+        WalkStrategy::Bulk { max_repetitions } => config
+            .pdu_sizes
+            .max_repetitions(&address, max_repetitions),
+    };
+    let mut session = open_session(&address, &security, &config).await?;
     let root_snmp = to_snmp2_oid(&address, &root_oid)?;
     let mut current = root_snmp.clone();
     let mut results = Vec::new();
-    let mut remaining = max_results;
+    let timeout_ms = duration_ms(config.timeout);
 
-    loop {
-        if max_results > 0 {
-            if remaining == 0 {
-                break;
-            }
-            remaining -= 1;
+    'walk: loop {
+        if max_results > 0 && results.len() >= max_results {
+            break;
         }
 
-        let timeout_ms = duration_ms(config.timeout);
         let mut attempts = 0;
+        let mut resynced = false;
         let pdu = loop {
-            match timeout(config.timeout, session.getnext(&current)).await {
+            let outcome = match strategy {
+                WalkStrategy::GetNext => timeout(config.timeout, session.getnext(&current)).await,
+                WalkStrategy::Bulk { .. } => {
+                    timeout(
+                        config.timeout,
+                        session.getbulk(&[&current], 0, repetitions),
+                    )
+                    .await
+                }
+            };
+            match outcome {
                 Ok(Ok(pdu)) => break pdu,
                 Ok(Err(error)) => {
+                    if should_resync(&error, &mut resynced) {
+                        continue;
+                    }
                     if attempts < config.retries {
                         attempts += 1;
                         continue;
@@ -569,6 +1014,20 @@ async fn async_walk(
                 }
             }
         };
+        let too_big = pdu.error_status == snmp2_constants::ERRSTATUS_TOOBIG;
+        if too_big && repetitions > 1 {
+            repetitions /= 2;
+            config
+                .pdu_sizes
+                .update(&address, |size| size.max_repetitions = repetitions);
+            warn!(
+                target: targets::SNMP,
+                address = %address_label,
+                repetitions,
+                "SNMP GETBULK tooBig; shrinking max-repetitions"
+            );
+            continue;
+        }
         if pdu.error_status != snmp2_constants::ERRSTATUS_NOERROR {
             let oid_refs = [&current];
             let details = snmp2_pdu_error_details(
@@ -584,10 +1043,7 @@ async fn async_walk(
                     details = %details,
                     "SNMP WALK reached end of MIB"
                 );
-                return Ok(SnmpResponse {
-                    address,
-                    varbinds: results,
-                });
+                break;
             }
             return Err(Error::SnmpFailure {
                 address: address.to_string(),
@@ -595,35 +1051,34 @@ async fn async_walk(
             });
         }
 
+        let collected = results.len();
         let mut progressed = false;
         for (oid, value) in pdu.varbinds {
-            let mapped_oid = map_snmp2_oid(&address_label, &oid);
-            if mapped_oid.0.is_empty() {
-                return Ok(SnmpResponse {
-                    address,
-                    varbinds: results,
-                });
+            if max_results > 0 && results.len() >= max_results {
+                break 'walk;
             }
-            if !oid_is_descendant(&root_oid, &mapped_oid) {
-                return Ok(SnmpResponse {
-                    address,
-                    varbinds: results,
-                });
+            let mapped_oid = map_snmp2_oid(&address_label, &oid);
+            if mapped_oid.0.is_empty()
+                || !oid_is_descendant(&root_oid, &mapped_oid)
+                || oid == current
+            {
+                break 'walk;
             }
-            if oid == current {
-                return Ok(SnmpResponse {
-                    address,
-                    varbinds: results,
-                });
+            let value = map_snmp2_value(&address_label, value);
+            if matches!(value, SnmpValue::EndOfMibView) {
+                break 'walk;
             }
 
             results.push(SnmpVarBind {
                 oid: mapped_oid,
-                value: map_snmp2_value(&address_label, value),
+                value,
             });
             current = oid.to_owned();
             progressed = true;
         }
+        if let Some(progress) = progress.as_ref() {
+            progress.add(results.len() - collected);
+        }
 
         if !progressed {
             break;
@@ -638,23 +1093,93 @@ async fn async_walk(
 
 async fn open_session(
     address: &SnmpAddress,
-    community: &str,
+    security: &SessionSecurity,
     config: &SnmpConfig,
 ) -> Result<AsyncSession, Error> {
     let timeout_ms = duration_ms(config.timeout);
     let target = format!("{}:{}", address.host, address.port);
-    match timeout(
-        config.timeout,
-        AsyncSession::new_v2c(target.as_str(), community.as_bytes(), 0),
+    let session = match security {
+        SessionSecurity::Community(community) => {
+            timeout(
+                config.timeout,
+                AsyncSession::new_v2c(target.as_str(), community.as_bytes(), 0),
+            )
+            .await
+        }
+        SessionSecurity::V3(credentials) => {
+            timeout(
+                config.timeout,
+                AsyncSession::new_v3(target.as_str(), 0, usm_security(credentials)),
+            )
+            .await
+        }
+    };
+    let mut session = match session {
+        Ok(Ok(session)) => session,
+        Ok(Err(error)) => return Err(map_snmp2_io_error(address, timeout_ms, error)),
+        Err(_) => {
+            return Err(Error::SnmpTimeout {
+                address: address.to_string(),
+                timeout_ms,
+            })
+        }
+    };
+
+    if let SessionSecurity::V3(_) = security {
+        // Engine ID discovery; the agent's boots/time are captured here too.
+        match timeout(config.timeout, session.init()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => return Err(map_snmp2_error(address, error)),
+            Err(_) => {
+                return Err(Error::SnmpTimeout {
+                    address: address.to_string(),
+                    timeout_ms,
+                })
+            }
+        }
+    }
+
+    Ok(session)
+}
+
+fn usm_security(credentials: &SnmpV3Credentials) -> snmp2_v3::Security {
+    let auth_protocol = match credentials.auth_protocol {
+        SnmpAuthProtocol::Md5 => snmp2_v3::AuthProtocol::Md5,
+        SnmpAuthProtocol::Sha1 => snmp2_v3::AuthProtocol::Sha1,
+        SnmpAuthProtocol::Sha224 => snmp2_v3::AuthProtocol::Sha224,
+        SnmpAuthProtocol::Sha256 => snmp2_v3::AuthProtocol::Sha256,
+        SnmpAuthProtocol::Sha384 => snmp2_v3::AuthProtocol::Sha384,
+        SnmpAuthProtocol::Sha512 => snmp2_v3::AuthProtocol::Sha512,
+    };
+    let cipher = match credentials.priv_protocol {
+        SnmpPrivProtocol::Des => snmp2_v3::Cipher::Des,
+        SnmpPrivProtocol::Aes128 => snmp2_v3::Cipher::Aes128,
+        SnmpPrivProtocol::Aes192 => snmp2_v3::Cipher::Aes192,
+        SnmpPrivProtocol::Aes256 => snmp2_v3::Cipher::Aes256,
+    };
+    let auth = match credentials.security_level {
+        SnmpSecurityLevel::NoAuthNoPriv => snmp2_v3::Auth::NoAuthNoPriv,
+        SnmpSecurityLevel::AuthNoPriv => snmp2_v3::Auth::AuthNoPriv,
+        SnmpSecurityLevel::AuthPriv => snmp2_v3::Auth::AuthPriv {
+            cipher,
+            privacy_password: credentials.priv_password.as_bytes().to_vec(),
+        },
+    };
+
+    let security = snmp2_v3::Security::new(
+        credentials.username.as_bytes(),
+        credentials.auth_password.as_bytes(),
     )
-    .await
+    .with_auth_protocol(auth_protocol)
+    .with_auth(auth);
+
+    // Ricoh agents follow net-snmp and extend short AES-192/256 keys per Blumenthal.
+    if credentials.security_level == SnmpSecurityLevel::AuthPriv
+        && cipher.priv_key_needs_extension(&auth_protocol)
     {
-        Ok(Ok(session)) => Ok(session),
-        Ok(Err(error)) => Err(map_snmp2_io_error(address, timeout_ms, error)),
-        Err(_) => Err(Error::SnmpTimeout {
-            address: address.to_string(),
-            timeout_ms,
-        }),
+        security.with_key_extension_method(snmp2_v3::KeyExtension::Blumenthal)
+    } else {
+        security
     }
 }
 
@@ -667,6 +1192,7 @@ async fn get_one_with_retries(
 ) -> Result<Vec<SnmpVarBind>, Error> {
     let timeout_ms = duration_ms(config.timeout);
     let mut attempts = 0;
+    let mut resynced = false;
     let oid_refs = [oid];
 
     loop {
@@ -698,6 +1224,9 @@ async fn get_one_with_retries(
                 return Ok(map_snmp2_varbinds(address_label, pdu));
             }
             Ok(Err(error)) => {
+                if should_resync(&error, &mut resynced) {
+                    continue;
+                }
                 if attempts < config.retries {
                     attempts += 1;
                     continue;
@@ -718,18 +1247,24 @@ async fn get_one_with_retries(
     }
 }
 
+/// GETs one batch. `Ok(None)` means the agent answered `tooBig` and the
+/// caller should retry with a smaller batch.
 async fn get_many_with_retries(
     session: &mut AsyncSession,
     address: &SnmpAddress,
     address_label: &str,
     config: &SnmpConfig,
     oids: &[&Snmp2Oid<'_>],
-) -> Result<Vec<SnmpVarBind>, Error> {
+) -> Result<Option<Vec<SnmpVarBind>>, Error> {
     let timeout_ms = duration_ms(config.timeout);
     let mut attempts = 0;
+    let mut resynced = false;
     loop {
         match timeout(config.timeout, session.get_many(oids)).await {
             Ok(Ok(pdu)) => {
+                if pdu.error_status == snmp2_constants::ERRSTATUS_TOOBIG && oids.len() > 1 {
+                    return Ok(None);
+                }
                 if pdu.error_status != snmp2_constants::ERRSTATUS_NOERROR {
                     let details = snmp2_pdu_error_details(
                         pdu.error_status,
@@ -758,11 +1293,14 @@ async fn get_many_with_retries(
                             .await?,
                         );
                     }
-                    return Ok(varbinds);
+                    return Ok(Some(varbinds));
                 }
-                return Ok(map_snmp2_varbinds(address_label, pdu));
+                return Ok(Some(map_snmp2_varbinds(address_label, pdu)));
             }
             Ok(Err(error)) => {
+                if should_resync(&error, &mut resynced) {
+                    continue;
+                }
                 if attempts < config.retries {
                     attempts += 1;
                     continue;
@@ -783,6 +1321,15 @@ async fn get_many_with_retries(
     }
 }
 
+/// SNMPv3 agents answer a stale engine time with a report; snmp2 resyncs the
+/// session and asks for the request to be repeated once without counting it.
+fn should_resync(error: &Snmp2Error, resynced: &mut bool) -> bool {
+    if matches!(error, Snmp2Error::AuthUpdated) && !*resynced {
+        *resynced = true;
+        return true;
+    }
+    false
+}
 
 fn duration_ms(duration: Duration) -> u64 {
     duration.as_millis().min(u128::from(u64::MAX)) as u64
@@ -811,19 +1358,33 @@ fn map_snmp2_io_error(address: &SnmpAddress, timeout_ms: u64, error: io::Error)
             timeout_ms,
         }
     } else {
-        Error::SnmpFailure {
+        Error::SnmpUnreachable {
             address: address.to_string(),
             details: error.to_string(),
         }
     }
 }
 
+/// USM rejections without a local explanation map to `SnmpAuth` with no
+/// details; `SnmpV3Client` fills those in from a diagnosis probe. Socket
+/// errors map to `SnmpUnreachable`.
 fn map_snmp2_error(address: &SnmpAddress, error: Snmp2Error) -> Error {
     match error {
         Snmp2Error::CommunityMismatch => Error::SnmpAuth {
             address: address.to_string(),
             details: Some(format!("{error}")),
         },
+        Snmp2Error::AuthFailure(kind) => Error::SnmpAuth {
+            address: address.to_string(),
+            details: match kind {
+                snmp2_v3::AuthErrorKind::NotAuthenticated => None,
+                kind => Some(UsmFailure::from_auth_error(&kind).to_string()),
+            },
+        },
+        Snmp2Error::Send | Snmp2Error::Receive => Error::SnmpUnreachable {
+            address: address.to_string(),
+            details: error.to_string(),
+        },
         other => Error::SnmpFailure {
             address: address.to_string(),
             details: other.to_string(),
@@ -1000,6 +1561,95 @@ mod tests {
         assert_eq!(response.varbinds[0].value.as_u64(), Some(123));
     }
 
+    #[test]
+    fn mock_snmp_serves_scripted_walks() {
+        let mock = MockSnmpClient::new();
+        let address = SnmpAddress::with_default_port("192.168.1.10");
+        let root: Oid = "1.3.6.1.2.1.43.10.2.1.4".parse().expect("oid");
+        let inside: Oid = "1.3.6.1.2.1.43.10.2.1.4.1.1".parse().expect("oid");
+        let outside: Oid = "1.3.6.1.2.1.43.11.1.1.9.1.1".parse().expect("oid");
+
+        mock.push_walk(
+            root.clone(),
+            vec![
+                SnmpVarBind {
+                    oid: inside.clone(),
+                    value: SnmpValue::Counter32(4_200),
+                },
+                SnmpVarBind {
+                    oid: outside,
+                    value: SnmpValue::Integer(80),
+                },
+            ],
+        );
+
+        let client: Arc<dyn SnmpClient> = Arc::new(mock);
+        let request = SnmpWalkRequest::new(address.clone(), root.clone());
+        let response = run_future(client.bulk_walk(request)).expect("scripted walk");
+        assert_eq!(response.varbinds.len(), 1);
+        assert_eq!(response.varbinds[0].oid, inside);
+
+        let request = SnmpWalkRequest::new(address, root);
+        let error = run_future(client.walk(request)).expect_err("script exhausted");
+        assert!(matches!(error, Error::SnmpFailure { .. }));
+    }
+
+    #[test]
+    fn mock_snmp_walk_respects_max_results() {
+        let mock = MockSnmpClient::new();
+        let address = SnmpAddress::with_default_port("192.168.1.10");
+        let root: Oid = "1.3.6.1.2.1.1".parse().expect("oid");
+        let varbinds = (1..=5)
+            .map(|index| SnmpVarBind {
+                oid: Oid(vec![1, 3, 6, 1, 2, 1, 1, index, 0]),
+                value: SnmpValue::Integer(i64::from(index)),
+            })
+            .collect();
+        mock.push_walk(root.clone(), varbinds);
+
+        let request = SnmpWalkRequest::new(address, root).with_max_results(2);
+        let response = run_future(mock.walk(request)).expect("scripted walk");
+        assert_eq!(response.varbinds.len(), 2);
+    }
+
+    #[test]
+    fn mock_snmp_walk_reports_progress() {
+        let mock = MockSnmpClient::new();
+        let address = SnmpAddress::with_default_port("192.168.1.10");
+        let root: Oid = "1.3.6.1.4.1.367".parse().expect("oid");
+        let varbinds = (1..=3)
+            .map(|index| SnmpVarBind {
+                oid: Oid(vec![1, 3, 6, 1, 4, 1, 367, index]),
+                value: SnmpValue::Counter32(index),
+            })
+            .collect();
+        mock.push_walk(root.clone(), varbinds);
+
+        let progress = WalkProgress::new();
+        let request = SnmpWalkRequest::new(address, root).with_progress(progress.clone());
+        run_future(mock.bulk_walk(request)).expect("scripted walk");
+        assert_eq!(progress.collected(), 3);
+    }
+
+    #[test]
+    fn pdu_size_cache_only_shrinks_per_address() {
+        let config = SnmpConfig::default();
+        let shared = config.clone();
+        let ricoh = SnmpAddress::with_default_port("192.168.1.10");
+        let other = SnmpAddress::with_default_port("192.168.1.11");
+
+        assert_eq!(config.pdu_sizes.max_repetitions(&ricoh, 40), 40);
+        shared
+            .pdu_sizes
+            .update(&ricoh, |size| size.max_repetitions = 10);
+        shared.pdu_sizes.update(&ricoh, |size| size.oids_per_get = 6);
+
+        assert_eq!(config.pdu_sizes.max_repetitions(&ricoh, 40), 10);
+        assert_eq!(config.pdu_sizes.max_repetitions(&ricoh, 4), 4);
+        assert_eq!(config.pdu_sizes.oids_per_get(&ricoh), 6);
+        assert_eq!(config.pdu_sizes.get(&other), PduSize::default());
+    }
+
     #[test]
     fn mock_snmp_empty_queue_returns_error() {
         let mock = MockSnmpClient::new();
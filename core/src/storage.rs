@@ -0,0 +1,133 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Error, StorageAction};
+
+/// Replaces `path` with `contents` through a synced temp file next to it and
+/// a rename, so a crash leaves either the old file or the new one.
+pub fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> Result<(), Error> {
+    let path = path.as_ref();
+    let temp_path = temp_path(path);
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    result.map_err(|source| storage_error(StorageAction::Save, path, source))
+}
+
+/// Reads one RON document.
+pub(crate) fn load_ron<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let contents = fs::read_to_string(path)
+        .map_err(|source| storage_error(StorageAction::Load, path, source))?;
+    ron::from_str(&contents).map_err(|error| Error::Ron {
+        action: StorageAction::Load,
+        path: Some(path.display().to_string()),
+        source: error.code,
+    })
+}
+
+/// Writes `value` as pretty RON with `write_atomic`.
+pub(crate) fn save_ron<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
+    let contents =
+        ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()).map_err(|source| {
+            Error::Ron {
+                action: StorageAction::Save,
+                path: Some(path.display().to_string()),
+                source,
+            }
+        })?;
+    write_atomic(path, contents.as_bytes())
+}
+
+pub(crate) fn storage_error(action: StorageAction, path: &Path, source: io::Error) -> Error {
+    Error::StorageIo {
+        action,
+        path: Some(path.display().to_string()),
+        source,
+    }
+}
+
+/// `receipts.ron` becomes `receipts.ron.tmp`.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+    /// A fresh directory for one test, removed on drop so a failed assertion
+    /// cleans up too.
+    pub(crate) struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub(crate) fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "printcountpay-{name}-{}-{}",
+                std::process::id(),
+                NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).expect("temp dir");
+            Self(path)
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.0
+        }
+
+        pub(crate) fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+            self.0.join(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn replaces_the_file_and_leaves_no_temp_file() {
+        let dir = TempDir::new("storage");
+        let path = dir.join("book.ron");
+        fs::write(&path, "old").expect("write");
+
+        save_ron(&path, &vec![1u32, 2, 3]).expect("save");
+        assert_eq!(load_ron::<Vec<u32>>(&path).expect("load"), vec![1, 2, 3]);
+        assert!(!temp_path(&path).exists());
+
+        let missing = load_ron::<Vec<u32>>(&dir.join("missing.ron")).unwrap_err();
+        assert!(matches!(
+            missing,
+            Error::StorageIo {
+                action: StorageAction::Load,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn temp_dirs_are_removed_when_dropped() {
+        let dir = TempDir::new("storage");
+        let other = TempDir::new("storage");
+        assert_ne!(dir.path(), other.path());
+        let path = dir.path().to_path_buf();
+        assert!(path.is_dir());
+        drop(dir);
+        assert!(!path.exists());
+    }
+}
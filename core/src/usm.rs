@@ -0,0 +1,353 @@
+use std::fmt;
+use std::time::Duration;
+
+use snmp2::asn1::{self, AsnReader};
+use snmp2::snmp as snmp2_constants;
+use snmp2::v3::AuthErrorKind;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+use tracing::debug;
+
+use crate::ber;
+use crate::model::{SnmpAddress, SnmpSecurityLevel, SnmpV3Credentials};
+use crate::snmp::{Oid, SYS_UPTIME_OID};
+use crate::targets;
+
+const USM_STATS_ROOT: [u32; 9] = [1, 3, 6, 1, 6, 3, 15, 1, 1];
+const SNMP_VERSION_3: i64 = 3;
+const USM_SECURITY_MODEL: i64 = 3;
+const MSG_MAX_SIZE: i64 = 65_507;
+const MSG_FLAGS_REPORTABLE: u8 = 0x04;
+const PROBE_MSG_ID: i64 = 0x5043;
+const MAX_REPORT_SIZE: usize = 4096;
+
+/// Reason an SNMPv3 agent rejected a request at the USM layer (RFC 3414).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsmFailure {
+    UnknownUser,
+    WrongDigest,
+    NotInTimeWindow,
+    UnknownEngineId,
+    UnsupportedSecurityLevel,
+    DecryptionError,
+    EngineIdMismatch,
+    Other(String),
+}
+
+impl UsmFailure {
+    /// Maps a `usmStats*` counter OID from a REPORT PDU to its failure reason.
+    pub fn from_report_oid(oid: &Oid) -> Option<Self> {
+        let arcs = oid.as_slice();
+        if arcs.len() < USM_STATS_ROOT.len() + 1 || arcs[..USM_STATS_ROOT.len()] != USM_STATS_ROOT
+        {
+            return None;
+        }
+        match arcs[USM_STATS_ROOT.len()] {
+            1 => Some(UsmFailure::UnsupportedSecurityLevel),
+            2 => Some(UsmFailure::NotInTimeWindow),
+            3 => Some(UsmFailure::UnknownUser),
+            4 => Some(UsmFailure::UnknownEngineId),
+            5 => Some(UsmFailure::WrongDigest),
+            6 => Some(UsmFailure::DecryptionError),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_auth_error(kind: &AuthErrorKind) -> Self {
+        match kind {
+            AuthErrorKind::EngineBootsMismatch
+            | AuthErrorKind::EngineBootsNotProvided
+            | AuthErrorKind::EngineTimeMismatch => UsmFailure::NotInTimeWindow,
+            AuthErrorKind::UsernameMismatch => UsmFailure::UnknownUser,
+            AuthErrorKind::SignatureMismatch => UsmFailure::WrongDigest,
+            AuthErrorKind::EngineIdMismatch => UsmFailure::EngineIdMismatch,
+            AuthErrorKind::PrivLengthMismatch
+            | AuthErrorKind::PayloadLengthMismatch
+            | AuthErrorKind::ReplyNotEncrypted => UsmFailure::DecryptionError,
+            other => UsmFailure::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for UsmFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsmFailure::UnknownUser => f.write_str("unknown user name"),
+            UsmFailure::WrongDigest => {
+                f.write_str("wrong digest (check auth protocol and password)")
+            }
+            UsmFailure::NotInTimeWindow => f.write_str("not in time window"),
+            UsmFailure::UnknownEngineId => f.write_str("unknown engine ID"),
+            UsmFailure::UnsupportedSecurityLevel => {
+                f.write_str("security level not supported for this user")
+            }
+            UsmFailure::DecryptionError => {
+                f.write_str("decryption error (check privacy protocol and password)")
+            }
+            UsmFailure::EngineIdMismatch => f.write_str("engine ID changed during session"),
+            UsmFailure::Other(details) => f.write_str(details),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UsmReport {
+    pub(crate) engine_id: Vec<u8>,
+    pub(crate) engine_boots: i64,
+    pub(crate) engine_time: i64,
+    pub(crate) failure: Option<UsmFailure>,
+}
+
+/// Works out why an authenticated request was rejected.
+///
+/// snmp2 drops the varbinds of REPORT PDUs, so this replays the exchange with
+/// unauthenticated probes: the first discovers the engine, the second asks as
+/// the configured user. An unknown user is reported as such; a known user
+/// means the keys derived from the passwords are wrong.
+pub(crate) async fn diagnose_rejection(
+    address: &SnmpAddress,
+    credentials: &SnmpV3Credentials,
+    wait: Duration,
+) -> UsmFailure {
+    let fallback = match credentials.security_level {
+        SnmpSecurityLevel::AuthPriv => UsmFailure::Other(
+            "request rejected (wrong auth or privacy password)".to_string(),
+        ),
+        SnmpSecurityLevel::AuthNoPriv => UsmFailure::WrongDigest,
+        SnmpSecurityLevel::NoAuthNoPriv => UsmFailure::UnsupportedSecurityLevel,
+    };
+
+    let engine = match send_probe(address, b"", &[], 0, 0, wait).await {
+        Some(ProbeReply::Report(report)) if !report.engine_id.is_empty() => report,
+        _ => return fallback,
+    };
+    let reply = send_probe(
+        address,
+        credentials.username.as_bytes(),
+        &engine.engine_id,
+        engine.engine_boots,
+        engine.engine_time,
+        wait,
+    )
+    .await;
+
+    debug!(
+        target: targets::SNMP,
+        address = %address,
+        reply = ?reply,
+        "SNMPv3 USM diagnosis probe"
+    );
+
+    match reply {
+        Some(ProbeReply::Report(UsmReport {
+            failure: Some(UsmFailure::UnknownUser),
+            ..
+        })) => UsmFailure::UnknownUser,
+        Some(ProbeReply::Report(UsmReport {
+            failure: Some(UsmFailure::UnsupportedSecurityLevel),
+            ..
+        }))
+        | Some(ProbeReply::Response) => match credentials.security_level {
+            SnmpSecurityLevel::NoAuthNoPriv => UsmFailure::UnsupportedSecurityLevel,
+            _ => UsmFailure::WrongDigest,
+        },
+        _ => fallback,
+    }
+}
+
+#[derive(Debug)]
+enum ProbeReply {
+    Report(UsmReport),
+    Response,
+}
+
+async fn send_probe(
+    address: &SnmpAddress,
+    username: &[u8],
+    engine_id: &[u8],
+    engine_boots: i64,
+    engine_time: i64,
+    wait: Duration,
+) -> Option<ProbeReply> {
+    let request = build_probe(username, engine_id, engine_boots, engine_time);
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).await.ok()?;
+    socket
+        .connect((address.host.as_str(), address.port))
+        .await
+        .ok()?;
+    socket.send(&request).await.ok()?;
+
+    let mut buffer = vec![0u8; MAX_REPORT_SIZE];
+    let len = timeout(wait, socket.recv(&mut buffer)).await.ok()?.ok()?;
+    match parse_report(&buffer[..len]) {
+        Some(report) => Some(ProbeReply::Report(report)),
+        None => Some(ProbeReply::Response),
+    }
+}
+
+/// Builds an unauthenticated, reportable GET for sysUpTime.0.
+pub(crate) fn build_probe(
+    username: &[u8],
+    engine_id: &[u8],
+    engine_boots: i64,
+    engine_time: i64,
+) -> Vec<u8> {
+    let varbind = ber::sequence(&[ber::oid(&SYS_UPTIME_OID), ber::null()]);
+    let pdu = ber::tlv(
+        snmp2_constants::MSG_GET,
+        &[
+            ber::integer(PROBE_MSG_ID),
+            ber::integer(0),
+            ber::integer(0),
+            ber::sequence(&[varbind]),
+        ]
+        .concat(),
+    );
+    encode_message(
+        &[MSG_FLAGS_REPORTABLE],
+        username,
+        engine_id,
+        engine_boots,
+        engine_time,
+        pdu,
+    )
+}
+
+fn encode_message(
+    flags: &[u8],
+    username: &[u8],
+    engine_id: &[u8],
+    engine_boots: i64,
+    engine_time: i64,
+    pdu: Vec<u8>,
+) -> Vec<u8> {
+    let header = ber::sequence(&[
+        ber::integer(PROBE_MSG_ID),
+        ber::integer(MSG_MAX_SIZE),
+        ber::octet_string(flags),
+        ber::integer(USM_SECURITY_MODEL),
+    ]);
+    let security = ber::sequence(&[
+        ber::octet_string(engine_id),
+        ber::integer(engine_boots),
+        ber::integer(engine_time),
+        ber::octet_string(username),
+        ber::octet_string(b""),
+        ber::octet_string(b""),
+    ]);
+    let scoped = ber::sequence(&[ber::octet_string(engine_id), ber::octet_string(b""), pdu]);
+    ber::sequence(&[
+        ber::integer(SNMP_VERSION_3),
+        header,
+        ber::octet_string(&security),
+        scoped,
+    ])
+}
+
+/// Decodes an unencrypted SNMPv3 message and returns its USM state when the
+/// PDU is a REPORT; any other PDU type yields `None`.
+pub(crate) fn parse_report(bytes: &[u8]) -> Option<UsmReport> {
+    let mut reader = AsnReader::from_bytes(bytes);
+    let message = reader.read_raw(asn1::TYPE_SEQUENCE).ok()?;
+    let mut message = AsnReader::from_bytes(message);
+    if message.read_asn_integer().ok()? != SNMP_VERSION_3 {
+        return None;
+    }
+    message.read_raw(asn1::TYPE_SEQUENCE).ok()?;
+
+    let security = message.read_asn_octetstring().ok()?;
+    let mut security = AsnReader::from_bytes(security);
+    let security = security.read_raw(asn1::TYPE_SEQUENCE).ok()?;
+    let mut security = AsnReader::from_bytes(security);
+    let engine_id = security.read_asn_octetstring().ok()?.to_vec();
+    let engine_boots = security.read_asn_integer().ok()?;
+    let engine_time = security.read_asn_integer().ok()?;
+
+    let scoped = message.read_raw(asn1::TYPE_SEQUENCE).ok()?;
+    let mut scoped = AsnReader::from_bytes(scoped);
+    scoped.read_asn_octetstring().ok()?;
+    scoped.read_asn_octetstring().ok()?;
+    if scoped.peek_byte().ok()? != snmp2_constants::MSG_REPORT {
+        return None;
+    }
+    let pdu = scoped.read_raw(snmp2_constants::MSG_REPORT).ok()?;
+    let mut pdu = AsnReader::from_bytes(pdu);
+    pdu.read_asn_integer().ok()?;
+    pdu.read_asn_integer().ok()?;
+    pdu.read_asn_integer().ok()?;
+    let varbinds = pdu.read_raw(asn1::TYPE_SEQUENCE).ok()?;
+    let mut varbinds = AsnReader::from_bytes(varbinds);
+
+    let failure = varbinds
+        .read_raw(asn1::TYPE_SEQUENCE)
+        .ok()
+        .and_then(|varbind| {
+            let mut varbind = AsnReader::from_bytes(varbind);
+            let oid = varbind.read_asn_objectidentifier().ok()?;
+            let arcs: Vec<u32> = oid
+                .iter()?
+                .map(|arc| u32::try_from(arc).ok())
+                .collect::<Option<Vec<u32>>>()?;
+            UsmFailure::from_report_oid(&Oid(arcs))
+        });
+
+    Some(UsmReport {
+        engine_id,
+        engine_boots,
+        engine_time,
+        failure,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_report(engine_id: &[u8], report_oid: &[u32]) -> Vec<u8> {
+        let varbind = ber::sequence(&[ber::oid(report_oid), ber::unsigned(ber::TAG_COUNTER32, 7)]);
+        let pdu = ber::tlv(
+            snmp2_constants::MSG_REPORT,
+            &[
+                ber::integer(PROBE_MSG_ID),
+                ber::integer(0),
+                ber::integer(0),
+                ber::sequence(&[varbind]),
+            ]
+            .concat(),
+        );
+        encode_message(&[0], b"", engine_id, 5, 1_234, pdu)
+    }
+
+    #[test]
+    fn maps_usm_stats_oids() {
+        let unknown_user: Oid = "1.3.6.1.6.3.15.1.1.3.0".parse().expect("oid");
+        let wrong_digest: Oid = "1.3.6.1.6.3.15.1.1.5.0".parse().expect("oid");
+        let unrelated: Oid = "1.3.6.1.2.1.1.3.0".parse().expect("oid");
+        assert_eq!(
+            UsmFailure::from_report_oid(&unknown_user),
+            Some(UsmFailure::UnknownUser)
+        );
+        assert_eq!(
+            UsmFailure::from_report_oid(&wrong_digest),
+            Some(UsmFailure::WrongDigest)
+        );
+        assert_eq!(UsmFailure::from_report_oid(&unrelated), None);
+    }
+
+    #[test]
+    fn parses_unknown_user_report() {
+        let engine_id = [0x80, 0x00, 0x01, 0x6f, 0x03];
+        let bytes = build_report(&engine_id, &[1, 3, 6, 1, 6, 3, 15, 1, 1, 3, 0]);
+        let report = parse_report(&bytes).expect("report");
+        assert_eq!(report.engine_id, engine_id);
+        assert_eq!(report.engine_boots, 5);
+        assert_eq!(report.engine_time, 1_234);
+        assert_eq!(report.failure, Some(UsmFailure::UnknownUser));
+    }
+
+    #[test]
+    fn probe_is_not_a_report() {
+        let bytes = build_probe(b"billing", &[0x80, 0x00], 1, 2);
+        assert_eq!(parse_report(&bytes), None);
+    }
+}
@@ -0,0 +1,471 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{format_utc_timestamp, EpochSeconds, RecordingCategory};
+use crate::money::{Currency, Money, MoneyFormat};
+use crate::receipt::push_columns;
+use crate::storage::{self, storage_error};
+use crate::{Error, StorageAction};
+
+/// Columns of the plain text export.
+const TEXT_COLUMNS: usize = 56;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PaymentMethod {
+    Cash,
+    Card,
+    /// Debited from a prepaid customer account.
+    Account,
+    InvoiceLater,
+}
+
+impl PaymentMethod {
+    pub const ALL: [PaymentMethod; 4] = [
+        PaymentMethod::Cash,
+        PaymentMethod::Card,
+        PaymentMethod::Account,
+        PaymentMethod::InvoiceLater,
+    ];
+}
+
+impl fmt::Display for PaymentMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentMethod::Cash => f.write_str("Cash"),
+            PaymentMethod::Card => f.write_str("Card"),
+            PaymentMethod::Account => f.write_str("Account"),
+            PaymentMethod::InvoiceLater => f.write_str("Invoice later"),
+        }
+    }
+}
+
+/// A completed session as it was when the day was closed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZReportSession {
+    pub printer: String,
+    pub started_at: EpochSeconds,
+    pub stopped_at: EpochSeconds,
+    #[serde(default)]
+    pub receipt: Option<String>,
+    /// `None` while nobody has marked the session as paid.
+    pub payment: Option<PaymentMethod>,
+    pub voided: bool,
+    /// Counter values were typed over the polled ones or a category was
+    /// left out of the price.
+    pub overridden: bool,
+    pub pages: BTreeMap<RecordingCategory, u64>,
+    /// Charge per tariff name, before the base fee and total rounding.
+    pub tariffs: BTreeMap<String, Money>,
+    pub total: Money,
+}
+
+/// Sums over the sessions of a report. Voided sessions are only counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ZReportTotals {
+    pub sessions: usize,
+    pub voided: usize,
+    pub overridden: usize,
+    pub by_method: BTreeMap<PaymentMethod, Money>,
+    pub unpaid_sessions: usize,
+    pub unpaid: Money,
+    pub pages: BTreeMap<RecordingCategory, u64>,
+    pub tariffs: BTreeMap<String, Money>,
+    pub total: Money,
+}
+
+/// An end-of-day report over every session stopped since the previous one.
+/// Reports are numbered without gaps and never rewritten once stored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZReport {
+    pub number: u32,
+    pub created_at: EpochSeconds,
+    /// When the previous report was closed; `None` for the first one.
+    pub since: Option<EpochSeconds>,
+    pub sessions: Vec<ZReportSession>,
+    pub currency: Currency,
+    pub format: MoneyFormat,
+}
+
+impl ZReport {
+    pub fn totals(&self) -> ZReportTotals {
+        let mut totals = ZReportTotals::default();
+        for session in &self.sessions {
+            totals.sessions += 1;
+            if session.overridden {
+                totals.overridden += 1;
+            }
+            if session.voided {
+                totals.voided += 1;
+                continue;
+            }
+            match session.payment {
+                Some(method) => *totals.by_method.entry(method).or_default() += session.total,
+                None => {
+                    totals.unpaid_sessions += 1;
+                    totals.unpaid += session.total;
+                }
+            }
+            for (category, pages) in &session.pages {
+                *totals.pages.entry(*category).or_default() += pages;
+            }
+            for (tariff, amount) in &session.tariffs {
+                *totals.tariffs.entry(tariff.clone()).or_default() += *amount;
+            }
+            totals.total += session.total;
+        }
+        totals
+    }
+
+    pub fn title(&self) -> String {
+        format!("Z report {}", self.number)
+    }
+
+    fn money(&self, amount: Money) -> String {
+        amount.format(&self.currency, &self.format)
+    }
+
+    /// The copies and prints categories, then any other category a session
+    /// of the report recorded.
+    fn categories(&self) -> Vec<RecordingCategory> {
+        RecordingCategory::ALL
+            .into_iter()
+            .filter(|category| {
+                category.is_base()
+                    || self
+                        .sessions
+                        .iter()
+                        .any(|session| session.pages.contains_key(category))
+            })
+            .collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        let totals = self.totals();
+        let width = TEXT_COLUMNS;
+        let mut out = String::new();
+        let row = |out: &mut String, label: &str, value: String| {
+            push_columns(out, label, &value, width);
+            out.push('\n');
+        };
+        let rule = "-".repeat(width) + "\n";
+
+        out.push_str(&self.title());
+        out.push('\n');
+        let since = self
+            .since
+            .map(format_utc_timestamp)
+            .unwrap_or_else(|| "first report".to_string());
+        row(&mut out, "Since", since);
+        row(&mut out, "Closed", format_utc_timestamp(self.created_at));
+        out.push_str(&rule);
+        row(&mut out, "Sessions", totals.sessions.to_string());
+        row(&mut out, "Voided", totals.voided.to_string());
+        row(&mut out, "Manual overrides", totals.overridden.to_string());
+        out.push_str(&rule);
+        out.push_str("By payment method\n");
+        for method in PaymentMethod::ALL {
+            let amount = totals.by_method.get(&method).copied().unwrap_or_default();
+            row(&mut out, &format!("  {method}"), self.money(amount));
+        }
+        if totals.unpaid_sessions > 0 {
+            let label = format!("  Unpaid ({} sessions)", totals.unpaid_sessions);
+            row(&mut out, &label, self.money(totals.unpaid));
+        }
+        out.push_str("By category\n");
+        for category in self.categories() {
+            let pages = totals.pages.get(&category).copied().unwrap_or_default();
+            row(
+                &mut out,
+                &format!("  {}", category.label()),
+                format!("{pages} pages"),
+            );
+        }
+        out.push_str("By tariff\n");
+        for (tariff, amount) in &totals.tariffs {
+            row(&mut out, &format!("  {tariff}"), self.money(*amount));
+        }
+        out.push_str(&rule);
+        row(&mut out, "TOTAL", self.money(totals.total));
+        out.push_str(&rule);
+
+        for session in &self.sessions {
+            let mut flags = Vec::new();
+            if session.voided {
+                flags.push("voided");
+            }
+            if session.overridden {
+                flags.push("override");
+            }
+            let payment = session
+                .payment
+                .map(|method| method.to_string())
+                .unwrap_or_else(|| "unpaid".to_string());
+            let receipt = session.receipt.as_deref().unwrap_or("no receipt");
+            let label = format!(
+                "{} {} ({receipt}, {payment})",
+                format_utc_timestamp(session.stopped_at),
+                session.printer
+            );
+            let mut value = self.money(session.total);
+            if !flags.is_empty() {
+                value = format!("{value} [{}]", flags.join(", "));
+            }
+            row(&mut out, &label, value);
+        }
+        out
+    }
+
+    /// One line per session, amounts in plain decimals so spreadsheets read
+    /// them whatever the display format.
+    pub fn to_csv(&self) -> String {
+        let plain = MoneyFormat::default();
+        let mut out =
+            String::from("report,printer,started_at,stopped_at,receipt,payment,voided,overridden");
+        let categories = self.categories();
+        for category in &categories {
+            out.push(',');
+            out.push_str(&csv_field(category.label()));
+        }
+        out.push_str(",total,currency\n");
+        for session in &self.sessions {
+            let fields = [
+                self.number.to_string(),
+                csv_field(&session.printer),
+                format_utc_timestamp(session.started_at),
+                format_utc_timestamp(session.stopped_at),
+                csv_field(session.receipt.as_deref().unwrap_or_default()),
+                session
+                    .payment
+                    .map(|method| method.to_string())
+                    .unwrap_or_default(),
+                session.voided.to_string(),
+                session.overridden.to_string(),
+            ];
+            out.push_str(&fields.join(","));
+            for category in &categories {
+                let pages = session.pages.get(category).copied().unwrap_or_default();
+                out.push_str(&format!(",{pages}"));
+            }
+            out.push_str(&format!(
+                ",{},{}\n",
+                session.total.format_amount(&self.currency, &plain),
+                csv_field(&self.currency.code)
+            ));
+        }
+        out
+    }
+}
+
+/// Stored reports, one write-once RON file each, with text and CSV exports
+/// next to them.
+#[derive(Debug, Clone)]
+pub struct ZReportStore {
+    dir: PathBuf,
+}
+
+impl ZReportStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, number: u32, extension: &str) -> PathBuf {
+        self.dir.join(format!("Z-{number:06}.{extension}"))
+    }
+
+    /// Every stored report, oldest first. A missing directory means none.
+    pub fn load_all(&self) -> Result<Vec<ZReport>, Error> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(storage_error(StorageAction::Load, &self.dir, source)),
+        };
+        let mut reports = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|source| storage_error(StorageAction::Load, &self.dir, source))?
+                .path();
+            if path.extension().and_then(|value| value.to_str()) != Some("ron") {
+                continue;
+            }
+            reports.push(storage::load_ron::<ZReport>(&path)?);
+        }
+        reports.sort_by_key(|report| report.number);
+        Ok(reports)
+    }
+
+    /// Writes a new report. The file must not exist yet and is made read-only
+    /// afterwards, so a stored report is never replaced.
+    pub fn store(&self, report: &ZReport) -> Result<PathBuf, Error> {
+        let path = self.path(report.number, "ron");
+        let contents = ron::ser::to_string_pretty(report, ron::ser::PrettyConfig::default())
+            .map_err(|source| Error::Ron {
+                action: StorageAction::Save,
+                path: Some(path.display().to_string()),
+                source,
+            })?;
+        let result = (|| {
+            fs::create_dir_all(&self.dir)?;
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)?;
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()?;
+            let mut permissions = file.metadata()?.permissions();
+            permissions.set_readonly(true);
+            fs::set_permissions(&path, permissions)
+        })();
+        result.map_err(|source| storage_error(StorageAction::Save, &path, source))?;
+        Ok(path)
+    }
+
+    /// Writes the text and CSV exports, replacing earlier ones; they are
+    /// derived from the stored report and can be made again at any time.
+    pub fn export(&self, report: &ZReport) -> Result<Vec<PathBuf>, Error> {
+        let documents = [("txt", report.to_text()), ("csv", report.to_csv())];
+        let mut paths = Vec::new();
+        for (extension, contents) in documents {
+            let path = self.path(report.number, extension);
+            storage::write_atomic(&path, contents.as_bytes())?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::TempDir;
+
+    fn cents(minor: i64) -> Money {
+        Money::from_minor(minor)
+    }
+
+    fn session(
+        printer: &str,
+        payment: Option<PaymentMethod>,
+        pages: u64,
+        total: i64,
+    ) -> ZReportSession {
+        ZReportSession {
+            printer: printer.to_string(),
+            started_at: 1_700_000_000,
+            stopped_at: 1_700_000_600,
+            receipt: None,
+            payment,
+            voided: false,
+            overridden: false,
+            pages: [(RecordingCategory::PrintsBw, pages)].into_iter().collect(),
+            tariffs: [("B/W".to_string(), cents(total))].into_iter().collect(),
+            total: cents(total),
+        }
+    }
+
+    fn report() -> ZReport {
+        ZReport {
+            number: 7,
+            created_at: 1_700_050_000,
+            since: Some(1_699_960_000),
+            sessions: vec![
+                session("ricoh-1", Some(PaymentMethod::Cash), 10, 150),
+                session("ricoh-1", Some(PaymentMethod::Card), 4, 100),
+                ZReportSession {
+                    voided: true,
+                    ..session("ricoh-2", Some(PaymentMethod::Cash), 30, 450)
+                },
+                ZReportSession {
+                    overridden: true,
+                    receipt: Some("R-000012".to_string()),
+                    ..session("ricoh, 2", None, 2, 50)
+                },
+            ],
+            currency: Currency::default(),
+            format: MoneyFormat::default(),
+        }
+    }
+
+    #[test]
+    fn sums_by_payment_method_and_category() {
+        let totals = report().totals();
+        assert_eq!(totals.sessions, 4);
+        assert_eq!(totals.voided, 1);
+        assert_eq!(totals.overridden, 1);
+        assert_eq!(
+            totals.by_method.get(&PaymentMethod::Cash),
+            Some(&cents(150))
+        );
+        assert_eq!(
+            totals.by_method.get(&PaymentMethod::Card),
+            Some(&cents(100))
+        );
+        assert_eq!(totals.by_method.get(&PaymentMethod::Account), None);
+        assert_eq!((totals.unpaid_sessions, totals.unpaid), (1, cents(50)));
+        assert_eq!(totals.pages.get(&RecordingCategory::PrintsBw), Some(&16));
+        assert_eq!(totals.tariffs.get("B/W"), Some(&cents(300)));
+        assert_eq!(totals.total, cents(300));
+
+        let text = report().to_text();
+        assert!(text.starts_with("Z report 7\n"));
+        assert!(text.contains("  Unpaid (1 sessions)"));
+        assert!(text.contains("4.50 EUR [voided]"));
+
+        let csv = report().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[4],
+            "7,\"ricoh, 2\",2023-11-14 22:13 UTC,2023-11-14 22:23 UTC,R-000012,,false,true,\
+             0,0,2,0,0.50,EUR"
+        );
+
+        // Categories beyond copies and prints show once a session has them.
+        assert!(!text.contains("Scans"));
+        let mut report = report();
+        report.sessions[0]
+            .pages
+            .insert(RecordingCategory::Scans, 12);
+        assert!(report.to_text().contains("  Scans"));
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].ends_with(",Prints color,Scans,total,currency"));
+        assert!(lines[4].ends_with(",0,0,2,0,0,0.50,EUR"));
+    }
+
+    #[test]
+    fn never_replaces_a_stored_report() {
+        let dir = TempDir::new("zreport");
+        let store = ZReportStore::new(dir.path());
+        assert!(store.load_all().expect("empty").is_empty());
+
+        let report = report();
+        let path = store.store(&report).expect("store");
+        assert!(fs::metadata(&path)
+            .expect("metadata")
+            .permissions()
+            .readonly());
+        assert!(store.store(&report).is_err());
+        assert_eq!(store.load_all().expect("load"), vec![report.clone()]);
+
+        let exports = store.export(&report).expect("export");
+        assert_eq!(exports.len(), 2);
+        assert_eq!(store.load_all().expect("load").len(), 1);
+    }
+}
@@ -0,0 +1,653 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use printcountpay_core::{ber, targets, Oid, SnmpAddress, SnmpValue, SnmpVarBind};
+use serde::{Deserialize, Serialize};
+use snmp2::{snmp as snmp2_constants, MessageType, Pdu, Version};
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, trace, warn};
+
+use crate::{Dataset, SimError};
+
+const MAX_DATAGRAM: usize = 65_507;
+const WRONG_COMMUNITY: &[u8] = b"printcountpay-sim-wrong";
+
+/// Misbehaviour a simulated agent can be told to show.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Fault {
+    /// Drop the request without answering.
+    Timeout,
+    /// Answer with error-status `noSuchName` on the first varbind.
+    NoSuchName,
+    /// Answer with a community string other than the one requested.
+    WrongCommunity,
+    /// Answer `tooBig` whenever a response would carry more varbinds.
+    TooBig { max_varbinds: usize },
+}
+
+/// Adds `step` to a numeric OID every `every` of wall-clock time, so polls
+/// see counters move the way a busy printer would.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterIncrement {
+    pub oid: Oid,
+    pub step: u64,
+    pub every: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct FaultRule {
+    fault: Fault,
+    remaining: Option<u32>,
+}
+
+/// One simulated printer: the data it serves and how it behaves.
+#[derive(Debug, Clone)]
+pub struct SimPrinter {
+    dataset: Dataset,
+    community: String,
+    increments: Vec<CounterIncrement>,
+    faults: Vec<FaultRule>,
+}
+
+impl SimPrinter {
+    pub fn new(dataset: Dataset) -> Self {
+        Self {
+            dataset,
+            community: "public".to_string(),
+            increments: Vec::new(),
+            faults: Vec::new(),
+        }
+    }
+
+    pub fn with_community(mut self, community: impl Into<String>) -> Self {
+        self.community = community.into();
+        self
+    }
+
+    pub fn with_increment(mut self, oid: Oid, step: u64, every: Duration) -> Self {
+        self.increments.push(CounterIncrement { oid, step, every });
+        self
+    }
+
+    pub fn with_fault(mut self, fault: Fault) -> Self {
+        self.faults.push(FaultRule {
+            fault,
+            remaining: None,
+        });
+        self
+    }
+
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+}
+
+#[derive(Debug)]
+struct AgentState {
+    printer: SimPrinter,
+    started: Instant,
+    applied_ticks: Vec<u64>,
+}
+
+/// A running simulated agent bound to a UDP port. Dropping it stops the agent.
+#[derive(Debug)]
+pub struct SimAgent {
+    local_addr: SocketAddr,
+    state: Arc<Mutex<AgentState>>,
+    requests: Arc<AtomicU64>,
+    task: JoinHandle<()>,
+}
+
+impl SimAgent {
+    /// Binds `bind` (use port 0 for an ephemeral port) and starts answering.
+    /// Must be called from inside a Tokio runtime.
+    pub async fn spawn(bind: SocketAddr, printer: SimPrinter) -> Result<Self, SimError> {
+        let socket = UdpSocket::bind(bind).await.map_err(|source| SimError::Bind {
+            address: bind.to_string(),
+            source,
+        })?;
+        let local_addr = socket.local_addr().map_err(|source| SimError::Bind {
+            address: bind.to_string(),
+            source,
+        })?;
+
+        info!(
+            target: targets::SNMP,
+            address = %local_addr,
+            oids = printer.dataset.len(),
+            "Simulated agent listening"
+        );
+
+        let applied_ticks = vec![0; printer.increments.len()];
+        let state = Arc::new(Mutex::new(AgentState {
+            printer,
+            started: Instant::now(),
+            applied_ticks,
+        }));
+        let requests = Arc::new(AtomicU64::new(0));
+        let task = tokio::spawn(serve(
+            socket,
+            local_addr,
+            Arc::clone(&state),
+            Arc::clone(&requests),
+        ));
+
+        Ok(Self {
+            local_addr,
+            state,
+            requests,
+            task,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn snmp_address(&self) -> SnmpAddress {
+        SnmpAddress::new(self.local_addr.ip().to_string(), self.local_addr.port())
+    }
+
+    /// Requests received so far, including dropped ones.
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Applies `fault` to every following request until cleared.
+    pub fn inject(&self, fault: Fault) {
+        self.push_fault(fault, None);
+    }
+
+    /// Applies `fault` to the next `count` requests only.
+    pub fn inject_for(&self, fault: Fault, count: u32) {
+        self.push_fault(fault, Some(count));
+    }
+
+    pub fn clear_faults(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.printer.faults.clear();
+        }
+    }
+
+    /// Adds `by` to a numeric OID right away.
+    pub fn bump(&self, oid: &Oid, by: u64) {
+        if let Ok(mut state) = self.state.lock()
+            && let Some(value) = state.printer.dataset.get_mut(oid)
+        {
+            add_to_value(value, by);
+        }
+    }
+
+    pub fn value(&self, oid: &Oid) -> Option<SnmpValue> {
+        let state = self.state.lock().ok()?;
+        state.printer.dataset.get(oid).cloned()
+    }
+
+    fn push_fault(&self, fault: Fault, remaining: Option<u32>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.printer.faults.push(FaultRule { fault, remaining });
+        }
+    }
+}
+
+impl Drop for SimAgent {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(
+    socket: UdpSocket,
+    local_addr: SocketAddr,
+    state: Arc<Mutex<AgentState>>,
+    requests: Arc<AtomicU64>,
+) {
+    let mut buffer = vec![0u8; MAX_DATAGRAM];
+    loop {
+        let (len, peer) = match socket.recv_from(&mut buffer).await {
+            Ok(received) => received,
+            Err(error) => {
+                warn!(
+                    target: targets::SNMP,
+                    address = %local_addr,
+                    error = %error,
+                    "Simulated agent receive failed"
+                );
+                continue;
+            }
+        };
+        requests.fetch_add(1, Ordering::Relaxed);
+
+        let reply = match state.lock() {
+            Ok(mut state) => state.handle(&buffer[..len], Instant::now()),
+            Err(_) => None,
+        };
+        let Some(reply) = reply else {
+            trace!(target: targets::SNMP, address = %local_addr, peer = %peer, "Request dropped");
+            continue;
+        };
+        if let Err(error) = socket.send_to(&reply, peer).await {
+            warn!(
+                target: targets::SNMP,
+                address = %local_addr,
+                peer = %peer,
+                error = %error,
+                "Simulated agent send failed"
+            );
+        }
+    }
+}
+
+impl AgentState {
+    fn handle(&mut self, bytes: &[u8], now: Instant) -> Option<Vec<u8>> {
+        let pdu = Pdu::from_bytes(bytes).ok()?;
+        let version = pdu.version().ok()?;
+        if pdu.community != self.printer.community.as_bytes() {
+            debug!(target: targets::SNMP, "Request with unknown community dropped");
+            return None;
+        }
+
+        self.apply_increments(now.saturating_duration_since(self.started));
+
+        let requested: Vec<Oid> = pdu
+            .varbinds
+            .clone()
+            .filter_map(|(oid, _)| {
+                let arcs = oid.iter()?.map(|arc| u32::try_from(arc).ok());
+                arcs.collect::<Option<Vec<u32>>>().map(Oid)
+            })
+            .collect();
+
+        let (mut error_status, mut error_index, mut varbinds) = match pdu.message_type {
+            MessageType::GetRequest => self.get(&requested, version),
+            MessageType::GetNextRequest => self.get_next(&requested, version),
+            MessageType::GetBulkRequest => {
+                let varbinds = self.get_bulk(&requested, pdu.error_status, pdu.error_index);
+                (0, 0, varbinds)
+            }
+            _ => return None,
+        };
+
+        let mut community = pdu.community;
+        match self.take_fault(varbinds.len()) {
+            Some(Fault::Timeout) => return None,
+            Some(Fault::NoSuchName) => {
+                error_status = snmp2_constants::ERRSTATUS_NOSUCHNAME;
+                error_index = 1;
+                varbinds = echo(&requested);
+            }
+            Some(Fault::WrongCommunity) => community = WRONG_COMMUNITY,
+            Some(Fault::TooBig { .. }) => {
+                error_status = snmp2_constants::ERRSTATUS_TOOBIG;
+                error_index = 0;
+                varbinds = echo(&requested);
+            }
+            None => {}
+        }
+
+        Some(encode_response(
+            version as i64,
+            community,
+            pdu.req_id,
+            error_status,
+            error_index,
+            &varbinds,
+        ))
+    }
+
+    fn get(&self, requested: &[Oid], version: Version) -> (u32, u32, Vec<SnmpVarBind>) {
+        let mut varbinds = Vec::with_capacity(requested.len());
+        for (index, oid) in requested.iter().enumerate() {
+            let value = match self.printer.dataset.get(oid) {
+                Some(value) => value.clone(),
+                None if version == Version::V1 => {
+                    return no_such_name(requested, index);
+                }
+                None => SnmpValue::NoSuchObject,
+            };
+            varbinds.push(SnmpVarBind {
+                oid: oid.clone(),
+                value,
+            });
+        }
+        (0, 0, varbinds)
+    }
+
+    fn get_next(&self, requested: &[Oid], version: Version) -> (u32, u32, Vec<SnmpVarBind>) {
+        let mut varbinds = Vec::with_capacity(requested.len());
+        for (index, oid) in requested.iter().enumerate() {
+            match self.printer.dataset.next_after(oid) {
+                Some(varbind) => varbinds.push(varbind),
+                None if version == Version::V1 => return no_such_name(requested, index),
+                None => varbinds.push(end_of_mib(oid)),
+            }
+        }
+        (0, 0, varbinds)
+    }
+
+    fn get_bulk(
+        &self,
+        requested: &[Oid],
+        non_repeaters: u32,
+        max_repetitions: u32,
+    ) -> Vec<SnmpVarBind> {
+        let non_repeaters = (non_repeaters as usize).min(requested.len());
+        let mut varbinds = Vec::new();
+        for oid in &requested[..non_repeaters] {
+            varbinds.push(
+                self.printer
+                    .dataset
+                    .next_after(oid)
+                    .unwrap_or_else(|| end_of_mib(oid)),
+            );
+        }
+
+        let mut cursors: Vec<Oid> = requested[non_repeaters..].to_vec();
+        for _ in 0..max_repetitions {
+            if cursors.is_empty() {
+                break;
+            }
+            let mut exhausted = true;
+            for cursor in &mut cursors {
+                match self.printer.dataset.next_after(cursor) {
+                    Some(varbind) => {
+                        *cursor = varbind.oid.clone();
+                        varbinds.push(varbind);
+                        exhausted = false;
+                    }
+                    None => varbinds.push(end_of_mib(cursor)),
+                }
+            }
+            if exhausted {
+                break;
+            }
+        }
+        varbinds
+    }
+
+    fn apply_increments(&mut self, elapsed: Duration) {
+        let AgentState {
+            printer,
+            applied_ticks,
+            ..
+        } = self;
+        for (increment, applied) in printer.increments.iter().zip(applied_ticks.iter_mut()) {
+            if increment.every.is_zero() {
+                continue;
+            }
+            let ticks = (elapsed.as_millis() / increment.every.as_millis().max(1)) as u64;
+            let due = ticks.saturating_sub(*applied);
+            if due == 0 {
+                continue;
+            }
+            *applied = ticks;
+            if let Some(value) = printer.dataset.get_mut(&increment.oid) {
+                add_to_value(value, increment.step.saturating_mul(due));
+            }
+        }
+    }
+
+    fn take_fault(&mut self, response_len: usize) -> Option<Fault> {
+        let faults = &mut self.printer.faults;
+        let index = faults.iter().position(|rule| match rule.fault {
+            Fault::TooBig { max_varbinds } => response_len > max_varbinds,
+            _ => true,
+        })?;
+        let fault = faults[index].fault.clone();
+        if let Some(remaining) = faults[index].remaining.as_mut() {
+            *remaining = remaining.saturating_sub(1);
+            if *remaining == 0 {
+                faults.remove(index);
+            }
+        }
+        Some(fault)
+    }
+}
+
+fn add_to_value(value: &mut SnmpValue, by: u64) {
+    match value {
+        SnmpValue::Integer(current) => {
+            *current = current.saturating_add(i64::try_from(by).unwrap_or(i64::MAX));
+        }
+        SnmpValue::Counter32(current) | SnmpValue::Unsigned32(current) => {
+            *current = current.wrapping_add(by as u32);
+        }
+        SnmpValue::Counter64(current) => *current = current.wrapping_add(by),
+        _ => {}
+    }
+}
+
+fn end_of_mib(oid: &Oid) -> SnmpVarBind {
+    SnmpVarBind {
+        oid: oid.clone(),
+        value: SnmpValue::EndOfMibView,
+    }
+}
+
+fn echo(requested: &[Oid]) -> Vec<SnmpVarBind> {
+    requested
+        .iter()
+        .map(|oid| SnmpVarBind {
+            oid: oid.clone(),
+            value: SnmpValue::Null,
+        })
+        .collect()
+}
+
+fn no_such_name(requested: &[Oid], index: usize) -> (u32, u32, Vec<SnmpVarBind>) {
+    (
+        snmp2_constants::ERRSTATUS_NOSUCHNAME,
+        u32::try_from(index + 1).unwrap_or(u32::MAX),
+        echo(requested),
+    )
+}
+
+fn encode_response(
+    version: i64,
+    community: &[u8],
+    req_id: i32,
+    error_status: u32,
+    error_index: u32,
+    varbinds: &[SnmpVarBind],
+) -> Vec<u8> {
+    let varbinds: Vec<Vec<u8>> = varbinds
+        .iter()
+        .map(|varbind| ber::varbind(&varbind.oid, &varbind.value))
+        .collect();
+    let pdu = ber::tlv(
+        snmp2_constants::MSG_RESPONSE,
+        &[
+            ber::integer(i64::from(req_id)),
+            ber::integer(i64::from(error_status)),
+            ber::integer(i64::from(error_index)),
+            ber::sequence(&varbinds),
+        ]
+        .concat(),
+    );
+    ber::sequence(&[ber::integer(version), ber::octet_string(community), pdu])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use printcountpay_core::{
+        probe_printer, Error, SnmpConfig, SnmpRequest, SnmpV2cClient, SnmpWalkRequest,
+    };
+
+    use super::*;
+
+    const COUNTER_OID: &str = "1.3.6.1.2.1.43.10.2.1.4.1.1";
+
+    // snmp2 sessions carry their 64 KiB buffers inline and debug builds copy
+    // the nested client futures on the stack, so a default test thread overflows.
+    const TEST_STACK_SIZE_BYTES: usize = 16 * 1024 * 1024;
+
+    fn run_future<T, F>(make_future: impl FnOnce() -> F + Send + 'static) -> T
+    where
+        T: Send + 'static,
+        F: std::future::Future<Output = T>,
+    {
+        std::thread::Builder::new()
+            .stack_size(TEST_STACK_SIZE_BYTES)
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("tokio runtime");
+                runtime.block_on(make_future())
+            })
+            .expect("test thread")
+            .join()
+            .expect("test thread panicked")
+    }
+
+    fn oid(text: &str) -> Oid {
+        text.parse().expect("oid")
+    }
+
+    fn dataset() -> Dataset {
+        let mut dataset = Dataset::parse(
+            "test",
+            "1.3.6.1.2.1.1.1.0 = RICOH Aficio MP 7502 1.04 / RICOH Network Printer C model\n\
+             1.3.6.1.2.1.1.2.0 = 1.3.6.1.4.1.367.1.1\n\
+             1.3.6.1.2.1.43.5.1.1.16.1 = Aficio MP 7502\n\
+             1.3.6.1.2.1.43.10.2.1.4.1.1 = 1640686\n",
+        )
+        .expect("dataset");
+        for index in 1..=30 {
+            dataset.insert(
+                Oid(vec![1, 3, 6, 1, 4, 1, 367, 3, 2, 1, 2, 19, 5, 1, 9, index]),
+                SnmpValue::Integer(i64::from(index) * 100),
+            );
+        }
+        dataset
+    }
+
+    fn client() -> SnmpV2cClient {
+        SnmpV2cClient::new(SnmpConfig {
+            timeout: Duration::from_millis(300),
+            retries: 0,
+            ..SnmpConfig::default()
+        })
+    }
+
+    async fn spawn(printer: SimPrinter) -> SimAgent {
+        SimAgent::spawn("127.0.0.1:0".parse().expect("bind"), printer)
+            .await
+            .expect("spawn agent")
+    }
+
+    #[test]
+    fn serves_get_walk_and_bulk_walk() {
+        run_future(|| async {
+            let agent = spawn(SimPrinter::new(dataset())).await;
+            let client = client();
+
+            let request = SnmpRequest::new(agent.snmp_address(), vec![oid(COUNTER_OID)]);
+            let response = client.get(request).await.expect("get");
+            assert_eq!(response.varbinds[0].value.as_u64(), Some(1_640_686));
+
+            let root = oid("1.3.6.1.4.1.367.3.2.1.2.19.5.1.9");
+            let request = SnmpWalkRequest::new(agent.snmp_address(), root.clone())
+                .with_max_results(0);
+            let walked = client.walk(request).await.expect("walk");
+            let request = SnmpWalkRequest::new(agent.snmp_address(), root)
+                .with_max_results(0)
+                .with_max_repetitions(8);
+            let bulk = client.bulk_walk(request).await.expect("bulk walk");
+            assert_eq!(walked.varbinds.len(), 30);
+            assert_eq!(bulk.varbinds.len(), 30);
+        });
+    }
+
+    #[test]
+    fn bulk_walk_shrinks_after_too_big() {
+        run_future(|| async {
+            let printer = SimPrinter::new(dataset()).with_fault(Fault::TooBig { max_varbinds: 5 });
+            let agent = spawn(printer).await;
+            let client = client();
+
+            let request = SnmpWalkRequest::new(
+                agent.snmp_address(),
+                oid("1.3.6.1.4.1.367.3.2.1.2.19.5.1.9"),
+            )
+            .with_max_results(0)
+            .with_max_repetitions(40);
+            let response = client.bulk_walk(request).await.expect("bulk walk");
+            assert_eq!(response.varbinds.len(), 30);
+            let learned = client.config().pdu_sizes.get(&agent.snmp_address());
+            assert!(learned.max_repetitions <= 5);
+        });
+    }
+
+    #[test]
+    fn injected_faults_surface_as_client_errors() {
+        run_future(|| async {
+            let agent = spawn(SimPrinter::new(dataset())).await;
+            let client = client();
+            let request = || SnmpRequest::new(agent.snmp_address(), vec![oid(COUNTER_OID)]);
+
+            agent.inject_for(Fault::WrongCommunity, 1);
+            let error = client.get(request()).await.expect_err("wrong community");
+            assert!(matches!(error, Error::SnmpAuth { .. }));
+
+            agent.inject_for(Fault::Timeout, 1);
+            let error = client.get(request()).await.expect_err("timeout");
+            assert!(matches!(error, Error::SnmpTimeout { .. }));
+
+            let response = client.get(request()).await.expect("faults expired");
+            assert_eq!(response.varbinds[0].value.as_u64(), Some(1_640_686));
+        });
+    }
+
+    #[test]
+    fn counters_advance_between_polls() {
+        run_future(|| async {
+            let agent = spawn(SimPrinter::new(dataset())).await;
+            let client = client();
+            agent.bump(&oid(COUNTER_OID), 25);
+
+            let request = SnmpRequest::new(agent.snmp_address(), vec![oid(COUNTER_OID)]);
+            let response = client.get(request).await.expect("get");
+            assert_eq!(response.varbinds[0].value.as_u64(), Some(1_640_711));
+        });
+    }
+
+    #[test]
+    fn increments_apply_per_elapsed_interval() {
+        let printer = SimPrinter::new(dataset()).with_increment(
+            oid(COUNTER_OID),
+            3,
+            Duration::from_secs(5),
+        );
+        let mut state = AgentState {
+            applied_ticks: vec![0],
+            printer,
+            started: Instant::now(),
+        };
+
+        state.apply_increments(Duration::from_secs(4));
+        state.apply_increments(Duration::from_secs(12));
+        state.apply_increments(Duration::from_secs(14));
+        assert_eq!(
+            state.printer.dataset.get(&oid(COUNTER_OID)),
+            Some(&SnmpValue::Integer(1_640_692))
+        );
+    }
+
+    #[test]
+    fn discovery_finds_simulated_printer() {
+        run_future(|| async {
+            let agent = spawn(SimPrinter::new(dataset())).await;
+            let record = probe_printer(Arc::new(client()), agent.snmp_address(), None)
+                .await
+                .expect("probe")
+                .expect("printer");
+            assert_eq!(record.model.as_deref(), Some("Aficio MP 7502"));
+        });
+    }
+}
@@ -0,0 +1,137 @@
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use printcountpay_core::{Oid, StorageAction};
+use ron::de::from_str;
+use serde::{Deserialize, Serialize};
+
+use crate::{Dataset, Fault, SimAgent, SimError, SimPrinter};
+
+/// RON description of a set of simulated printers, one UDP port each.
+///
+/// Dataset paths are resolved relative to the config file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimConfig {
+    #[serde(default = "default_bind")]
+    pub bind: IpAddr,
+    pub printers: Vec<SimPrinterConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimPrinterConfig {
+    pub port: u16,
+    pub dataset: PathBuf,
+    #[serde(default = "default_community")]
+    pub community: String,
+    #[serde(default)]
+    pub increments: Vec<IncrementConfig>,
+    #[serde(default)]
+    pub faults: Vec<Fault>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncrementConfig {
+    pub oid: String,
+    pub step: u64,
+    pub every_secs: u64,
+}
+
+fn default_bind() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
+
+fn default_community() -> String {
+    "public".to_string()
+}
+
+impl SimConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SimError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| {
+            SimError::Core(printcountpay_core::Error::StorageIo {
+                action: StorageAction::Load,
+                path: Some(path.display().to_string()),
+                source,
+            })
+        })?;
+        let mut config: SimConfig = from_str(&contents).map_err(|error| {
+            SimError::Core(printcountpay_core::Error::Ron {
+                action: StorageAction::Load,
+                path: Some(path.display().to_string()),
+                source: error.into(),
+            })
+        })?;
+
+        if let Some(base) = path.parent() {
+            for printer in &mut config.printers {
+                if printer.dataset.is_relative() {
+                    printer.dataset = base.join(&printer.dataset);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Loads every dataset and starts one agent per printer.
+    pub async fn spawn_all(&self) -> Result<Vec<SimAgent>, SimError> {
+        let mut agents = Vec::with_capacity(self.printers.len());
+        for printer in &self.printers {
+            let bind = SocketAddr::new(self.bind, printer.port);
+            agents.push(SimAgent::spawn(bind, printer.build()?).await?);
+        }
+        Ok(agents)
+    }
+}
+
+impl SimPrinterConfig {
+    pub fn build(&self) -> Result<SimPrinter, SimError> {
+        let dataset = Dataset::load(&self.dataset)?;
+        let mut printer = SimPrinter::new(dataset).with_community(self.community.clone());
+        for increment in &self.increments {
+            let oid: Oid = increment.oid.parse().map_err(|error| SimError::Config {
+                details: format!("increment OID {}: {error}", increment.oid),
+            })?;
+            printer = printer.with_increment(
+                oid,
+                increment.step,
+                Duration::from_secs(increment.every_secs),
+            );
+        }
+        for fault in &self.faults {
+            printer = printer.with_fault(fault.clone());
+        }
+        Ok(printer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_multi_printer_config() {
+        let config: SimConfig = from_str(
+            r#"(
+                printers: [
+                    (port: 1161, dataset: "polling_export.txt"),
+                    (
+                        port: 1162,
+                        dataset: "walk.txt",
+                        community: "ricoh",
+                        increments: [(oid: "1.3.6.1.2.1.43.10.2.1.4.1.1", step: 2, every_secs: 10)],
+                        faults: [TooBig(max_varbinds: 20)],
+                    ),
+                ],
+            )"#,
+        )
+        .expect("config");
+
+        assert_eq!(config.bind, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(config.printers.len(), 2);
+        assert_eq!(config.printers[0].community, "public");
+        assert_eq!(config.printers[1].faults, vec![Fault::TooBig { max_varbinds: 20 }]);
+        assert_eq!(config.printers[1].increments[0].every_secs, 10);
+    }
+}
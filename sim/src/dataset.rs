@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::ops::Bound;
+use std::path::Path;
+
+use printcountpay_core::{Oid, SnmpValue, SnmpVarBind, StorageAction};
+
+use crate::SimError;
+
+/// Ordered OID table served by a simulated agent.
+///
+/// Loads the `oid = value` lines of our poll exports as well as
+/// `snmpwalk -On` dumps (`.1.3.6... = Counter32: 42`). Header lines and
+/// missing values (`NoSuchObject`, `null`, ...) are skipped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dataset {
+    entries: BTreeMap<Oid, SnmpValue>,
+}
+
+impl Dataset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SimError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| {
+            SimError::Core(printcountpay_core::Error::StorageIo {
+                action: StorageAction::Load,
+                path: Some(path.display().to_string()),
+                source,
+            })
+        })?;
+        Self::parse(&path.display().to_string(), &contents)
+    }
+
+    pub fn parse(name: &str, contents: &str) -> Result<Self, SimError> {
+        let mut dataset = Self::new();
+        for (index, line) in contents.lines().enumerate() {
+            let Some((oid_text, value_text)) = line.split_once(" = ") else {
+                continue;
+            };
+            let oid_text = oid_text.trim();
+            if !oid_text.starts_with(|c: char| c == '.' || c.is_ascii_digit()) {
+                continue;
+            }
+            let oid: Oid = oid_text.parse().map_err(|error| SimError::Dataset {
+                path: name.to_string(),
+                line: index + 1,
+                details: format!("{error}"),
+            })?;
+            if let Some(value) = parse_value(value_text.trim()) {
+                dataset.insert(oid, value);
+            }
+        }
+        Ok(dataset)
+    }
+
+    pub fn insert(&mut self, oid: Oid, value: SnmpValue) {
+        self.entries.insert(oid, value);
+    }
+
+    pub fn get(&self, oid: &Oid) -> Option<&SnmpValue> {
+        self.entries.get(oid)
+    }
+
+    pub fn get_mut(&mut self, oid: &Oid) -> Option<&mut SnmpValue> {
+        self.entries.get_mut(oid)
+    }
+
+    /// First entry strictly after `oid`, as GETNEXT sees it.
+    pub fn next_after(&self, oid: &Oid) -> Option<SnmpVarBind> {
+        self.entries
+            .range((Bound::Excluded(oid.clone()), Bound::Unbounded))
+            .next()
+            .map(|(oid, value)| SnmpVarBind {
+                oid: oid.clone(),
+                value: value.clone(),
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn parse_value(text: &str) -> Option<SnmpValue> {
+    if let Some((kind, rest)) = text.split_once(": ")
+        && let Some(value) = parse_typed_value(kind, rest.trim())
+    {
+        return Some(value);
+    }
+    parse_export_value(text)
+}
+
+fn parse_typed_value(kind: &str, text: &str) -> Option<SnmpValue> {
+    match kind {
+        "STRING" => Some(SnmpValue::OctetString(unquote(text).as_bytes().to_vec())),
+        "Hex-STRING" => {
+            let bytes = text
+                .split_whitespace()
+                .map(|byte| u8::from_str_radix(byte, 16).ok())
+                .collect::<Option<Vec<u8>>>()?;
+            Some(SnmpValue::OctetString(bytes))
+        }
+        "INTEGER" => leading_number(text).map(SnmpValue::Integer),
+        "Counter32" => text.parse().ok().map(SnmpValue::Counter32),
+        "Gauge32" | "Unsigned32" => text.parse().ok().map(SnmpValue::Unsigned32),
+        "Counter64" => text.parse().ok().map(SnmpValue::Counter64),
+        "Timeticks" => {
+            let ticks = text.strip_prefix('(')?.split_once(')')?.0;
+            ticks.parse().ok().map(SnmpValue::Timeticks)
+        }
+        "OID" => text.parse().ok().map(SnmpValue::ObjectIdentifier),
+        "IpAddress" => text
+            .parse::<Ipv4Addr>()
+            .ok()
+            .map(|ip| SnmpValue::IpAddress(ip.octets())),
+        _ => None,
+    }
+}
+
+/// Values as written by `SnmpValue`'s `Display` impl in poll exports. The
+/// type is not recorded, so numbers come back as INTEGER.
+fn parse_export_value(text: &str) -> Option<SnmpValue> {
+    match text {
+        "null" | "NoSuchObject" | "NoSuchInstance" | "EndOfMibView" => return None,
+        _ => {}
+    }
+    if let Some(ticks) = text.strip_suffix(" ticks")
+        && let Ok(ticks) = ticks.parse()
+    {
+        return Some(SnmpValue::Timeticks(ticks));
+    }
+    if let Ok(value) = text.parse::<i64>() {
+        return Some(SnmpValue::Integer(value));
+    }
+    if let Ok(value) = text.parse::<u64>() {
+        return Some(SnmpValue::Counter64(value));
+    }
+    let parts: Vec<&str> = text.split('.').collect();
+    if parts.len() >= 2 && parts.iter().all(|part| part.parse::<u32>().is_ok()) {
+        if parts.len() == 4
+            && let Ok(ip) = text.parse::<Ipv4Addr>()
+        {
+            return Some(SnmpValue::IpAddress(ip.octets()));
+        }
+        return text.parse().ok().map(SnmpValue::ObjectIdentifier);
+    }
+    Some(SnmpValue::OctetString(text.as_bytes().to_vec()))
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+/// `INTEGER: idle(3)` and `INTEGER: 3` both yield 3.
+fn leading_number(text: &str) -> Option<i64> {
+    if let Ok(value) = text.parse() {
+        return Some(value);
+    }
+    let inner = text.split_once('(')?.1.split_once(')')?.0;
+    inner.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oid(text: &str) -> Oid {
+        text.parse().expect("oid")
+    }
+
+    #[test]
+    fn parses_poll_export() {
+        let contents = "PrintCountPay poll export\n\
+            printer_id=snmp-192.168.129.23\n\
+            address=192.168.129.23:161\n\
+            \n\
+            1.3.6.1.2.1.1.1.0 = RICOH Aficio MP 7502 1.04 / RICOH Network Printer C model\n\
+            1.3.6.1.2.1.1.2.0 = 1.3.6.1.4.1.367.1.1\n\
+            1.3.6.1.2.1.1.3.0 = 165577600 ticks\n\
+            1.3.6.1.2.1.43.10.2.1.4.1.1 = 1640686\n\
+            1.3.6.1.2.1.43.5.1.1.16.1 = NoSuchObject\n";
+        let dataset = Dataset::parse("export", contents).expect("parse");
+
+        assert_eq!(dataset.len(), 4);
+        assert_eq!(
+            dataset.get(&oid("1.3.6.1.2.1.1.2.0")),
+            Some(&SnmpValue::ObjectIdentifier(oid("1.3.6.1.4.1.367.1.1")))
+        );
+        assert_eq!(
+            dataset.get(&oid("1.3.6.1.2.1.1.3.0")),
+            Some(&SnmpValue::Timeticks(165_577_600))
+        );
+        assert_eq!(
+            dataset.get(&oid("1.3.6.1.2.1.43.10.2.1.4.1.1")),
+            Some(&SnmpValue::Integer(1_640_686))
+        );
+        assert_eq!(dataset.get(&oid("1.3.6.1.2.1.43.5.1.1.16.1")), None);
+    }
+
+    #[test]
+    fn parses_snmpwalk_dump() {
+        let contents = ".1.3.6.1.2.1.1.1.0 = STRING: \"RICOH IM C3000\"\n\
+            .1.3.6.1.2.1.1.3.0 = Timeticks: (4242) 0:00:42.42\n\
+            .1.3.6.1.2.1.25.3.5.1.1.1 = INTEGER: idle(3)\n\
+            .1.3.6.1.2.1.43.10.2.1.4.1.1 = Counter32: 98765\n";
+        let dataset = Dataset::parse("walk", contents).expect("parse");
+
+        assert_eq!(
+            dataset.get(&oid("1.3.6.1.2.1.1.1.0")),
+            Some(&SnmpValue::OctetString(b"RICOH IM C3000".to_vec()))
+        );
+        assert_eq!(
+            dataset.get(&oid("1.3.6.1.2.1.1.3.0")),
+            Some(&SnmpValue::Timeticks(4242))
+        );
+        assert_eq!(
+            dataset.get(&oid("1.3.6.1.2.1.25.3.5.1.1.1")),
+            Some(&SnmpValue::Integer(3))
+        );
+        assert_eq!(
+            dataset.get(&oid("1.3.6.1.2.1.43.10.2.1.4.1.1")),
+            Some(&SnmpValue::Counter32(98_765))
+        );
+    }
+
+    #[test]
+    fn next_after_follows_oid_order() {
+        let mut dataset = Dataset::new();
+        dataset.insert(oid("1.3.6.1.2.1.1.10.0"), SnmpValue::Integer(10));
+        dataset.insert(oid("1.3.6.1.2.1.1.2.0"), SnmpValue::Integer(2));
+
+        let next = dataset.next_after(&oid("1.3.6.1.2.1.1")).expect("next");
+        assert_eq!(next.oid, oid("1.3.6.1.2.1.1.2.0"));
+        let next = dataset.next_after(&next.oid).expect("next");
+        assert_eq!(next.oid, oid("1.3.6.1.2.1.1.10.0"));
+        assert!(dataset.next_after(&next.oid).is_none());
+    }
+}
@@ -0,0 +1,21 @@
+use std::io;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SimError {
+    #[error("Failed to bind simulator on {address}")]
+    Bind {
+        address: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Invalid dataset {path} line {line}: {details}")]
+    Dataset {
+        path: String,
+        line: usize,
+        details: String,
+    },
+    #[error("Invalid simulator config: {details}")]
+    Config { details: String },
+    #[error(transparent)]
+    Core(#[from] printcountpay_core::Error),
+}
@@ -0,0 +1,9 @@
+pub mod agent;
+pub mod config;
+pub mod dataset;
+pub mod error;
+
+pub use agent::{CounterIncrement, Fault, SimAgent, SimPrinter};
+pub use config::{IncrementConfig, SimConfig, SimPrinterConfig};
+pub use dataset::Dataset;
+pub use error::SimError;
@@ -0,0 +1,79 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::process::ExitCode;
+
+use printcountpay_sim::{Dataset, SimAgent, SimConfig, SimError, SimPrinter};
+use tracing_subscriber::EnvFilter;
+
+const USAGE: &str = "usage:
+  printcountpay-sim <config.ron>
+  printcountpay-sim --dataset <file> [--port 1161] [--community public]";
+
+fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let runtime = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(error) => {
+            eprintln!("failed to start runtime: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match runtime.block_on(run(&args)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: &[String]) -> Result<(), SimError> {
+    let agents = match args {
+        [path] if !path.starts_with("--") => SimConfig::load(path)?.spawn_all().await?,
+        _ => vec![spawn_single(args).await?],
+    };
+
+    for agent in &agents {
+        println!("simulated printer listening on udp://{}", agent.local_addr());
+    }
+
+    std::future::pending::<()>().await;
+    Ok(())
+}
+
+async fn spawn_single(args: &[String]) -> Result<SimAgent, SimError> {
+    let mut dataset = None;
+    let mut port = 1161;
+    let mut community = "public".to_string();
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| usage_error(format!("{flag} needs a value")))?;
+        match flag.as_str() {
+            "--dataset" => dataset = Some(value.clone()),
+            "--port" => {
+                port = value
+                    .parse()
+                    .map_err(|_| usage_error(format!("invalid port {value}")))?;
+            }
+            "--community" => community = value.clone(),
+            _ => return Err(usage_error(format!("unknown argument {flag}"))),
+        }
+    }
+
+    let dataset = dataset.ok_or_else(|| usage_error("--dataset is required".to_string()))?;
+    let printer = SimPrinter::new(Dataset::load(dataset)?).with_community(community);
+    SimAgent::spawn(SocketAddr::from((Ipv4Addr::LOCALHOST, port)), printer).await
+}
+
+fn usage_error(details: String) -> SimError {
+    SimError::Config {
+        details: format!("{details}\n{USAGE}"),
+    }
+}
@@ -1,5 +1,8 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
 use iced::alignment::Horizontal;
@@ -14,9 +17,19 @@ use ron::de::from_str;
 use ron::ser::{to_string_pretty, PrettyConfig};
 
 use printcountpay_core::{
-    default_discovery_cidr, probe_printer, resolve_counters, targets, CidrRange, CounterOidSet, Oid,
-    PrinterId, PrinterRecord, SnmpAddress, SnmpConfig, SnmpRequest,
-    SnmpV2cClient, SnmpVarBind, SnmpWalkRequest, DEFAULT_SNMP_PORT,
+    check_idle, default_discovery_cidr, format_utc_timestamp, increases_between, jobs_from_walk,
+    probe_printer, resolve_counters, snmp_client_for, targets, write_atomic, AccountBook, AccountId,
+    BalanceState, CalibrationProposal, CategoryList, CidrRange, ConcurrentActivity, CounterMapping,
+    CounterOidSet, CounterRef, CounterSpan, CounterTable, Currency, DecimalSeparator, EscPosTarget,
+    GapAction, GapDecision, HistoryStore, IdleCheck, LedgerKind, MappingTemplate, MappingTemplates,
+    Money, MoneyFormat, Oid, PaymentMethod, PollTarget, Poller, PriceBasis, PriceBook,
+    PriceListVersion, PriceQuote, PriceTier, PrintJob, PrinterId, PrinterRecord, PrinterStatus,
+    ProfileRegistry, Receipt, ReceiptConfig, Reconciliation, ReconciliationLog, RecordingCategory,
+    ReplaySnmpClient, ResetResolution, RoundingMode, SessionSpan, SnmpAddress, SnmpAuthProtocol,
+    SnmpClient, SnmpConfig, SnmpPrivProtocol, SnmpRecorder, SnmpSecurityLevel, SnmpV3Credentials,
+    SnmpVarBind, SnmpWalkRequest, Tariff, ThousandsSeparator, UnbilledGap, VatRate, WalkProgress,
+    ZReport, ZReportSession, ZReportStore, DEFAULT_SNMP_PORT, JM_JOB_ENTRY_OID,
+    RICOH_ENG_COUNTER_ENTRY_OID, SECONDS_PER_DAY,
 };
 
 use crate::logging::{apply_log_level, LogEntry, LogLevel, LogStore, ReloadHandle};
@@ -27,7 +40,7 @@ mod helpers;
 mod styles;
 mod types;
 
-pub use types::{Flags, Message, Tab, PrinterTab, RecordingCategory, SnmpErrorInfo, DiscoveryProbeResult, DiscoveryOutcome};
+pub use types::{Flags, Message, Tab, PrinterTab, SnmpErrorInfo, SnmpV3Edit, DiscoveryProbeResult, DiscoveryOutcome};
 
 use constants::*;
 use badge_overlay::BadgeOverlay;
@@ -49,6 +62,7 @@ pub struct PrintCountApp {
     printer_tab: PrinterTab,
     discovery_cidr: String,
     discovery_community: String,
+    discovery_snmp_v3: SnmpV3Inputs,
     discovery_status: Option<String>,
     discovery_active: bool,
     discovery_queue: VecDeque<SnmpAddress>,
@@ -62,24 +76,40 @@ pub struct PrintCountApp {
     manual_host: String,
     manual_port: String,
     manual_community: String,
+    manual_snmp_v3: SnmpV3Inputs,
     manual_status: Option<String>,
     printers_path: String,
     printers_status: Option<String>,
     printers: Vec<PrinterRecord>,
     selected_printer: Option<PrinterId>,
     poll_states: HashMap<PrinterId, SnmpPollStatus>,
-    poll_in_flight: HashSet<PrinterId>,
     poll_export_path: String,
     poll_export_status: Option<String>,
     snmp_config: SnmpConfig,
+    poller: Poller,
+    history: HistoryStore,
+    snmp_capture: SnmpCaptureMode,
+    snmp_capture_path: String,
+    snmp_capture_status: Option<String>,
     counter_oids: CounterOidSet,
     oids_path: String,
     oids_total_text: String,
     oids_status: Option<String>,
     oids_crawl_in_flight: bool,
+    oids_crawl_progress: WalkProgress,
     recording_oids: RecordingOidSettings,
     recording_sessions: HashMap<PrinterId, RecordingSession>,
+    completed_recordings: HashMap<PrinterId, Vec<RecordingSession>>,
+    recordings_status: Option<String>,
     pricing: PricingSettings,
+    receipts: ReceiptSettings,
+    accounts: AccountSettings,
+    zreports: ZReportSettings,
+    reconcile: ReconcileSettings,
+    profiles: ProfileRegistry,
+    mapping_templates: MappingTemplates,
+    calibration: CalibrationSettings,
+    categories: CategoryList,
 }
 
 impl Application for PrintCountApp {
@@ -129,6 +159,7 @@ impl Application for PrintCountApp {
                 printer_tab: PrinterTab::Recording,
                 discovery_cidr,
                 discovery_community: "public".to_string(),
+                discovery_snmp_v3: SnmpV3Inputs::default(),
                 discovery_status,
                 discovery_active: false,
                 discovery_queue: VecDeque::new(),
@@ -142,29 +173,84 @@ impl Application for PrintCountApp {
                 manual_host: String::new(),
                 manual_port: DEFAULT_SNMP_PORT.to_string(),
                 manual_community: "public".to_string(),
+                manual_snmp_v3: SnmpV3Inputs::default(),
                 manual_status: None,
                 printers_path: "printers.ron".to_string(),
                 printers_status: None,
                 printers,
                 selected_printer: None,
                 poll_states,
-                poll_in_flight: HashSet::new(),
                 poll_export_path: "polling_export.txt".to_string(),
                 poll_export_status: None,
                 snmp_config: SnmpConfig::default(),
+                poller: Poller::new(),
+                history: HistoryStore::new(HISTORY_DIR),
+                snmp_capture: SnmpCaptureMode::Off,
+                snmp_capture_path: "snmp_capture.ron".to_string(),
+                snmp_capture_status: None,
                 counter_oids,
                 oids_path: "counter_oids.ron".to_string(),
                 oids_total_text,
                 oids_status: None,
                 oids_crawl_in_flight: false,
+                oids_crawl_progress: WalkProgress::new(),
                 recording_oids,
                 recording_sessions: HashMap::new(),
-                pricing: PricingSettings::default(),
+                completed_recordings: HashMap::new(),
+                recordings_status: None,
+                pricing: PricingSettings::from_book(
+                    PriceBook::default(),
+                    PRICING_PATH.to_string(),
+                ),
+                receipts: ReceiptSettings::from_config(ReceiptConfig::default()),
+                accounts: AccountSettings::from_book(
+                    AccountBook::default(),
+                    &Currency::default(),
+                    &MoneyFormat::default(),
+                ),
+                zreports: ZReportSettings {
+                    store: ZReportStore::new(ZREPORTS_DIR),
+                    reports: Vec::new(),
+                    status: None,
+                },
+                reconcile: ReconcileSettings {
+                    day_input: format_utc_timestamp(now_epoch_seconds())[..10].to_string(),
+                    log: ReconciliationLog::default(),
+                    report: None,
+                    note_input: String::new(),
+                    status: None,
+                },
+                profiles: ProfileRegistry::builtin(),
+                mapping_templates: MappingTemplates::default(),
+                calibration: CalibrationSettings::default(),
+                categories: CategoryList::default(),
             };
         if !app.advanced_mode {
             app.printers_path = "printers.ron".to_string();
             app.load_printers_from_path();
         }
+        if Path::new(PRICING_PATH).exists() {
+            app.load_pricing();
+        }
+        if Path::new(RECEIPTS_PATH).exists() {
+            app.load_receipts();
+        }
+        if Path::new(ACCOUNTS_PATH).exists() {
+            app.load_accounts();
+        }
+        if Path::new(RECONCILIATION_PATH).exists() {
+            app.load_reconciliation_log();
+        }
+        if Path::new(MAPPING_TEMPLATES_PATH).exists() {
+            app.load_mapping_templates();
+        }
+        if Path::new(CATEGORIES_PATH).exists() {
+            app.load_categories();
+        }
+        app.load_profiles();
+        app.load_recordings();
+        app.load_zreports();
+        app.sync_poller();
 
         (app, Command::none())
     }
@@ -174,7 +260,51 @@ impl Application for PrintCountApp {
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
-        match message {
+        // Poll targets follow the printer list, each printer's mapping and
+        // polling settings, the selection, active recordings and the SNMP
+        // client in use.
+        let resync_poller = matches!(
+            message,
+            Message::StartSnmpCapture
+                | Message::StopSnmpCapture
+                | Message::LoadSnmpReplay
+                | Message::StopSnmpReplay
+                | Message::AddManualPrinter
+                | Message::LoadPrinters
+                | Message::DiscoveryProbeFinished(_)
+                | Message::SelectPrinter(_)
+                | Message::DeleteSelectedPrinter
+                | Message::PollEnabledToggled(_)
+                | Message::PollIntervalChanged(_)
+                | Message::ApplyOids
+                | Message::ApplyProfileOids
+                | Message::SaveMappingTemplate
+                | Message::CopyMappingToModel
+                | Message::ResetPrinterMapping
+                | Message::CounterNamesRead { .. }
+                | Message::StartRecording
+                | Message::StopRecording
+                | Message::ReopenRecording(_)
+        );
+        let save_recordings = matches!(
+            message,
+            Message::StartRecording
+                | Message::StopRecording
+                | Message::RecordingStartChanged { .. }
+                | Message::RecordingEndChanged { .. }
+                | Message::RecordingToggleInclude(_)
+                | Message::RecordingResetResolved { .. }
+                | Message::ToggleConcurrentExclusion(_)
+                | Message::ReopenRecording(_)
+                | Message::IssueReceipt(_)
+                | Message::SetPayment(_)
+                | Message::ToggleVoid
+                | Message::DeleteSelectedPrinter
+        );
+        if self.edits_closed_recording(&message) {
+            return Command::none();
+        }
+        let command = match message {
             Message::LogTick => {
                 self.refresh_logs();
                 Command::none()
@@ -183,7 +313,14 @@ impl Application for PrintCountApp {
                 self.advanced_mode = !self.advanced_mode;
                 if !self.advanced_mode {
                     self.active_tab = Tab::Printers;
-                    if !matches!(self.printer_tab, PrinterTab::Recording | PrinterTab::Pricing) {
+                    if !matches!(
+                        self.printer_tab,
+                        PrinterTab::Recording
+                            | PrinterTab::Pricing
+                            | PrinterTab::Accounts
+                            | PrinterTab::Reports
+                            | PrinterTab::Reconcile
+                    ) {
                         self.printer_tab = PrinterTab::Recording;
                     }
                     self.printers_path = "printers.ron".to_string();
@@ -221,6 +358,26 @@ impl Application for PrintCountApp {
                 );
                 Command::none()
             }
+            Message::SnmpCapturePathChanged(value) => {
+                self.snmp_capture_path = value;
+                Command::none()
+            }
+            Message::StartSnmpCapture => {
+                self.start_snmp_capture();
+                Command::none()
+            }
+            Message::StopSnmpCapture => {
+                self.stop_snmp_capture();
+                Command::none()
+            }
+            Message::LoadSnmpReplay => {
+                self.load_snmp_replay();
+                Command::none()
+            }
+            Message::StopSnmpReplay => {
+                self.stop_snmp_replay();
+                Command::none()
+            }
             Message::ManualNameChanged(value) => {
                 self.manual_name = value;
                 Command::none()
@@ -237,6 +394,10 @@ impl Application for PrintCountApp {
                 self.manual_community = value;
                 Command::none()
             }
+            Message::ManualSnmpV3Changed(edit) => {
+                self.manual_snmp_v3.apply(edit);
+                Command::none()
+            }
             Message::AddManualPrinter => {
                 self.add_manual_printer();
                 Command::none()
@@ -261,6 +422,10 @@ impl Application for PrintCountApp {
                 self.discovery_community = value;
                 Command::none()
             }
+            Message::DiscoverySnmpV3Changed(edit) => {
+                self.discovery_snmp_v3.apply(edit);
+                Command::none()
+            }
             Message::StartDiscovery => self.start_discovery(),
             Message::StopDiscovery => {
                 self.stop_discovery();
@@ -275,7 +440,14 @@ impl Application for PrintCountApp {
             }
             Message::SelectPrinterTab(tab) => {
                 if self.advanced_mode
-                    || matches!(tab, PrinterTab::Recording | PrinterTab::Pricing)
+                    || matches!(
+                        tab,
+                        PrinterTab::Recording
+                            | PrinterTab::Pricing
+                            | PrinterTab::Accounts
+                            | PrinterTab::Reports
+                            | PrinterTab::Reconcile
+                    )
                 {
                     self.printer_tab = tab;
                 }
@@ -283,13 +455,52 @@ impl Application for PrintCountApp {
             }
             Message::SelectPrinter(printer_id) => {
                 self.selected_printer = Some(printer_id);
-                self.poll_selected_printer()
+                self.load_mapping_inputs();
+                self.poll_selected_printer();
+                Command::none()
             }
             Message::DeleteSelectedPrinter => {
                 self.delete_selected_printer();
                 Command::none()
             }
-            Message::PollSelectedSnmp => self.poll_selected_printer(),
+            Message::PollSelectedSnmp => {
+                self.poll_selected_printer();
+                Command::none()
+            }
+            Message::PollEnabledToggled(enabled) => {
+                if let Some(record) = self.selected_printer_mut() {
+                    record.polling.enabled = enabled;
+                }
+                Command::none()
+            }
+            Message::PollIntervalChanged(value) => {
+                let value = value.trim();
+                let interval_secs = if value.is_empty() {
+                    Some(None)
+                } else {
+                    value.parse::<u64>().ok().map(|secs| Some(secs.max(1)))
+                };
+                if let Some(interval_secs) = interval_secs
+                    && let Some(record) = self.selected_printer_mut()
+                {
+                    record.polling.interval_secs = interval_secs;
+                }
+                Command::none()
+            }
+            Message::AutoStopIdleChanged(value) => {
+                let value = value.trim();
+                let idle_secs = if value.is_empty() {
+                    Some(None)
+                } else {
+                    value.parse::<u64>().ok().map(|mins| Some(mins.max(1) * 60))
+                };
+                if let Some(idle_secs) = idle_secs
+                    && let Some(record) = self.selected_printer_mut()
+                {
+                    record.polling.auto_stop_idle_secs = idle_secs;
+                }
+                Command::none()
+            }
             Message::PollExportPathChanged(value) => {
                 self.poll_export_path = value;
                 Command::none()
@@ -298,22 +509,33 @@ impl Application for PrintCountApp {
                 self.export_poll_data();
                 Command::none()
             }
-            Message::SnmpPolled { printer_id, result } => {
-                self.poll_in_flight.remove(&printer_id);
+            Message::SnmpPolled {
+                printer_id,
+                result,
+                status,
+                last_seen,
+            } => {
+                if let Some(record) = self
+                    .printers
+                    .iter_mut()
+                    .find(|record| record.id == printer_id)
+                {
+                    record.status = status;
+                    record.last_seen = last_seen;
+                }
                 let received_at = now_epoch_seconds();
                 let mut poll_name = None;
                 let mut allow_override = false;
                 let mut sys_descr = None;
                 let state = match result {
-                    Ok(response) => {
+                    Ok(resolution) => {
+                        let varbinds = resolution.raw_varbinds;
                         let printer_name = extract_text(
-                            &response.varbinds,
+                            &varbinds,
                             &Oid::from_slice(&PRT_GENERAL_PRINTER_NAME_OID),
                         );
-                        let sys_name =
-                            extract_text(&response.varbinds, &Oid::from_slice(&SYS_NAME_OID));
-                        sys_descr =
-                            extract_text(&response.varbinds, &Oid::from_slice(&SYS_DESCR_OID));
+                        let sys_name = extract_text(&varbinds, &Oid::from_slice(&SYS_NAME_OID));
+                        sys_descr = extract_text(&varbinds, &Oid::from_slice(&SYS_DESCR_OID));
                         allow_override =
                             printer_name.is_some() || sys_name.is_some() || sys_descr.is_some();
                         poll_name = printer_name
@@ -321,7 +543,7 @@ impl Application for PrintCountApp {
                             .or_else(|| sys_descr.clone());
                         SnmpPollStatus::Ok {
                             received_at,
-                            varbinds: response.varbinds,
+                            varbinds,
                         }
                     }
                     Err(error) => SnmpPollStatus::Error {
@@ -338,8 +560,30 @@ impl Application for PrintCountApp {
                         sys_descr.as_deref(),
                     );
                 }
+                let mut printed = false;
+                if let SnmpPollStatus::Ok {
+                    received_at,
+                    varbinds,
+                } = &state
+                {
+                    printed = self.track_recording_counters(&printer_id, *received_at, varbinds);
+                }
+                // Jobs are only looked up once the session has a baseline.
+                let job_log_session = self
+                    .recording_sessions
+                    .get(&printer_id)
+                    .filter(|session| printed && session.job_baseline.is_some())
+                    .and_then(RecordingSession::started_at);
+                let command = match job_log_session {
+                    Some(started_at) => self.read_job_log(printer_id.clone(), started_at),
+                    None => Command::none(),
+                };
                 self.poll_states.insert(printer_id, state);
-                Command::none()
+                // Priced only now that the live snapshot includes this poll.
+                if self.settle_recording_accounts() {
+                    self.save_recordings();
+                }
+                command
             }
             Message::OidsPathChanged(value) => {
                 self.oids_path = value;
@@ -353,6 +597,22 @@ impl Application for PrintCountApp {
                 self.apply_oid_inputs();
                 Command::none()
             }
+            Message::ApplyProfileOids => {
+                self.apply_profile_oids();
+                Command::none()
+            }
+            Message::SaveMappingTemplate => {
+                self.save_mapping_template();
+                Command::none()
+            }
+            Message::CopyMappingToModel => {
+                self.copy_mapping_to_model();
+                Command::none()
+            }
+            Message::ResetPrinterMapping => {
+                self.reset_printer_mapping();
+                Command::none()
+            }
             Message::LoadOids => {
                 self.load_oids_from_path();
                 Command::none()
@@ -386,26 +646,45 @@ impl Application for PrintCountApp {
                 }
                 Command::none()
             }
-            Message::RecordingOidCopiesBwChanged(value) => {
-                self.recording_oids.copies_bw_input = value;
+            Message::ReadCounterNames => self.read_counter_names(),
+            Message::CounterNamesRead { printer_id, result } => {
+                self.counter_names_read(&printer_id, result);
                 Command::none()
             }
-            Message::RecordingOidCopiesColorChanged(value) => {
-                self.recording_oids.copies_color_input = value;
+            Message::CalibrationPagesChanged { category, value } => {
+                self.calibration.page_inputs.insert(category, value);
+                Command::none()
+            }
+            Message::StartCalibration => self.walk_for_calibration(CalibrationStage::Before),
+            Message::FinishCalibration => self.walk_for_calibration(CalibrationStage::After),
+            Message::CancelCalibration => {
+                self.calibration = CalibrationSettings {
+                    page_inputs: std::mem::take(&mut self.calibration.page_inputs),
+                    ..CalibrationSettings::default()
+                };
                 Command::none()
             }
-            Message::RecordingOidPrintsBwChanged(value) => {
-                self.recording_oids.prints_bw_input = value;
+            Message::CalibrationWalked {
+                printer_id,
+                stage,
+                result,
+            } => {
+                self.calibration_walked(printer_id, stage, result);
                 Command::none()
             }
-            Message::RecordingOidPrintsColorChanged(value) => {
-                self.recording_oids.prints_color_input = value;
+            Message::UseCalibration => {
+                self.use_calibration();
                 Command::none()
             }
-            Message::StartRecording => {
-                self.start_recording();
+            Message::RecordingOidChanged { category, value } => {
+                self.recording_oids.inputs.insert(category, value);
                 Command::none()
             }
+            Message::CategoryToggled { category, enabled } => {
+                self.toggle_category(category, enabled);
+                Command::none()
+            }
+            Message::StartRecording => self.start_recording(),
             Message::StopRecording => {
                 self.stop_recording();
                 Command::none()
@@ -441,34 +720,255 @@ impl Application for PrintCountApp {
                 }
                 Command::none()
             }
-            Message::PricingBwFirstChanged(value) => {
-                self.pricing.bw_first_input = value;
+            Message::RecordingResetResolved {
+                category,
+                resolution,
+            } => {
+                if let Some(printer_id) = self.selected_printer.clone() {
+                    let session = self
+                        .recording_sessions
+                        .entry(printer_id)
+                        .or_default();
+                    session.edits.category_mut(category).reset_resolution = Some(resolution);
+                }
                 Command::none()
             }
-            Message::PricingBwNextChanged(value) => {
-                self.pricing.bw_next_input = value;
+            Message::ReopenRecording(started_at) => {
+                self.reopen_recording(started_at);
                 Command::none()
             }
-            Message::PricingBwRestChanged(value) => {
-                self.pricing.bw_rest_input = value;
+            Message::PricingPathChanged(value) => {
+                self.pricing.path = value;
                 Command::none()
             }
-            Message::PricingColorChanged(value) => {
-                self.pricing.color_input = value;
+            Message::LoadPricing => {
+                self.load_pricing();
                 Command::none()
             }
-            Message::PricingRoundChanged(value) => {
-                self.pricing.round_to_half_euro = value;
+            Message::SavePricing => {
+                self.save_pricing();
                 Command::none()
             }
+            Message::PricingListChanged { field, value } => {
+                let pricing = &mut self.pricing;
+                match field {
+                    PricingListField::Currency => pricing.currency_input = value,
+                    PricingListField::CurrencyDigits => pricing.digits_input = value,
+                    PricingListField::BaseFee => pricing.base_fee_input = value,
+                    PricingListField::BaseFeeVat => pricing.base_fee_vat_input = value,
+                    PricingListField::Rounding => pricing.rounding_input = value,
+                }
+                self.apply_pricing_edits();
+                Command::none()
+            }
+            Message::PricingVatChanged { category, value } => {
+                self.pricing.vat_inputs.insert(category, value);
+                self.apply_pricing_edits();
+                Command::none()
+            }
+            Message::PricingBasisChanged(basis) => {
+                self.pricing.draft.price_basis = basis;
+                self.apply_pricing_edits();
+                Command::none()
+            }
+            Message::PricingFormatChanged(format) => {
+                if format.is_unambiguous() {
+                    self.pricing.book.set_format(format);
+                    let draft = self.pricing.draft.clone();
+                    self.pricing.set_draft(draft);
+                    self.pricing.status =
+                        Some("Number format changed; save to keep it.".to_string());
+                } else {
+                    self.pricing.status = Some(
+                        "Not applied: decimal and thousands separators must differ.".to_string(),
+                    );
+                }
+                Command::none()
+            }
+            Message::PricingRoundingModeChanged { tariff, mode } => {
+                match tariff {
+                    Some(index) => {
+                        if let Some(inputs) = self.pricing.tariffs.get_mut(index) {
+                            inputs.rounding_mode = mode;
+                        }
+                    }
+                    None => self.pricing.rounding_mode = mode,
+                }
+                self.apply_pricing_edits();
+                Command::none()
+            }
+            Message::PricingTariffChanged {
+                index,
+                field,
+                value,
+            } => {
+                if let Some(inputs) = self.pricing.tariffs.get_mut(index) {
+                    match field {
+                        PricingField::Tiers => inputs.tiers_input = value,
+                        PricingField::Minimum => inputs.minimum_input = value,
+                        PricingField::Rounding => inputs.rounding_input = value,
+                    }
+                    self.apply_pricing_edits();
+                }
+                Command::none()
+            }
+            Message::PricingAddTariff(category) => {
+                self.add_pricing_tariff(category);
+                Command::none()
+            }
+            Message::PricingEffectiveChanged(value) => {
+                self.pricing.effective_input = value;
+                Command::none()
+            }
+            Message::PublishPricing => {
+                self.publish_pricing();
+                Command::none()
+            }
+            Message::SelectPriceVersion(version) => {
+                self.pricing.selected_version = Some(version);
+                Command::none()
+            }
+            Message::DraftFromPriceVersion(version) => {
+                if let Some(entry) = self.pricing.book.version(version) {
+                    let draft = entry.price_list.clone();
+                    self.pricing.set_draft(draft);
+                    self.pricing.status = Some(format!("Draft copied from version {version}."));
+                }
+                Command::none()
+            }
+            Message::ReceiptFieldChanged { field, value } => {
+                let receipts = &mut self.receipts;
+                match field {
+                    ReceiptField::ShopName => receipts.shop_name_input = value,
+                    ReceiptField::Address => receipts.address_input = value,
+                    ReceiptField::VatId => receipts.vat_id_input = value,
+                    ReceiptField::Footer => receipts.footer_input = value,
+                    ReceiptField::Title => receipts.title_input = value,
+                    ReceiptField::NumberPrefix => receipts.prefix_input = value,
+                    ReceiptField::ReceiptPrinter => receipts.printer_input = value,
+                    ReceiptField::ReceiptColumns => receipts.columns_input = value,
+                }
+                Command::none()
+            }
+            Message::SaveReceiptSettings => {
+                self.save_receipt_settings();
+                Command::none()
+            }
+            Message::IssueReceipt(started_at) => self.issue_receipt(started_at),
+            Message::ReceiptPrinted {
+                printer_id,
+                number,
+                result,
+            } => {
+                let status = match result {
+                    Ok(()) => format!("Receipt {number} printed."),
+                    Err(error) => format!("Receipt {number} not printed: {error}"),
+                };
+                self.recording_sessions.entry(printer_id).or_default().status = Some(status);
+                Command::none()
+            }
+            Message::AccountFieldChanged { field, value } => {
+                let accounts = &mut self.accounts;
+                match field {
+                    AccountField::Name => accounts.name_input = value,
+                    AccountField::CardNumber => accounts.card_input = value,
+                    AccountField::Group => accounts.group_input = value,
+                    AccountField::TopUp => accounts.top_up_input = value,
+                    AccountField::LowBalance => accounts.low_balance_input = value,
+                    AccountField::CardLookup => accounts.lookup_input = value,
+                }
+                Command::none()
+            }
+            Message::OpenAccount => {
+                self.open_account();
+                Command::none()
+            }
+            Message::SelectAccount(account) => {
+                self.accounts.selected = account;
+                Command::none()
+            }
+            Message::SelectAccountByCard => {
+                let card = self.accounts.lookup_input.trim().to_string();
+                let found = self.accounts.book.account_by_card(&card).map(|account| account.id);
+                self.accounts.status = Some(match found {
+                    Some(id) => {
+                        self.accounts.selected = Some(id);
+                        self.accounts.lookup_input.clear();
+                        format!("Card {card} selected.")
+                    }
+                    None => format!("No account has card {card:?}."),
+                });
+                Command::none()
+            }
+            Message::TopUpAccount => {
+                self.top_up_account();
+                Command::none()
+            }
+            Message::ApplyLowBalance => {
+                self.apply_low_balance();
+                Command::none()
+            }
+            Message::SetPayment(method) => {
+                self.set_payment(method);
+                Command::none()
+            }
+            Message::ToggleVoid => {
+                self.toggle_void();
+                Command::none()
+            }
+            Message::CloseDay => {
+                self.close_day();
+                Command::none()
+            }
+            Message::ExportZReport(number) => {
+                self.export_zreport(number);
+                Command::none()
+            }
+            Message::JobLogRead {
+                printer_id,
+                started_at,
+                result,
+            } => {
+                self.job_log_read(&printer_id, started_at, result);
+                self.save_recordings();
+                Command::none()
+            }
+            Message::ToggleConcurrentExclusion(at) => {
+                self.toggle_concurrent_exclusion(at);
+                Command::none()
+            }
+            Message::ReconcileDayChanged(value) => {
+                self.reconcile.day_input = value;
+                Command::none()
+            }
+            Message::RunReconciliation => {
+                self.run_reconciliation();
+                Command::none()
+            }
+            Message::ReconcileNoteChanged(value) => {
+                self.reconcile.note_input = value;
+                Command::none()
+            }
+            Message::ResolveGap { gap, action } => {
+                self.resolve_gap(gap, action);
+                Command::none()
+            }
+        };
+        if save_recordings {
+            self.settle_recording_accounts();
+            self.save_recordings();
+        }
+        if resync_poller {
+            self.sync_poller();
         }
+        command
     }
 
     fn subscription(&self) -> Subscription<Message> {
         let log_tick = iced::time::every(Duration::from_millis(250)).map(|_| Message::LogTick);
-        let poll_tick = iced::time::every(Duration::from_secs(5)).map(|_| Message::PollSelectedSnmp);
+        let poller = poller_events(self.poller.clone(), self.history.clone());
         let delete_key = keyboard::on_key_press(delete_key_event);
-        Subscription::batch(vec![log_tick, poll_tick, delete_key])
+        Subscription::batch(vec![log_tick, poller, delete_key])
     }
 
     fn view(&self) -> Element<'_, Message> {
@@ -44,6 +44,7 @@ impl PrintCountApp {
             output.push_str(&format!("Selected printer: {}\n", selected));
         }
         output.push_str(&format!("Mock SNMP entries: {}\n", self.mock_snmp_count));
+        output.push_str(&format!("SNMP capture: {}\n", self.snmp_capture_label()));
         output.push_str(&format!(
             "Targets enabled: {}\n",
             self.sorted_targets()
@@ -52,7 +53,18 @@ impl PrintCountApp {
                 .collect::<Vec<String>>()
                 .join(", ")
         ));
-        output.push_str("Per-printer errors: none recorded yet\n");
+        output.push_str("Per-printer status:\n");
+        for record in &self.printers {
+            output.push_str(&format!("  {}: {}", record.id, self.printer_status_text(record)));
+            if let Some(transition) = self
+                .poller
+                .health(&record.id)
+                .and_then(|health| health.transitions().last().cloned())
+            {
+                output.push_str(&format!(" ({})", transition.reason));
+            }
+            output.push('\n');
+        }
         output.push_str("SNMP OIDs used: not captured yet\n");
         output.push_str("Persistence diagnostics: not captured yet\n");
         output.push_str("Recent logs:\n");
@@ -92,6 +104,11 @@ impl PrintCountApp {
             return Command::none();
         }
 
+        if let Err(error) = self.discovery_snmp_v3.credentials() {
+            self.discovery_status = Some(error);
+            return Command::none();
+        }
+
         self.discovery_run_id = self.discovery_run_id.wrapping_add(1);
         self.discovery_active = true;
         self.discovery_queue = queue;
@@ -127,7 +144,7 @@ impl PrintCountApp {
         match result.outcome {
             DiscoveryOutcome::Printer(record) => {
                 self.discovery_found = self.discovery_found.saturating_add(1);
-                self.upsert_printer(record);
+                self.upsert_printer(*record);
             }
             DiscoveryOutcome::NotPrinter => {}
             DiscoveryOutcome::Error(error) => {
@@ -165,14 +182,18 @@ impl PrintCountApp {
             let run_id = self.discovery_run_id;
             let community = self.discovery_community.trim().to_string();
             let community = (!community.is_empty()).then_some(community);
-            let config = self.snmp_config.clone();
+            let snmp_v3 = self.discovery_snmp_v3.credentials().unwrap_or(None);
+            let client = self.snmp_client(snmp_v3.clone());
 
             self.discovery_in_flight += 1;
             commands.push(Command::perform(
                 async move {
-                    let result = probe_printer(address, community, config).await;
+                    let result = probe_printer(client, address, community).await;
                     let outcome = match result {
-                        Ok(Some(record)) => DiscoveryOutcome::Printer(record),
+                        Ok(Some(mut record)) => {
+                            record.snmp_v3 = snmp_v3;
+                            DiscoveryOutcome::Printer(Box::new(record))
+                        }
                         Ok(None) => DiscoveryOutcome::NotPrinter,
                         Err(error) => DiscoveryOutcome::Error(SnmpErrorInfo {
                             summary: error.user_summary(),
@@ -206,6 +227,7 @@ impl PrintCountApp {
             existing.sys_object_id = record.sys_object_id;
             existing.snmp_address = record.snmp_address;
             existing.community = record.community;
+            existing.snmp_v3 = record.snmp_v3;
             existing.status = record.status;
             existing.last_seen = record.last_seen;
         } else {
@@ -231,7 +253,6 @@ impl PrintCountApp {
 
         self.printers.remove(index);
         self.poll_states.remove(&selected);
-        self.poll_in_flight.remove(&selected);
         self.recording_sessions.remove(&selected);
 
         if self.printers.is_empty() {
@@ -241,6 +262,104 @@ impl PrintCountApp {
 
         let new_index = index.min(self.printers.len() - 1);
         self.selected_printer = Some(self.printers[new_index].id.clone());
+        self.load_mapping_inputs();
+    }
+
+    fn snmp_client(&self, snmp_v3: Option<SnmpV3Credentials>) -> Arc<dyn SnmpClient> {
+        match &self.snmp_capture {
+            SnmpCaptureMode::Off => snmp_client_for(self.snmp_config.clone(), snmp_v3),
+            SnmpCaptureMode::Capturing(recorder) => {
+                recorder.wrap(snmp_client_for(self.snmp_config.clone(), snmp_v3))
+            }
+            SnmpCaptureMode::Replaying(replay) => replay.clone(),
+        }
+    }
+
+    fn start_snmp_capture(&mut self) {
+        let path = self.snmp_capture_path.trim().to_string();
+        if path.is_empty() {
+            self.snmp_capture_status = Some("Capture failed: path is empty.".to_string());
+            return;
+        }
+
+        let recorder = SnmpRecorder::new(&self.snmp_config).with_path(&path);
+        if let Err(error) = recorder.flush() {
+            self.snmp_capture_status = Some(format!(
+                "Capture failed: {}",
+                error.technical_detail()
+            ));
+            return;
+        }
+
+        tracing::info!(target: targets::SNMP, path = %path, "SNMP capture started");
+        self.snmp_capture = SnmpCaptureMode::Capturing(recorder);
+        self.snmp_capture_status = Some(format!("Capturing SNMP traffic to {path}."));
+    }
+
+    fn stop_snmp_capture(&mut self) {
+        let SnmpCaptureMode::Capturing(recorder) = &self.snmp_capture else {
+            return;
+        };
+
+        let count = recorder.len();
+        let path = recorder
+            .path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        self.snmp_capture_status = match recorder.flush() {
+            Ok(()) => Some(format!("Captured {count} SNMP exchanges to {path}.")),
+            Err(error) => Some(format!("Capture save failed: {}", error.technical_detail())),
+        };
+        tracing::info!(target: targets::SNMP, path = %path, count, "SNMP capture stopped");
+        self.snmp_capture = SnmpCaptureMode::Off;
+    }
+
+    fn load_snmp_replay(&mut self) {
+        let path = self.snmp_capture_path.trim().to_string();
+        if path.is_empty() {
+            self.snmp_capture_status = Some("Replay failed: path is empty.".to_string());
+            return;
+        }
+
+        match ReplaySnmpClient::load(&path) {
+            Ok(replay) => {
+                let config = replay.config();
+                self.snmp_capture_status = Some(format!(
+                    "Replaying {} SNMP exchanges from {path} (community {}, timeout {}ms, {} retries).",
+                    replay.exchanges(),
+                    config.community,
+                    config.timeout_ms,
+                    config.retries
+                ));
+                tracing::info!(target: targets::SNMP, path = %path, "SNMP replay loaded");
+                self.snmp_capture = SnmpCaptureMode::Replaying(Arc::new(replay));
+            }
+            Err(error) => {
+                self.snmp_capture_status = Some(format!(
+                    "Replay failed: {}",
+                    error.technical_detail()
+                ));
+            }
+        }
+    }
+
+    fn stop_snmp_replay(&mut self) {
+        if matches!(self.snmp_capture, SnmpCaptureMode::Replaying(_)) {
+            self.snmp_capture = SnmpCaptureMode::Off;
+            self.snmp_capture_status = Some("Replay stopped; using the network.".to_string());
+        }
+    }
+
+    fn snmp_capture_label(&self) -> String {
+        match &self.snmp_capture {
+            SnmpCaptureMode::Off => "off".to_string(),
+            SnmpCaptureMode::Capturing(recorder) => {
+                format!("capturing ({} exchanges)", recorder.len())
+            }
+            SnmpCaptureMode::Replaying(replay) => {
+                format!("replaying ({} exchanges)", replay.exchanges())
+            }
+        }
     }
 
     fn find_printer_by_host_mut(&mut self, host: &str) -> Option<&mut PrinterRecord> {
@@ -265,6 +384,14 @@ impl PrintCountApp {
             return;
         }
 
+        let snmp_v3 = match self.manual_snmp_v3.credentials() {
+            Ok(snmp_v3) => snmp_v3,
+            Err(error) => {
+                self.manual_status = Some(format!("Add failed: {error}"));
+                return;
+            }
+        };
+
         let port = if port_text.is_empty() {
             DEFAULT_SNMP_PORT
         } else {
@@ -287,6 +414,7 @@ impl PrintCountApp {
             if !community.is_empty() {
                 existing.community = Some(community);
             }
+            existing.snmp_v3 = snmp_v3;
             existing.last_seen = Some(now);
             self.manual_status = Some(format!("Updated printer {host}."));
             return;
@@ -297,6 +425,7 @@ impl PrintCountApp {
         record.model = (!name.is_empty()).then_some(name);
         record.snmp_address = Some(SnmpAddress::new(host.clone(), port));
         record.community = (!community.is_empty()).then_some(community);
+        record.snmp_v3 = snmp_v3;
         record.last_seen = Some(now);
 
         self.poll_states
@@ -348,15 +477,17 @@ impl PrintCountApp {
         }
 
         let mut should_replace = false;
-        if let Some(sys_descr) = sys_descr.map(str::trim) {
-            if !sys_descr.is_empty() && existing == sys_descr {
-                should_replace = true;
-            }
+        if let Some(sys_descr) = sys_descr.map(str::trim)
+            && !sys_descr.is_empty()
+            && existing == sys_descr
+        {
+            should_replace = true;
         }
-        if let Some(host) = record.ip_or_hostname.as_deref().map(str::trim) {
-            if !host.is_empty() && existing == host {
-                should_replace = true;
-            }
+        if let Some(host) = record.ip_or_hostname.as_deref().map(str::trim)
+            && !host.is_empty()
+            && existing == host
+        {
+            should_replace = true;
         }
 
         if should_replace && existing != name {
@@ -418,9 +549,6 @@ impl PrintCountApp {
         let selected = self.selected_printer.clone();
         self.printers = printers;
         self.poll_states.clear();
-        self.poll_in_flight.clear();
-        self.recording_sessions
-            .retain(|id, _| self.printers.iter().any(|record| &record.id == id));
 
         for record in &self.printers {
             self.poll_states
@@ -430,72 +558,111 @@ impl PrintCountApp {
         if let Some(selected) = selected {
             if self.printers.iter().any(|record| record.id == selected) {
                 self.selected_printer = Some(selected);
+                self.load_mapping_inputs();
             } else {
                 self.selected_printer = None;
             }
         }
     }
 
-    fn poll_selected_printer(&mut self) -> Command<Message> {
+    /// Asks the poller for an immediate poll of the selected printer.
+    fn poll_selected_printer(&mut self) {
         let Some(printer_id) = self.selected_printer.clone() else {
-            return Command::none();
+            return;
         };
 
-        if self.poll_in_flight.contains(&printer_id) {
-            return Command::none();
-        }
-
         let Some(record) = self.printers.iter().find(|record| record.id == printer_id) else {
-            return Command::none();
+            return;
         };
 
-        let now = now_epoch_seconds();
-        let Some(address) = record.snmp_address.clone() else {
+        if record.snmp_address.is_none() {
             self.poll_states.insert(
                 printer_id,
                 SnmpPollStatus::Error {
-                    received_at: now,
+                    received_at: now_epoch_seconds(),
                     summary: "Missing SNMP address".to_string(),
                     detail: "Printer has no SNMP address configured.".to_string(),
                 },
             );
-            return Command::none();
-        };
-
-        let mut request = SnmpRequest::new(
-            address,
-            snmp_oids(&self.counter_oids, &self.recording_oids),
-        );
-        if let Some(community) = record.community.clone() {
-            request = request.with_community(community);
+            return;
         }
 
-        let config = self.snmp_config.clone();
-        let printer_id = printer_id.clone();
-
-        self.poll_in_flight.insert(printer_id.clone());
+        self.sync_poller();
+        self.poller.poll_now(&printer_id);
         self.poll_states
-            .entry(printer_id.clone())
+            .entry(printer_id)
             .or_insert(SnmpPollStatus::Idle);
+    }
 
-        Command::perform(
-            async move {
-                let client = SnmpV2cClient::new(config);
-                match client.get(request).await {
-                    Ok(response) => Ok(response),
-                    Err(error) => Err(SnmpErrorInfo {
-                        summary: error.user_summary(),
-                        detail: error.technical_detail(),
-                    }),
-                }
-            },
-            move |result| Message::SnmpPolled { printer_id, result },
-        )
+    /// Pushes the current fleet to the poller. Printers with polling enabled,
+    /// an active recording session, or the current selection are polled on
+    /// their interval; the rest only on "Poll now".
+    fn sync_poller(&self) {
+        let default = default_counter_mapping();
+        self.poller.set_oids(
+            default.clicks.clone(),
+            &snmp_oids(&default, &CounterTable::default()),
+        );
+
+        let targets = self.printers.iter().filter_map(|record| {
+            let address = record.snmp_address.clone()?;
+            let recording = self
+                .recording_sessions
+                .get(&record.id)
+                .is_some_and(|session| session.active);
+            let selected = self.selected_printer.as_ref() == Some(&record.id);
+
+            let mut target = PollTarget::new(
+                record.id.clone(),
+                address,
+                self.snmp_client(record.snmp_v3.clone()),
+            )
+            .with_enabled(record.polling.enabled || recording || selected)
+            .with_last_known(record.status, record.last_seen);
+            let (mapping, _) = self.mapping_for(record);
+            let extra = snmp_oids(&mapping, &record.counter_table);
+            target = target.with_oids(mapping.clicks, extra);
+            if let Some(community) = record.community.clone() {
+                target = target.with_community(community);
+            }
+            if let Some(interval_secs) = record.polling.interval_secs {
+                target = target.with_interval(Duration::from_secs(interval_secs));
+            }
+            Some(target)
+        });
+        self.poller.set_targets(targets);
+    }
+
+    /// Status label, with the time of the last transition when the poller
+    /// has seen one ("Offline since 14:02 UTC").
+    fn printer_status_text(&self, record: &PrinterRecord) -> String {
+        let label = status_label(record.status);
+        match self
+            .poller
+            .health(&record.id)
+            .filter(|health| health.status() == record.status)
+            .and_then(|health| health.since())
+        {
+            Some(since) => format!("{label} since {}", format_time_of_day(since)),
+            None => label.to_string(),
+        }
+    }
+
+    fn selected_printer_record(&self) -> Option<&PrinterRecord> {
+        let selected = self.selected_printer.as_ref()?;
+        self.printers.iter().find(|record| &record.id == selected)
+    }
+
+    fn selected_printer_mut(&mut self) -> Option<&mut PrinterRecord> {
+        let selected = self.selected_printer.clone()?;
+        self.printers.iter_mut().find(|record| record.id == selected)
     }
 
-    fn start_recording(&mut self) {
+    /// Starts a session on the selected printer and reads its job log, so
+    /// jobs sent during the session can be told apart from older ones.
+    fn start_recording(&mut self) -> Command<Message> {
         let Some(printer_id) = self.selected_printer.clone() else {
-            return;
+            return Command::none();
         };
 
         let already_active = self
@@ -509,7 +676,20 @@ impl PrintCountApp {
                 .entry(printer_id.clone())
                 .or_default();
             session.status = Some("Start ignored: recording already active.".to_string());
-            return;
+            return Command::none();
+        }
+
+        let account = self.accounts.selected;
+        if let Some(id) = account
+            && self.accounts.book.balance_state(id) == Some(BalanceState::Exhausted)
+        {
+            let name = self.account_label(id);
+            let session = self
+                .recording_sessions
+                .entry(printer_id.clone())
+                .or_default();
+            session.status = Some(format!("Start refused: {name} has no credit left."));
+            return Command::none();
         }
 
         let snapshot_result = self.snapshot_for_printer(&printer_id);
@@ -521,8 +701,23 @@ impl PrintCountApp {
         match snapshot_result {
             Ok(snapshot) => {
                 session.active = true;
+                session.account = account;
+                session.charged = Money::ZERO;
                 session.start = Some(snapshot.clone());
                 session.end = None;
+                session.trackers.clear();
+                session.receipt = None;
+                session.price_version = self
+                    .pricing
+                    .book
+                    .active_at(snapshot.received_at)
+                    .map(|entry| entry.version);
+                session.last_change = Some(snapshot.clone());
+                session.auto_stopped = false;
+                session.concurrent.clear();
+                session.job_baseline = None;
+                session.new_jobs.clear();
+                observe_recording_snapshot(&printer_id, session, &snapshot);
                 session.edits.apply_start_snapshot(&snapshot);
                 session.status = Some(format!(
                     "Recording started at {}.",
@@ -531,15 +726,21 @@ impl PrintCountApp {
             }
             Err(error) => {
                 session.status = Some(format!("Start failed: {error}"));
+                return Command::none();
             }
         }
+        let started_at = session.started_at().unwrap_or_default();
+        self.read_job_log(printer_id, started_at)
     }
 
     fn stop_recording(&mut self) {
         let Some(printer_id) = self.selected_printer.clone() else {
             return;
         };
+        self.stop_recording_for(printer_id);
+    }
 
+    fn stop_recording_for(&mut self, printer_id: PrinterId) {
         let is_active = self
             .recording_sessions
             .get(&printer_id)
@@ -562,9 +763,7 @@ impl PrintCountApp {
 
         match snapshot_result {
             Ok(snapshot) => {
-                session.active = false;
-                session.end = Some(snapshot.clone());
-                session.edits.apply_end_snapshot(&snapshot);
+                end_recording(&printer_id, session, &snapshot);
                 session.status = Some(format!(
                     "Recording stopped at {}.",
                     snapshot.received_at
@@ -663,7 +862,7 @@ impl PrintCountApp {
             SnmpPollStatus::Ok {
                 received_at,
                 varbinds,
-            } => Ok(self.build_recording_snapshot(*received_at, varbinds)),
+            } => Ok(self.build_recording_snapshot(printer_id, *received_at, varbinds)),
             SnmpPollStatus::Error { summary, detail, .. } => {
                 Err(format!("{summary} ({detail})"))
             }
@@ -671,192 +870,1879 @@ impl PrintCountApp {
         }
     }
 
+    /// Reads the enabled categories' counters from a poll with the printer's
+    /// mapping, looking named counters up in its own counter table.
     fn build_recording_snapshot(
         &self,
+        printer_id: &PrinterId,
         received_at: u64,
         varbinds: &[SnmpVarBind],
     ) -> RecordingSnapshot {
-        let copies_bw_oids =
-            parse_oid_list(&self.recording_oids.copies_bw_input).unwrap_or_default();
-        let copies_color_oids =
-            parse_oid_list(&self.recording_oids.copies_color_input).unwrap_or_default();
-        let prints_bw_oids =
-            parse_oid_list(&self.recording_oids.prints_bw_input).unwrap_or_default();
-        let prints_color_oids =
-            parse_oid_list(&self.recording_oids.prints_color_input).unwrap_or_default();
-
-        let copies_bw_value = copies_bw_oids
-            .iter()
-            .find_map(|oid| extract_counter_u64(varbinds, oid));
-        let copies_color_value = copies_color_oids
-            .iter()
-            .find_map(|oid| extract_counter_u64(varbinds, oid));
-        let prints_bw_value = prints_bw_oids
-            .iter()
-            .find_map(|oid| extract_counter_u64(varbinds, oid));
-        let prints_color_value = prints_color_oids
+        let empty = CounterTable::default();
+        let record = self.printers.iter().find(|record| &record.id == printer_id);
+        let mapping = match record {
+            Some(record) => self.mapping_for(record).0,
+            None => default_counter_mapping(),
+        };
+        let table = record.map_or(&empty, |record| &record.counter_table);
+        let counters = self
+            .categories
+            .enabled()
             .iter()
-            .find_map(|oid| extract_counter_u64(varbinds, oid));
+            .filter_map(|category| {
+                let value = mapping
+                    .recording_oids(*category, table)
+                    .iter()
+                    .find_map(|oid| extract_counter_u64(varbinds, oid))?;
+                Some((*category, value))
+            })
+            .collect();
 
         RecordingSnapshot {
             received_at,
-            bw_printer: prints_bw_value,
-            bw_copier: copies_bw_value,
-            color_printer: prints_color_value,
-            color_copier: copies_color_value,
+            counters,
+            uptime: extract_uptime(varbinds),
         }
     }
 
-    fn sync_oid_inputs(&mut self) {
-        self.recording_oids = recording_oids_from_counter_set(&self.counter_oids);
-        self.oids_total_text = format_oid_list(&self.counter_oids.total);
-    }
-
-    fn apply_oid_inputs(&mut self) {
-        match self.parse_oid_inputs() {
-            Ok(set) => {
-                self.counter_oids = set;
-                self.oids_status = Some("Applied OID mapping.".to_string());
+    /// Follows an active session's counters poll by poll, so a reset between
+    /// polls is caught even if the counter later climbs back. Returns whether
+    /// print clicks arrived, so the caller can read the job log for them.
+    fn track_recording_counters(
+        &mut self,
+        printer_id: &PrinterId,
+        received_at: u64,
+        varbinds: &[SnmpVarBind],
+    ) -> bool {
+        let active = self
+            .recording_sessions
+            .get(printer_id)
+            .is_some_and(|session| session.active);
+        if !active {
+            return false;
+        }
+        let snapshot = self.build_recording_snapshot(printer_id, received_at, varbinds);
+        let idle_limit = self
+            .printers
+            .iter()
+            .find(|record| &record.id == printer_id)
+            .and_then(|record| record.polling.auto_stop_idle_secs);
+        let mut printed = false;
+        let mut changed = false;
+        let mut stopped = false;
+        if let Some(session) = self.recording_sessions.get_mut(printer_id) {
+            let pages = print_pages_since_last_poll(session, &snapshot);
+            if !pages.is_empty() {
+                printed = true;
+                let total: u64 = pages.values().sum();
+                session.status = Some(format!(
+                    "{total} print pages arrived at {} while recording; a PC job may have \
+                     landed in this session. Exclude them below if the customer did not print.",
+                    format_time_of_day(received_at)
+                ));
+                tracing::info!(
+                    target: targets::POLLING,
+                    printer_id = %printer_id,
+                    pages = total,
+                    "Print clicks during a recording"
+                );
+                session.concurrent.push(ConcurrentActivity {
+                    at: received_at,
+                    pages,
+                    excluded: false,
+                });
             }
-            Err(error) => {
-                self.oids_status = Some(format!("Apply failed: {error}"));
+            observe_recording_snapshot(printer_id, session, &snapshot);
+            let check = match &session.last_change {
+                Some(last) => check_idle(
+                    last.received_at,
+                    &last.counters,
+                    received_at,
+                    &snapshot.counters,
+                    idle_limit,
+                ),
+                None => IdleCheck::Changed {
+                    counters: snapshot.counters.clone(),
+                },
+            };
+            match check {
+                IdleCheck::Changed { counters } => {
+                    changed = true;
+                    session.last_change = Some(RecordingSnapshot {
+                        counters,
+                        ..snapshot.clone()
+                    });
+                }
+                IdleCheck::Quiet => {}
+                IdleCheck::Stop { idle_secs } => {
+                    if let Some(last) = session.last_change.clone() {
+                        end_recording(printer_id, session, &last);
+                        session.auto_stopped = true;
+                        stopped = true;
+                        session.status = Some(format!(
+                            "Recording auto-stopped: no counter changed for {}. It ends at \
+                             the last change, {}.",
+                            format_duration(idle_secs),
+                            format_utc_timestamp(last.received_at)
+                        ));
+                        tracing::info!(
+                            target: targets::POLLING,
+                            printer_id = %printer_id,
+                            ended_at = last.received_at,
+                            "Recording auto-stopped after going idle"
+                        );
+                    }
+                }
             }
         }
+        // Quiet polls leave nothing worth writing; print clicks and resets
+        // come with a counter change.
+        if changed || stopped {
+            self.save_recordings();
+        }
+        if stopped {
+            // A stopped session no longer keeps an unselected printer polled.
+            self.sync_poller();
+        }
+        printed
     }
 
-    fn parse_oid_inputs(&self) -> Result<CounterOidSet, String> {
-        let copies_bw = parse_oid_list(&self.recording_oids.copies_bw_input)
-            .map_err(|error| format!("Copies B/W OIDs: {error}"))?;
-        let prints_bw = parse_oid_list(&self.recording_oids.prints_bw_input)
-            .map_err(|error| format!("Prints B/W OIDs: {error}"))?;
-        let copies_color = parse_oid_list(&self.recording_oids.copies_color_input)
-            .map_err(|error| format!("Copies color OIDs: {error}"))?;
-        let prints_color = parse_oid_list(&self.recording_oids.prints_color_input)
-            .map_err(|error| format!("Prints color OIDs: {error}"))?;
-        let total = parse_oid_list(&self.oids_total_text)
-            .map_err(|error| format!("Total OIDs: {error}"))?;
-
-        let mut bw = copies_bw;
-        bw.extend(prints_bw);
-        let mut color = copies_color;
-        color.extend(prints_color);
-
-        Ok(CounterOidSet { bw, color, total })
+    /// Walks the printer's job log (Job Monitoring MIB) for the session that
+    /// started at `started_at`. Printers without one answer with no rows.
+    fn read_job_log(&self, printer_id: PrinterId, started_at: u64) -> Command<Message> {
+        let Some(record) = self.printers.iter().find(|record| record.id == printer_id) else {
+            return Command::none();
+        };
+        let Some(address) = record.snmp_address.clone() else {
+            return Command::none();
+        };
+        let mut request = SnmpWalkRequest::new(address, Oid::from_slice(&JM_JOB_ENTRY_OID))
+            .with_max_results(JOB_LOG_MAX_VARBINDS)
+            .with_max_repetitions(CRAWL_MAX_REPETITIONS);
+        if let Some(community) = record.community.clone() {
+            request = request.with_community(community);
+        }
+        let client = self.snmp_client(record.snmp_v3.clone());
+        Command::perform(
+            async move {
+                client
+                    .bulk_walk(request)
+                    .await
+                    .map(|response| jobs_from_walk(&response.varbinds))
+                    .map_err(|error| SnmpErrorInfo {
+                        summary: error.user_summary(),
+                        detail: error.technical_detail(),
+                    })
+            },
+            move |result| Message::JobLogRead {
+                printer_id,
+                started_at,
+                result,
+            },
+        )
     }
 
-    fn load_oids_from_path(&mut self) {
-        let path = self.oids_path.trim().to_string();
-        if path.is_empty() {
-            self.oids_status = Some("Load failed: path is empty.".to_string());
+    /// Takes the first read of a session as its baseline; later reads list
+    /// the jobs sent since.
+    fn job_log_read(
+        &mut self,
+        printer_id: &PrinterId,
+        started_at: u64,
+        result: Result<Vec<PrintJob>, SnmpErrorInfo>,
+    ) {
+        let Some(session) = self
+            .recording_sessions
+            .get_mut(printer_id)
+            .filter(|session| session.started_at() == Some(started_at))
+        else {
             return;
-        }
-
-        match fs::read_to_string(&path) {
-            Ok(contents) => match from_str::<CounterOidSet>(&contents) {
-                Ok(set) => {
-                    self.counter_oids = set;
-                    self.sync_oid_inputs();
-                    self.oids_status = Some(format!("Loaded OIDs from {path}."));
-                }
-                Err(error) => {
-                    self.oids_status = Some(format!("Load failed: {error}"));
-                }
-            },
+        };
+        let jobs = match result {
+            Ok(jobs) => jobs,
             Err(error) => {
-                self.oids_status = Some(format!("Load failed: {error}"));
+                tracing::debug!(
+                    target: targets::SNMP,
+                    printer_id = %printer_id,
+                    "Job log not read: {}",
+                    error.detail
+                );
+                return;
+            }
+        };
+        match &session.job_baseline {
+            None => {
+                session.job_baseline = Some(jobs.iter().map(|job| job.index).collect());
+            }
+            Some(baseline) => {
+                session.new_jobs = jobs
+                    .into_iter()
+                    .filter(|job| !baseline.contains(&job.index))
+                    .collect();
             }
         }
     }
 
-    fn save_oids_to_path(&mut self) {
-        let path = self.oids_path.trim().to_string();
-        if path.is_empty() {
-            self.oids_status = Some("Save failed: path is empty.".to_string());
+    fn toggle_concurrent_exclusion(&mut self, at: u64) {
+        let Some(printer_id) = self.selected_printer.clone() else {
             return;
-        }
+        };
+        let Some(session) = self.recording_sessions.get_mut(&printer_id) else {
+            return;
+        };
+        let Some(activity) = session
+            .concurrent
+            .iter_mut()
+            .find(|activity| activity.at == at)
+        else {
+            return;
+        };
+        activity.excluded = !activity.excluded;
+        let pages: u64 = activity.pages.values().sum();
+        session.status = Some(if activity.excluded {
+            format!("{pages} print pages from {} excluded.", format_time_of_day(at))
+        } else {
+            format!("{pages} print pages from {} counted again.", format_time_of_day(at))
+        });
+    }
 
-        let config = PrettyConfig::new();
-        match to_string_pretty(&self.counter_oids, config) {
-            Ok(contents) => match fs::write(&path, contents) {
-                Ok(()) => {
-                    self.oids_status = Some(format!("Saved OIDs to {path}."));
-                }
-                Err(error) => {
-                    self.oids_status = Some(format!("Save failed: {error}"));
+    /// Restores sessions saved by `save_recordings`. A file that no longer
+    /// parses is moved aside rather than overwritten by the next save.
+    fn load_recordings(&mut self) {
+        let path = Path::new(RECORDINGS_PATH);
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return,
+            Err(error) => {
+                self.recordings_status = Some(format!("Loading recordings failed: {error}"));
+                return;
+            }
+        };
+
+        match from_str::<RecordingArchive>(&contents) {
+            Ok(archive) => {
+                self.recording_sessions = archive.current;
+                self.completed_recordings = archive.completed;
+                for session in self.recording_sessions.values_mut() {
+                    if session.active {
+                        session.status = Some("Recording restored after restart.".to_string());
+                    }
                 }
-            },
+                self.pin_unversioned_recordings();
+                tracing::info!(
+                    target: targets::STORAGE,
+                    sessions = self.recording_sessions.len(),
+                    "Restored recording sessions from {RECORDINGS_PATH}"
+                );
+            }
             Err(error) => {
-                self.oids_status = Some(format!("Save failed: {error}"));
+                let aside = path.with_extension("ron.corrupt");
+                let moved = fs::rename(path, &aside);
+                tracing::warn!(
+                    target: targets::STORAGE,
+                    error = %error,
+                    moved = moved.is_ok(),
+                    "Unreadable recordings file moved to {}",
+                    aside.display()
+                );
+                self.recordings_status = Some(format!(
+                    "Loading recordings failed: {error}. The file was moved to {}.",
+                    aside.display()
+                ));
             }
         }
     }
 
-    fn crawl_oids(&mut self) -> Command<Message> {
-        if self.oids_crawl_in_flight {
-            return Command::none();
-        }
-
-        let Some(printer_id) = self.selected_printer.clone() else {
-            self.oids_status = Some("Crawl failed: select a printer first.".to_string());
-            return Command::none();
-        };
-
-        let Some(record) = self.printers.iter().find(|record| record.id == printer_id) else {
-            self.oids_status = Some("Crawl failed: selected printer missing.".to_string());
-            return Command::none();
+    /// Writes every session to disk with `write_atomic`, so a crash
+    /// mid-write leaves the previous copy intact.
+    fn save_recordings(&mut self) {
+        self.archive_completed_recordings();
+        let archive = RecordingArchive {
+            current: self.recording_sessions.clone(),
+            completed: self.completed_recordings.clone(),
         };
 
-        let Some(address) = record.snmp_address.clone() else {
-            self.oids_status = Some("Crawl failed: printer has no SNMP address.".to_string());
-            return Command::none();
+        let path = Path::new(RECORDINGS_PATH);
+        let result = to_string_pretty(&archive, PrettyConfig::new())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                write_atomic(path, contents.as_bytes()).map_err(|error| error.technical_detail())
+            });
+        self.recordings_status = match result {
+            Ok(()) => None,
+            Err(error) => {
+                tracing::warn!(
+                    target: targets::STORAGE,
+                    "Saving recordings to {RECORDINGS_PATH} failed: {error}"
+                );
+                Some(format!("Saving recordings failed: {error}"))
+            }
         };
+    }
 
-        let community = record.community.clone();
-        let config = self.snmp_config.clone();
-        self.oids_crawl_in_flight = true;
-        self.oids_status = Some("Crawling printer/vendor MIBs...".to_string());
-
-        Command::perform(
-            async move {
-                let client = SnmpV2cClient::new(config);
-                let mut varbinds = Vec::new();
-                let mut last_error = None;
-
-                for root in CRAWL_ROOTS {
-                    let mut request =
-                        SnmpWalkRequest::new(address.clone(), Oid::from_slice(root))
-                            .with_max_results(0);
-                    if let Some(ref community) = community {
-                        request = request.with_community(community.clone());
-                    }
-
-                    match client.walk(request).await {
-                        Ok(response) => varbinds.extend(response.varbinds),
-                        Err(error) => {
-                            last_error = Some(SnmpErrorInfo {
-                                summary: error.user_summary(),
-                                detail: error.technical_detail(),
-                            });
-                        }
+    /// Copies stopped sessions into the per-printer list of completed ones,
+    /// replacing the earlier copy when a reopened session was edited.
+    fn archive_completed_recordings(&mut self) {
+        for (printer_id, session) in &self.recording_sessions {
+            if session.active || session.end.is_none() {
+                continue;
+            }
+            let Some(started_at) = session.started_at() else {
+                continue;
+            };
+            let completed = self
+                .completed_recordings
+                .entry(printer_id.clone())
+                .or_default();
+            match completed
+                .iter_mut()
+                .find(|past| past.started_at() == Some(started_at))
+            {
+                Some(existing) => *existing = session.clone(),
+                None => {
+                    completed.push(session.clone());
+                    // Only sessions a Z report covers may go; the rest are
+                    // still owed to the next report.
+                    while completed.len() > MAX_COMPLETED_RECORDINGS {
+                        let Some(oldest) =
+                            completed.iter().position(|past| past.z_report.is_some())
+                        else {
+                            break;
+                        };
+                        completed.remove(oldest);
                     }
                 }
+            }
+        }
+    }
 
-                if varbinds.is_empty() {
-                    Err(last_error.unwrap_or(SnmpErrorInfo {
-                        summary: "Crawl failed.".to_string(),
-                        detail: "No OIDs returned from crawl.".to_string(),
-                    }))
-                } else {
-                    Ok(counter_oids_from_walk(&varbinds))
-                }
+    fn reopen_recording(&mut self, started_at: u64) {
+        let Some(printer_id) = self.selected_printer.clone() else {
+            return;
+        };
+        let Some(past) = self
+            .completed_recordings
+            .get(&printer_id)
+            .and_then(|completed| {
+                completed
+                    .iter()
+                    .find(|past| past.started_at() == Some(started_at))
+            })
+            .cloned()
+        else {
+            return;
+        };
+
+        let session = self.recording_sessions.entry(printer_id).or_default();
+        if session.active {
+            session.status =
+                Some("Stop the active recording before reopening a past one.".to_string());
+            return;
+        }
+        *session = past;
+        session.status = Some(format!("Reopened recording started at {started_at}."));
+    }
+
+    fn sync_oid_inputs(&mut self) {
+        self.recording_oids = recording_oids_from_counter_set(&self.counter_oids);
+        self.oids_total_text = format_oid_list(&self.counter_oids.total);
+    }
+
+    /// The printer's own mapping, else its model template, else the default.
+    fn mapping_for(&self, record: &PrinterRecord) -> (CounterMapping, MappingSource) {
+        if let Some(mapping) = &record.counter_mapping {
+            return (mapping.clone(), MappingSource::Printer);
+        }
+        match self.mapping_templates.find(record) {
+            Some(template) => (template.mapping.clone(), MappingSource::Template),
+            None => (default_counter_mapping(), MappingSource::Default),
+        }
+    }
+
+    /// Shows the selected printer's mapping in the editor.
+    fn load_mapping_inputs(&mut self) {
+        let Some(record) = self.selected_printer_record() else {
+            return;
+        };
+        let (mapping, _) = self.mapping_for(record);
+        (self.recording_oids, self.oids_total_text) = mapping_inputs(&mapping);
+        self.counter_oids = mapping.clicks;
+    }
+
+    /// Makes `mapping` the selected printer's own and saves the printer list
+    /// it is kept in.
+    fn store_printer_mapping(&mut self, mapping: CounterMapping) -> bool {
+        let Some(record) = self.selected_printer_mut() else {
+            self.oids_status = Some("Select a printer first.".to_string());
+            return false;
+        };
+        record.counter_mapping = Some(mapping);
+        self.load_mapping_inputs();
+        self.save_printers_to_path();
+        true
+    }
+
+    fn apply_oid_inputs(&mut self) {
+        match mapping_from_inputs(&self.recording_oids, &self.oids_total_text) {
+            Ok(mapping) => {
+                if self.store_printer_mapping(mapping) {
+                    self.oids_status = Some("Applied the mapping to this printer.".to_string());
+                }
+            }
+            Err(error) => {
+                self.oids_status = Some(format!("Apply failed: {error}"));
+            }
+        }
+    }
+
+    /// Saves the editor's mapping for every printer of the selected one's
+    /// model that has no mapping of its own.
+    fn save_mapping_template(&mut self) {
+        let mapping = match mapping_from_inputs(&self.recording_oids, &self.oids_total_text) {
+            Ok(mapping) => mapping,
+            Err(error) => {
+                self.oids_status = Some(format!("Save failed: {error}"));
+                return;
+            }
+        };
+        let Some(record) = self.selected_printer_record() else {
+            self.oids_status = Some("Select a printer first.".to_string());
+            return;
+        };
+        let Some(template) = MappingTemplate::for_printer(record, mapping) else {
+            self.oids_status = Some(
+                "Save failed: the printer reported no model or sysObjectID.".to_string(),
+            );
+            return;
+        };
+        self.mapping_templates.set(template);
+        self.oids_status = Some(match self.mapping_templates.save(MAPPING_TEMPLATES_PATH) {
+            Ok(()) => "Saved the mapping as the template for this model.".to_string(),
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                format!("Save failed: {}", error.user_summary())
+            }
+        });
+    }
+
+    /// Gives every other printer of the selected one's model a copy of its
+    /// current mapping.
+    fn copy_mapping_to_model(&mut self) {
+        let Some(record) = self.selected_printer_record() else {
+            self.oids_status = Some("Select a printer first.".to_string());
+            return;
+        };
+        let (mapping, _) = self.mapping_for(record);
+        let Some(model) = MappingTemplate::for_printer(record, mapping.clone()) else {
+            self.oids_status =
+                Some("Copy failed: the printer reported no model or sysObjectID.".to_string());
+            return;
+        };
+        let selected = record.id.clone();
+        let mut copied = 0;
+        for other in &mut self.printers {
+            if other.id != selected && model.matches(other) {
+                other.counter_mapping = Some(mapping.clone());
+                copied += 1;
+            }
+        }
+        if copied == 0 {
+            self.oids_status = Some("No other printers of this model.".to_string());
+            return;
+        }
+        self.save_printers_to_path();
+        self.oids_status = Some(format!(
+            "Copied the mapping to {copied} other printer{} of this model.",
+            if copied == 1 { "" } else { "s" }
+        ));
+    }
+
+    /// Drops the selected printer's own mapping so it follows its model
+    /// template or the default again.
+    fn reset_printer_mapping(&mut self) {
+        let Some(record) = self.selected_printer_mut() else {
+            self.oids_status = Some("Select a printer first.".to_string());
+            return;
+        };
+        record.counter_mapping = None;
+        self.load_mapping_inputs();
+        self.save_printers_to_path();
+        if let Some(record) = self.selected_printer_record() {
+            let (_, source) = self.mapping_for(record);
+            self.oids_status = Some(format!("The printer now uses {}.", source.label()));
+        }
+    }
+
+    fn load_mapping_templates(&mut self) {
+        match MappingTemplates::load(MAPPING_TEMPLATES_PATH) {
+            Ok(templates) => self.mapping_templates = templates,
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                self.oids_status = Some(format!("Load failed: {}", error.user_summary()));
+            }
+        }
+    }
+
+    fn load_categories(&mut self) {
+        match CategoryList::load(CATEGORIES_PATH) {
+            Ok(categories) => self.categories = categories,
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                self.pricing.status =
+                    Some(format!("Categories not loaded: {}", error.user_summary()));
+            }
+        }
+    }
+
+    /// Turns a category on or off and saves the list. A category turned on
+    /// is read from the next poll, on printers whose mapping names it.
+    fn toggle_category(&mut self, category: RecordingCategory, enabled: bool) {
+        self.categories.set_enabled(category, enabled);
+        if let Err(error) = self.categories.save(CATEGORIES_PATH) {
+            tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+            self.pricing.status = Some(format!(
+                "Categories not saved: {}",
+                error.user_summary()
+            ));
+        }
+    }
+
+    /// Adds the operator's profiles to the built-in ones. A file that does
+    /// not load is skipped so the others still apply.
+    fn load_profiles(&mut self) {
+        let errors = self.profiles.load_dir(PROFILES_DIR);
+        for error in &errors {
+            tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+        }
+        if let Some(error) = errors.first() {
+            self.oids_status = Some(format!("Profiles: {}", error.user_summary()));
+        }
+    }
+
+    /// Replaces the mapping with the counters of the selected printer's
+    /// profile.
+    fn apply_profile_oids(&mut self) {
+        let Some(record) = self.selected_printer_record() else {
+            self.oids_status = Some("Select a printer first.".to_string());
+            return;
+        };
+        let profile = self.profiles.identify_printer(record);
+        let Some(vendor) = profile.vendor else {
+            self.oids_status = Some("No profile matches this printer.".to_string());
+            return;
+        };
+        let mapping = CounterMapping {
+            recording: profile
+                .recording_oids
+                .iter()
+                .map(|(category, oids)| {
+                    (*category, oids.iter().cloned().map(CounterRef::Oid).collect())
+                })
+                .collect(),
+            clicks: profile.counter_oids,
+        };
+        if !self.store_printer_mapping(mapping) {
+            return;
+        }
+        self.oids_status = Some(format!("Applied the {vendor} profile OIDs."));
+    }
+
+    fn load_oids_from_path(&mut self) {
+        let path = self.oids_path.trim().to_string();
+        if path.is_empty() {
+            self.oids_status = Some("Load failed: path is empty.".to_string());
+            return;
+        }
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => match from_str::<CounterOidSet>(&contents) {
+                Ok(set) => {
+                    self.counter_oids = set;
+                    self.sync_oid_inputs();
+                    self.oids_status = Some(format!("Loaded OIDs from {path}."));
+                }
+                Err(error) => {
+                    self.oids_status = Some(format!("Load failed: {error}"));
+                }
+            },
+            Err(error) => {
+                self.oids_status = Some(format!("Load failed: {error}"));
+            }
+        }
+    }
+
+    fn save_oids_to_path(&mut self) {
+        let path = self.oids_path.trim().to_string();
+        if path.is_empty() {
+            self.oids_status = Some("Save failed: path is empty.".to_string());
+            return;
+        }
+
+        let config = PrettyConfig::new();
+        match to_string_pretty(&self.counter_oids, config) {
+            Ok(contents) => match fs::write(&path, contents) {
+                Ok(()) => {
+                    self.oids_status = Some(format!("Saved OIDs to {path}."));
+                }
+                Err(error) => {
+                    self.oids_status = Some(format!("Save failed: {error}"));
+                }
+            },
+            Err(error) => {
+                self.oids_status = Some(format!("Save failed: {error}"));
+            }
+        }
+    }
+
+    fn crawl_oids(&mut self) -> Command<Message> {
+        if self.oids_crawl_in_flight {
+            return Command::none();
+        }
+
+        let Some(printer_id) = self.selected_printer.clone() else {
+            self.oids_status = Some("Crawl failed: select a printer first.".to_string());
+            return Command::none();
+        };
+
+        let Some(record) = self.printers.iter().find(|record| record.id == printer_id) else {
+            self.oids_status = Some("Crawl failed: selected printer missing.".to_string());
+            return Command::none();
+        };
+
+        let Some(address) = record.snmp_address.clone() else {
+            self.oids_status = Some("Crawl failed: printer has no SNMP address.".to_string());
+            return Command::none();
+        };
+
+        let community = record.community.clone();
+        let client = self.snmp_client(record.snmp_v3.clone());
+        let progress = WalkProgress::new();
+        self.oids_crawl_progress = progress.clone();
+        self.oids_crawl_in_flight = true;
+        self.oids_status = Some("Crawling printer/vendor MIBs...".to_string());
+
+        Command::perform(
+            async move {
+                crawl_varbinds(client, address, community, progress)
+                    .await
+                    .map(|varbinds| counter_oids_from_walk(&varbinds))
             },
             Message::OidsCrawled,
         )
     }
 
-    fn counter_oids_empty(&self) -> bool {
-        self.counter_oids.bw.is_empty()
-            && self.counter_oids.color.is_empty()
-            && self.counter_oids.total.is_empty()
+    /// Walks the selected printer's engine counter table for its counter
+    /// names.
+    fn read_counter_names(&mut self) -> Command<Message> {
+        let Some(printer_id) = self.selected_printer.clone() else {
+            self.oids_status = Some("Read failed: select a printer first.".to_string());
+            return Command::none();
+        };
+        let Some(record) = self.printers.iter().find(|record| record.id == printer_id) else {
+            return Command::none();
+        };
+        let Some(address) = record.snmp_address.clone() else {
+            self.oids_status = Some("Read failed: printer has no SNMP address.".to_string());
+            return Command::none();
+        };
+        let mut request =
+            SnmpWalkRequest::new(address, Oid::from_slice(&RICOH_ENG_COUNTER_ENTRY_OID))
+                .with_max_results(COUNTER_TABLE_MAX_VARBINDS)
+                .with_max_repetitions(CRAWL_MAX_REPETITIONS);
+        if let Some(community) = record.community.clone() {
+            request = request.with_community(community);
+        }
+        let client = self.snmp_client(record.snmp_v3.clone());
+        self.oids_status = Some("Reading counter names...".to_string());
+        Command::perform(
+            async move {
+                client
+                    .bulk_walk(request)
+                    .await
+                    .map(|response| CounterTable::from_walk(&response.varbinds))
+                    .map_err(|error| SnmpErrorInfo {
+                        summary: error.user_summary(),
+                        detail: error.technical_detail(),
+                    })
+            },
+            move |result| Message::CounterNamesRead { printer_id, result },
+        )
+    }
+
+    fn counter_names_read(
+        &mut self,
+        printer_id: &PrinterId,
+        result: Result<CounterTable, SnmpErrorInfo>,
+    ) {
+        let table = match result {
+            Ok(table) if table.is_empty() => {
+                self.oids_status =
+                    Some("The printer has no engine counter table.".to_string());
+                return;
+            }
+            Ok(table) => table,
+            Err(error) => {
+                self.oids_status =
+                    Some(format!("Read failed: {} ({})", error.summary, error.detail));
+                return;
+            }
+        };
+        let Some(record) = self.printers.iter_mut().find(|record| &record.id == printer_id)
+        else {
+            return;
+        };
+        self.oids_status = Some(format!(
+            "Read {} named counters. Save the printer list to keep them.",
+            table.counters().len()
+        ));
+        record.counter_table = table;
+    }
+
+    /// Test pages per category entered for the calibration; empty counts
+    /// as none.
+    fn calibration_pages(&self) -> Result<BTreeMap<RecordingCategory, u64>, String> {
+        let mut pages = BTreeMap::new();
+        for category in self.categories.enabled() {
+            let input = self
+                .calibration
+                .page_inputs
+                .get(category)
+                .map_or("", |input| input.trim());
+            let count = if input.is_empty() {
+                0
+            } else {
+                input.parse::<u64>().map_err(|_| {
+                    format!("{}: enter a whole number of pages.", category.label())
+                })?
+            };
+            pages.insert(*category, count);
+        }
+        if pages.values().all(|count| *count == 0) {
+            return Err("Enter the pages you will make in at least one category.".to_string());
+        }
+        Ok(pages)
+    }
+
+    /// Takes the first or second calibration walk of the selected printer.
+    fn walk_for_calibration(&mut self, stage: CalibrationStage) -> Command<Message> {
+        if self.calibration.in_flight.is_some() {
+            return Command::none();
+        }
+        let Some(printer_id) = self.selected_printer.clone() else {
+            self.calibration.status = Some("Select a printer first.".to_string());
+            return Command::none();
+        };
+        let Some(record) = self.printers.iter().find(|record| record.id == printer_id) else {
+            return Command::none();
+        };
+        let Some(address) = record.snmp_address.clone() else {
+            self.calibration.status = Some("The printer has no SNMP address.".to_string());
+            return Command::none();
+        };
+        if stage == CalibrationStage::After
+            && (self.calibration.printer.as_ref() != Some(&printer_id)
+                || self.calibration.before.is_none())
+        {
+            self.calibration.status =
+                Some("Take the first walk of this printer first.".to_string());
+            return Command::none();
+        }
+        if let Err(error) = self.calibration_pages() {
+            self.calibration.status = Some(error);
+            return Command::none();
+        }
+
+        let community = record.community.clone();
+        let client = self.snmp_client(record.snmp_v3.clone());
+        let progress = WalkProgress::new();
+        self.calibration.progress = progress.clone();
+        self.calibration.in_flight = Some(stage);
+        self.calibration.status = Some(match stage {
+            CalibrationStage::Before => "Taking the first walk...".to_string(),
+            CalibrationStage::After => "Taking the second walk...".to_string(),
+        });
+        Command::perform(
+            crawl_varbinds(client, address, community, progress),
+            move |result| Message::CalibrationWalked {
+                printer_id,
+                stage,
+                result,
+            },
+        )
+    }
+
+    fn calibration_walked(
+        &mut self,
+        printer_id: PrinterId,
+        stage: CalibrationStage,
+        result: Result<Vec<SnmpVarBind>, SnmpErrorInfo>,
+    ) {
+        self.calibration.in_flight = None;
+        let varbinds = match result {
+            Ok(varbinds) => varbinds,
+            Err(error) => {
+                self.calibration.status =
+                    Some(format!("Walk failed: {} ({})", error.summary, error.detail));
+                return;
+            }
+        };
+        match stage {
+            CalibrationStage::Before => {
+                self.calibration.status = Some(format!(
+                    "First walk read {} OIDs. Make exactly the pages entered above, wait \
+                     until the printer is idle, then take the second walk.",
+                    varbinds.len()
+                ));
+                self.calibration.printer = Some(printer_id);
+                self.calibration.before = Some(varbinds);
+                self.calibration.proposal = None;
+            }
+            CalibrationStage::After => {
+                if self.calibration.printer.as_ref() != Some(&printer_id) {
+                    return;
+                }
+                let Some(before) = self.calibration.before.take() else {
+                    return;
+                };
+                let pages = match self.calibration_pages() {
+                    Ok(pages) => pages,
+                    Err(error) => {
+                        self.calibration.status = Some(error);
+                        self.calibration.before = Some(before);
+                        return;
+                    }
+                };
+                let increases = increases_between(&before, &varbinds);
+                let table = CounterTable::from_walk(&before);
+                let proposal = CalibrationProposal::rank(&increases, &pages, &table);
+                self.calibration.status = Some(if increases.is_empty() {
+                    "No counter went up. Check that the pages were made on this printer."
+                        .to_string()
+                } else {
+                    format!(
+                        "{} OIDs went up, {} by the pages of a category.",
+                        increases.len(),
+                        increases.len() - proposal.unmatched
+                    )
+                });
+                self.calibration.proposal = Some(proposal);
+            }
+        }
+    }
+
+    /// Fills the mapping editor with the best candidate of each category.
+    /// Categories without a candidate keep their input; nothing is stored
+    /// until the operator applies the mapping.
+    fn use_calibration(&mut self) {
+        let Some(proposal) = self
+            .calibration
+            .proposal
+            .as_ref()
+            .filter(|_| self.calibration.printer == self.selected_printer)
+        else {
+            return;
+        };
+        let mapping = proposal.mapping();
+        if mapping.is_empty() {
+            self.calibration.status =
+                Some("No OID went up by the pages made; nothing to use.".to_string());
+            return;
+        }
+        for (category, refs) in &mapping {
+            self.recording_oids
+                .inputs
+                .insert(*category, format_counter_refs(refs));
+        }
+        let labels: Vec<&str> = mapping.keys().map(|category| category.label()).collect();
+        self.calibration.status = Some(format!(
+            "Filled in {}. Check them, then press Apply to this printer.",
+            labels.join(", ")
+        ));
+    }
+
+    /// Sessions saved before price lists were versioned get the version
+    /// that was in effect at their start, so later publishing leaves them be.
+    fn pin_unversioned_recordings(&mut self) {
+        let book = &self.pricing.book;
+        let sessions = self
+            .recording_sessions
+            .values_mut()
+            .chain(self.completed_recordings.values_mut().flatten());
+        for session in sessions {
+            if session.price_version.is_none()
+                && let Some(started_at) = session.started_at()
+            {
+                session.price_version = book.active_at(started_at).map(|entry| entry.version);
+            }
+        }
+    }
+
+    /// The price list version a session is billed with: the one pinned at
+    /// its start, falling back to the newest when that is missing.
+    fn session_price_version(&self, session: &RecordingSession) -> Option<&PriceListVersion> {
+        let book = &self.pricing.book;
+        session
+            .price_version
+            .and_then(|version| book.version(version))
+            .or_else(|| {
+                session
+                    .started_at()
+                    .and_then(|started_at| book.active_at(started_at))
+            })
+            .or_else(|| book.latest())
+    }
+
+    fn load_pricing(&mut self) {
+        let path = self.pricing.path.trim().to_string();
+        if path.is_empty() {
+            self.pricing.status = Some("Load failed: path is empty.".to_string());
+            return;
+        }
+
+        match PriceBook::load(&path) {
+            Ok(book) => {
+                let count = book.versions().len();
+                self.pricing = PricingSettings::from_book(book, path.clone());
+                self.pricing.status =
+                    Some(format!("Loaded {count} price list versions from {path}."));
+            }
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                self.pricing.status = Some(format!("Load failed: {}", error.user_summary()));
+            }
+        }
+    }
+
+    fn save_pricing(&mut self) {
+        let path = self.pricing.path.trim().to_string();
+        if path.is_empty() {
+            self.pricing.status = Some("Save failed: path is empty.".to_string());
+            return;
+        }
+
+        self.pricing.status = match self.pricing.book.save(&path) {
+            Ok(()) => Some(format!("Saved price list versions to {path}.")),
+            Err(error) => Some(format!("Save failed: {}", error.user_summary())),
+        };
+    }
+
+    /// Adds the draft as a new version and saves the book right away, since
+    /// sessions starting from now on may be pinned to it.
+    fn publish_pricing(&mut self) {
+        let now = now_epoch_seconds();
+        let effective_from = match parse_timestamp_input(&self.pricing.effective_input) {
+            Ok(effective_from) => effective_from.unwrap_or(now),
+            Err(error) => {
+                self.pricing.status = Some(format!("Publish failed: {error}"));
+                return;
+            }
+        };
+
+        let draft = self.pricing.draft.clone();
+        let version = match self.pricing.book.publish(draft, effective_from, now) {
+            Ok(version) => version,
+            Err(error) => {
+                self.pricing.status = Some(format!("Publish failed: {}", error.user_summary()));
+                return;
+            }
+        };
+        tracing::info!(
+            target: targets::STORAGE,
+            version,
+            effective_from,
+            "Published price list version"
+        );
+        self.pricing.selected_version = Some(version);
+        self.pricing.effective_input.clear();
+
+        let path = self.pricing.path.trim().to_string();
+        let saved = if path.is_empty() {
+            Err("path is empty".to_string())
+        } else {
+            self.pricing
+                .book
+                .save(&path)
+                .map_err(|error| error.user_summary())
+        };
+        let published = format!(
+            "Published version {version}, effective {}",
+            format_utc_timestamp(effective_from)
+        );
+        self.pricing.status = Some(match saved {
+            Ok(()) => format!("{published}; saved to {path}."),
+            Err(error) => format!("{published}; not saved: {error}"),
+        });
+    }
+
+    /// Adds a free tariff for a category no tariff of the draft prices yet;
+    /// the operator then enters its tiers.
+    fn add_pricing_tariff(&mut self, category: RecordingCategory) {
+        let draft = &self.pricing.draft;
+        if draft
+            .tariffs
+            .iter()
+            .any(|tariff| tariff.categories.contains(&category))
+        {
+            return;
+        }
+        let name = if category.is_surcharge() {
+            format!("{} surcharge", category.label())
+        } else {
+            category.label().to_string()
+        };
+        let tariff = Tariff {
+            name,
+            categories: vec![category],
+            tiers: vec![PriceTier::rest(Money::ZERO)],
+            minimum: Money::ZERO,
+            rounding: None,
+        };
+        let inputs =
+            TariffInputs::from_tariff(&tariff, &draft.currency, &self.pricing.book.format());
+        self.pricing.draft.tariffs.push(tariff);
+        self.pricing.tariffs.push(inputs);
+        self.pricing.status = Some(if category.is_surcharge() {
+            format!(
+                "Added a surcharge for {}; enter what those pages cost on top of their \
+                 copies or prints price, then publish.",
+                category.label()
+            )
+        } else {
+            format!(
+                "Added a tariff for {}; enter its tiers, then publish.",
+                category.label()
+            )
+        });
+    }
+
+    /// Rebuilds the draft from the text inputs. While an input does not
+    /// parse, the last valid draft is kept.
+    fn apply_pricing_edits(&mut self) {
+        self.pricing.status = match apply_pricing_inputs(&mut self.pricing) {
+            Ok(()) => None,
+            Err(error) => Some(format!("Not applied: {error}")),
+        };
+    }
+
+    fn load_receipts(&mut self) {
+        match ReceiptConfig::load(RECEIPTS_PATH) {
+            Ok(config) => self.receipts = ReceiptSettings::from_config(config),
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                self.receipts.status = Some(format!("Load failed: {}", error.user_summary()));
+            }
+        }
+    }
+
+    /// Applies the shop inputs and saves them with the number sequence.
+    fn save_receipt_settings(&mut self) {
+        let lines = |value: &str| -> Vec<String> {
+            value
+                .split('|')
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let receipts = &mut self.receipts;
+        let title = receipts.title_input.trim();
+        if title.is_empty() {
+            receipts.status = Some("Not saved: the title is empty.".to_string());
+            return;
+        }
+        let columns = match receipts.columns_input.trim().parse::<usize>() {
+            Ok(columns) if (RECEIPT_MIN_COLUMNS..=RECEIPT_MAX_COLUMNS).contains(&columns) => {
+                columns
+            }
+            _ => {
+                receipts.status = Some(format!(
+                    "Not saved: columns must be {RECEIPT_MIN_COLUMNS} to {RECEIPT_MAX_COLUMNS}."
+                ));
+                return;
+            }
+        };
+        let config = &mut receipts.config;
+        config.escpos.columns = columns;
+        config.escpos_target = EscPosTarget::parse(&receipts.printer_input);
+        config.title = title.to_string();
+        config.number_prefix = receipts.prefix_input.trim().to_string();
+        config.shop.name = receipts.shop_name_input.trim().to_string();
+        config.shop.address_lines = lines(&receipts.address_input);
+        config.shop.footer_lines = lines(&receipts.footer_input);
+        let vat_id = receipts.vat_id_input.trim();
+        config.shop.vat_id = (!vat_id.is_empty()).then(|| vat_id.to_string());
+
+        receipts.status = Some(match config.save(RECEIPTS_PATH) {
+            Ok(()) => format!("Saved to {RECEIPTS_PATH}."),
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                format!("Save failed: {}", error.user_summary())
+            }
+        });
+    }
+
+    /// Writes the receipt of a stopped session of the selected printer as
+    /// text, HTML and PDF, and sends it to the receipt printer when one is
+    /// set. The first issue takes the next number; later ones rewrite and
+    /// reprint the same document, e.g. after a correction.
+    fn issue_receipt(&mut self, started_at: u64) -> Command<Message> {
+        let Some(printer_id) = self.selected_printer.clone() else {
+            return Command::none();
+        };
+        let current = self
+            .recording_sessions
+            .get(&printer_id)
+            .filter(|session| session.started_at() == Some(started_at));
+        let Some(session) = current
+            .or_else(|| {
+                self.completed_recordings
+                    .get(&printer_id)?
+                    .iter()
+                    .find(|past| past.started_at() == Some(started_at))
+            })
+            .cloned()
+        else {
+            return Command::none();
+        };
+
+        let session_printer = printer_id.clone();
+        let (status, command) = match self.write_receipt(&printer_id, &session) {
+            Ok((message, receipt)) => match self.receipts.config.escpos_target.clone() {
+                Some(target) => {
+                    let job = receipt.to_escpos(&self.receipts.config.escpos);
+                    let number = receipt.number.clone();
+                    let status = format!("{message} Printing on {target}...");
+                    let command = Command::perform(
+                        async move {
+                            target
+                                .send(&job, RECEIPT_PRINTER_TIMEOUT)
+                                .await
+                                .map_err(|error| {
+                                    tracing::warn!(
+                                        target: targets::UI,
+                                        "{}",
+                                        error.technical_detail()
+                                    );
+                                    error.user_summary()
+                                })
+                        },
+                        move |result| Message::ReceiptPrinted {
+                            printer_id,
+                            number,
+                            result,
+                        },
+                    );
+                    (status, Some(command))
+                }
+                None => (message, None),
+            },
+            Err(error) => (format!("Receipt not issued: {error}"), None),
+        };
+        self.recording_sessions
+            .entry(session_printer)
+            .or_default()
+            .status = Some(status);
+        command.unwrap_or_else(Command::none)
+    }
+
+    fn write_receipt(
+        &mut self,
+        printer_id: &PrinterId,
+        session: &RecordingSession,
+    ) -> Result<(String, Receipt), String> {
+        if session.active || session.end.is_none() {
+            return Err("stop the recording first.".to_string());
+        }
+        let pages = included_pages(
+            session,
+            session_categories(session, &self.categories)
+                .into_iter()
+                .map(|category| (category, category_delta(session, category, None))),
+        )
+        .ok_or_else(|| "resolve the counter resets first.".to_string())?;
+        let entry = self
+            .session_price_version(session)
+            .cloned()
+            .ok_or_else(|| "no price list published.".to_string())?;
+        let storage_error = |error: printcountpay_core::Error| {
+            tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+            error.user_summary()
+        };
+
+        let issued = match &session.receipt {
+            Some(issued) => issued.clone(),
+            None => {
+                let number = self
+                    .receipts
+                    .config
+                    .issue_number(RECEIPTS_PATH)
+                    .map_err(storage_error)?;
+                let issued = IssuedReceipt {
+                    number,
+                    issued_at: now_epoch_seconds(),
+                };
+                // Kept with the session straight away so a failed write below
+                // is retried under the same number.
+                let started_at = session.started_at();
+                let stored = self
+                    .recording_sessions
+                    .get_mut(printer_id)
+                    .into_iter()
+                    .chain(
+                        self.completed_recordings
+                            .get_mut(printer_id)
+                            .into_iter()
+                            .flatten(),
+                    );
+                for stored in stored {
+                    if stored.started_at() == started_at {
+                        stored.receipt = Some(issued.clone());
+                    }
+                }
+                issued
+            }
+        };
+
+        let receipt = Receipt::new(
+            issued.number.clone(),
+            issued.issued_at,
+            entry.price_list,
+            &pages,
+        )
+        .with_config(&self.receipts.config)
+        .with_printer(printer_id.to_string())
+        .with_session(
+            session.started_at(),
+            session.end.as_ref().map(|snapshot| snapshot.received_at),
+        )
+        .with_counters(session_categories(session, &self.categories).into_iter().map(
+            |category| {
+                let span = CounterSpan {
+                    start: category_start_value(session, category),
+                    end: category_end_value(session, category, None),
+                };
+                (category, span)
+            },
+        ))
+        .with_version(entry.version)
+        .with_format(self.pricing.book.format());
+        let paths = receipt
+            .write_documents(RECEIPTS_DIR)
+            .map_err(storage_error)?;
+        tracing::info!(
+            target: targets::STORAGE,
+            number = %issued.number,
+            "Issued receipt for {printer_id}"
+        );
+        let paths: Vec<String> = paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        let message = format!(
+            "{} {} written to {}.",
+            receipt.title,
+            issued.number,
+            paths.join(", ")
+        );
+        Ok((message, receipt))
+    }
+
+    fn load_accounts(&mut self) {
+        match AccountBook::load(ACCOUNTS_PATH) {
+            Ok(book) => {
+                let currency = self.billing_currency();
+                let format = self.pricing.book.format();
+                self.accounts = AccountSettings::from_book(book, &currency, &format);
+            }
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                self.accounts.status = Some(format!("Load failed: {}", error.user_summary()));
+            }
+        }
+    }
+
+    /// Saves the book after every change, since each one moves money.
+    fn save_accounts(&mut self) -> Result<(), String> {
+        self.accounts.book.save(ACCOUNTS_PATH).map_err(|error| {
+            tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+            error.user_summary()
+        })
+    }
+
+    /// Currency of the newest price list, which balances are kept in.
+    fn billing_currency(&self) -> Currency {
+        self.pricing
+            .book
+            .latest()
+            .map(|entry| entry.price_list.currency.clone())
+            .unwrap_or_default()
+    }
+
+    fn format_money(&self, amount: Money) -> String {
+        amount.format(&self.billing_currency(), &self.pricing.book.format())
+    }
+
+    fn account_label(&self, id: AccountId) -> String {
+        self.accounts
+            .book
+            .account(id)
+            .map(|account| account.name.clone())
+            .unwrap_or_else(|| format!("Account {id}"))
+    }
+
+    fn open_account(&mut self) {
+        let accounts = &mut self.accounts;
+        let opened = accounts.book.open_account(
+            &accounts.name_input,
+            &accounts.card_input,
+            &accounts.group_input,
+        );
+        let id = match opened {
+            Ok(id) => id,
+            Err(error) => {
+                accounts.status = Some(format!("Not added: {}", error.technical_detail()));
+                return;
+            }
+        };
+        accounts.selected = Some(id);
+        accounts.name_input.clear();
+        accounts.card_input.clear();
+        accounts.group_input.clear();
+        let name = self.account_label(id);
+        self.accounts.status = Some(match self.save_accounts() {
+            Ok(()) => format!("Added {name}."),
+            Err(error) => format!("Added {name}; not saved: {error}"),
+        });
+    }
+
+    fn top_up_account(&mut self) {
+        let Some(id) = self.accounts.selected else {
+            self.accounts.status = Some("Top-up failed: select an account first.".to_string());
+            return;
+        };
+        let currency = self.billing_currency();
+        let format = self.pricing.book.format();
+        let amount = match Money::parse(&self.accounts.top_up_input, &currency, &format) {
+            Ok(amount) => amount,
+            Err(error) => {
+                self.accounts.status = Some(format!("Top-up failed: {}", error.user_summary()));
+                return;
+            }
+        };
+        let booked = self
+            .accounts
+            .book
+            .top_up(id, amount, now_epoch_seconds(), "Top-up");
+        let balance = match booked {
+            Ok(balance) => balance,
+            Err(error) => {
+                self.accounts.status = Some(format!("Top-up failed: {}", error.technical_detail()));
+                return;
+            }
+        };
+        self.accounts.top_up_input.clear();
+        let topped_up = format!(
+            "Topped up {} by {}; balance {}",
+            self.account_label(id),
+            self.format_money(amount),
+            self.format_money(balance)
+        );
+        self.accounts.status = Some(match self.save_accounts() {
+            Ok(()) => format!("{topped_up}."),
+            Err(error) => format!("{topped_up}; not saved: {error}"),
+        });
+    }
+
+    fn apply_low_balance(&mut self) {
+        let currency = self.billing_currency();
+        let format = self.pricing.book.format();
+        let threshold = match Money::parse(&self.accounts.low_balance_input, &currency, &format) {
+            Ok(threshold) if threshold >= Money::ZERO => threshold,
+            Ok(_) => {
+                self.accounts.status =
+                    Some("Not applied: the warning threshold is negative.".to_string());
+                return;
+            }
+            Err(error) => {
+                self.accounts.status = Some(format!("Not applied: {}", error.user_summary()));
+                return;
+            }
+        };
+        self.accounts.book.set_low_balance(threshold);
+        let warning = format!("Low-balance warning below {}", self.format_money(threshold));
+        self.accounts.status = Some(match self.save_accounts() {
+            Ok(()) => format!("{warning}."),
+            Err(error) => format!("{warning}; not saved: {error}"),
+        });
+    }
+
+    /// Books what each account-backed session has cost so far, live while
+    /// it runs and again when its counters are edited after the stop. Warns
+    /// once a balance runs low and stops sessions whose credit is used up.
+    /// Returns whether anything was booked.
+    fn settle_recording_accounts(&mut self) -> bool {
+        let mut totals = Vec::new();
+        for (printer_id, session) in &self.recording_sessions {
+            let Some(account) = session.account else {
+                continue;
+            };
+            if session.z_report.is_some() {
+                continue;
+            }
+            let total = if session.voided {
+                Money::ZERO
+            } else {
+                let live = if session.active {
+                    self.snapshot_for_printer(printer_id).ok()
+                } else {
+                    None
+                };
+                let pages = included_pages(
+                    session,
+                    session_categories(session, &self.categories)
+                        .into_iter()
+                        .map(|category| {
+                            (category, category_delta(session, category, live.as_ref()))
+                        }),
+                );
+                let Some((pages, entry)) = pages.zip(self.session_price_version(session)) else {
+                    continue;
+                };
+                entry.price_list.quote(&pages).total
+            };
+            if total != session.charged {
+                totals.push((printer_id.clone(), account, total));
+            }
+        }
+        if totals.is_empty() {
+            return false;
+        }
+
+        let now = now_epoch_seconds();
+        let mut exhausted = Vec::new();
+        for (printer_id, account, total) in totals {
+            let Some(session) = self.recording_sessions.get_mut(&printer_id) else {
+                continue;
+            };
+            let Some(started_at) = session.started_at() else {
+                continue;
+            };
+            let note = format!(
+                "Recording on {printer_id} started {}",
+                format_utc_timestamp(started_at)
+            );
+            // The ledger tags entries with the session, so a settlement
+            // replayed after a crash that lost `charged` books nothing twice.
+            let booked = self.accounts.book.settle(
+                account,
+                &format!("{printer_id}@{started_at}"),
+                &mut session.charged,
+                total,
+                now,
+                &note,
+            );
+            let balance = match booked {
+                Ok(Some(balance)) => balance,
+                Ok(None) => continue,
+                Err(error) => {
+                    tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                    session.status = Some(format!("Not charged: {}", error.user_summary()));
+                    continue;
+                }
+            };
+            let active = session.active;
+            let state = self.accounts.book.balance_state(account);
+            let name = self.account_label(account);
+            let balance = self.format_money(balance);
+            let status = match state {
+                Some(BalanceState::Exhausted) if active => {
+                    exhausted.push(printer_id.clone());
+                    None
+                }
+                Some(BalanceState::Low) => Some(format!("Low balance: {name} has {balance} left.")),
+                _ => None,
+            };
+            if let Some(status) = status
+                && let Some(session) = self.recording_sessions.get_mut(&printer_id)
+            {
+                session.status = Some(status);
+            }
+        }
+        if let Err(error) = self.save_accounts() {
+            self.accounts.status = Some(format!("Saving accounts failed: {error}"));
+        }
+
+        let stopped_any = !exhausted.is_empty();
+        for printer_id in exhausted {
+            let name = self
+                .recording_sessions
+                .get(&printer_id)
+                .and_then(|session| session.account)
+                .map(|account| self.account_label(account))
+                .unwrap_or_default();
+            tracing::info!(
+                target: targets::UI,
+                "Stopping recording on {printer_id}: no credit left"
+            );
+            self.stop_recording_for(printer_id.clone());
+            if let Some(session) = self.recording_sessions.get_mut(&printer_id)
+                && !session.active
+            {
+                session.status = Some(format!("Recording stopped: {name} has no credit left."));
+            }
+        }
+        if stopped_any {
+            self.sync_poller();
+        }
+        true
+    }
+
+    /// Refuses changes to the selected session once a Z report covers it.
+    fn edits_closed_recording(&mut self, message: &Message) -> bool {
+        let edits = matches!(
+            message,
+            Message::RecordingStartChanged { .. }
+                | Message::RecordingEndChanged { .. }
+                | Message::RecordingToggleInclude(_)
+                | Message::RecordingResetResolved { .. }
+                | Message::ToggleConcurrentExclusion(_)
+                | Message::SetPayment(_)
+                | Message::ToggleVoid
+        );
+        let Some(printer_id) = self.selected_printer.as_ref().filter(|_| edits) else {
+            return false;
+        };
+        let Some(session) = self.recording_sessions.get_mut(printer_id) else {
+            return false;
+        };
+        let Some(number) = session.z_report else {
+            return false;
+        };
+        session.status = Some(format!(
+            "Closed in Z report {number}; the recording can no longer be changed."
+        ));
+        true
+    }
+
+    fn set_payment(&mut self, method: PaymentMethod) {
+        let Some(printer_id) = self.selected_printer.clone() else {
+            return;
+        };
+        let session = self.recording_sessions.entry(printer_id).or_default();
+        if session.active || session.end.is_none() {
+            session.status = Some("Stop the recording before marking it as paid.".to_string());
+            return;
+        }
+        if method == PaymentMethod::Account && session.account.is_none() {
+            session.status =
+                Some("Only recordings started against an account are paid by account.".to_string());
+            return;
+        }
+        session.payment = Some(method);
+        session.status = Some(format!("Marked as paid by {method}."));
+    }
+
+    /// Voids the selected printer's stopped session, or restores a voided
+    /// one. An account is refunded by the next settlement.
+    fn toggle_void(&mut self) {
+        let Some(printer_id) = self.selected_printer.clone() else {
+            return;
+        };
+        let session = self.recording_sessions.entry(printer_id).or_default();
+        if session.active || session.end.is_none() {
+            session.status = Some("Stop the recording before voiding it.".to_string());
+            return;
+        }
+        session.voided = !session.voided;
+        session.status = Some(if session.voided {
+            "Recording voided; it is left out of the Z report totals.".to_string()
+        } else {
+            "Recording restored.".to_string()
+        });
+    }
+
+    fn load_zreports(&mut self) {
+        match self.zreports.store.load_all() {
+            Ok(reports) => self.zreports.reports = reports,
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                self.zreports.status = Some(format!("Load failed: {}", error.user_summary()));
+                return;
+            }
+        }
+        if self.mark_zreport_sessions() {
+            self.save_recordings();
+        }
+    }
+
+    /// Marks every stored session a Z report lists with that report, and
+    /// returns whether any mark was missing. The report file is the record:
+    /// a crash after storing it but before the recordings were saved is
+    /// repaired here instead of reporting those sessions again.
+    fn mark_zreport_sessions(&mut self) -> bool {
+        let mut covered = HashMap::new();
+        for report in &self.zreports.reports {
+            for session in &report.sessions {
+                covered.insert((session.printer.clone(), session.started_at), report.number);
+            }
+        }
+        let mut marked = false;
+        let sessions = self.recording_sessions.iter_mut().chain(
+            self.completed_recordings
+                .iter_mut()
+                .flat_map(|(printer_id, completed)| {
+                    completed.iter_mut().map(move |session| (&*printer_id, session))
+                }),
+        );
+        for (printer_id, session) in sessions {
+            if session.z_report.is_some() {
+                continue;
+            }
+            let Some(started_at) = session.started_at() else {
+                continue;
+            };
+            if let Some(number) = covered.get(&(printer_id.to_string(), started_at)) {
+                session.z_report = Some(*number);
+                marked = true;
+            }
+        }
+        marked
+    }
+
+    /// Completed sessions no Z report covers yet, oldest stop first, priced
+    /// the way their receipts are.
+    fn pending_zreport_sessions(&self) -> Result<Vec<(PrinterId, ZReportSession)>, String> {
+        let mut sessions = Vec::new();
+        for (printer_id, completed) in &self.completed_recordings {
+            for session in completed {
+                if session.z_report.is_some() {
+                    continue;
+                }
+                let (Some(started_at), Some(stopped_at)) = (
+                    session.started_at(),
+                    session.end.as_ref().map(|snapshot| snapshot.received_at),
+                ) else {
+                    continue;
+                };
+                let described = || {
+                    format!(
+                        "the recording on {printer_id} started {}",
+                        format_utc_timestamp(started_at)
+                    )
+                };
+                let pages = included_pages(
+                    session,
+                    session_categories(session, &self.categories)
+                        .into_iter()
+                        .map(|category| (category, category_delta(session, category, None))),
+                )
+                .ok_or_else(|| format!("resolve the counter resets of {} first.", described()))?;
+                let entry = self
+                    .session_price_version(session)
+                    .ok_or_else(|| "no price list published.".to_string())?;
+                let quote = entry.price_list.quote(&pages);
+                sessions.push((
+                    printer_id.clone(),
+                    ZReportSession {
+                        printer: printer_id.to_string(),
+                        started_at,
+                        stopped_at,
+                        receipt: session.receipt.as_ref().map(|issued| issued.number.clone()),
+                        payment: session.payment,
+                        voided: session.voided,
+                        overridden: has_manual_override(session),
+                        pages: pages.into_iter().collect(),
+                        tariffs: quote
+                            .charges
+                            .iter()
+                            .map(|charge| (charge.tariff.clone(), charge.amount))
+                            .collect(),
+                        total: quote.total,
+                    },
+                ));
+            }
+        }
+        sessions.sort_by_key(|(_, session)| (session.stopped_at, session.started_at));
+        Ok(sessions)
+    }
+
+    /// Stores a Z report over every completed session since the last one,
+    /// exports it, and closes those sessions to further changes.
+    fn close_day(&mut self) {
+        let pending = match self.pending_zreport_sessions() {
+            Ok(pending) => pending,
+            Err(error) => {
+                self.zreports.status = Some(format!("Day not closed: {error}"));
+                return;
+            }
+        };
+        if pending.is_empty() {
+            self.zreports.status =
+                Some("Day not closed: no completed recordings since the last report.".to_string());
+            return;
+        }
+
+        let last = self.zreports.reports.last();
+        let report = ZReport {
+            number: last.map_or(1, |report| report.number + 1),
+            created_at: now_epoch_seconds(),
+            since: last.map(|report| report.created_at),
+            sessions: pending.iter().map(|(_, session)| session.clone()).collect(),
+            currency: self.billing_currency(),
+            format: self.pricing.book.format(),
+        };
+        let store = &self.zreports.store;
+        if let Err(error) = store.store(&report) {
+            tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+            self.zreports.status = Some(format!("Day not closed: {}", error.user_summary()));
+            return;
+        }
+        tracing::info!(
+            target: targets::STORAGE,
+            number = report.number,
+            sessions = report.sessions.len(),
+            "Stored Z report"
+        );
+        let exported = store.export(&report);
+        let closed = format!("{} stored in {}", report.title(), store.dir().display());
+        self.zreports.status = Some(match exported {
+            Ok(_) => format!("{closed} with text and CSV exports."),
+            Err(error) => format!("{closed}; export failed: {}", error.user_summary()),
+        });
+        self.zreports.reports.push(report);
+
+        // Saved right away rather than after the message, and repaired from
+        // the stored report on the next start if this save never happens.
+        self.mark_zreport_sessions();
+        self.save_recordings();
+    }
+
+    fn export_zreport(&mut self, number: u32) {
+        let Some(report) = self
+            .zreports
+            .reports
+            .iter()
+            .find(|report| report.number == number)
+        else {
+            return;
+        };
+        self.zreports.status = Some(match self.zreports.store.export(report) {
+            Ok(paths) => {
+                let paths: Vec<String> = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                format!("{} exported to {}.", report.title(), paths.join(", "))
+            }
+            Err(error) => format!("Export failed: {}", error.user_summary()),
+        });
+    }
+
+    fn load_reconciliation_log(&mut self) {
+        match ReconciliationLog::load(RECONCILIATION_PATH) {
+            Ok(log) => self.reconcile.log = log,
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                self.reconcile.status = Some(format!("Load failed: {}", error.user_summary()));
+            }
+        }
+    }
+
+    /// Compares the selected printer's polled counters over the typed day
+    /// with the sessions recorded on it, current one included. Sessions
+    /// count their copies and prints only, whatever categories are enabled,
+    /// because those are the pages the total counter sees.
+    fn run_reconciliation(&mut self) {
+        let Some(printer_id) = self.selected_printer.clone() else {
+            self.reconcile.status = Some("Select a printer first.".to_string());
+            return;
+        };
+        let day_start = match parse_timestamp_input(&self.reconcile.day_input) {
+            Ok(Some(day)) => day - day % SECONDS_PER_DAY,
+            Ok(None) => {
+                self.reconcile.status = Some("Enter a day as YYYY-MM-DD.".to_string());
+                return;
+            }
+            Err(error) => {
+                self.reconcile.status = Some(format!("Not reconciled: {error}."));
+                return;
+            }
+        };
+        // The day before is read too, so the first step into the day has a
+        // poll to start from.
+        let range = day_start.saturating_sub(SECONDS_PER_DAY)..day_start + SECONDS_PER_DAY;
+        let records = match self.history.query(&printer_id, range) {
+            Ok(records) => records,
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                self.reconcile.status = Some(format!("Not reconciled: {}", error.user_summary()));
+                return;
+            }
+        };
+
+        let now = now_epoch_seconds();
+        let sessions: Vec<SessionSpan> = self
+            .completed_recordings
+            .get(&printer_id)
+            .into_iter()
+            .flatten()
+            .chain(self.recording_sessions.get(&printer_id))
+            .filter_map(|session| {
+                let start = session.started_at()?;
+                let live = if session.active {
+                    self.snapshot_for_printer(&printer_id).ok()
+                } else {
+                    None
+                };
+                let end = session
+                    .end
+                    .as_ref()
+                    .map_or(now, |snapshot| snapshot.received_at);
+                let pages = RecordingCategory::DEFAULT
+                    .into_iter()
+                    .filter_map(|category| category_delta(session, category, live.as_ref()))
+                    .sum();
+                Some(SessionSpan { start, end, pages })
+            })
+            .collect();
+
+        let report = Reconciliation::build(&printer_id, day_start, &records, &sessions);
+        tracing::info!(
+            target: targets::UI,
+            printer = %printer_id,
+            counter_pages = report.counter_pages,
+            unbilled_pages = report.unbilled_pages(),
+            "Reconciled counters with recordings"
+        );
+        self.reconcile.status = Some(if records.is_empty() {
+            format!("No polls stored for {printer_id} on that day.")
+        } else {
+            format!("Reconciled {printer_id} for {}.", &format_utc_timestamp(day_start)[..10])
+        });
+        self.reconcile.report = Some(report);
+    }
+
+    /// Records what a gap of the shown report was, with the typed note.
+    fn resolve_gap(&mut self, gap: UnbilledGap, action: GapAction) {
+        let Some(report) = &self.reconcile.report else {
+            return;
+        };
+        self.reconcile.log.decide(GapDecision {
+            printer: report.printer.clone(),
+            gap,
+            action,
+            note: self.reconcile.note_input.trim().to_string(),
+            decided_at: now_epoch_seconds(),
+        });
+        let saved = self.reconcile.log.save(RECONCILIATION_PATH);
+        self.reconcile.status = Some(match saved {
+            Ok(()) => {
+                self.reconcile.note_input.clear();
+                format!("{} pages marked {}.", gap.pages, action.to_string().to_lowercase())
+            }
+            Err(error) => {
+                tracing::warn!(target: targets::STORAGE, "{}", error.technical_detail());
+                format!("Save failed: {}", error.user_summary())
+            }
+        });
     }
 }
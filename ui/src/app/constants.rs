@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 pub(crate) const SYS_DESCR_OID: [u32; 9] = [1, 3, 6, 1, 2, 1, 1, 1, 0];
 pub(crate) const SYS_OBJECT_ID_OID: [u32; 9] = [1, 3, 6, 1, 2, 1, 1, 2, 0];
 pub(crate) const SYS_NAME_OID: [u32; 9] = [1, 3, 6, 1, 2, 1, 1, 5, 0];
@@ -48,6 +50,43 @@ pub(crate) const CRAWL_ROOTS: [&[u32]; 4] = [
     &RICOH_COUNTER_ROOT,
     &RICOH_TONER_ROOT,
 ];
+// Starting GETBULK size for crawls; shrunk per printer when agents reply tooBig.
+pub(crate) const CRAWL_MAX_REPETITIONS: u32 = 40;
+/// Cells read from the engine counter table: up to 9 columns of ~150 rows.
+pub(crate) const COUNTER_TABLE_MAX_VARBINDS: usize = 2_000;
+/// Candidates listed per category after a calibration.
+pub(crate) const CALIBRATION_CANDIDATES_SHOWN: usize = 5;
+/// Rows read from a printer's job log; agents keep a few hundred jobs.
+pub(crate) const JOB_LOG_MAX_VARBINDS: usize = 4_000;
 pub(crate) const DISCOVERY_CONCURRENCY: usize = 24;
+pub(crate) const POLLER_EVENT_BUFFER: usize = 64;
+pub(crate) const HISTORY_DIR: &str = "history";
+pub(crate) const RECORDINGS_PATH: &str = "recordings.ron";
+pub(crate) const PRICING_PATH: &str = "pricing.ron";
+/// Shop details and the receipt number sequence.
+pub(crate) const RECEIPTS_PATH: &str = "receipts.ron";
+pub(crate) const RECEIPTS_DIR: &str = "receipts";
+/// Characters per line a thermal receipt printer may be set to.
+pub(crate) const RECEIPT_MIN_COLUMNS: usize = 24;
+pub(crate) const RECEIPT_MAX_COLUMNS: usize = 64;
+pub(crate) const RECEIPT_PRINTER_TIMEOUT: Duration = Duration::from_secs(10);
+/// Prepaid accounts and their ledger.
+pub(crate) const ACCOUNTS_PATH: &str = "accounts.ron";
+/// Ledger entries listed for the selected account, newest first.
+pub(crate) const MAX_LEDGER_ENTRIES_SHOWN: usize = 50;
+/// Stored end-of-day reports and their exports.
+pub(crate) const ZREPORTS_DIR: &str = "zreports";
+/// Decisions on pages printed outside any recording.
+pub(crate) const RECONCILIATION_PATH: &str = "reconciliation.ron";
+/// The categories recorded, shown and priced.
+pub(crate) const CATEGORIES_PATH: &str = "categories.ron";
+/// Counter mappings shared by every printer of one model.
+pub(crate) const MAPPING_TEMPLATES_PATH: &str = "mapping_templates.ron";
+/// Printer profiles that extend or replace the built-in ones.
+pub(crate) const PROFILES_DIR: &str = "profiles";
+/// Completed recordings kept per printer; the oldest a Z report covers are
+/// dropped first, and none that is still owed to a report.
+pub(crate) const MAX_COMPLETED_RECORDINGS: usize = 50;
+pub(crate) const HISTORY_COMPACTION_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
 pub(crate) const MAX_VARBINDS_SHOWN: usize = 200;
 pub(crate) const FALLBACK_DISCOVERY_CIDR: &str = "192.168.129.1/24";
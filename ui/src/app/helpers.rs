@@ -1,13 +1,21 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use iced::futures::SinkExt;
 use iced::keyboard;
-use iced::Color;
+use iced::{Color, Subscription};
 use printcountpay_core::{
-    CounterOidSet, Oid, PrinterRecord, PrinterStatus, SnmpVarBind,
+    billed_pages, print_pages_between, targets, utc_timestamp, CategoryList, CompactionPolicy,
+    CounterDelta, CounterMapping, CounterOidSet, CounterReading, CounterRef, CounterTable,
+    CounterTracker, Currency, HistoryEntry, HistoryStore, Money, MoneyFormat, Oid, PaymentMethod,
+    PollEvent, Poller, PriceTier, PrinterId, PrinterRecord, PrinterStatus, RecordingCategory,
+    Rounding, RoundingMode, SnmpAddress, SnmpClient, SnmpValue, SnmpVarBind, SnmpWalkRequest,
+    VatRate, WalkProgress,
 };
 
 use crate::app::constants::{
+    CRAWL_MAX_REPETITIONS, CRAWL_ROOTS, HISTORY_COMPACTION_INTERVAL, POLLER_EVENT_BUFFER,
     PRT_GENERAL_PRINTER_NAME_OID, PRT_MARKER_LIFECOUNT_1, PRT_MARKER_LIFECOUNT_2,
     PRT_MARKER_LIFECOUNT_3, RICOH_BW_COPIER_COUNT_OID, RICOH_BW_PRINTER_COUNT_OID,
     RICOH_COLOR_COPIER_COUNT_OID, RICOH_COLOR_PRINTER_COUNT_OID, RICOH_TONER_BLACK_OID,
@@ -15,8 +23,8 @@ use crate::app::constants::{
     SYS_NAME_OID, SYS_OBJECT_ID_OID, SYS_UPTIME_OID,
 };
 use crate::app::types::{
-    BwPricing, Message, PricingSettings, RecordingCategory, RecordingOidSettings, RecordingSession,
-    RecordingSnapshot,
+    Message, PricingSettings, RecordingOidSettings, RecordingSession, RecordingSnapshot,
+    SnmpErrorInfo,
 };
 
 pub(crate) fn level_color(level: tracing::Level) -> Color {
@@ -41,6 +49,72 @@ pub(crate) fn delete_key_event(
     }
 }
 
+/// Runs the fleet poller for the lifetime of the app, stores each successful
+/// snapshot in the counter history and forwards each finished poll as a
+/// message. History is compacted at startup and once a day after that.
+pub(crate) fn poller_events(poller: Poller, history: HistoryStore) -> Subscription<Message> {
+    iced::subscription::channel(
+        std::any::TypeId::of::<Poller>(),
+        POLLER_EVENT_BUFFER,
+        move |mut output| async move {
+            let (sender, mut receiver) = tokio::sync::mpsc::channel(POLLER_EVENT_BUFFER);
+            tokio::spawn(async move { poller.run(sender).await });
+            compact_history(&history);
+            let mut last_compaction = std::time::Instant::now();
+            while let Some(event) = receiver.recv().await {
+                if let Ok(resolution) = &event.result
+                    && let Err(error) = history.append(
+                        &event.printer_id,
+                        &HistoryEntry::from_resolution(resolution),
+                    )
+                {
+                    tracing::warn!(
+                        target: targets::STORAGE,
+                        printer_id = %event.printer_id.0,
+                        "Failed to store counter history: {}",
+                        error.technical_detail()
+                    );
+                }
+                if last_compaction.elapsed() >= HISTORY_COMPACTION_INTERVAL {
+                    compact_history(&history);
+                    last_compaction = std::time::Instant::now();
+                }
+                let _ = output.send(poll_event_message(event)).await;
+            }
+            std::future::pending().await
+        },
+    )
+}
+
+fn compact_history(history: &HistoryStore) {
+    match history.compact_all(now_epoch_seconds(), &CompactionPolicy::default()) {
+        Ok(report) if report.records_before != report.records_after => tracing::info!(
+            target: targets::STORAGE,
+            "Compacted counter history from {} to {} records",
+            report.records_before,
+            report.records_after
+        ),
+        Ok(_) => {}
+        Err(error) => tracing::warn!(
+            target: targets::STORAGE,
+            "Failed to compact counter history: {}",
+            error.technical_detail()
+        ),
+    }
+}
+
+fn poll_event_message(event: PollEvent) -> Message {
+    Message::SnmpPolled {
+        printer_id: event.printer_id,
+        result: event.result.map_err(|error| SnmpErrorInfo {
+            summary: error.user_summary(),
+            detail: error.technical_detail(),
+        }),
+        status: event.status,
+        last_seen: event.last_seen,
+    }
+}
+
 pub(crate) fn status_label(status: PrinterStatus) -> &'static str {
     match status {
         PrinterStatus::Unknown => "Unknown",
@@ -50,6 +124,29 @@ pub(crate) fn status_label(status: PrinterStatus) -> &'static str {
     }
 }
 
+/// "HH:MM UTC" for status lines such as "Offline since 14:02 UTC".
+pub(crate) fn format_time_of_day(epoch_seconds: u64) -> String {
+    let seconds_of_day = epoch_seconds % 86_400;
+    format!(
+        "{:02}:{:02} UTC",
+        seconds_of_day / 3_600,
+        (seconds_of_day % 3_600) / 60
+    )
+}
+
+/// "45 seconds", "30 minutes" or "1 minute 30 seconds".
+pub(crate) fn format_duration(secs: u64) -> String {
+    let count = |value: u64, unit: &str| match value {
+        1 => format!("1 {unit}"),
+        value => format!("{value} {unit}s"),
+    };
+    match (secs / 60, secs % 60) {
+        (0, seconds) => count(seconds, "second"),
+        (minutes, 0) => count(minutes, "minute"),
+        (minutes, seconds) => format!("{} {}", count(minutes, "minute"), count(seconds, "second")),
+    }
+}
+
 pub(crate) fn now_epoch_seconds() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -57,6 +154,31 @@ pub(crate) fn now_epoch_seconds() -> u64 {
         .unwrap_or(0)
 }
 
+/// Parses "YYYY-MM-DD" or "YYYY-MM-DD HH:MM" as UTC. Empty input is `None`.
+pub(crate) fn parse_timestamp_input(value: &str) -> Result<Option<u64>, String> {
+    let value = value.trim().trim_end_matches("UTC").trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || format!("invalid date {value:?}, expected YYYY-MM-DD [HH:MM]");
+    let (date, time) = value.split_once(' ').unwrap_or((value, "00:00"));
+    let mut date_parts = date.split('-').map(str::parse::<u64>);
+    let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day)), None) = (
+        date_parts.next(),
+        date_parts.next(),
+        date_parts.next(),
+        date_parts.next(),
+    ) else {
+        return Err(invalid());
+    };
+    let (hours, minutes) = time.trim().split_once(':').ok_or_else(invalid)?;
+    let hours: u64 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u64 = minutes.parse().map_err(|_| invalid())?;
+    utc_timestamp(year, month, day, hours, minutes)
+        .map(Some)
+        .ok_or_else(invalid)
+}
+
 pub(crate) fn default_counter_oids() -> CounterOidSet {
     CounterOidSet {
         bw: vec![
@@ -74,12 +196,75 @@ pub(crate) fn default_counter_oids() -> CounterOidSet {
 }
 
 pub(crate) fn default_recording_oid_inputs() -> RecordingOidSettings {
+    let inputs = [
+        (RecordingCategory::CopiesBw, &RICOH_BW_COPIER_COUNT_OID),
+        (RecordingCategory::CopiesColor, &RICOH_COLOR_COPIER_COUNT_OID),
+        (RecordingCategory::PrintsBw, &RICOH_BW_PRINTER_COUNT_OID),
+        (RecordingCategory::PrintsColor, &RICOH_COLOR_PRINTER_COUNT_OID),
+    ];
     RecordingOidSettings {
-        copies_bw_input: Oid::from_slice(&RICOH_BW_COPIER_COUNT_OID).to_string(),
-        copies_color_input: Oid::from_slice(&RICOH_COLOR_COPIER_COUNT_OID).to_string(),
-        prints_bw_input: Oid::from_slice(&RICOH_BW_PRINTER_COUNT_OID).to_string(),
-        prints_color_input: Oid::from_slice(&RICOH_COLOR_PRINTER_COUNT_OID).to_string(),
+        inputs: inputs
+            .into_iter()
+            .map(|(category, oid)| (category, Oid::from_slice(oid).to_string()))
+            .collect(),
+    }
+}
+
+/// What printers read until they get their own mapping or a model template.
+pub(crate) fn default_counter_mapping() -> CounterMapping {
+    let inputs = default_recording_oid_inputs();
+    let total = format_oid_list(&default_counter_oids().total);
+    let mut mapping = mapping_from_inputs(&inputs, &total).unwrap_or_default();
+    mapping.clicks = default_counter_oids();
+    mapping
+}
+
+/// Builds a mapping from the editor. Named counters differ per printer, so
+/// only fixed OIDs of the copies and prints categories count towards the
+/// B/W and color clicks.
+pub(crate) fn mapping_from_inputs(
+    inputs: &RecordingOidSettings,
+    total_text: &str,
+) -> Result<CounterMapping, String> {
+    let mut mapping = CounterMapping::default();
+    for (category, input) in &inputs.inputs {
+        let refs = parse_counter_refs(input)
+            .map_err(|error| format!("{} OIDs: {error}", category.label()))?;
+        if category.is_base() {
+            let fixed = resolve_counter_refs(&refs, &CounterTable::default());
+            if category.is_color() {
+                mapping.clicks.color.extend(fixed);
+            } else {
+                mapping.clicks.bw.extend(fixed);
+            }
+        }
+        if !refs.is_empty() {
+            mapping.recording.insert(*category, refs);
+        }
     }
+    mapping.clicks.total =
+        parse_oid_list(total_text).map_err(|error| format!("Total OIDs: {error}"))?;
+    Ok(mapping)
+}
+
+/// The editor inputs showing `mapping`.
+pub(crate) fn mapping_inputs(mapping: &CounterMapping) -> (RecordingOidSettings, String) {
+    let inputs = RecordingOidSettings {
+        inputs: mapping
+            .recording
+            .iter()
+            .map(|(category, refs)| (*category, format_counter_refs(refs)))
+            .collect(),
+    };
+    (inputs, format_oid_list(&mapping.clicks.total))
+}
+
+/// Counters as the mapping inputs take them, separated by commas.
+pub(crate) fn format_counter_refs(refs: &[CounterRef]) -> String {
+    refs.iter()
+        .map(|counter| counter.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 pub(crate) fn recording_oids_from_counter_set(
@@ -110,11 +295,17 @@ pub(crate) fn recording_oids_from_counter_set(
         }
     }
 
+    let inputs = [
+        (RecordingCategory::CopiesBw, copies_bw),
+        (RecordingCategory::CopiesColor, copies_color),
+        (RecordingCategory::PrintsBw, prints_bw),
+        (RecordingCategory::PrintsColor, prints_color),
+    ];
     RecordingOidSettings {
-        copies_bw_input: format_oid_list(&copies_bw),
-        copies_color_input: format_oid_list(&copies_color),
-        prints_bw_input: format_oid_list(&prints_bw),
-        prints_color_input: format_oid_list(&prints_color),
+        inputs: inputs
+            .into_iter()
+            .map(|(category, oids)| (category, format_oid_list(&oids)))
+            .collect(),
     }
 }
 
@@ -142,6 +333,45 @@ pub(crate) fn parse_oid_list(value: &str) -> Result<Vec<Oid>, String> {
 }
 
 
+/// Parses a mapping input: dotted OIDs and double-quoted counter names,
+/// separated by commas or spaces.
+pub(crate) fn parse_counter_refs(value: &str) -> Result<Vec<CounterRef>, String> {
+    let mut refs = Vec::new();
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches(|ch: char| ch == ',' || ch.is_whitespace());
+        if rest.is_empty() {
+            return Ok(refs);
+        }
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let Some(end) = quoted.find('"') else {
+                return Err(format!("unclosed quote in '{rest}'"));
+            };
+            let name = quoted[..end].trim();
+            if name.is_empty() {
+                return Err("empty counter name".to_string());
+            }
+            refs.push(CounterRef::Named(name.to_string()));
+            rest = &quoted[end + 1..];
+        } else {
+            let end = rest
+                .find(|ch: char| ch == ',' || ch == '"' || ch.is_whitespace())
+                .unwrap_or(rest.len());
+            let token = &rest[..end];
+            let oid = token
+                .parse::<Oid>()
+                .map_err(|error| format!("invalid OID '{token}': {error}"))?;
+            refs.push(CounterRef::Oid(oid));
+            rest = &rest[end..];
+        }
+    }
+}
+
+/// OIDs of `refs` on a printer with `table`, skipping names it lacks.
+pub(crate) fn resolve_counter_refs(refs: &[CounterRef], table: &CounterTable) -> Vec<Oid> {
+    refs.iter().filter_map(|counter| counter.resolve(table)).collect()
+}
+
 pub(crate) fn extract_text(varbinds: &[SnmpVarBind], oid: &Oid) -> Option<String> {
     let varbind = varbinds.iter().find(|varbind| varbind.oid == *oid)?;
     if varbind.value.is_missing() {
@@ -178,37 +408,18 @@ pub(crate) fn extract_counter_u64(varbinds: &[SnmpVarBind], oid: &Oid) -> Option
     varbind.value.as_u64()
 }
 
-pub(crate) fn delta_value(start: Option<u64>, end: Option<u64>) -> Option<u64> {
-    let start = start?;
-    let end = end?;
-    end.checked_sub(start)
+pub(crate) fn extract_uptime(varbinds: &[SnmpVarBind]) -> Option<u64> {
+    let oid = Oid::from_slice(&SYS_UPTIME_OID);
+    match varbinds.iter().find(|varbind| varbind.oid == oid)?.value {
+        SnmpValue::Timeticks(ticks) => Some(u64::from(ticks)),
+        _ => None,
+    }
 }
 
 pub(crate) fn sum_two(left: Option<u64>, right: Option<u64>) -> Option<u64> {
     Some(left? + right?)
 }
 
-pub(crate) fn bw_cost_cents(count: u64, pricing: BwPricing) -> u64 {
-    let first = count.min(5);
-    let second = count.saturating_sub(5).min(5);
-    let rest = count.saturating_sub(10);
-    first * pricing.first_cents + second * pricing.next_cents + rest * pricing.rest_cents
-}
-
-pub(crate) fn color_cost_cents(count: u64, price_cents: u64) -> u64 {
-    count * price_cents
-}
-
-pub(crate) fn round_to_nearest_50_cents(total_cents: u64) -> u64 {
-    (total_cents + 25) / 50 * 50
-}
-
-pub(crate) fn format_cents(cents: u64) -> String {
-    let euros = cents / 100;
-    let remainder = cents % 100;
-    format!("{euros}.{remainder:02} EUR")
-}
-
 pub(crate) fn format_count(value: Option<u64>) -> String {
     value.map(|value| value.to_string()).unwrap_or_else(|| "N/A".to_string())
 }
@@ -221,44 +432,208 @@ pub(crate) fn parse_count_input(value: &str) -> Result<Option<u64>, ()> {
     trimmed.parse::<u64>().map(Some).map_err(|_| ())
 }
 
-pub(crate) fn parse_price_input(value: &str) -> Result<Option<u64>, ()> {
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
+/// "+0.13 EUR" or "-0.12 EUR", for what rounding changed.
+pub(crate) fn format_signed_money(
+    amount: Money,
+    currency: &Currency,
+    format: &MoneyFormat,
+) -> String {
+    let sign = if amount < Money::ZERO { "" } else { "+" };
+    format!("{sign}{}", amount.format(currency, format))
+}
+
+/// Parses a non-negative amount; empty input is `None`.
+pub(crate) fn parse_money_input(
+    value: &str,
+    currency: &Currency,
+    format: &MoneyFormat,
+) -> Result<Option<Money>, String> {
+    if value.trim().is_empty() {
         return Ok(None);
     }
-    let normalized = trimmed.replace(',', ".");
-    let parsed = normalized.parse::<f64>().map_err(|_| ())?;
-    if parsed < 0.0 {
-        return Err(());
+    let amount =
+        Money::parse(value, currency, format).map_err(|error| error.technical_detail())?;
+    if amount < Money::ZERO {
+        return Err(format!("{value:?} is negative"));
     }
-    Ok(Some((parsed * 100.0).round() as u64))
+    Ok(Some(amount))
 }
 
-pub(crate) fn bw_pricing_from_settings(settings: &PricingSettings) -> Option<BwPricing> {
-    let first = parse_price_input(&settings.bw_first_input).ok().flatten()?;
-    let next = parse_price_input(&settings.bw_next_input).ok().flatten()?;
-    let rest = parse_price_input(&settings.bw_rest_input).ok().flatten()?;
-    Some(BwPricing {
-        first_cents: first,
-        next_cents: next,
-        rest_cents: rest,
-    })
+/// Rounding step as an amount, empty when there is none.
+pub(crate) fn format_rounding_input(
+    rounding: Option<Rounding>,
+    currency: &Currency,
+    format: &MoneyFormat,
+) -> String {
+    match rounding {
+        Some(rounding) if rounding.step > Money::from_minor(1) => {
+            rounding.step.format_amount(currency, format)
+        }
+        _ => String::new(),
+    }
 }
 
-pub(crate) fn color_price_from_settings(settings: &PricingSettings) -> Option<u64> {
-    parse_price_input(&settings.color_input).ok().flatten()
+pub(crate) fn parse_rounding_input(
+    value: &str,
+    mode: RoundingMode,
+    currency: &Currency,
+    format: &MoneyFormat,
+) -> Result<Option<Rounding>, String> {
+    let step = parse_money_input(value, currency, format)
+        .map_err(|error| format!("rounding step: {error}"))?;
+    Ok(step
+        .filter(|step| *step > Money::from_minor(1))
+        .map(|step| Rounding { step, mode }))
 }
 
-pub(crate) fn snapshot_category_value(
-    snapshot: &RecordingSnapshot,
-    category: RecordingCategory,
-) -> Option<u64> {
-    match category {
-        RecordingCategory::CopiesBw => snapshot.bw_copier,
-        RecordingCategory::CopiesColor => snapshot.color_copier,
-        RecordingCategory::PrintsBw => snapshot.bw_printer,
-        RecordingCategory::PrintsColor => snapshot.color_printer,
+/// "19" for 19%, empty for none.
+pub(crate) fn format_vat_input(rate: VatRate) -> String {
+    if rate == VatRate::ZERO {
+        return String::new();
+    }
+    rate.to_string().trim_end_matches('%').to_string()
+}
+
+pub(crate) fn parse_vat_input(value: &str) -> Result<VatRate, String> {
+    if value.trim().is_empty() {
+        return Ok(VatRate::ZERO);
+    }
+    VatRate::parse(value).map_err(|error| error.technical_detail())
+}
+
+/// Tiers are separated by ";" whenever "," may appear inside an amount.
+pub(crate) fn tier_separator(format: &MoneyFormat) -> &'static str {
+    let comma = Some(',');
+    if format.decimal.as_char() == ',' || format.thousands.as_char() == comma {
+        "; "
+    } else {
+        ", "
+    }
+}
+
+/// Tiers as "5 @ 0.25, 10 @ 0.10, rest @ 0.06": the last page of each tier
+/// and its unit price.
+pub(crate) fn format_tiers_input(
+    tiers: &[PriceTier],
+    currency: &Currency,
+    format: &MoneyFormat,
+) -> String {
+    tiers
+        .iter()
+        .map(|tier| {
+            let price = tier.unit_price.format_amount(currency, format);
+            match tier.up_to {
+                Some(up_to) => format!("{up_to} @ {price}"),
+                None => format!("rest @ {price}"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(tier_separator(format))
+}
+
+pub(crate) fn parse_tiers_input(
+    value: &str,
+    currency: &Currency,
+    format: &MoneyFormat,
+) -> Result<Vec<PriceTier>, String> {
+    let separator = tier_separator(format).trim();
+    value
+        .split(|ch| ch == ';' || separator == "," && ch == ',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (pages, price) = part
+                .split_once('@')
+                .ok_or_else(|| format!("tier {part:?} needs pages @ price"))?;
+            let unit_price = parse_money_input(price, currency, format)?
+                .ok_or_else(|| format!("tier {part:?} has no price"))?;
+            let pages = pages.trim();
+            if pages.eq_ignore_ascii_case("rest") {
+                return Ok(PriceTier::rest(unit_price));
+            }
+            let up_to = pages
+                .parse::<u64>()
+                .map_err(|_| format!("invalid page count in tier {part:?}"))?;
+            Ok(PriceTier::up_to(up_to, unit_price))
+        })
+        .collect()
+}
+
+/// Rebuilds the draft from the pricing inputs. Returns an error message and
+/// leaves the draft unchanged when any input is invalid.
+pub(crate) fn apply_pricing_inputs(settings: &mut PricingSettings) -> Result<(), String> {
+    let format = settings.book.format();
+    let mut list = settings.draft.clone();
+    let digits = settings
+        .digits_input
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| format!("invalid decimal places {:?}", settings.digits_input))?;
+    list.currency = Currency::new(settings.currency_input.trim().to_uppercase(), digits);
+    let currency = list.currency.clone();
+
+    list.base_fee = parse_money_input(&settings.base_fee_input, &currency, &format)
+        .map_err(|error| format!("base fee: {error}"))?
+        .unwrap_or(Money::ZERO);
+    list.base_fee_vat = parse_vat_input(&settings.base_fee_vat_input)
+        .map_err(|error| format!("base fee VAT: {error}"))?;
+    list.rounding = parse_rounding_input(
+        &settings.rounding_input,
+        settings.rounding_mode,
+        &currency,
+        &format,
+    )?;
+    list.vat_rates.clear();
+    for (category, value) in &settings.vat_inputs {
+        let rate =
+            parse_vat_input(value).map_err(|error| format!("{} VAT: {error}", category.label()))?;
+        if rate != VatRate::ZERO {
+            list.vat_rates.insert(*category, rate);
+        }
+    }
+    for (tariff, inputs) in list.tariffs.iter_mut().zip(&settings.tariffs) {
+        tariff.tiers = parse_tiers_input(&inputs.tiers_input, &currency, &format)
+            .map_err(|error| format!("{}: {error}", tariff.name))?;
+        tariff.minimum = parse_money_input(&inputs.minimum_input, &currency, &format)
+            .map_err(|error| format!("{} minimum: {error}", tariff.name))?
+            .unwrap_or(Money::ZERO);
+        tariff.rounding =
+            parse_rounding_input(&inputs.rounding_input, inputs.rounding_mode, &currency, &format)
+                .map_err(|error| format!("{}: {error}", tariff.name))?;
+    }
+    list.validate().map_err(|error| error.technical_detail())?;
+    settings.draft = list;
+    Ok(())
+}
+
+/// Categories a session counts: the enabled copies and prints categories,
+/// and any other category it has a start value for.
+pub(crate) fn session_categories(
+    session: &RecordingSession,
+    categories: &CategoryList,
+) -> Vec<RecordingCategory> {
+    RecordingCategory::ALL
+        .into_iter()
+        .filter(|category| {
+            (category.is_base() && categories.is_enabled(*category))
+                || category_start_value(session, *category).is_some()
+        })
+        .collect()
+}
+
+/// Pages per category for pricing, or `None` while any included category
+/// has no delta yet.
+pub(crate) fn included_pages(
+    session: &RecordingSession,
+    deltas: impl IntoIterator<Item = (RecordingCategory, Option<u64>)>,
+) -> Option<HashMap<RecordingCategory, u64>> {
+    let mut pages = HashMap::new();
+    for (category, delta) in deltas {
+        if session.edits.category(category).include_in_price {
+            pages.insert(category, delta?);
+        }
     }
+    Some(pages)
 }
 
 pub(crate) fn category_start_value(
@@ -271,7 +646,7 @@ pub(crate) fn category_start_value(
         Ok(None) => session
             .start
             .as_ref()
-            .and_then(|snapshot| snapshot_category_value(snapshot, category)),
+            .and_then(|snapshot| snapshot.value(category)),
         Err(()) => None,
     }
 }
@@ -287,28 +662,167 @@ pub(crate) fn category_end_value(
         Ok(None) => session
             .end
             .as_ref()
-            .and_then(|snapshot| snapshot_category_value(snapshot, category))
-            .or_else(|| fallback.and_then(|snapshot| snapshot_category_value(snapshot, category))),
+            .and_then(|snapshot| snapshot.value(category))
+            .or_else(|| fallback.and_then(|snapshot| snapshot.value(category))),
         Err(()) => None,
     }
 }
 
-pub(crate) fn sum_optional_included(
-    values: impl IntoIterator<Item = (bool, Option<u64>)>,
-) -> Option<u64> {
-    let mut total = 0u64;
-    let mut included_any = false;
-    for (included, value) in values {
-        if !included {
+/// Whether a counter value was typed over the polled one, a category was
+/// left out of the price or print clicks were excluded, as counted in the
+/// Z report.
+pub(crate) fn has_manual_override(session: &RecordingSession) -> bool {
+    RecordingCategory::ALL.into_iter().any(|category| {
+        let edits = session.edits.category(category);
+        let typed_over = |input: &str, snapshot: Option<&RecordingSnapshot>| {
+            let polled = snapshot.and_then(|snapshot| snapshot.value(category));
+            matches!(parse_count_input(input), Ok(Some(value)) if Some(value) != polled)
+        };
+        !edits.include_in_price
+            || typed_over(&edits.start_input, session.start.as_ref())
+            || typed_over(&edits.end_input, session.end.as_ref())
+    }) || session.concurrent.iter().any(|activity| activity.excluded)
+}
+
+/// Print pages in `snapshot` since the session's previous poll.
+pub(crate) fn print_pages_since_last_poll(
+    session: &RecordingSession,
+    snapshot: &RecordingSnapshot,
+) -> BTreeMap<RecordingCategory, u64> {
+    let previous: BTreeMap<RecordingCategory, u64> = session
+        .trackers
+        .iter()
+        .map(|(category, tracker)| (*category, tracker.last().value))
+        .collect();
+    print_pages_between(&previous, &snapshot.counters)
+}
+
+fn category_reading(
+    snapshot: &RecordingSnapshot,
+    category: RecordingCategory,
+) -> Option<CounterReading> {
+    snapshot.value(category)
+        .map(|value| CounterReading::new(value, snapshot.uptime))
+}
+
+/// Feeds a polled snapshot into the session's trackers, logging and flagging
+/// any counter that went backwards.
+pub(crate) fn observe_recording_snapshot(
+    printer_id: &PrinterId,
+    session: &mut RecordingSession,
+    snapshot: &RecordingSnapshot,
+) {
+    for category in RecordingCategory::ALL {
+        let Some(reading) = category_reading(snapshot, category) else {
             continue;
+        };
+        let tracker = session
+            .trackers
+            .entry(category)
+            .or_insert_with(|| CounterTracker::new(reading));
+        if let CounterDelta::Reset(reset) = tracker.observe(reading) {
+            let error = reset.error(printer_id, category.label());
+            tracing::warn!(
+                target: targets::POLLING,
+                printer_id = %printer_id,
+                "{}",
+                error.technical_detail()
+            );
+            session.status = Some(format!(
+                "{} Choose how to bridge the gap below.",
+                error.user_summary()
+            ));
         }
-        included_any = true;
-        total = total.saturating_add(value?);
     }
-    if included_any {
-        Some(total)
+}
+
+/// Closes an active session with `snapshot` as its end. Sessions charged
+/// to an account are marked as paid by it.
+pub(crate) fn end_recording(
+    printer_id: &PrinterId,
+    session: &mut RecordingSession,
+    snapshot: &RecordingSnapshot,
+) {
+    session.active = false;
+    if session.account.is_some() {
+        session.payment = Some(PaymentMethod::Account);
+    }
+    observe_recording_snapshot(printer_id, session, snapshot);
+    session.end = Some(snapshot.clone());
+    session.edits.apply_end_snapshot(snapshot);
+}
+
+/// Counter history for one category between the start and end values. While
+/// those still match what was polled, this is the session's poll-by-poll
+/// tracker; values typed over them are compared directly.
+pub(crate) fn category_tracker(
+    session: &RecordingSession,
+    category: RecordingCategory,
+    fallback: Option<&RecordingSnapshot>,
+) -> Option<CounterTracker> {
+    let start = category_start_value(session, category)?;
+    let end = category_end_value(session, category, fallback)?;
+    if let Some(tracker) = session.trackers.get(&category)
+        && tracker.start().value == start
+        && tracker.last().value == end
+    {
+        return Some(tracker.clone());
+    }
+    let mut tracker = CounterTracker::new(CounterReading::new(start, None));
+    tracker.observe(CounterReading::new(end, None));
+    Some(tracker)
+}
+
+/// Pages for one category less any excluded print clicks, or `None` while
+/// values are missing or a reset has not been bridged yet.
+pub(crate) fn category_delta(
+    session: &RecordingSession,
+    category: RecordingCategory,
+    fallback: Option<&RecordingSnapshot>,
+) -> Option<u64> {
+    let resolution = session.edits.category(category).reset_resolution;
+    let pages = category_tracker(session, category, fallback)?.pages(resolution)?;
+    Some(billed_pages(pages, &session.concurrent, category))
+}
+
+/// Walks every crawl root of one printer. Roots that fail are skipped; the
+/// walk only fails when no root returned anything.
+pub(crate) async fn crawl_varbinds(
+    client: Arc<dyn SnmpClient>,
+    address: SnmpAddress,
+    community: Option<String>,
+    progress: WalkProgress,
+) -> Result<Vec<SnmpVarBind>, SnmpErrorInfo> {
+    let mut varbinds = Vec::new();
+    let mut last_error = None;
+
+    for root in CRAWL_ROOTS {
+        let mut request = SnmpWalkRequest::new(address.clone(), Oid::from_slice(root))
+            .with_max_results(0)
+            .with_max_repetitions(CRAWL_MAX_REPETITIONS)
+            .with_progress(progress.clone());
+        if let Some(ref community) = community {
+            request = request.with_community(community.clone());
+        }
+
+        match client.bulk_walk(request).await {
+            Ok(response) => varbinds.extend(response.varbinds),
+            Err(error) => {
+                last_error = Some(SnmpErrorInfo {
+                    summary: error.user_summary(),
+                    detail: error.technical_detail(),
+                });
+            }
+        }
+    }
+
+    if varbinds.is_empty() {
+        Err(last_error.unwrap_or(SnmpErrorInfo {
+            summary: "Crawl failed.".to_string(),
+            detail: "No OIDs returned from crawl.".to_string(),
+        }))
     } else {
-        Some(0)
+        Ok(varbinds)
     }
 }
 
@@ -338,10 +852,9 @@ pub(crate) fn counter_oids_from_walk(varbinds: &[SnmpVarBind]) -> CounterOidSet
         if oid.as_slice() == PRT_MARKER_LIFECOUNT_2.as_slice() {
             mapping.color.push(oid.clone());
         }
-        if oid.as_slice() == PRT_MARKER_LIFECOUNT_3.as_slice() {
-            if total_seen.insert(oid.clone()) {
-                total.push(oid.clone());
-            }
+        if oid.as_slice() == PRT_MARKER_LIFECOUNT_3.as_slice() && total_seen.insert(oid.clone())
+        {
+            total.push(oid.clone());
         }
     }
 
@@ -355,10 +868,9 @@ pub(crate) fn counter_oids_from_walk(varbinds: &[SnmpVarBind]) -> CounterOidSet
     mapping
 }
 
-pub(crate) fn snmp_oids(
-    counter_oids: &CounterOidSet,
-    recording_oids: &RecordingOidSettings,
-) -> Vec<Oid> {
+/// OIDs a poll of one printer asks for, its mapping's named counters looked
+/// up in its counter table.
+pub(crate) fn snmp_oids(mapping: &CounterMapping, table: &CounterTable) -> Vec<Oid> {
     let mut oids = Vec::new();
     let mut seen = HashSet::new();
 
@@ -374,35 +886,8 @@ pub(crate) fn snmp_oids(
     push(Oid::from_slice(&SYS_UPTIME_OID));
     push(Oid::from_slice(&PRT_GENERAL_PRINTER_NAME_OID));
 
-    if let Ok(oids) = parse_oid_list(&recording_oids.copies_bw_input) {
-        for oid in oids {
-            push(oid);
-        }
-    }
-    if let Ok(oids) = parse_oid_list(&recording_oids.prints_bw_input) {
-        for oid in oids {
-            push(oid);
-        }
-    }
-    if let Ok(oids) = parse_oid_list(&recording_oids.copies_color_input) {
-        for oid in oids {
-            push(oid);
-        }
-    }
-    if let Ok(oids) = parse_oid_list(&recording_oids.prints_color_input) {
-        for oid in oids {
-            push(oid);
-        }
-    }
-
-    for oid in &counter_oids.bw {
-        push(oid.clone());
-    }
-    for oid in &counter_oids.color {
-        push(oid.clone());
-    }
-    for oid in &counter_oids.total {
-        push(oid.clone());
+    for oid in mapping.poll_oids(table) {
+        push(oid);
     }
     push(Oid::from_slice(&RICOH_TONER_BLACK_OID));
     push(Oid::from_slice(&RICOH_TONER_CYAN_OID));
@@ -40,16 +40,16 @@ impl iced::widget::button::StyleSheet for FirefoxTabStyle {
 
     fn hovered(&self, style: &Self::Style) -> iced::widget::button::Appearance {
         let mut appearance = self.active(style);
-        if !self.active {
-            if let Some(Background::Color(color)) = appearance.background {
-                let lifted = Color {
-                    r: (color.r + 0.05).min(1.0),
-                    g: (color.g + 0.05).min(1.0),
-                    b: (color.b + 0.05).min(1.0),
-                    a: color.a,
-                };
-                appearance.background = Some(Background::Color(lifted));
-            }
+        if !self.active
+            && let Some(Background::Color(color)) = appearance.background
+        {
+            let lifted = Color {
+                r: (color.r + 0.05).min(1.0),
+                g: (color.g + 0.05).min(1.0),
+                b: (color.b + 0.05).min(1.0),
+                a: color.a,
+            };
+            appearance.background = Some(Background::Color(lifted));
         }
         appearance
     }
@@ -1,5 +1,20 @@
-use printcountpay_core::{CounterOidSet, PrinterId, PrinterRecord, SnmpResponse, SnmpVarBind};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
+
+use printcountpay_core::{
+    AccountBook, AccountId, CalibrationProposal, ConcurrentActivity, CounterOidSet,
+    CounterResolution, CounterTable, CounterTracker, Currency, EpochSeconds, GapAction, Money,
+    MoneyFormat, PaymentMethod, PriceBasis, PriceBook, PriceList, PrintJob, PrinterId,
+    PrinterRecord, PrinterStatus, ReceiptConfig, Reconciliation, ReconciliationLog,
+    RecordingCategory, ReplaySnmpClient, ResetResolution, RoundingMode, SnmpAuthProtocol,
+    SnmpPrivProtocol, SnmpRecorder, SnmpSecurityLevel, SnmpV3Credentials, SnmpVarBind, Tariff,
+    UnbilledGap, WalkProgress, ZReport, ZReportStore,
+};
+
+use crate::app::helpers::{format_rounding_input, format_tiers_input, format_vat_input};
 use crate::logging::{LogLevel, LogStore, ReloadHandle};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,6 +28,9 @@ pub enum PrinterTab {
     Polling,
     Recording,
     Pricing,
+    Accounts,
+    Reports,
+    Reconcile,
     Oids,
     AddPrinters,
 }
@@ -28,16 +46,23 @@ pub enum Message {
     ToggleTarget(String, bool),
     CopyDiagnostics,
     AddMockSnmp,
+    SnmpCapturePathChanged(String),
+    StartSnmpCapture,
+    StopSnmpCapture,
+    LoadSnmpReplay,
+    StopSnmpReplay,
     ManualNameChanged(String),
     ManualHostChanged(String),
     ManualPortChanged(String),
     ManualCommunityChanged(String),
+    ManualSnmpV3Changed(SnmpV3Edit),
     AddManualPrinter,
     PrintersPathChanged(String),
     LoadPrinters,
     SavePrinters,
     DiscoveryCidrChanged(String),
     DiscoveryCommunityChanged(String),
+    DiscoverySnmpV3Changed(SnmpV3Edit),
     StartDiscovery,
     StopDiscovery,
     DiscoveryProbeFinished(DiscoveryProbeResult),
@@ -46,23 +71,59 @@ pub enum Message {
     SelectPrinter(PrinterId),
     DeleteSelectedPrinter,
     PollSelectedSnmp,
+    PollEnabledToggled(bool),
+    PollIntervalChanged(String),
+    /// Minutes without counter changes before a recording stops itself;
+    /// empty turns auto-stop off.
+    AutoStopIdleChanged(String),
     PollExportPathChanged(String),
     ExportPollData,
     SnmpPolled {
         printer_id: PrinterId,
-        result: Result<SnmpResponse, SnmpErrorInfo>,
+        result: Result<CounterResolution, SnmpErrorInfo>,
+        status: PrinterStatus,
+        last_seen: Option<EpochSeconds>,
     },
     OidsPathChanged(String),
     OidsTotalChanged(String),
     ApplyOids,
+    ApplyProfileOids,
+    SaveMappingTemplate,
+    CopyMappingToModel,
+    ResetPrinterMapping,
     LoadOids,
     SaveOids,
     CrawlOids,
     OidsCrawled(Result<CounterOidSet, SnmpErrorInfo>),
-    RecordingOidCopiesBwChanged(String),
-    RecordingOidCopiesColorChanged(String),
-    RecordingOidPrintsBwChanged(String),
-    RecordingOidPrintsColorChanged(String),
+    ReadCounterNames,
+    CounterNamesRead {
+        printer_id: PrinterId,
+        result: Result<CounterTable, SnmpErrorInfo>,
+    },
+    /// Test pages the operator makes in one category between the walks.
+    CalibrationPagesChanged {
+        category: RecordingCategory,
+        value: String,
+    },
+    StartCalibration,
+    FinishCalibration,
+    CancelCalibration,
+    CalibrationWalked {
+        printer_id: PrinterId,
+        stage: CalibrationStage,
+        result: Result<Vec<SnmpVarBind>, SnmpErrorInfo>,
+    },
+    /// Copies the proposed counters into the mapping editor.
+    UseCalibration,
+    RecordingOidChanged {
+        category: RecordingCategory,
+        value: String,
+    },
+    /// Turns a category on or off for recording, the tables and pricing.
+    CategoryToggled {
+        category: RecordingCategory,
+        enabled: bool,
+    },
     StartRecording,
     StopRecording,
     RecordingStartChanged {
@@ -74,11 +135,185 @@ pub enum Message {
         value: String,
     },
     RecordingToggleInclude(RecordingCategory),
-    PricingBwFirstChanged(String),
-    PricingBwNextChanged(String),
-    PricingBwRestChanged(String),
-    PricingColorChanged(String),
-    PricingRoundChanged(bool),
+    RecordingResetResolved {
+        category: RecordingCategory,
+        resolution: ResetResolution,
+    },
+    ReopenRecording(u64),
+    PricingPathChanged(String),
+    LoadPricing,
+    SavePricing,
+    PricingListChanged {
+        field: PricingListField,
+        value: String,
+    },
+    PricingVatChanged {
+        category: RecordingCategory,
+        value: String,
+    },
+    PricingBasisChanged(PriceBasis),
+    PricingFormatChanged(MoneyFormat),
+    /// `None` for the rounding of the session total.
+    PricingRoundingModeChanged {
+        tariff: Option<usize>,
+        mode: RoundingMode,
+    },
+    PricingTariffChanged {
+        index: usize,
+        field: PricingField,
+        value: String,
+    },
+    /// Adds a tariff pricing one category on its own.
+    PricingAddTariff(RecordingCategory),
+    PricingEffectiveChanged(String),
+    PublishPricing,
+    SelectPriceVersion(u32),
+    DraftFromPriceVersion(u32),
+    ReceiptFieldChanged {
+        field: ReceiptField,
+        value: String,
+    },
+    SaveReceiptSettings,
+    /// Issues or rewrites the receipt of the selected printer's stopped
+    /// session that started at this time.
+    IssueReceipt(u64),
+    ReceiptPrinted {
+        printer_id: PrinterId,
+        number: String,
+        result: Result<(), String>,
+    },
+    AccountFieldChanged {
+        field: AccountField,
+        value: String,
+    },
+    OpenAccount,
+    /// Selects the account shown in the ledger and charged for the next
+    /// recording; `None` records without an account.
+    SelectAccount(Option<AccountId>),
+    /// Selects the account whose card number was typed or scanned.
+    SelectAccountByCard,
+    TopUpAccount,
+    ApplyLowBalance,
+    /// Marks the selected printer's stopped session as paid this way.
+    SetPayment(PaymentMethod),
+    ToggleVoid,
+    CloseDay,
+    ExportZReport(u32),
+    /// Job log read for the recording on `printer_id` that started at
+    /// `started_at`.
+    JobLogRead {
+        printer_id: PrinterId,
+        started_at: u64,
+        result: Result<Vec<PrintJob>, SnmpErrorInfo>,
+    },
+    /// Leaves the print clicks counted at this poll out of the selected
+    /// printer's session, or puts them back.
+    ToggleConcurrentExclusion(u64),
+    ReconcileDayChanged(String),
+    /// Compares the selected printer's counters with its sessions over the
+    /// typed day.
+    RunReconciliation,
+    ReconcileNoteChanged(String),
+    ResolveGap {
+        gap: UnbilledGap,
+        action: GapAction,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum SnmpV3Edit {
+    Enabled(bool),
+    Username(String),
+    SecurityLevel(SnmpSecurityLevel),
+    AuthProtocol(SnmpAuthProtocol),
+    AuthPassword(String),
+    PrivProtocol(SnmpPrivProtocol),
+    PrivPassword(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SnmpV3Inputs {
+    pub(crate) enabled: bool,
+    pub(crate) username: String,
+    pub(crate) security_level: SnmpSecurityLevel,
+    pub(crate) auth_protocol: SnmpAuthProtocol,
+    pub(crate) auth_password: String,
+    pub(crate) priv_protocol: SnmpPrivProtocol,
+    pub(crate) priv_password: String,
+}
+
+impl SnmpV3Inputs {
+    pub(crate) fn apply(&mut self, edit: SnmpV3Edit) {
+        match edit {
+            SnmpV3Edit::Enabled(value) => self.enabled = value,
+            SnmpV3Edit::Username(value) => self.username = value,
+            SnmpV3Edit::SecurityLevel(value) => self.security_level = value,
+            SnmpV3Edit::AuthProtocol(value) => self.auth_protocol = value,
+            SnmpV3Edit::AuthPassword(value) => self.auth_password = value,
+            SnmpV3Edit::PrivProtocol(value) => self.priv_protocol = value,
+            SnmpV3Edit::PrivPassword(value) => self.priv_password = value,
+        }
+    }
+
+    /// Credentials to store on the printer, or `Err` with a status message
+    /// when v3 is enabled but incomplete. `Ok(None)` means v2c.
+    pub(crate) fn credentials(&self) -> Result<Option<SnmpV3Credentials>, String> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let username = self.username.trim();
+        if username.is_empty() {
+            return Err("SNMPv3 username is empty.".to_string());
+        }
+        let needs_auth = self.security_level != SnmpSecurityLevel::NoAuthNoPriv;
+        if needs_auth && self.auth_password.chars().count() < 8 {
+            return Err("SNMPv3 auth password must be at least 8 characters.".to_string());
+        }
+        let needs_priv = self.security_level == SnmpSecurityLevel::AuthPriv;
+        if needs_priv && self.priv_password.chars().count() < 8 {
+            return Err("SNMPv3 privacy password must be at least 8 characters.".to_string());
+        }
+
+        let mut credentials = SnmpV3Credentials::new(username);
+        credentials.security_level = self.security_level;
+        credentials.auth_protocol = self.auth_protocol;
+        credentials.priv_protocol = self.priv_protocol;
+        if needs_auth {
+            credentials.auth_password = self.auth_password.clone();
+        }
+        if needs_priv {
+            credentials.priv_password = self.priv_password.clone();
+        }
+        Ok(Some(credentials))
+    }
+}
+
+/// Where a printer's counter mapping comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MappingSource {
+    Printer,
+    Template,
+    Default,
+}
+
+impl MappingSource {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            MappingSource::Printer => "this printer's own mapping",
+            MappingSource::Template => "the template for its model",
+            MappingSource::Default => "the default mapping",
+        }
+    }
+}
+
+/// Where SNMP requests go: straight to the network, through a recorder that
+/// writes a capture file, or to a loaded capture instead of the network.
+#[derive(Debug, Clone, Default)]
+pub(crate) enum SnmpCaptureMode {
+    #[default]
+    Off,
+    Capturing(SnmpRecorder),
+    Replaying(Arc<ReplaySnmpClient>),
 }
 
 #[derive(Debug, Clone)]
@@ -101,90 +336,156 @@ pub(crate) enum SnmpPollStatus {
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredSnapshot")]
 pub(crate) struct RecordingSnapshot {
     pub(crate) received_at: u64,
-    pub(crate) bw_printer: Option<u64>,
-    pub(crate) bw_copier: Option<u64>,
-    pub(crate) color_printer: Option<u64>,
-    pub(crate) color_copier: Option<u64>,
+    /// Polled value per category; categories without a counter are missing.
+    pub(crate) counters: BTreeMap<RecordingCategory, u64>,
+    pub(crate) uptime: Option<u64>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum RecordingCategory {
-    CopiesBw,
-    CopiesColor,
-    PrintsBw,
-    PrintsColor,
+impl RecordingSnapshot {
+    pub(crate) fn value(&self, category: RecordingCategory) -> Option<u64> {
+        self.counters.get(&category).copied()
+    }
 }
 
-#[derive(Debug, Clone)]
+/// A snapshot as stored, including the four counter fields snapshots had
+/// before the categories were configurable.
+#[derive(Deserialize)]
+struct StoredSnapshot {
+    received_at: u64,
+    #[serde(default)]
+    counters: BTreeMap<RecordingCategory, u64>,
+    #[serde(default)]
+    uptime: Option<u64>,
+    #[serde(default)]
+    bw_printer: Option<u64>,
+    #[serde(default)]
+    bw_copier: Option<u64>,
+    #[serde(default)]
+    color_printer: Option<u64>,
+    #[serde(default)]
+    color_copier: Option<u64>,
+}
+
+impl From<StoredSnapshot> for RecordingSnapshot {
+    fn from(stored: StoredSnapshot) -> Self {
+        let mut counters = stored.counters;
+        for (category, value) in [
+            (RecordingCategory::CopiesBw, stored.bw_copier),
+            (RecordingCategory::CopiesColor, stored.color_copier),
+            (RecordingCategory::PrintsBw, stored.bw_printer),
+            (RecordingCategory::PrintsColor, stored.color_printer),
+        ] {
+            if let Some(value) = value {
+                counters.entry(category).or_insert(value);
+            }
+        }
+        Self {
+            received_at: stored.received_at,
+            counters,
+            uptime: stored.uptime,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct RecordingCategoryEdits {
     pub(crate) include_in_price: bool,
     pub(crate) start_input: String,
     pub(crate) end_input: String,
+    /// Operator's choice for bridging counter resets in this session.
+    pub(crate) reset_resolution: Option<ResetResolution>,
 }
 
 impl Default for RecordingCategoryEdits {
     fn default() -> Self {
-        Self {
-            include_in_price: true,
-            start_input: String::new(),
-            end_input: String::new(),
-        }
+        DEFAULT_CATEGORY_EDITS.clone()
     }
 }
 
-#[derive(Debug, Clone, Default)]
+static DEFAULT_CATEGORY_EDITS: RecordingCategoryEdits = RecordingCategoryEdits {
+    include_in_price: true,
+    start_input: String::new(),
+    end_input: String::new(),
+    reset_resolution: None,
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "StoredEdits")]
 pub(crate) struct RecordingEdits {
-    pub(crate) copies_bw: RecordingCategoryEdits,
-    pub(crate) copies_color: RecordingCategoryEdits,
-    pub(crate) prints_bw: RecordingCategoryEdits,
-    pub(crate) prints_color: RecordingCategoryEdits,
+    categories: BTreeMap<RecordingCategory, RecordingCategoryEdits>,
+}
+
+/// Edits as stored, including the four per-category fields they had before
+/// the categories were configurable.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct StoredEdits {
+    categories: BTreeMap<RecordingCategory, RecordingCategoryEdits>,
+    copies_bw: RecordingCategoryEdits,
+    copies_color: RecordingCategoryEdits,
+    prints_bw: RecordingCategoryEdits,
+    prints_color: RecordingCategoryEdits,
+}
+
+impl From<StoredEdits> for RecordingEdits {
+    fn from(stored: StoredEdits) -> Self {
+        let mut categories = stored.categories;
+        for (category, edits) in [
+            (RecordingCategory::CopiesBw, stored.copies_bw),
+            (RecordingCategory::CopiesColor, stored.copies_color),
+            (RecordingCategory::PrintsBw, stored.prints_bw),
+            (RecordingCategory::PrintsColor, stored.prints_color),
+        ] {
+            categories.entry(category).or_insert(edits);
+        }
+        Self { categories }
+    }
 }
 
 impl RecordingEdits {
     pub(crate) fn category(&self, category: RecordingCategory) -> &RecordingCategoryEdits {
-        match category {
-            RecordingCategory::CopiesBw => &self.copies_bw,
-            RecordingCategory::CopiesColor => &self.copies_color,
-            RecordingCategory::PrintsBw => &self.prints_bw,
-            RecordingCategory::PrintsColor => &self.prints_color,
-        }
+        self.categories
+            .get(&category)
+            .unwrap_or(&DEFAULT_CATEGORY_EDITS)
     }
 
     pub(crate) fn category_mut(
         &mut self,
         category: RecordingCategory,
     ) -> &mut RecordingCategoryEdits {
-        match category {
-            RecordingCategory::CopiesBw => &mut self.copies_bw,
-            RecordingCategory::CopiesColor => &mut self.copies_color,
-            RecordingCategory::PrintsBw => &mut self.prints_bw,
-            RecordingCategory::PrintsColor => &mut self.prints_color,
-        }
+        self.categories.entry(category).or_default()
     }
 
-    pub(crate) fn apply_start_snapshot(&mut self, snapshot: &RecordingSnapshot) {
-        set_input(&mut self.copies_bw.start_input, snapshot.bw_copier);
-        set_input(&mut self.copies_color.start_input, snapshot.color_copier);
-        set_input(&mut self.prints_bw.start_input, snapshot.bw_printer);
-        set_input(&mut self.prints_color.start_input, snapshot.color_printer);
-        self.clear_end_inputs();
+    /// Categories with edits or a counter in `snapshot`.
+    fn categories_with(&self, snapshot: &RecordingSnapshot) -> BTreeSet<RecordingCategory> {
+        self.categories
+            .keys()
+            .chain(snapshot.counters.keys())
+            .copied()
+            .collect()
     }
 
-    pub(crate) fn apply_end_snapshot(&mut self, snapshot: &RecordingSnapshot) {
-        set_input(&mut self.copies_bw.end_input, snapshot.bw_copier);
-        set_input(&mut self.copies_color.end_input, snapshot.color_copier);
-        set_input(&mut self.prints_bw.end_input, snapshot.bw_printer);
-        set_input(&mut self.prints_color.end_input, snapshot.color_printer);
+    pub(crate) fn apply_start_snapshot(&mut self, snapshot: &RecordingSnapshot) {
+        for category in self.categories_with(snapshot) {
+            let edits = self.category_mut(category);
+            set_input(&mut edits.start_input, snapshot.value(category));
+            edits.end_input.clear();
+            edits.reset_resolution = None;
+        }
     }
 
-    fn clear_end_inputs(&mut self) {
-        self.copies_bw.end_input.clear();
-        self.copies_color.end_input.clear();
-        self.prints_bw.end_input.clear();
-        self.prints_color.end_input.clear();
+    pub(crate) fn apply_end_snapshot(&mut self, snapshot: &RecordingSnapshot) {
+        for category in self.categories_with(snapshot) {
+            set_input(
+                &mut self.category_mut(category).end_input,
+                snapshot.value(category),
+            );
+        }
     }
 }
 
@@ -195,49 +496,355 @@ fn set_input(target: &mut String, value: Option<u64>) {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct RecordingSession {
     pub(crate) active: bool,
     pub(crate) start: Option<RecordingSnapshot>,
     pub(crate) end: Option<RecordingSnapshot>,
+    #[serde(skip)]
     pub(crate) status: Option<String>,
     pub(crate) edits: RecordingEdits,
+    /// Page counts followed poll by poll since the start snapshot.
+    pub(crate) trackers: HashMap<RecordingCategory, CounterTracker>,
+    /// Price list version in effect when the session started.
+    pub(crate) price_version: Option<u32>,
+    /// Set once a receipt is issued, so reissuing keeps its number.
+    pub(crate) receipt: Option<IssuedReceipt>,
+    /// Prepaid account the session is debited from as pages come in.
+    pub(crate) account: Option<AccountId>,
+    /// Amount booked against `account` so far.
+    pub(crate) charged: Money,
+    pub(crate) payment: Option<PaymentMethod>,
+    /// Left out of every total; an account is refunded what it paid.
+    pub(crate) voided: bool,
+    /// Z report the session was closed in; it can no longer be changed.
+    pub(crate) z_report: Option<u32>,
+    /// Latest poll at which a counter moved, starting with the start
+    /// snapshot; counters missing from it keep their last known value. An
+    /// idle session is stopped here.
+    pub(crate) last_change: Option<RecordingSnapshot>,
+    /// Stopped by the idle detector rather than by the operator.
+    pub(crate) auto_stopped: bool,
+    /// Print clicks counted while the session ran.
+    pub(crate) concurrent: Vec<ConcurrentActivity>,
+    /// Job log indexes present at the start; `None` until the job log was
+    /// read, or when the printer has none.
+    pub(crate) job_baseline: Option<BTreeSet<u32>>,
+    /// Jobs that entered the job log after the start.
+    pub(crate) new_jobs: Vec<PrintJob>,
 }
 
+impl RecordingSession {
+    pub(crate) fn started_at(&self) -> Option<u64> {
+        self.start.as_ref().map(|snapshot| snapshot.received_at)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct IssuedReceipt {
+    pub(crate) number: String,
+    pub(crate) issued_at: EpochSeconds,
+}
+
+/// Which walk of a calibration a result belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationStage {
+    Before,
+    After,
+}
+
+/// A guided calibration of one printer: a walk, a few test pages, a second
+/// walk, and the counters that went up by exactly those pages.
 #[derive(Debug, Clone)]
+pub(crate) struct CalibrationSettings {
+    pub(crate) printer: Option<PrinterId>,
+    pub(crate) before: Option<Vec<SnmpVarBind>>,
+    pub(crate) page_inputs: BTreeMap<RecordingCategory, String>,
+    pub(crate) in_flight: Option<CalibrationStage>,
+    pub(crate) progress: WalkProgress,
+    pub(crate) proposal: Option<CalibrationProposal>,
+    pub(crate) status: Option<String>,
+}
+
+impl Default for CalibrationSettings {
+    fn default() -> Self {
+        Self {
+            printer: None,
+            before: None,
+            page_inputs: RecordingCategory::ALL
+                .into_iter()
+                .map(|category| {
+                    let pages = match category {
+                        RecordingCategory::CopiesBw => "3",
+                        RecordingCategory::PrintsColor => "2",
+                        _ => "0",
+                    };
+                    (category, pages.to_string())
+                })
+                .collect(),
+            in_flight: None,
+            progress: WalkProgress::new(),
+            proposal: None,
+            status: None,
+        }
+    }
+}
+
+/// Recording state as written to disk: the current session per printer and
+/// the completed ones kept for review.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct RecordingArchive {
+    pub(crate) current: HashMap<PrinterId, RecordingSession>,
+    pub(crate) completed: HashMap<PrinterId, Vec<RecordingSession>>,
+}
+
+/// Mapping editor inputs per category; categories without one are unmapped.
+#[derive(Debug, Clone, Default)]
 pub(crate) struct RecordingOidSettings {
-    pub(crate) copies_bw_input: String,
-    pub(crate) copies_color_input: String,
-    pub(crate) prints_bw_input: String,
-    pub(crate) prints_color_input: String,
+    pub(crate) inputs: BTreeMap<RecordingCategory, String>,
+}
+
+impl RecordingOidSettings {
+    pub(crate) fn input(&self, category: RecordingCategory) -> &str {
+        self.inputs.get(&category).map_or("", String::as_str)
+    }
 }
 
+/// The published price list versions plus a draft the operator is editing.
+/// Inputs that do not parse leave `draft` at its last valid state, and the
+/// draft only prices sessions once published.
 #[derive(Debug, Clone)]
 pub(crate) struct PricingSettings {
-    pub(crate) bw_first_input: String,
-    pub(crate) bw_next_input: String,
-    pub(crate) bw_rest_input: String,
-    pub(crate) color_input: String,
-    pub(crate) round_to_half_euro: bool,
+    pub(crate) book: PriceBook,
+    pub(crate) draft: PriceList,
+    pub(crate) path: String,
+    pub(crate) status: Option<String>,
+    pub(crate) currency_input: String,
+    pub(crate) digits_input: String,
+    pub(crate) base_fee_input: String,
+    pub(crate) base_fee_vat_input: String,
+    pub(crate) rounding_input: String,
+    pub(crate) rounding_mode: RoundingMode,
+    pub(crate) vat_inputs: BTreeMap<RecordingCategory, String>,
+    pub(crate) tariffs: Vec<TariffInputs>,
+    /// "YYYY-MM-DD" or "YYYY-MM-DD HH:MM" in UTC; empty publishes as of now.
+    pub(crate) effective_input: String,
+    /// Version shown in the price history.
+    pub(crate) selected_version: Option<u32>,
 }
 
-impl Default for PricingSettings {
-    fn default() -> Self {
+impl PricingSettings {
+    /// Starts a draft from the most recently published version.
+    pub(crate) fn from_book(book: PriceBook, path: String) -> Self {
+        let draft = book
+            .latest()
+            .map(|entry| entry.price_list.clone())
+            .unwrap_or_default();
+        let mut settings = Self {
+            selected_version: book.latest().map(|entry| entry.version),
+            book,
+            draft: PriceList::default(),
+            path,
+            status: None,
+            currency_input: String::new(),
+            digits_input: String::new(),
+            base_fee_input: String::new(),
+            base_fee_vat_input: String::new(),
+            rounding_input: String::new(),
+            rounding_mode: RoundingMode::HalfUp,
+            vat_inputs: BTreeMap::new(),
+            tariffs: Vec::new(),
+            effective_input: String::new(),
+        };
+        settings.set_draft(draft);
+        settings
+    }
+
+    /// Replaces the draft and rewrites every input from it.
+    pub(crate) fn set_draft(&mut self, draft: PriceList) {
+        let format = self.book.format();
+        let currency = &draft.currency;
+        self.currency_input = currency.code.clone();
+        self.digits_input = currency.minor_digits.to_string();
+        self.base_fee_input = draft.base_fee.format_amount(currency, &format);
+        self.base_fee_vat_input = format_vat_input(draft.base_fee_vat);
+        self.rounding_input = format_rounding_input(draft.rounding, currency, &format);
+        self.rounding_mode = draft
+            .rounding
+            .map_or(RoundingMode::HalfUp, |rounding| rounding.mode);
+        self.vat_inputs = RecordingCategory::ALL
+            .into_iter()
+            .map(|category| (category, format_vat_input(draft.vat_rate(category))))
+            .collect();
+        self.tariffs = draft
+            .tariffs
+            .iter()
+            .map(|tariff| TariffInputs::from_tariff(tariff, currency, &format))
+            .collect();
+        self.draft = draft;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TariffInputs {
+    pub(crate) tiers_input: String,
+    pub(crate) minimum_input: String,
+    pub(crate) rounding_input: String,
+    pub(crate) rounding_mode: RoundingMode,
+}
+
+impl TariffInputs {
+    pub(crate) fn from_tariff(tariff: &Tariff, currency: &Currency, format: &MoneyFormat) -> Self {
         Self {
-            bw_first_input: "0.25".to_string(),
-            bw_next_input: "0.10".to_string(),
-            bw_rest_input: "0.06".to_string(),
-            color_input: "0.50".to_string(),
-            round_to_half_euro: true,
+            tiers_input: format_tiers_input(&tariff.tiers, currency, format),
+            minimum_input: tariff.minimum.format_amount(currency, format),
+            rounding_input: format_rounding_input(tariff.rounding, currency, format),
+            rounding_mode: tariff
+                .rounding
+                .map_or(RoundingMode::HalfUp, |rounding| rounding.mode),
         }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
-pub(crate) struct BwPricing {
-    pub(crate) first_cents: u64,
-    pub(crate) next_cents: u64,
-    pub(crate) rest_cents: u64,
+pub enum PricingField {
+    Tiers,
+    Minimum,
+    Rounding,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PricingListField {
+    Currency,
+    CurrencyDigits,
+    BaseFee,
+    BaseFeeVat,
+    Rounding,
+}
+
+/// Shop details as typed; address and footer lines are separated by "|".
+#[derive(Debug, Clone)]
+pub(crate) struct ReceiptSettings {
+    pub(crate) config: ReceiptConfig,
+    pub(crate) status: Option<String>,
+    pub(crate) shop_name_input: String,
+    pub(crate) address_input: String,
+    pub(crate) vat_id_input: String,
+    pub(crate) footer_input: String,
+    pub(crate) title_input: String,
+    pub(crate) prefix_input: String,
+    /// "host[:port]" or a device path; empty for no receipt printer.
+    pub(crate) printer_input: String,
+    pub(crate) columns_input: String,
+}
+
+impl ReceiptSettings {
+    pub(crate) fn from_config(config: ReceiptConfig) -> Self {
+        let shop = &config.shop;
+        Self {
+            status: None,
+            shop_name_input: shop.name.clone(),
+            address_input: shop.address_lines.join(" | "),
+            vat_id_input: shop.vat_id.clone().unwrap_or_default(),
+            footer_input: shop.footer_lines.join(" | "),
+            title_input: config.title.clone(),
+            prefix_input: config.number_prefix.clone(),
+            printer_input: config
+                .escpos_target
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            columns_input: config.escpos.columns.to_string(),
+            config,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReceiptField {
+    ShopName,
+    Address,
+    VatId,
+    Footer,
+    Title,
+    NumberPrefix,
+    ReceiptPrinter,
+    ReceiptColumns,
+}
+
+/// Prepaid accounts with the inputs of the accounts tab.
+#[derive(Debug, Clone)]
+pub(crate) struct AccountSettings {
+    pub(crate) book: AccountBook,
+    pub(crate) status: Option<String>,
+    /// Account the next recording is started against.
+    pub(crate) selected: Option<AccountId>,
+    pub(crate) name_input: String,
+    pub(crate) card_input: String,
+    pub(crate) group_input: String,
+    pub(crate) top_up_input: String,
+    pub(crate) low_balance_input: String,
+    /// Card number typed or scanned on the recording tab.
+    pub(crate) lookup_input: String,
+}
+
+impl AccountSettings {
+    pub(crate) fn from_book(book: AccountBook, currency: &Currency, format: &MoneyFormat) -> Self {
+        Self {
+            low_balance_input: book.low_balance().format_amount(currency, format),
+            book,
+            status: None,
+            selected: None,
+            name_input: String::new(),
+            card_input: String::new(),
+            group_input: String::new(),
+            top_up_input: String::new(),
+            lookup_input: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AccountField {
+    Name,
+    CardNumber,
+    Group,
+    TopUp,
+    LowBalance,
+    CardLookup,
+}
+
+/// Stored Z reports and the close-day status.
+#[derive(Debug, Clone)]
+pub(crate) struct ZReportSettings {
+    pub(crate) store: ZReportStore,
+    pub(crate) reports: Vec<ZReport>,
+    pub(crate) status: Option<String>,
+}
+
+/// The last reconciliation run and the gap decisions made so far.
+#[derive(Debug, Clone)]
+pub(crate) struct ReconcileSettings {
+    pub(crate) day_input: String,
+    pub(crate) log: ReconciliationLog,
+    pub(crate) report: Option<Reconciliation>,
+    pub(crate) note_input: String,
+    pub(crate) status: Option<String>,
+}
+
+/// An entry of the account picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AccountChoice {
+    pub(crate) id: Option<AccountId>,
+    pub(crate) label: String,
+}
+
+impl fmt::Display for AccountChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -248,7 +855,7 @@ pub struct DiscoveryProbeResult {
 
 #[derive(Debug, Clone)]
 pub enum DiscoveryOutcome {
-    Printer(PrinterRecord),
+    Printer(Box<PrinterRecord>),
     NotPrinter,
     Error(SnmpErrorInfo),
 }
@@ -72,7 +72,10 @@ impl PrintCountApp {
     fn printer_tab_bar(&self) -> Element<'_, Message> {
         let mut tabs = row![
             self.printer_tab_button(PrinterTab::Recording, "Recording"),
-            self.printer_tab_button(PrinterTab::Pricing, "Pricing")
+            self.printer_tab_button(PrinterTab::Pricing, "Pricing"),
+            self.printer_tab_button(PrinterTab::Accounts, "Accounts"),
+            self.printer_tab_button(PrinterTab::Reports, "Z report"),
+            self.printer_tab_button(PrinterTab::Reconcile, "Reconciliation")
         ]
         .spacing(4)
         .align_items(Alignment::Center);
@@ -82,6 +85,9 @@ impl PrintCountApp {
                 self.printer_tab_button(PrinterTab::Polling, "Polling"),
                 self.printer_tab_button(PrinterTab::Recording, "Recording"),
                 self.printer_tab_button(PrinterTab::Pricing, "Pricing"),
+                self.printer_tab_button(PrinterTab::Accounts, "Accounts"),
+                self.printer_tab_button(PrinterTab::Reports, "Z report"),
+                self.printer_tab_button(PrinterTab::Reconcile, "Reconciliation"),
                 self.printer_tab_button(PrinterTab::Oids, "SNMP OIDs"),
                 self.printer_tab_button(PrinterTab::AddPrinters, "Discovery + Manual")
             ]
@@ -156,6 +162,7 @@ impl PrintCountApp {
                 community_input,
             ]
             .spacing(4),
+            self.snmp_v3_inputs_view(&self.discovery_snmp_v3, Message::DiscoverySnmpV3Changed),
             row![action_button]
                 .spacing(8)
                 .align_items(Alignment::Center),
@@ -230,6 +237,7 @@ impl PrintCountApp {
                 community_input,
             ]
             .spacing(4),
+            self.snmp_v3_inputs_view(&self.manual_snmp_v3, Message::ManualSnmpV3Changed),
             row![button("Add printer").on_press(Message::AddManualPrinter)]
                 .spacing(8)
                 .align_items(Alignment::Center),
@@ -245,6 +253,94 @@ impl PrintCountApp {
             .into()
     }
 
+    fn snmp_v3_inputs_view(
+        &self,
+        inputs: &SnmpV3Inputs,
+        on_edit: fn(SnmpV3Edit) -> Message,
+    ) -> Element<'_, Message> {
+        let toggle = checkbox("Use SNMPv3", inputs.enabled)
+            .on_toggle(move |value| on_edit(SnmpV3Edit::Enabled(value)))
+            .size(12);
+        if !inputs.enabled {
+            return toggle.into();
+        }
+
+        let label = |value: &'static str| {
+            text(value)
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a)))
+        };
+        let username_input = text_input("billing", &inputs.username)
+            .on_input(move |value| on_edit(SnmpV3Edit::Username(value)))
+            .padding(6)
+            .size(12)
+            .width(Length::Fill);
+        let level_picker = pick_list(
+            &SnmpSecurityLevel::ALL[..],
+            Some(inputs.security_level),
+            move |value| on_edit(SnmpV3Edit::SecurityLevel(value)),
+        )
+        .text_size(12)
+        .width(Length::Fill);
+
+        let mut content = column![
+            toggle,
+            column![label("Username"), username_input].spacing(4),
+            column![label("Security level"), level_picker].spacing(4),
+        ]
+        .spacing(6);
+
+        if inputs.security_level != SnmpSecurityLevel::NoAuthNoPriv {
+            let auth_picker = pick_list(
+                &SnmpAuthProtocol::ALL[..],
+                Some(inputs.auth_protocol),
+                move |value| on_edit(SnmpV3Edit::AuthProtocol(value)),
+            )
+            .text_size(12);
+            let auth_input = text_input("Auth password", &inputs.auth_password)
+                .on_input(move |value| on_edit(SnmpV3Edit::AuthPassword(value)))
+                .secure(true)
+                .padding(6)
+                .size(12)
+                .width(Length::Fill);
+            content = content.push(
+                column![
+                    label("Authentication"),
+                    row![auth_picker, auth_input]
+                        .spacing(6)
+                        .align_items(Alignment::Center),
+                ]
+                .spacing(4),
+            );
+        }
+
+        if inputs.security_level == SnmpSecurityLevel::AuthPriv {
+            let priv_picker = pick_list(
+                &SnmpPrivProtocol::ALL[..],
+                Some(inputs.priv_protocol),
+                move |value| on_edit(SnmpV3Edit::PrivProtocol(value)),
+            )
+            .text_size(12);
+            let priv_input = text_input("Privacy password", &inputs.priv_password)
+                .on_input(move |value| on_edit(SnmpV3Edit::PrivPassword(value)))
+                .secure(true)
+                .padding(6)
+                .size(12)
+                .width(Length::Fill);
+            content = content.push(
+                column![
+                    label("Privacy"),
+                    row![priv_picker, priv_input]
+                        .spacing(6)
+                        .align_items(Alignment::Center),
+                ]
+                .spacing(4),
+            );
+        }
+
+        content.into()
+    }
+
     fn printer_storage_controls_view(&self) -> Element<'_, Message> {
         let status = self.printers_status.as_deref().unwrap_or("Ready.");
         let path_input = text_input("printers.ron", &self.printers_path)
@@ -328,6 +424,8 @@ impl PrintCountApp {
         let status = session.status.as_deref().unwrap_or("Ready.");
         let state_label = if session.active {
             "Recording active"
+        } else if session.auto_stopped {
+            "Recording auto-stopped"
         } else {
             "Recording idle"
         };
@@ -343,6 +441,16 @@ impl PrintCountApp {
         } else {
             button("Stop recording").on_press(Message::StopRecording)
         };
+        let receipt_label = match &session.receipt {
+            Some(issued) => format!("Rewrite receipt {}", issued.number),
+            None => "Issue receipt".to_string(),
+        };
+        let receipt_button = match session.started_at() {
+            Some(started_at) if !session.active && session.end.is_some() => {
+                button(text(receipt_label)).on_press(Message::IssueReceipt(started_at))
+            }
+            _ => button(text(receipt_label)).style(theme::Button::Secondary),
+        };
 
         let start_time = session
             .start
@@ -367,144 +475,82 @@ impl PrintCountApp {
                     return input.to_string();
                 }
                 live_snapshot_ref
-                    .and_then(|snapshot| snapshot_category_value(snapshot, category))
+                    .and_then(|snapshot| snapshot.value(category))
                     .map(|value| value.to_string())
                     .unwrap_or_else(|| input.to_string())
             };
 
-            let copies_bw_start = category_start_value(&session, RecordingCategory::CopiesBw);
-            let copies_bw_end =
-                category_end_value(&session, RecordingCategory::CopiesBw, live_snapshot_ref);
-            let copies_bw_delta = delta_value(copies_bw_start, copies_bw_end);
-
-            let copies_color_start = category_start_value(&session, RecordingCategory::CopiesColor);
-            let copies_color_end =
-                category_end_value(&session, RecordingCategory::CopiesColor, live_snapshot_ref);
-            let copies_color_delta = delta_value(copies_color_start, copies_color_end);
-
-            let prints_bw_start = category_start_value(&session, RecordingCategory::PrintsBw);
-            let prints_bw_end =
-                category_end_value(&session, RecordingCategory::PrintsBw, live_snapshot_ref);
-            let prints_bw_delta = delta_value(prints_bw_start, prints_bw_end);
-
-            let prints_color_start = category_start_value(&session, RecordingCategory::PrintsColor);
-            let prints_color_end =
-                category_end_value(&session, RecordingCategory::PrintsColor, live_snapshot_ref);
-            let prints_color_delta = delta_value(prints_color_start, prints_color_end);
-
-            let include_copies_bw = session.edits.copies_bw.include_in_price;
-            let include_copies_color = session.edits.copies_color.include_in_price;
-            let include_prints_bw = session.edits.prints_bw.include_in_price;
-            let include_prints_color = session.edits.prints_color.include_in_price;
-
-            let copies_bw_end_input =
-                end_display(&session.edits.copies_bw.end_input, RecordingCategory::CopiesBw);
-            let copies_color_end_input =
-                end_display(&session.edits.copies_color.end_input, RecordingCategory::CopiesColor);
-            let prints_bw_end_input =
-                end_display(&session.edits.prints_bw.end_input, RecordingCategory::PrintsBw);
-            let prints_color_end_input =
-                end_display(&session.edits.prints_color.end_input, RecordingCategory::PrintsColor);
-
-            let start_bw_total = sum_two(copies_bw_start, prints_bw_start);
-            let end_bw_total = sum_two(copies_bw_end, prints_bw_end);
-            let total_bw_delta = delta_value(start_bw_total, end_bw_total);
-
-            let start_color_total = sum_two(copies_color_start, prints_color_start);
-            let end_color_total = sum_two(copies_color_end, prints_color_end);
-            let total_color_delta = delta_value(start_color_total, end_color_total);
-
-            let bw_delta = sum_optional_included([
-                (include_copies_bw, copies_bw_delta),
-                (include_prints_bw, prints_bw_delta),
-            ]);
-            let color_delta = sum_optional_included([
-                (include_copies_color, copies_color_delta),
-                (include_prints_color, prints_color_delta),
-            ]);
-
-            let bw_pricing = bw_pricing_from_settings(&self.pricing);
-            let color_price = color_price_from_settings(&self.pricing);
-            let bw_cost_raw = match bw_delta {
-                Some(0) => Some(0),
-                Some(count) => bw_pricing.map(|pricing| bw_cost_cents(count, pricing)),
-                None => None,
-            };
-            let bw_cost_value = bw_cost_raw.map(|value| {
-                if self.pricing.round_to_half_euro {
-                    round_to_nearest_50_cents(value)
-                } else {
-                    value
+            let counted = session_categories(&session, &self.categories);
+            let categories: Vec<RecordingCategory> = RecordingCategory::ALL
+                .into_iter()
+                .filter(|category| {
+                    counted.contains(category) || self.categories.is_enabled(*category)
+                })
+                .collect();
+            let deltas: Vec<(RecordingCategory, Option<u64>)> = categories
+                .iter()
+                .map(|category| {
+                    (
+                        *category,
+                        category_delta(&session, *category, live_snapshot_ref),
+                    )
+                })
+                .collect();
+
+            let price_version = self.session_price_version(&session);
+            let quote = included_pages(
+                &session,
+                deltas
+                    .iter()
+                    .copied()
+                    .filter(|(category, _)| counted.contains(category)),
+            )
+            .zip(price_version)
+            .map(|(pages, entry)| entry.price_list.quote(&pages));
+
+            let mut rows = column![self.recording_table_header()].spacing(6);
+            for (category, delta) in &deltas {
+                let edits = session.edits.category(*category);
+                rows = rows.push(self.recording_table_row_editable(
+                    *category,
+                    category.label(),
+                    &edits.start_input,
+                    &end_display(&edits.end_input, *category),
+                    *delta,
+                    edits.include_in_price,
+                ));
+            }
+
+            // The copies and prints categories add up to the clicks.
+            let mut totals = column![].spacing(6);
+            for (label, color) in [("Total B/W", false), ("Total color", true)] {
+                let base: Vec<RecordingCategory> = categories
+                    .iter()
+                    .copied()
+                    .filter(|category| category.is_base() && category.is_color() == color)
+                    .collect();
+                if base.is_empty() {
+                    continue;
                 }
-            });
-            let color_cost_value = match color_delta {
-                Some(0) => Some(0),
-                Some(count) => color_price.map(|price| color_cost_cents(count, price)),
-                None => None,
-            };
-            let subtotal_cents = match (bw_cost_value, color_cost_value) {
-                (Some(bw), Some(color)) => Some(bw + color),
-                _ => None,
-            };
-            let total_cents = subtotal_cents;
-            let rounding_label = if self.pricing.round_to_half_euro {
-                "B/W rounded to nearest 0.50 EUR"
-            } else {
-                "No rounding applied"
-            };
+                let sum = |value: &dyn Fn(RecordingCategory) -> Option<u64>| {
+                    base.iter()
+                        .try_fold(0, |total, category| sum_two(Some(total), value(*category)))
+                };
+                totals = totals.push(self.recording_table_row(
+                    label,
+                    sum(&|category| category_start_value(&session, category)),
+                    sum(&|category| category_end_value(&session, category, live_snapshot_ref)),
+                    sum(&|category| category_delta(&session, category, live_snapshot_ref)),
+                ));
+            }
 
             column![
-                self.recording_table_header(),
-                self.recording_table_row_editable(
-                    RecordingCategory::CopiesBw,
-                    "Copies B/W",
-                    &session.edits.copies_bw.start_input,
-                    &copies_bw_end_input,
-                    copies_bw_delta,
-                    include_copies_bw,
-                ),
-                self.recording_table_row_editable(
-                    RecordingCategory::CopiesColor,
-                    "Copies color",
-                    &session.edits.copies_color.start_input,
-                    &copies_color_end_input,
-                    copies_color_delta,
-                    include_copies_color,
-                ),
-                self.recording_table_row_editable(
-                    RecordingCategory::PrintsBw,
-                    "Prints B/W",
-                    &session.edits.prints_bw.start_input,
-                    &prints_bw_end_input,
-                    prints_bw_delta,
-                    include_prints_bw,
-                ),
-                self.recording_table_row_editable(
-                    RecordingCategory::PrintsColor,
-                    "Prints color",
-                    &session.edits.prints_color.start_input,
-                    &prints_color_end_input,
-                    prints_color_delta,
-                    include_prints_color,
-                ),
+                rows.push_maybe(self.recording_resets_view(&session, live_snapshot_ref))
+                    .push_maybe(self.recording_concurrent_view(&session)),
                 Rule::horizontal(1),
-                self.recording_table_row(
-                    "Total B/W",
-                    start_bw_total,
-                    end_bw_total,
-                    total_bw_delta,
-                ),
-                self.recording_table_row(
-                    "Total color",
-                    start_color_total,
-                    end_color_total,
-                    total_color_delta,
-                ),
+                totals,
                 Rule::horizontal(1),
-                self.value_line("Total price", total_cents.map(format_cents)),
-                text(rounding_label)
-                    .size(11)
-                    .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+                self.price_quote_view(quote.as_ref(), price_version),
             ]
             .spacing(6)
             .into()
@@ -534,10 +580,14 @@ impl PrintCountApp {
             );
         }
         content = content.push(
-            row![start_button, stop_button]
+            row![start_button, stop_button, receipt_button]
                 .spacing(8)
                 .align_items(Alignment::Center),
         );
+        if !session.active && session.end.is_some() {
+            content = content.push(self.recording_payment_view(&session));
+        }
+        content = content.push(self.recording_account_view(&session));
         content = content.push(
             text(format!("Start snapshot: {start_time}"))
                 .size(12)
@@ -554,6 +604,16 @@ impl PrintCountApp {
                 .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
         );
         content = content.push(delta_section);
+        if let Some(status) = &self.recordings_status {
+            content = content.push(
+                text(status)
+                    .size(12)
+                    .style(theme::Text::Color(Color::from_rgb8(0xe0, 0x4f, 0x4f))),
+            );
+        }
+        if let Some(printer_id) = selected_id {
+            content = content.push(self.completed_recordings_view(printer_id, &session));
+        }
 
         container(content)
             .padding(12)
@@ -564,62 +624,1056 @@ impl PrintCountApp {
     }
 
     fn pricing_tab_view(&self) -> Element<'_, Message> {
-        let bw_section = column![
-            text("Black/white pricing")
+        let status = self.pricing.status.as_deref().unwrap_or("Ready.");
+        let path_input = text_input("pricing.ron", &self.pricing.path)
+            .on_input(Message::PricingPathChanged)
+            .padding(6)
+            .size(12)
+            .width(Length::Fill);
+        let in_effect = match self.pricing.book.active_at(now_epoch_seconds()) {
+            Some(entry) => format!(
+                "In effect now: {} (version {})",
+                entry.price_list.name, entry.version
+            ),
+            None => "No price list in effect yet.".to_string(),
+        };
+        let storage = column![
+            text(in_effect)
                 .size(14)
                 .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
-            self.pricing_input(
-                "First 5 pages (EUR)",
-                "0.25",
-                &self.pricing.bw_first_input,
-                Message::PricingBwFirstChanged,
+            row![
+                path_input,
+                button("Load").on_press(Message::LoadPricing),
+                button("Save").on_press(Message::SavePricing),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+            text(format!("Status: {status}"))
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+        ]
+        .spacing(6);
+
+        let list_field = |field| move |value| Message::PricingListChanged { field, value };
+        let format = self.pricing.book.format();
+        let number_format = row![
+            self.pricing_picker(
+                "Decimal separator",
+                &DecimalSeparator::ALL[..],
+                format.decimal,
+                move |decimal| Message::PricingFormatChanged(MoneyFormat { decimal, ..format }),
+            ),
+            self.pricing_picker(
+                "Thousands separator",
+                &ThousandsSeparator::ALL[..],
+                format.thousands,
+                move |thousands| {
+                    Message::PricingFormatChanged(MoneyFormat {
+                        thousands,
+                        ..format
+                    })
+                },
             ),
+        ]
+        .spacing(8);
+        let currency = row![
             self.pricing_input(
-                "Next 5 pages (EUR)",
-                "0.10",
-                &self.pricing.bw_next_input,
-                Message::PricingBwNextChanged,
+                "Currency",
+                "EUR",
+                &self.pricing.currency_input,
+                list_field(PricingListField::Currency),
             ),
             self.pricing_input(
-                "Rest (EUR)",
-                "0.06",
-                &self.pricing.bw_rest_input,
-                Message::PricingBwRestChanged,
+                "Decimal places",
+                "2",
+                &self.pricing.digits_input,
+                list_field(PricingListField::CurrencyDigits),
             ),
         ]
+        .spacing(8);
+
+        let mut categories = column![
+            text("Categories")
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
+            text(
+                "Recorded, shown and priced. Large, two-color, single-color and duplex \
+                 pages are counted in copies and prints too: their price is a surcharge \
+                 charged on top of the copies or prints price.",
+            )
+            .size(11)
+            .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+        ]
         .spacing(6);
+        for chunk in RecordingCategory::ALL.chunks(5) {
+            let mut line = row![].spacing(12);
+            for category in chunk {
+                let category = *category;
+                line = line.push(
+                    checkbox(category.label(), self.categories.is_enabled(category))
+                        .on_toggle(move |enabled| Message::CategoryToggled {
+                            category,
+                            enabled,
+                        })
+                        .size(12),
+                );
+            }
+            categories = categories.push(line);
+        }
 
-        let color_section = column![
-            text("Color pricing")
+        let mut content = column![
+            storage,
+            categories,
+            text(format!("Draft: {}", self.pricing.draft.name))
                 .size(14)
                 .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
-            self.pricing_input(
-                "Per page (EUR)",
-                "0.50",
-                &self.pricing.color_input,
-                Message::PricingColorChanged,
+            currency,
+            number_format,
+        ]
+        .spacing(12);
+        for (index, (tariff, inputs)) in self
+            .pricing
+            .draft
+            .tariffs
+            .iter()
+            .zip(&self.pricing.tariffs)
+            .enumerate()
+        {
+            let categories = tariff
+                .categories
+                .iter()
+                .map(|category| category.label())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let on_change = move |field| {
+                move |value| Message::PricingTariffChanged {
+                    index,
+                    field,
+                    value,
+                }
+            };
+            content = content.push(
+                column![
+                    text(&tariff.name)
+                        .size(14)
+                        .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
+                    text(if tariff.is_surcharge() {
+                        format!("Surcharge on top of the copies and prints price for: {categories}")
+                    } else {
+                        format!("Covers: {categories}")
+                    })
+                        .size(11)
+                        .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+                    self.pricing_input(
+                        "Tiers (last page @ price per page)",
+                        "5 @ 0.25, 10 @ 0.10, rest @ 0.06",
+                        &inputs.tiers_input,
+                        on_change(PricingField::Tiers),
+                    ),
+                    self.pricing_input(
+                        "Minimum charge",
+                        "0.00",
+                        &inputs.minimum_input,
+                        on_change(PricingField::Minimum),
+                    ),
+                    self.rounding_inputs(
+                        "Round to (empty for none)",
+                        &inputs.rounding_input,
+                        inputs.rounding_mode,
+                        on_change(PricingField::Rounding),
+                        Some(index),
+                    ),
+                ]
+                .spacing(6),
+            );
+        }
+
+        let unpriced: Vec<RecordingCategory> = self
+            .categories
+            .enabled()
+            .iter()
+            .copied()
+            .filter(|category| {
+                !self
+                    .pricing
+                    .draft
+                    .tariffs
+                    .iter()
+                    .any(|tariff| tariff.categories.contains(category))
+            })
+            .collect();
+        if !unpriced.is_empty() {
+            let mut add = row![text("Not priced:")
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a)))]
+            .spacing(8)
+            .align_items(Alignment::Center);
+            for category in unpriced {
+                let label = if category.is_surcharge() {
+                    format!("Add {} surcharge", category.label())
+                } else {
+                    format!("Price {}", category.label())
+                };
+                add = add.push(
+                    button(text(label).size(12))
+                        .on_press(Message::PricingAddTariff(category))
+                        .padding([4, 10]),
+                );
+            }
+            content = content.push(add);
+        }
+
+        let mut vat = column![
+            text("VAT")
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
+            self.pricing_picker(
+                "Prices",
+                &PriceBasis::ALL[..],
+                self.pricing.draft.price_basis,
+                Message::PricingBasisChanged,
             ),
         ]
         .spacing(6);
+        for (category, value) in &self.pricing.vat_inputs {
+            let category = *category;
+            if !self.categories.is_enabled(category) {
+                continue;
+            }
+            vat = vat.push(self.pricing_input(
+                &format!("{} VAT (%)", category.label()),
+                "0",
+                value,
+                move |value| Message::PricingVatChanged { category, value },
+            ));
+        }
+        content = content.push(vat);
 
-        let rounding_toggle =
-            checkbox("Round B/W to nearest 0.50 EUR", self.pricing.round_to_half_euro)
-                .on_toggle(Message::PricingRoundChanged)
-                .size(12);
+        content = content.push(
+            column![
+                text("Session")
+                    .size(14)
+                    .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
+                row![
+                    self.pricing_input(
+                        "Base fee per session",
+                        "0.00",
+                        &self.pricing.base_fee_input,
+                        list_field(PricingListField::BaseFee),
+                    ),
+                    self.pricing_input(
+                        "Base fee VAT (%)",
+                        "0",
+                        &self.pricing.base_fee_vat_input,
+                        list_field(PricingListField::BaseFeeVat),
+                    ),
+                ]
+                .spacing(8),
+                self.rounding_inputs(
+                    "Round total to (empty for none)",
+                    &self.pricing.rounding_input,
+                    self.pricing.rounding_mode,
+                    list_field(PricingListField::Rounding),
+                    None,
+                ),
+            ]
+            .spacing(6),
+        );
+        content = content.push(
+            row![
+                self.pricing_input(
+                    "Effective from (UTC, empty for now)",
+                    "2026-01-01 00:00",
+                    &self.pricing.effective_input,
+                    Message::PricingEffectiveChanged,
+                ),
+                button("Publish").on_press(Message::PublishPricing),
+            ]
+            .spacing(8)
+            .align_items(Alignment::End),
+        );
+        content = content.push(
+            text(format!(
+                "Edits change the draft only; publish it as a new version to price sessions \
+                 starting from its effective date. Separate tiers with \"{}\". Tariffs and \
+                 their categories are defined in the RON file.",
+                tier_separator(&format).trim()
+            ))
+            .size(11)
+            .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+        );
+        content = content.push(Rule::horizontal(1));
+        content = content.push(self.receipt_settings_view());
+        content = content.push(Rule::horizontal(1));
+        content = content.push(self.price_history_view());
 
-        let hint = text("Used for recording totals. Decimals accept . or ,")
+        container(scrollable(content))
+            .padding(12)
+            .width(Length::Fill)
+            .style(theme::Container::Box)
+            .into()
+    }
+
+    /// How a stopped session was paid, and voiding it.
+    fn recording_payment_view(&self, session: &RecordingSession) -> Element<'_, Message> {
+        let muted = theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a));
+        if let Some(number) = session.z_report {
+            let payment = session
+                .payment
+                .map(|method| method.to_string())
+                .unwrap_or_else(|| "unpaid".to_string());
+            let voided = if session.voided { ", voided" } else { "" };
+            return text(format!("Closed in Z report {number} ({payment}{voided})."))
+                .size(12)
+                .style(muted)
+                .into();
+        }
+        let void_label = if session.voided { "Restore" } else { "Void" };
+        row![
+            text("Paid by").size(12).style(muted),
+            pick_list(
+                &PaymentMethod::ALL[..],
+                session.payment,
+                Message::SetPayment
+            )
+            .placeholder("Unpaid")
+            .text_size(12),
+            button(text(void_label).size(12))
+                .on_press(Message::ToggleVoid)
+                .padding([4, 10]),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    /// What closing the day would report, and the reports stored so far.
+    fn reports_tab_view(&self) -> Element<'_, Message> {
+        let status = self.zreports.status.as_deref().unwrap_or("Ready.");
+        let muted = theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a));
+        let dark = theme::Text::Color(Color::from_rgb8(0x1f, 0x2a, 0x37));
+
+        let pending: Element<'_, Message> = match self.pending_zreport_sessions() {
+            Ok(sessions) if sessions.is_empty() => {
+                text("No completed recordings since the last report.")
+                    .size(12)
+                    .style(muted)
+                    .into()
+            }
+            Ok(sessions) => {
+                let preview = ZReport {
+                    number: 0,
+                    created_at: now_epoch_seconds(),
+                    since: None,
+                    sessions: sessions.into_iter().map(|(_, session)| session).collect(),
+                    currency: self.billing_currency(),
+                    format: self.pricing.book.format(),
+                };
+                let totals = preview.totals();
+                let mut lines = column![text(format!(
+                    "{} recordings, {} voided, {} with manual overrides; total {}",
+                    totals.sessions,
+                    totals.voided,
+                    totals.overridden,
+                    self.format_money(totals.total)
+                ))
+                .size(12)
+                .style(dark)]
+                .spacing(4);
+                for (method, amount) in &totals.by_method {
+                    lines = lines.push(
+                        text(format!("{method}: {}", self.format_money(*amount)))
+                            .size(12)
+                            .style(dark),
+                    );
+                }
+                if totals.unpaid_sessions > 0 {
+                    lines = lines.push(
+                        text(format!(
+                            "Unpaid: {} in {} recordings",
+                            self.format_money(totals.unpaid),
+                            totals.unpaid_sessions
+                        ))
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb8(0xe0, 0x4f, 0x4f))),
+                    );
+                }
+                lines.into()
+            }
+            Err(error) => text(format!("Cannot close the day yet: {error}"))
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0xe0, 0x4f, 0x4f)))
+                .into(),
+        };
+
+        let mut history = column![text("Stored reports")
+            .size(14)
+            .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12)))]
+        .spacing(6);
+        if self.zreports.reports.is_empty() {
+            history = history.push(text("No reports yet.").size(12).style(muted));
+        }
+        for report in self.zreports.reports.iter().rev() {
+            let totals = report.totals();
+            history = history.push(
+                row![
+                    text(format!(
+                        "{}, closed {}: {} recordings, total {}",
+                        report.title(),
+                        format_utc_timestamp(report.created_at),
+                        totals.sessions,
+                        totals.total.format(&report.currency, &report.format)
+                    ))
+                    .size(12)
+                    .width(Length::Fill)
+                    .style(dark),
+                    button(text("Export").size(12))
+                        .on_press(Message::ExportZReport(report.number))
+                        .padding([4, 10]),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+
+        let content = column![
+            text("Since the last report")
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
+            pending,
+            button("Close day (Z report)").on_press(Message::CloseDay),
+            text(format!("Status: {status}")).size(12).style(muted),
+            text(format!(
+                "Closing the day stores every recording stopped since the last report in \
+                 {ZREPORTS_DIR}/ as a read-only file, with text and CSV exports, and locks \
+                 those recordings against further changes."
+            ))
             .size(11)
-            .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a)));
+            .style(muted),
+            Rule::horizontal(1),
+            history,
+        ]
+        .spacing(8);
 
-        let content = column![bw_section, color_section, rounding_toggle, hint].spacing(12);
+        container(scrollable(content))
+            .padding(12)
+            .width(Length::Fill)
+            .style(theme::Container::Box)
+            .into()
+    }
 
-        container(content)
+    /// Counter pages of the selected printer against its recordings over a
+    /// day, with the gaps still to be accounted for.
+    fn reconcile_tab_view(&self) -> Element<'_, Message> {
+        let reconcile = &self.reconcile;
+        let status = reconcile.status.as_deref().unwrap_or("Ready.");
+        let muted = theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a));
+        let dark = theme::Text::Color(Color::from_rgb8(0x1f, 0x2a, 0x37));
+        let warning = theme::Text::Color(Color::from_rgb8(0xe0, 0x4f, 0x4f));
+
+        let controls = row![
+            text("Day (UTC)").size(12).style(dark),
+            text_input("YYYY-MM-DD", &reconcile.day_input)
+                .on_input(Message::ReconcileDayChanged)
+                .on_submit(Message::RunReconciliation)
+                .padding(4)
+                .size(12)
+                .width(Length::Fixed(120.0)),
+            button(text("Reconcile").size(12))
+                .on_press(Message::RunReconciliation)
+                .padding([4, 10]),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+
+        let mut report_view = column![].spacing(6);
+        match &reconcile.report {
+            None => {
+                report_view = report_view.push(
+                    text("Pick a printer and a day to compare its counters with its recordings.")
+                        .size(12)
+                        .style(muted),
+                );
+            }
+            Some(report) => {
+                let open_pages = reconcile.log.open_pages(report);
+                report_view = report_view
+                    .push(
+                        text(format!(
+                            "{} on {}",
+                            report.printer,
+                            &format_utc_timestamp(report.day_start)[..10]
+                        ))
+                        .size(14)
+                        .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
+                    )
+                    .push(
+                        text(format!(
+                            "Counters: {} pages, {} of them during recordings; recordings \
+                             counted {} pages.",
+                            report.counter_pages, report.covered_pages, report.session_pages
+                        ))
+                        .size(12)
+                        .style(dark),
+                    )
+                    .push(
+                        text(format!(
+                            "Outside recordings: {} pages, {} not yet accounted for.",
+                            report.unbilled_pages(),
+                            open_pages
+                        ))
+                        .size(12)
+                        .style(if open_pages > 0 { warning } else { dark }),
+                    );
+                if report.resets > 0 {
+                    report_view = report_view.push(
+                        text(format!(
+                            "{} counter resets; pages across them are not counted.",
+                            report.resets
+                        ))
+                        .size(12)
+                        .style(warning),
+                    );
+                }
+                if report.gaps.is_empty() {
+                    report_view = report_view
+                        .push(text("No pages outside recordings.").size(12).style(muted));
+                }
+                for gap in &report.gaps {
+                    let decision = match reconcile.log.decision_for(&report.printer, gap) {
+                        Some(decision) if decision.note.is_empty() => decision.action.to_string(),
+                        Some(decision) => format!("{}: {}", decision.action, decision.note),
+                        None => "Open".to_string(),
+                    };
+                    let resolve = |action| Message::ResolveGap { gap: *gap, action };
+                    report_view = report_view.push(
+                        row![
+                            text(format!(
+                                "{} to {}: {} pages",
+                                format_utc_timestamp(gap.from),
+                                format_time_of_day(gap.to),
+                                gap.pages
+                            ))
+                            .size(12)
+                            .width(Length::Fill)
+                            .style(dark),
+                            text(decision).size(12).width(Length::Fill).style(muted),
+                            button(text("Attribute").size(12))
+                                .on_press(resolve(GapAction::Attributed))
+                                .padding([4, 10]),
+                            button(text("Write off").size(12))
+                                .on_press(resolve(GapAction::WrittenOff))
+                                .padding([4, 10]),
+                        ]
+                        .spacing(8)
+                        .align_items(Alignment::Center),
+                    );
+                }
+                if !report.gaps.is_empty() {
+                    report_view = report_view.push(
+                        text_input("Note for the next decision", &reconcile.note_input)
+                            .on_input(Message::ReconcileNoteChanged)
+                            .padding(4)
+                            .size(12),
+                    );
+                }
+            }
+        }
+
+        let content = column![
+            controls,
+            text(format!("Status: {status}")).size(12).style(muted),
+            Rule::horizontal(1),
+            report_view,
+            text(
+                "Gaps are pages the counters show between polls when no recording ran. \
+                 Attribute a gap that was billed another way, or write it off."
+            )
+            .size(11)
+            .style(muted),
+        ]
+        .spacing(8);
+
+        container(scrollable(content))
             .padding(12)
             .width(Length::Fill)
             .style(theme::Container::Box)
             .into()
     }
 
+    /// The account the next recording is charged to, and what the shown
+    /// session has been charged so far.
+    fn recording_account_view(&self, session: &RecordingSession) -> Element<'_, Message> {
+        let book = &self.accounts.book;
+        let choice = |id: Option<AccountId>| AccountChoice {
+            id,
+            label: match id.and_then(|id| book.account(id)) {
+                Some(account) => format!(
+                    "{} ({})",
+                    account.name,
+                    self.format_money(account.balance)
+                ),
+                None => "No account".to_string(),
+            },
+        };
+        let choices: Vec<AccountChoice> = std::iter::once(None)
+            .chain(book.accounts().iter().map(|account| Some(account.id)))
+            .map(choice)
+            .collect();
+        let selected = choice(self.accounts.selected);
+        let picker = column![
+            text("Account for the next recording")
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a))),
+            pick_list(choices, Some(selected), |choice: AccountChoice| {
+                Message::SelectAccount(choice.id)
+            })
+            .text_size(12)
+            .width(Length::Fill),
+        ]
+        .spacing(4);
+        let lookup = text_input("Card number", &self.accounts.lookup_input)
+            .on_input(|value| Message::AccountFieldChanged {
+                field: AccountField::CardLookup,
+                value,
+            })
+            .on_submit(Message::SelectAccountByCard)
+            .padding(6)
+            .size(12)
+            .width(Length::Fixed(160.0));
+
+        let mut content = column![row![picker, lookup]
+            .spacing(8)
+            .align_items(Alignment::End)]
+        .spacing(4);
+        if let Some(id) = session.account {
+            let balance = book
+                .account(id)
+                .map(|account| self.format_money(account.balance))
+                .unwrap_or_else(|| "n/a".to_string());
+            let color = match book.balance_state(id) {
+                Some(BalanceState::Ok) => Color::from_rgb8(0x3a, 0x4a, 0x5a),
+                _ => Color::from_rgb8(0xe0, 0x4f, 0x4f),
+            };
+            content = content.push(
+                text(format!(
+                    "Session charged to {}: {} so far, balance {balance}",
+                    self.account_label(id),
+                    self.format_money(session.charged)
+                ))
+                .size(12)
+                .style(theme::Text::Color(color)),
+            );
+        }
+        content.into()
+    }
+
+    fn accounts_tab_view(&self) -> Element<'_, Message> {
+        let accounts = &self.accounts;
+        let book = &accounts.book;
+        let field = |field| move |value| Message::AccountFieldChanged { field, value };
+        let status = accounts.status.as_deref().unwrap_or("Ready.");
+
+        let mut list = column![text("Accounts")
+            .size(14)
+            .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12)))]
+        .spacing(6);
+        if book.accounts().is_empty() {
+            list = list.push(
+                text("No accounts yet.")
+                    .size(12)
+                    .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            );
+        }
+        for account in book.accounts() {
+            let selected = accounts.selected == Some(account.id);
+            let select_button = if selected {
+                button(text("Selected").size(12)).style(theme::Button::Secondary)
+            } else {
+                button(text("Select").size(12))
+                    .on_press(Message::SelectAccount(Some(account.id)))
+            };
+            let color = match book.balance_state(account.id) {
+                Some(BalanceState::Ok) => Color::from_rgb8(0x1f, 0x2a, 0x37),
+                _ => Color::from_rgb8(0xe0, 0x4f, 0x4f),
+            };
+            let card = if account.card_number.is_empty() {
+                "no card".to_string()
+            } else {
+                format!("card {}", account.card_number)
+            };
+            let group = if account.group.is_empty() {
+                String::new()
+            } else {
+                format!(", {}", account.group)
+            };
+            list = list.push(
+                row![
+                    text(format!("{} ({card}{group})", account.name))
+                        .size(12)
+                        .width(Length::Fill)
+                        .style(theme::Text::Color(Color::from_rgb8(0x1f, 0x2a, 0x37))),
+                    text(self.format_money(account.balance))
+                        .size(12)
+                        .style(theme::Text::Color(color)),
+                    select_button.padding([4, 10]),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+
+        let add_form = column![
+            row![
+                self.pricing_input(
+                    "Name",
+                    "Jane Doe",
+                    &accounts.name_input,
+                    field(AccountField::Name),
+                ),
+                self.pricing_input(
+                    "Card number",
+                    "",
+                    &accounts.card_input,
+                    field(AccountField::CardNumber),
+                ),
+                self.pricing_input(
+                    "Group",
+                    "Students",
+                    &accounts.group_input,
+                    field(AccountField::Group),
+                ),
+            ]
+            .spacing(8),
+            button("Add account").on_press(Message::OpenAccount),
+        ]
+        .spacing(6);
+
+        let top_up_label = match accounts.selected {
+            Some(id) => format!("Top up {}", self.account_label(id)),
+            None => "Top up (select an account first)".to_string(),
+        };
+        let top_up = row![
+            self.pricing_input(
+                &top_up_label,
+                "10.00",
+                &accounts.top_up_input,
+                field(AccountField::TopUp),
+            ),
+            self.pricing_input(
+                "Warn when the balance drops below",
+                "2.00",
+                &accounts.low_balance_input,
+                field(AccountField::LowBalance),
+            ),
+        ]
+        .spacing(8);
+        let top_up_buttons = row![
+            button("Top up").on_press(Message::TopUpAccount),
+            button("Apply warning").on_press(Message::ApplyLowBalance),
+        ]
+        .spacing(8);
+
+        let content = column![
+            list,
+            Rule::horizontal(1),
+            add_form,
+            Rule::horizontal(1),
+            top_up,
+            top_up_buttons,
+            text(format!("Status: {status}"))
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            text(format!(
+                "Sessions started against an account are charged as pages come in and \
+                 stop when the balance reaches zero. Accounts and every top-up and charge \
+                 are saved in {ACCOUNTS_PATH}."
+            ))
+            .size(11)
+            .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            Rule::horizontal(1),
+            self.ledger_view(),
+        ]
+        .spacing(8);
+
+        container(scrollable(content))
+            .padding(12)
+            .width(Length::Fill)
+            .style(theme::Container::Box)
+            .into()
+    }
+
+    /// The selected account's top-ups and charges, newest first.
+    fn ledger_view(&self) -> Element<'_, Message> {
+        let mut list = column![text("Ledger")
+            .size(14)
+            .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12)))]
+        .spacing(6);
+        let Some(id) = self.accounts.selected else {
+            return list
+                .push(
+                    text("Select an account to see its ledger.")
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+                )
+                .into();
+        };
+
+        let entries: Vec<_> = self.accounts.book.entries_for(id).collect();
+        if entries.is_empty() {
+            list = list.push(
+                text("No entries yet.")
+                    .size(12)
+                    .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            );
+        }
+        for entry in entries.iter().rev().take(MAX_LEDGER_ENTRIES_SHOWN) {
+            let amount = match entry.kind {
+                LedgerKind::Charge => self.format_money(-entry.amount),
+                LedgerKind::TopUp | LedgerKind::Refund => self.format_money(entry.amount),
+            };
+            list = list.push(
+                row![
+                    text(format!(
+                        "{}  {}  {}",
+                        format_utc_timestamp(entry.at),
+                        entry.kind,
+                        entry.note
+                    ))
+                    .size(12)
+                    .width(Length::Fill)
+                    .style(theme::Text::Color(Color::from_rgb8(0x1f, 0x2a, 0x37))),
+                    text(amount)
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb8(0x1f, 0x2a, 0x37))),
+                    text(format!("= {}", self.format_money(entry.balance_after)))
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+                ]
+                .spacing(12),
+            );
+        }
+        list.into()
+    }
+
+    /// Shop header and footer printed on receipts, and the numbering.
+    fn receipt_settings_view(&self) -> Element<'_, Message> {
+        let receipts = &self.receipts;
+        let field = |field| move |value| Message::ReceiptFieldChanged { field, value };
+        let status = receipts.status.as_deref().unwrap_or("Ready.");
+        column![
+            text("Receipts")
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
+            self.pricing_input(
+                "Shop name",
+                "Copy shop",
+                &receipts.shop_name_input,
+                field(ReceiptField::ShopName),
+            ),
+            self.pricing_input(
+                "Address (separate lines with |)",
+                "Main Street 1 | 12345 Town",
+                &receipts.address_input,
+                field(ReceiptField::Address),
+            ),
+            self.pricing_input(
+                "VAT ID",
+                "",
+                &receipts.vat_id_input,
+                field(ReceiptField::VatId),
+            ),
+            self.pricing_input(
+                "Footer (separate lines with |)",
+                "Thank you!",
+                &receipts.footer_input,
+                field(ReceiptField::Footer),
+            ),
+            row![
+                self.pricing_input(
+                    "Title",
+                    "Receipt",
+                    &receipts.title_input,
+                    field(ReceiptField::Title),
+                ),
+                self.pricing_input(
+                    "Number prefix",
+                    "R-",
+                    &receipts.prefix_input,
+                    field(ReceiptField::NumberPrefix),
+                ),
+            ]
+            .spacing(8),
+            row![
+                self.pricing_input(
+                    "Receipt printer (host[:port] or device, empty for none)",
+                    "192.168.1.50:9100",
+                    &receipts.printer_input,
+                    field(ReceiptField::ReceiptPrinter),
+                ),
+                self.pricing_input(
+                    "Characters per line",
+                    "48",
+                    &receipts.columns_input,
+                    field(ReceiptField::ReceiptColumns),
+                ),
+            ]
+            .spacing(8),
+            row![
+                button("Save receipt settings").on_press(Message::SaveReceiptSettings),
+                text(format!(
+                    "Next number: {}",
+                    receipts.config.format_number(receipts.config.next_number)
+                ))
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a))),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+            text(format!("Status: {status}"))
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            text(format!(
+                "Receipts are written to {RECEIPTS_DIR}/ as text, HTML and PDF, and sent as \
+                 ESC/POS to the receipt printer when one is set (48 characters per line on \
+                 80 mm paper, 32 on 58 mm). Numbers are saved in {RECEIPTS_PATH} as they are \
+                 issued and never reused."
+            ))
+            .size(11)
+            .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+        ]
+        .spacing(6)
+        .into()
+    }
+
+    /// A rounding step input with its mode; `tariff` is `None` for the
+    /// session total.
+    fn rounding_inputs<'a>(
+        &self,
+        label: &str,
+        value: &str,
+        mode: RoundingMode,
+        on_change: impl Fn(String) -> Message + 'a,
+        tariff: Option<usize>,
+    ) -> Element<'a, Message> {
+        row![
+            self.pricing_input(label, "0.05, 0.10 or 0.50", value, on_change),
+            self.pricing_picker("Rounding", &RoundingMode::ALL[..], mode, move |mode| {
+                Message::PricingRoundingModeChanged { tariff, mode }
+            }),
+        ]
+        .spacing(8)
+        .into()
+    }
+
+    /// Every published version, newest first, with the selected one spelled
+    /// out so any past charge can be traced to the prices behind it.
+    fn price_history_view(&self) -> Element<'_, Message> {
+        let mut list = column![text("Price history")
+            .size(14)
+            .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12)))]
+        .spacing(6);
+
+        for entry in self.pricing.book.versions().iter().rev() {
+            let selected = self.pricing.selected_version == Some(entry.version);
+            let effective = if entry.effective_from == 0 {
+                "always".to_string()
+            } else {
+                format!("from {}", format_utc_timestamp(entry.effective_from))
+            };
+            let view_button = if selected {
+                button(text("Shown").size(12)).style(theme::Button::Secondary)
+            } else {
+                button(text("View").size(12)).on_press(Message::SelectPriceVersion(entry.version))
+            };
+            list = list.push(
+                row![
+                    text(format!(
+                        "Version {}: {}, effective {effective}",
+                        entry.version, entry.price_list.name
+                    ))
+                    .size(12)
+                    .width(Length::Fill)
+                    .style(theme::Text::Color(Color::from_rgb8(0x1f, 0x2a, 0x37))),
+                    view_button.padding([4, 10]),
+                    button(text("Copy to draft").size(12))
+                        .on_press(Message::DraftFromPriceVersion(entry.version))
+                        .padding([4, 10]),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+            if selected {
+                list = list.push(self.price_version_details(entry));
+            }
+        }
+        list.into()
+    }
+
+    fn price_version_details(&self, entry: &PriceListVersion) -> Element<'_, Message> {
+        let price_list = &entry.price_list;
+        let format = self.pricing.book.format();
+        let currency = &price_list.currency;
+        let money = |amount: Money| amount.format(currency, &format);
+        let published = if entry.published_at == 0 {
+            "before versioning".to_string()
+        } else {
+            format_utc_timestamp(entry.published_at)
+        };
+        let mut lines = vec![format!(
+            "Published {published}. Prices in {}, {}.",
+            currency.code,
+            price_list.price_basis.to_string().to_lowercase()
+        )];
+        for tariff in &price_list.tariffs {
+            let categories = tariff
+                .categories
+                .iter()
+                .map(|category| category.label())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut line = format!(
+                "{} ({categories}): {}, VAT {}",
+                tariff.name,
+                format_tiers_input(&tariff.tiers, currency, &format),
+                price_list.tariff_vat_rate(tariff)
+            );
+            if tariff.minimum > Money::ZERO {
+                line.push_str(&format!(", minimum {}", money(tariff.minimum)));
+            }
+            if let Some(rounding) = tariff.rounding {
+                line.push_str(&format!(
+                    ", rounded to {} ({})",
+                    money(rounding.step),
+                    rounding.mode.to_string().to_lowercase()
+                ));
+            }
+            lines.push(line);
+        }
+        lines.push(format!(
+            "Base fee: {}, VAT {}",
+            money(price_list.base_fee),
+            price_list.base_fee_vat
+        ));
+        if let Some(rounding) = price_list.rounding {
+            lines.push(format!(
+                "Total rounded to {} ({})",
+                money(rounding.step),
+                rounding.mode.to_string().to_lowercase()
+            ));
+        }
+
+        let mut details = column![].spacing(4);
+        for line in lines {
+            details = details.push(
+                text(line)
+                    .size(11)
+                    .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            );
+        }
+        container(details).padding([0, 0, 0, 12]).into()
+    }
+
     fn printer_list_view(&self) -> Element<'_, Message> {
         let mut list_items = column![].spacing(6);
 
@@ -682,7 +1736,7 @@ impl PrintCountApp {
             .or_else(|| record.snmp_address.as_ref().map(|addr| addr.host.as_str()))
             .unwrap_or("unknown host");
         let name = record.model.as_deref().unwrap_or("Unknown name");
-        let status = status_label(record.status);
+        let status = self.printer_status_text(record);
         let content = column![
             text(name)
                 .size(14)
@@ -733,6 +1787,9 @@ impl PrintCountApp {
                 let title = match self.printer_tab {
                     PrinterTab::Recording => "Recording",
                     PrinterTab::Pricing => "Pricing",
+                    PrinterTab::Accounts => "Accounts",
+                    PrinterTab::Reports => "Z report",
+                    PrinterTab::Reconcile => "Reconciliation",
                     _ => "Printer details",
                 };
                 let mut content = column![text(title)
@@ -779,13 +1836,13 @@ impl PrintCountApp {
         let body = match self.printer_tab {
             PrinterTab::Polling => {
                 if let Some(record) = record {
-                    let in_flight = self.poll_in_flight.contains(&record.id);
+                    let in_flight = self.poller.is_polling(&record.id);
                     let state = self
                         .poll_states
                         .get(&record.id)
                         .cloned()
                         .unwrap_or(SnmpPollStatus::Idle);
-                    self.printer_poll_view(&state, in_flight)
+                    self.printer_poll_view(record, &state, in_flight)
                 } else if selection_missing {
                     self.empty_printer_tab_view("Selected printer not found.")
                 } else {
@@ -803,6 +1860,9 @@ impl PrintCountApp {
             }
             PrinterTab::Recording => self.recording_tab_view(),
             PrinterTab::Pricing => self.pricing_tab_view(),
+            PrinterTab::Accounts => self.accounts_tab_view(),
+            PrinterTab::Reports => self.reports_tab_view(),
+            PrinterTab::Reconcile => self.reconcile_tab_view(),
             PrinterTab::AddPrinters => self.printer_add_printers_view(),
         };
 
@@ -832,13 +1892,54 @@ impl PrintCountApp {
             .into()
     }
 
-    fn printer_poll_view(&self, state: &SnmpPollStatus, in_flight: bool) -> Element<'_, Message> {
+    fn printer_poll_view(
+        &self,
+        record: &PrinterRecord,
+        state: &SnmpPollStatus,
+        in_flight: bool,
+    ) -> Element<'_, Message> {
+        let interval_secs = record
+            .polling
+            .interval_secs
+            .unwrap_or_else(|| self.poller.interval().as_secs());
+        let interval_text = record
+            .polling
+            .interval_secs
+            .map(|secs| secs.to_string())
+            .unwrap_or_default();
+        let global_interval = self.poller.interval().as_secs().to_string();
+        let idle_text = record
+            .polling
+            .auto_stop_idle_secs
+            .map(|secs| (secs / 60).to_string())
+            .unwrap_or_default();
+        let poll_controls = row![
+            checkbox("Poll continuously", record.polling.enabled)
+                .on_toggle(Message::PollEnabledToggled),
+            text("Interval (s)").size(12),
+            text_input(&global_interval, &interval_text)
+                .on_input(Message::PollIntervalChanged)
+                .padding(4)
+                .size(12)
+                .width(Length::Fixed(60.0)),
+            text("Auto-stop idle recordings (min)").size(12),
+            text_input("Off", &idle_text)
+                .on_input(Message::AutoStopIdleChanged)
+                .padding(4)
+                .size(12)
+                .width(Length::Fixed(60.0)),
+            button("Poll now").on_press(Message::PollSelectedSnmp),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+
         let content = column![
-            text("Polling every 5 seconds")
+            text(format!("Polling every {interval_secs} seconds"))
                 .size(12)
                 .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            poll_controls,
             self.poll_state_view(state, in_flight),
-            self.counters_view(state, in_flight),
+            self.counters_view(record, state, in_flight),
             self.poll_export_controls_view(),
         ]
         .spacing(8);
@@ -868,61 +1969,100 @@ impl PrintCountApp {
         .spacing(8)
         .align_items(Alignment::Center);
 
-        let counter_inputs = column![
-            self.oids_input(
-                "Copies B/W OIDs",
-                "1.3.6.1.4.1.367.3.2.1.2.19.5.1.9.18",
-                &self.recording_oids.copies_bw_input,
-                Message::RecordingOidCopiesBwChanged,
-            ),
-            self.oids_input(
-                "Copies color OIDs",
-                "1.3.6.1.4.1.367.3.2.1.2.19.5.1.9.17",
-                &self.recording_oids.copies_color_input,
-                Message::RecordingOidCopiesColorChanged,
-            ),
-            self.oids_input(
-                "Prints B/W OIDs",
-                "1.3.6.1.4.1.367.3.2.1.2.19.5.1.9.61",
-                &self.recording_oids.prints_bw_input,
-                Message::RecordingOidPrintsBwChanged,
-            ),
-            self.oids_input(
-                "Prints color OIDs",
-                "1.3.6.1.4.1.367.3.2.1.2.19.5.1.9.60",
-                &self.recording_oids.prints_color_input,
-                Message::RecordingOidPrintsColorChanged,
-            ),
-            self.oids_input(
-                "Total clicks OIDs",
-                "1.3.6.1.2.1.43.10.2.1.4.1.3",
-                &self.oids_total_text,
-                Message::OidsTotalChanged,
-            ),
-        ]
-        .spacing(8);
+        let defaults = default_recording_oid_inputs();
+        let mut counter_inputs = column![].spacing(8);
+        for category in self.categories.enabled() {
+            let category = *category;
+            counter_inputs = counter_inputs.push(self.pricing_input(
+                &format!("{} OIDs", category.label()),
+                match defaults.input(category) {
+                    "" => "\"Counter name\" or OID",
+                    placeholder => placeholder,
+                },
+                self.recording_oids.input(category),
+                move |value| Message::RecordingOidChanged { category, value },
+            ));
+        }
+        counter_inputs = counter_inputs.push(self.oids_input(
+            "Total clicks OIDs",
+            "1.3.6.1.2.1.43.10.2.1.4.1.1",
+            &self.oids_total_text,
+            Message::OidsTotalChanged,
+        ));
 
         let crawl_label = if self.oids_crawl_in_flight {
-            "Crawling..."
+            format!("Crawling... {} OIDs", self.oids_crawl_progress.collected())
         } else {
-            "Crawl from printer"
+            "Crawl from printer".to_string()
         };
 
         let crawl_button = if self.oids_crawl_in_flight {
-            button(crawl_label).style(theme::Button::Secondary)
+            button(text(crawl_label)).style(theme::Button::Secondary)
         } else {
-            button(crawl_label).on_press(Message::CrawlOids)
+            button(text(crawl_label)).on_press(Message::CrawlOids)
         };
 
-        let actions = row![button("Apply mapping").on_press(Message::ApplyOids), crawl_button]
-            .spacing(8)
-            .align_items(Alignment::Center);
+        let profile = self.profiles.identify_printer(record);
+        let profile_button = if profile.vendor.is_some() {
+            button("Use profile OIDs").on_press(Message::ApplyProfileOids)
+        } else {
+            button("Use profile OIDs").style(theme::Button::Secondary)
+        };
+        let actions = row![
+            button("Apply to this printer").on_press(Message::ApplyOids),
+            profile_button,
+            crawl_button,
+            button("Read counter names").on_press(Message::ReadCounterNames),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+
+        let (_, source) = self.mapping_for(record);
+        let reset_button = if source == MappingSource::Printer {
+            button("Use model template").on_press(Message::ResetPrinterMapping)
+        } else {
+            button("Use model template").style(theme::Button::Secondary)
+        };
+        let model_actions = row![
+            button("Save as model template").on_press(Message::SaveMappingTemplate),
+            button("Copy mapping to other printers of this model")
+                .on_press(Message::CopyMappingToModel),
+            reset_button,
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+        let model_key = match (&record.model, &record.sys_object_id) {
+            (Some(model), Some(sys_object_id)) => format!("{model} ({sys_object_id})"),
+            (Some(model), None) => model.clone(),
+            (None, Some(sys_object_id)) => sys_object_id.clone(),
+            (None, None) => "unknown model".to_string(),
+        };
+
+        let profile_summary = match (&profile.vendor, &profile.model) {
+            (Some(vendor), Some(model)) => format!("Profile: {vendor}, model {model}"),
+            (Some(vendor), None) => format!("Profile: {vendor}, model unknown"),
+            (None, _) => "Profile: none matches this printer".to_string(),
+        };
+        let mut profile_details = column![text(profile_summary)
+            .size(12)
+            .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a)))]
+        .spacing(2);
+        for note in &profile.notes {
+            profile_details = profile_details.push(
+                text(note)
+                    .size(12)
+                    .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            );
+        }
 
         let content = column![
             text("Counter OID mapping")
                 .size(18)
                 .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
-            text("Enter dotted OIDs separated by commas or spaces.")
+            text(
+                "Enter dotted OIDs or quoted counter names (\"Copier: Black & White\"), \
+                 separated by commas or spaces. Names are looked up per printer.",
+            )
                 .size(12)
                 .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
             column![
@@ -932,8 +2072,15 @@ impl PrintCountApp {
                 path_controls,
             ]
             .spacing(4),
+            profile_details,
+            text(format!("Polled with {}. Model: {model_key}", source.label()))
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a))),
             counter_inputs,
             actions,
+            model_actions,
+            self.calibration_view(record),
+            self.counter_table_view(record),
             text(format!("Status: {status}"))
                 .size(12)
                 .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
@@ -951,13 +2098,177 @@ impl PrintCountApp {
         content.into()
     }
 
-    fn pricing_input(
+    /// The guided calibration: page counts, the two walks and the counters
+    /// proposed for each category.
+    fn calibration_view(&self, record: &PrinterRecord) -> Element<'_, Message> {
+        let calibration = &self.calibration;
+        let this_printer = calibration.printer.as_ref() == Some(&record.id);
+
+        let mut page_inputs = row![].spacing(8).align_items(Alignment::Center);
+        for category in self.categories.enabled() {
+            let category = *category;
+            let value = calibration
+                .page_inputs
+                .get(&category)
+                .map_or("", String::as_str);
+            page_inputs = page_inputs.push(
+                column![
+                    text(category.label())
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a))),
+                    text_input("0", value)
+                        .on_input(move |value| Message::CalibrationPagesChanged {
+                            category,
+                            value,
+                        })
+                        .padding(6)
+                        .size(12)
+                        .width(Length::Fixed(90.0)),
+                ]
+                .spacing(4),
+            );
+        }
+
+        let walking = |stage| calibration.in_flight == Some(stage);
+        let walk_label = |stage, idle: &str| {
+            if walking(stage) {
+                format!("Walking... {} OIDs", calibration.progress.collected())
+            } else {
+                idle.to_string()
+            }
+        };
+        let first_button = button(text(walk_label(CalibrationStage::Before, "Take first walk")));
+        let first_button = if calibration.in_flight.is_none() {
+            first_button.on_press(Message::StartCalibration)
+        } else {
+            first_button.style(theme::Button::Secondary)
+        };
+        let second_button = button(text(walk_label(CalibrationStage::After, "Take second walk")));
+        let second_button =
+            if calibration.in_flight.is_none() && this_printer && calibration.before.is_some() {
+                second_button.on_press(Message::FinishCalibration)
+            } else {
+                second_button.style(theme::Button::Secondary)
+            };
+        let proposal = calibration.proposal.as_ref().filter(|_| this_printer);
+        let use_button = if proposal.is_some_and(|proposal| !proposal.candidates.is_empty()) {
+            button("Use proposed mapping").on_press(Message::UseCalibration)
+        } else {
+            button("Use proposed mapping").style(theme::Button::Secondary)
+        };
+        let actions = row![
+            first_button,
+            second_button,
+            use_button,
+            button("Cancel").on_press(Message::CancelCalibration),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+
+        let mut content = column![
+            text("Calibration")
+                .size(16)
+                .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
+            text(
+                "Enter the test pages you will make, take the first walk, make exactly \
+                 those pages at this printer, then take the second walk. Counters that \
+                 went up by the same number are proposed for that category.",
+            )
+            .size(12)
+            .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            page_inputs,
+            actions,
+        ]
+        .spacing(6);
+
+        let mut counts: Vec<&str> = self
+            .categories
+            .enabled()
+            .iter()
+            .filter_map(|category| calibration.page_inputs.get(category))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty() && *value != "0")
+            .collect();
+        let entered = counts.len();
+        counts.sort_unstable();
+        counts.dedup();
+        if counts.len() < entered {
+            content = content.push(
+                text(
+                    "Two categories have the same number of pages; use a different \
+                     number per category so their counters can be told apart.",
+                )
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0xe0, 0x4f, 0x4f))),
+            );
+        }
+
+        if let Some(proposal) = proposal {
+            for (category, candidates) in &proposal.candidates {
+                content = content.push(
+                    text(category.label())
+                        .size(13)
+                        .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a))),
+                );
+                for candidate in candidates.iter().take(CALIBRATION_CANDIDATES_SHOWN) {
+                    let mut label = format!("{} (+{})", candidate.counter, candidate.delta);
+                    if !candidate.shared_with.is_empty() {
+                        let shared: Vec<&str> = candidate
+                            .shared_with
+                            .iter()
+                            .map(|category| category.label())
+                            .collect();
+                        label.push_str(&format!(", also matches {}", shared.join(", ")));
+                    }
+                    content = content.push(
+                        text(label)
+                            .size(12)
+                            .style(theme::Text::Color(Color::from_rgb8(0x1f, 0x2a, 0x37))),
+                    );
+                }
+            }
+        }
+
+        if let Some(status) = &calibration.status {
+            content = content.push(
+                text(status)
+                    .size(12)
+                    .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            );
+        }
+        content.into()
+    }
+
+    /// The printer's named counters, as they were when last read.
+    fn counter_table_view(&self, record: &PrinterRecord) -> Element<'_, Message> {
+        let table = &record.counter_table;
+        if table.is_empty() {
+            return text("No counter names read from this printer yet.")
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a)))
+                .into();
+        }
+        let mut lines = column![text("Named counters")
+            .size(13)
+            .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a)))]
+        .spacing(2);
+        for counter in table.counters() {
+            let label = match counter.type_code {
+                Some(code) => format!("\"{}\" (type {code})", counter.name),
+                None => format!("\"{}\"", counter.name),
+            };
+            lines = lines.push(self.counter_line(&label, counter.value));
+        }
+        lines.into()
+    }
+
+    fn pricing_input<'a>(
         &self,
         label: &str,
         placeholder: &str,
         value: &str,
-        on_change: fn(String) -> Message,
-    ) -> Element<'_, Message> {
+        on_change: impl Fn(String) -> Message + 'a,
+    ) -> Element<'a, Message> {
         let input = text_input(placeholder, value)
             .on_input(on_change)
             .padding(6)
@@ -974,6 +2285,28 @@ impl PrintCountApp {
         .into()
     }
 
+    fn pricing_picker<'a, T>(
+        &self,
+        label: &str,
+        options: &'a [T],
+        selected: T,
+        on_select: impl Fn(T) -> Message + 'a,
+    ) -> Element<'a, Message>
+    where
+        T: ToString + PartialEq + Clone + 'a,
+    {
+        column![
+            text(label)
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a))),
+            pick_list(options, Some(selected), on_select)
+                .text_size(12)
+                .width(Length::Fill),
+        ]
+        .spacing(4)
+        .into()
+    }
+
     fn oids_input(
         &self,
         label: &str,
@@ -1086,7 +2419,12 @@ impl PrintCountApp {
         column![header, body].spacing(6).into()
     }
 
-    fn counters_view(&self, state: &SnmpPollStatus, in_flight: bool) -> Element<'_, Message> {
+    fn counters_view(
+        &self,
+        record: &PrinterRecord,
+        state: &SnmpPollStatus,
+        in_flight: bool,
+    ) -> Element<'_, Message> {
         let header = row![
             text("Counters")
                 .size(18)
@@ -1102,42 +2440,19 @@ impl PrintCountApp {
                 received_at,
                 varbinds,
             } => {
-                let resolution = resolve_counters(*received_at, &self.counter_oids, varbinds);
+                let (mapping, _) = self.mapping_for(record);
+                let resolution = resolve_counters(*received_at, &mapping.clicks, varbinds);
+                let mapped = self.build_recording_snapshot(&record.id, *received_at, varbinds);
+                let mut categories = column![text("Category counts")
+                    .size(13)
+                    .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a)))]
+                .spacing(4);
+                for category in self.categories.enabled() {
+                    categories = categories
+                        .push(self.counter_line(category.label(), mapped.value(*category)));
+                }
                 let mut lines = column![
-                    text("Printer counts")
-                        .size(13)
-                        .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a))),
-                    self.value_line(
-                        "B/W printer",
-                        extract_value_string(
-                            varbinds,
-                            &Oid::from_slice(&RICOH_BW_PRINTER_COUNT_OID),
-                        ),
-                    ),
-                    self.value_line(
-                        "Color printer",
-                        extract_value_string(
-                            varbinds,
-                            &Oid::from_slice(&RICOH_COLOR_PRINTER_COUNT_OID),
-                        ),
-                    ),
-                    text("Copier counts")
-                        .size(13)
-                        .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a))),
-                    self.value_line(
-                        "B/W copier",
-                        extract_value_string(
-                            varbinds,
-                            &Oid::from_slice(&RICOH_BW_COPIER_COUNT_OID),
-                        ),
-                    ),
-                    self.value_line(
-                        "Color copier",
-                        extract_value_string(
-                            varbinds,
-                            &Oid::from_slice(&RICOH_COLOR_COPIER_COUNT_OID),
-                        ),
-                    ),
+                    categories,
                     text("Click totals")
                         .size(13)
                         .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a))),
@@ -1178,7 +2493,10 @@ impl PrintCountApp {
                 ]
                 .spacing(4);
 
-                if self.counter_oids_empty() {
+                if mapping.clicks.bw.is_empty()
+                    && mapping.clicks.color.is_empty()
+                    && mapping.clicks.total.is_empty()
+                {
                     lines = lines.push(
                         text("Counter OIDs not mapped yet.")
                             .size(12)
@@ -1344,6 +2662,304 @@ impl PrintCountApp {
             .into()
     }
 
+    /// Per-tariff charges, base fee and total for a session; every amount
+    /// shows N/A until all included categories have a delta.
+    fn price_quote_view<'a>(
+        &'a self,
+        quote: Option<&PriceQuote>,
+        price_version: Option<&'a PriceListVersion>,
+    ) -> Element<'a, Message> {
+        let Some(entry) = price_version else {
+            return text("No price list published.")
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a)))
+                .into();
+        };
+        let price_list = &entry.price_list;
+        let format = self.pricing.book.format();
+        let currency = &price_list.currency;
+        let money = |amount: Money| amount.format(currency, &format);
+        let mut lines = column![].spacing(6);
+        for (index, tariff) in price_list.tariffs.iter().enumerate() {
+            let charge = quote.and_then(|quote| quote.charges.get(index));
+            let label = match charge {
+                Some(charge) => {
+                    let mut notes = vec![format!("{} pages", charge.pages)];
+                    if charge.surcharge {
+                        notes.push("surcharge".to_string());
+                    }
+                    if charge.minimum_applied {
+                        notes.push("minimum charge".to_string());
+                    }
+                    format!("{} ({})", tariff.name, notes.join(", "))
+                }
+                None => tariff.name.clone(),
+            };
+            let amount = charge.map(|charge| money(charge.amount));
+            lines = lines.push(self.value_line(&label, amount));
+        }
+        if price_list.base_fee > Money::ZERO {
+            lines = lines.push(self.value_line("Base fee", Some(money(price_list.base_fee))));
+        }
+
+        let taxed = !price_list.vat_rates.is_empty() || price_list.base_fee_vat != VatRate::ZERO;
+        if taxed {
+            lines = lines.push(self.value_line("Net", quote.map(|quote| money(quote.net))));
+            match quote {
+                Some(quote) => {
+                    for line in &quote.vat {
+                        let label = format!("VAT {} on {}", line.rate, money(line.net));
+                        lines = lines.push(self.value_line(&label, Some(money(line.vat))));
+                    }
+                }
+                None => lines = lines.push(self.value_line("VAT", None)),
+            }
+        }
+        lines = lines.push(self.value_line(
+            "Total price",
+            quote.map(|quote| money(quote.total)),
+        ));
+
+        let rounding: Vec<String> = quote
+            .into_iter()
+            .flat_map(|quote| {
+                quote
+                    .charges
+                    .iter()
+                    .map(|charge| (charge.tariff.as_str(), charge.rounding))
+                    .chain([("Total", quote.rounding)])
+            })
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(name, amount)| {
+                format!("{name} {}", format_signed_money(amount, currency, &format))
+            })
+            .collect();
+        let source = format!("Price list: {} version {}", price_list.name, entry.version);
+        let rounding_label = if rounding.is_empty() {
+            format!("{source}. No rounding applied.")
+        } else {
+            format!("{source}. Rounding: {}.", rounding.join(", "))
+        };
+        lines
+            .push(
+                text(rounding_label)
+                    .size(11)
+                    .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            )
+            .into()
+    }
+
+    /// Stopped recordings of the printer, newest first, each of which can be
+    /// reopened into the table above for review or correction.
+    fn completed_recordings_view(
+        &self,
+        printer_id: &PrinterId,
+        current: &RecordingSession,
+    ) -> Element<'_, Message> {
+        let mut list = column![text("Past recordings")
+            .size(13)
+            .style(theme::Text::Color(Color::from_rgb8(0x3a, 0x4a, 0x5a)))]
+        .spacing(6);
+
+        let completed = self
+            .completed_recordings
+            .get(printer_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if completed.is_empty() {
+            return list
+                .push(
+                    text("No completed recordings yet.")
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+                )
+                .into();
+        }
+
+        for past in completed.iter().rev() {
+            let Some(started_at) = past.started_at() else {
+                continue;
+            };
+            let stopped_at = past
+                .end
+                .as_ref()
+                .map(|snapshot| snapshot.received_at.to_string())
+                .unwrap_or_else(|| "n/a".to_string());
+            let version = past
+                .price_version
+                .map(|version| format!(", price list version {version}"))
+                .unwrap_or_default();
+            let receipt = past
+                .receipt
+                .as_ref()
+                .map(|issued| format!(", receipt {}", issued.number))
+                .unwrap_or_default();
+            let payment = match (past.voided, past.payment) {
+                (true, _) => ", voided".to_string(),
+                (false, Some(method)) => format!(", paid by {method}"),
+                (false, None) => ", unpaid".to_string(),
+            };
+            let auto_stopped = if past.auto_stopped { ", auto-stopped" } else { "" };
+            let closed = past
+                .z_report
+                .map(|number| format!(", Z {number}"))
+                .unwrap_or_default();
+            let shown = !current.active && current.started_at() == Some(started_at);
+            let open_button = if current.active || shown {
+                button(text(if shown { "Open" } else { "Reopen" }).size(12))
+                    .style(theme::Button::Secondary)
+            } else {
+                button(text("Reopen").size(12)).on_press(Message::ReopenRecording(started_at))
+            };
+            list = list.push(
+                row![
+                    text(format!(
+                        "Started {started_at}, stopped {stopped_at}{auto_stopped}{version}\
+                         {receipt}{payment}{closed}"
+                    ))
+                    .size(12)
+                    .width(Length::Fill)
+                    .style(theme::Text::Color(Color::from_rgb8(0x1f, 0x2a, 0x37))),
+                    open_button.padding([4, 10]),
+                    button(text("Receipt").size(12))
+                        .on_press(Message::IssueReceipt(started_at))
+                        .padding([4, 10]),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+        list.into()
+    }
+
+    /// Counter resets seen in the session, each with the choices for bridging
+    /// the gap. Deltas for those rows stay n/a until one is picked.
+    fn recording_resets_view(
+        &self,
+        session: &RecordingSession,
+        live_snapshot: Option<&RecordingSnapshot>,
+    ) -> Option<Element<'_, Message>> {
+        let mut notices = column![].spacing(6);
+        let mut any_reset = false;
+        for category in RecordingCategory::ALL {
+            let Some(tracker) = category_tracker(session, category, live_snapshot) else {
+                continue;
+            };
+            let Some(reset) = tracker.resets().last() else {
+                continue;
+            };
+            any_reset = true;
+
+            let evidence = if reset.rebooted {
+                "printer restarted"
+            } else {
+                "no restart seen"
+            };
+            let repeats = match tracker.resets().len() {
+                1 => String::new(),
+                count => format!(" {count} resets in this session."),
+            };
+            let message = format!(
+                "{}: counter went back from {} to {} ({evidence}).{repeats}",
+                category.label(),
+                reset.previous,
+                reset.current
+            );
+            let chosen = session.edits.category(category).reset_resolution;
+            let choice = |label: &'static str, resolution: ResetResolution| {
+                let choice = button(text(label).size(12))
+                    .on_press(Message::RecordingResetResolved {
+                        category,
+                        resolution,
+                    })
+                    .padding([4, 10]);
+                if chosen == Some(resolution) {
+                    choice
+                } else {
+                    choice.style(theme::Button::Secondary)
+                }
+            };
+
+            notices = notices.push(
+                column![
+                    text(message)
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb8(0xe0, 0x4f, 0x4f))),
+                    row![
+                        choice("Count from zero", ResetResolution::CountFromZero),
+                        choice("Keep as new baseline", ResetResolution::Rebaseline),
+                    ]
+                    .spacing(8),
+                ]
+                .spacing(4),
+            );
+        }
+        any_reset.then(|| notices.into())
+    }
+
+    /// Print clicks that arrived during the session, each of which can be
+    /// left out, plus the jobs the printer's job log shows since the start.
+    fn recording_concurrent_view(
+        &self,
+        session: &RecordingSession,
+    ) -> Option<Element<'_, Message>> {
+        if session.concurrent.is_empty() {
+            return None;
+        }
+        let warning = theme::Text::Color(Color::from_rgb8(0xe0, 0x4f, 0x4f));
+        let muted = theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a));
+        let mut notices = column![text(
+            "Print clicks arrived during this recording. If the customer only copied, \
+             they are probably a PC job; exclude them to leave them off the bill."
+        )
+        .size(12)
+        .style(warning)]
+        .spacing(4);
+        for activity in &session.concurrent {
+            let pages: Vec<String> = activity
+                .pages
+                .iter()
+                .map(|(category, pages)| format!("{pages} {}", category.label()))
+                .collect();
+            let at = activity.at;
+            notices = notices.push(
+                row![
+                    text(format!("{}: {}", format_time_of_day(at), pages.join(", ")))
+                        .size(12)
+                        .width(Length::Fill)
+                        .style(theme::Text::Color(Color::from_rgb8(0x1f, 0x2a, 0x37))),
+                    checkbox("Exclude", activity.excluded)
+                        .on_toggle(move |_| Message::ToggleConcurrentExclusion(at))
+                        .size(14)
+                        .text_size(12),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+        let jobs = match (&session.job_baseline, session.new_jobs.as_slice()) {
+            (None, _) => "The printer's job log could not be read.".to_string(),
+            (Some(_), []) => "The printer's job log shows no new jobs.".to_string(),
+            (Some(_), jobs) => {
+                let jobs: Vec<String> = jobs
+                    .iter()
+                    .map(|job| {
+                        let owner = job.owner.as_deref().unwrap_or("unknown sender");
+                        match job.impressions {
+                            Some(impressions) => {
+                                format!("#{} by {owner} ({impressions} pages)", job.index)
+                            }
+                            None => format!("#{} by {owner}", job.index),
+                        }
+                    })
+                    .collect();
+                format!("Jobs sent since the start: {}.", jobs.join(", "))
+            }
+        };
+        notices = notices.push(text(jobs).size(12).style(muted));
+        Some(notices.into())
+    }
+
     fn recording_table_row(
         &self,
         label: &str,
@@ -1503,13 +3119,57 @@ impl PrintCountApp {
             .into()
     }
 
+    fn snmp_capture_controls_view(&self) -> Element<'_, Message> {
+        let capture_button = match self.snmp_capture {
+            SnmpCaptureMode::Off => button("Capture").on_press(Message::StartSnmpCapture),
+            SnmpCaptureMode::Capturing(_) => {
+                button("Stop capture").on_press(Message::StopSnmpCapture)
+            }
+            SnmpCaptureMode::Replaying(_) => button("Capture").style(theme::Button::Secondary),
+        };
+        let replay_button = match self.snmp_capture {
+            SnmpCaptureMode::Off => button("Replay").on_press(Message::LoadSnmpReplay),
+            SnmpCaptureMode::Capturing(_) => button("Replay").style(theme::Button::Secondary),
+            SnmpCaptureMode::Replaying(_) => {
+                button("Stop replay").on_press(Message::StopSnmpReplay)
+            }
+        };
+        let status = self.snmp_capture_status.as_deref().unwrap_or("Ready");
+
+        column![
+            text(format!("SNMP capture: {}", self.snmp_capture_label()))
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb8(0x4a, 0x4a, 0x4a))),
+            text_input("snmp_capture.ron", &self.snmp_capture_path)
+                .on_input(Message::SnmpCapturePathChanged)
+                .padding(6)
+                .size(12),
+            row![capture_button, replay_button].spacing(8),
+            text(status)
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+        ]
+        .spacing(8)
+        .into()
+    }
+
     fn debug_panel_view(&self) -> Element<'_, Message> {
         let copy_status = self.copy_status.as_deref().unwrap_or("Ready");
+        let offline = self
+            .printers
+            .iter()
+            .filter(|record| record.status == PrinterStatus::Offline)
+            .count();
+        let errors = self
+            .printers
+            .iter()
+            .filter(|record| record.status == PrinterStatus::Error)
+            .count();
         let panel = column![
             text("Debug panel")
                 .size(20)
                 .style(theme::Text::Color(Color::from_rgb8(0x12, 0x12, 0x12))),
-            text("Per-printer errors: none recorded yet.")
+            text(format!("Per-printer errors: {offline} offline, {errors} in error."))
                 .size(14)
                 .style(theme::Text::Color(Color::from_rgb8(0x4a, 0x4a, 0x4a))),
             text("SNMP OIDs used: not captured yet.")
@@ -1526,6 +3186,8 @@ impl PrintCountApp {
             text(format!("Clipboard: {copy_status}"))
                 .size(12)
                 .style(theme::Text::Color(Color::from_rgb8(0x6a, 0x6a, 0x6a))),
+            Rule::horizontal(1),
+            self.snmp_capture_controls_view(),
         ]
         .spacing(10);
 
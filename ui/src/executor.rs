@@ -21,7 +21,7 @@ impl Executor for StackSizedTokioExecutor {
     }
 
     fn spawn(&self, future: impl Future<Output = ()> + Send + 'static) {
-        let _ = self.runtime.spawn(future);
+        drop(self.runtime.spawn(future));
     }
 
     fn enter<R>(&self, f: impl FnOnce() -> R) -> R {